@@ -65,6 +65,7 @@ enum LIBRA_ERRNO
   LIBRA_ERRNO_SHADER_PARAMETER_ERROR = 5,
   LIBRA_ERRNO_REFLECT_ERROR = 6,
   LIBRA_ERRNO_RUNTIME_ERROR = 7,
+  LIBRA_ERRNO_CACHE_ERROR = 8,
 };
 #ifndef __cplusplus
 typedef int32_t LIBRA_ERRNO;
@@ -99,6 +100,23 @@ enum LIBRA_PRESET_CTX_RUNTIME
 typedef uint32_t LIBRA_PRESET_CTX_RUNTIME;
 #endif // __cplusplus
 
+/// An enum representing the color space of the output of a filter chain.
+enum LIBRA_HDR_MODE
+#ifdef __cplusplus
+  : uint32_t
+#endif // __cplusplus
+ {
+  /// SDR output.
+  LIBRA_HDR_MODE_DISABLED = 0,
+  /// scRGB output. The final pass should render to a `R16G16B16A16_SFLOAT` target.
+  LIBRA_HDR_MODE_SC_RGB,
+  /// HDR10 output. The final pass should render to a `A2B10G10R10_UNORM_PACK32` target.
+  LIBRA_HDR_MODE_HDR10,
+};
+#ifndef __cplusplus
+typedef uint32_t LIBRA_HDR_MODE;
+#endif // __cplusplus
+
 /// Opaque struct for a Direct3D 11 filter chain.
 typedef struct _filter_chain_d3d11 _filter_chain_d3d11;
 
@@ -126,6 +144,9 @@ typedef struct _shader_preset _shader_preset;
 /// Opaque struct for a preset context.
 typedef struct _preset_ctx _preset_ctx;
 
+/// Opaque struct for a shader cache.
+typedef struct _shader_cache _shader_cache;
+
 /// A handle to a librashader error object.
 typedef struct _libra_error *libra_error_t;
 
@@ -135,6 +156,9 @@ typedef struct _shader_preset *libra_shader_preset_t;
 /// A handle to a preset wildcard context object.
 typedef struct _preset_ctx *libra_preset_ctx_t;
 
+/// A handle to a shader cache object.
+typedef struct _shader_cache *libra_shader_cache_t;
+
 /// A preset parameter.
 typedef struct libra_preset_param_t {
   /// The name of the parameter
@@ -186,6 +210,16 @@ typedef struct filter_chain_gl_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The shader cache to use. If null, the default shader cache is used.
+  /// The filter chain keeps its own handle to the cache, so the cache may be freed
+  /// after the filter chain is created.
+  libra_shader_cache_t cache;
+  /// The color space of the output. If HDR output is enabled, the final pass should render
+  /// to a target of the matching format, and the HDR options of every frame are set to match.
+  LIBRA_HDR_MODE hdr_mode;
+  /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+  /// Leave this disabled if the shader preset produces HDR output by itself.
+  bool hdr_output_pass;
 } filter_chain_gl_opt_t;
 #endif
 
@@ -248,6 +282,25 @@ typedef struct frame_gl_opt_t {
   uint32_t total_subframes;
   /// The current sub frame. Default is 1.
   uint32_t current_subframe;
+  /// The aspect ratio of the original input. If this is 0, the aspect ratio
+  /// is calculated from the size of the original input. Default is 0.
+  float aspect_ratio;
+  /// The frame rate of the original input, in frames per second. Default is 60.
+  float frames_per_second;
+  /// The time elapsed since the previous frame, in microseconds. Default is 0.
+  uint32_t frametime_delta;
+  /// Whether or not HDR output is enabled. Default is false.
+  bool enable_hdr;
+  /// The luminance of paper white, in nits. Default is 200.
+  float paper_white_nits;
+  /// The peak luminance of the display, in nits. Default is 1000.
+  float max_nits;
+  /// The gamut expansion mode to use when converting to HDR. Default is 0.
+  uint32_t expand_gamut;
+  /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+  bool inverse_tonemap;
+  /// Whether or not the output is encoded as HDR10. Default is false.
+  bool hdr10;
 } frame_gl_opt_t;
 #endif
 
@@ -284,6 +337,16 @@ typedef struct filter_chain_vk_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The shader cache to use. If null, the default shader cache is used.
+  /// The filter chain keeps its own handle to the cache, so the cache may be freed
+  /// after the filter chain is created.
+  libra_shader_cache_t cache;
+  /// The color space of the output. If HDR output is enabled, the final pass should render
+  /// to a target of the matching format, and the HDR options of every frame are set to match.
+  LIBRA_HDR_MODE hdr_mode;
+  /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+  /// Leave this disabled if the shader preset produces HDR output by itself.
+  bool hdr_output_pass;
 } filter_chain_vk_opt_t;
 #endif
 
@@ -332,6 +395,25 @@ typedef struct frame_vk_opt_t {
   uint32_t total_subframes;
   /// The current sub frame. Default is 1.
   uint32_t current_subframe;
+  /// The aspect ratio of the original input. If this is 0, the aspect ratio
+  /// is calculated from the size of the original input. Default is 0.
+  float aspect_ratio;
+  /// The frame rate of the original input, in frames per second. Default is 60.
+  float frames_per_second;
+  /// The time elapsed since the previous frame, in microseconds. Default is 0.
+  uint32_t frametime_delta;
+  /// Whether or not HDR output is enabled. Default is false.
+  bool enable_hdr;
+  /// The luminance of paper white, in nits. Default is 200.
+  float paper_white_nits;
+  /// The peak luminance of the display, in nits. Default is 1000.
+  float max_nits;
+  /// The gamut expansion mode to use when converting to HDR. Default is 0.
+  uint32_t expand_gamut;
+  /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+  bool inverse_tonemap;
+  /// Whether or not the output is encoded as HDR10. Default is false.
+  bool hdr10;
 } frame_vk_opt_t;
 #endif
 
@@ -346,6 +428,16 @@ typedef struct filter_chain_d3d11_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The shader cache to use. If null, the default shader cache is used.
+  /// The filter chain keeps its own handle to the cache, so the cache may be freed
+  /// after the filter chain is created.
+  libra_shader_cache_t cache;
+  /// The color space of the output. If HDR output is enabled, the final pass should render
+  /// to a target of the matching format, and the HDR options of every frame are set to match.
+  LIBRA_HDR_MODE hdr_mode;
+  /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+  /// Leave this disabled if the shader preset produces HDR output by itself.
+  bool hdr_output_pass;
 } filter_chain_d3d11_opt_t;
 #endif
 
@@ -382,6 +474,25 @@ typedef struct frame_d3d11_opt_t {
   uint32_t total_subframes;
   /// The current sub frame. Default is 1.
   uint32_t current_subframe;
+  /// The aspect ratio of the original input. If this is 0, the aspect ratio
+  /// is calculated from the size of the original input. Default is 0.
+  float aspect_ratio;
+  /// The frame rate of the original input, in frames per second. Default is 60.
+  float frames_per_second;
+  /// The time elapsed since the previous frame, in microseconds. Default is 0.
+  uint32_t frametime_delta;
+  /// Whether or not HDR output is enabled. Default is false.
+  bool enable_hdr;
+  /// The luminance of paper white, in nits. Default is 200.
+  float paper_white_nits;
+  /// The peak luminance of the display, in nits. Default is 1000.
+  float max_nits;
+  /// The gamut expansion mode to use when converting to HDR. Default is 0.
+  uint32_t expand_gamut;
+  /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+  bool inverse_tonemap;
+  /// Whether or not the output is encoded as HDR10. Default is false.
+  bool hdr10;
 } frame_d3d11_opt_t;
 #endif
 
@@ -396,6 +507,16 @@ typedef struct filter_chain_d3d9_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The shader cache to use. If null, the default shader cache is used.
+  /// The filter chain keeps its own handle to the cache, so the cache may be freed
+  /// after the filter chain is created.
+  libra_shader_cache_t cache;
+  /// The color space of the output. If HDR output is enabled, the final pass should render
+  /// to a target of the matching format, and the HDR options of every frame are set to match.
+  LIBRA_HDR_MODE hdr_mode;
+  /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+  /// Leave this disabled if the shader preset produces HDR output by itself.
+  bool hdr_output_pass;
 } filter_chain_d3d9_opt_t;
 #endif
 
@@ -420,6 +541,25 @@ typedef struct frame_d3d9_opt_t {
   uint32_t total_subframes;
   /// The current sub frame. Default is 1.
   uint32_t current_subframe;
+  /// The aspect ratio of the original input. If this is 0, the aspect ratio
+  /// is calculated from the size of the original input. Default is 0.
+  float aspect_ratio;
+  /// The frame rate of the original input, in frames per second. Default is 60.
+  float frames_per_second;
+  /// The time elapsed since the previous frame, in microseconds. Default is 0.
+  uint32_t frametime_delta;
+  /// Whether or not HDR output is enabled. Default is false.
+  bool enable_hdr;
+  /// The luminance of paper white, in nits. Default is 200.
+  float paper_white_nits;
+  /// The peak luminance of the display, in nits. Default is 1000.
+  float max_nits;
+  /// The gamut expansion mode to use when converting to HDR. Default is 0.
+  uint32_t expand_gamut;
+  /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+  bool inverse_tonemap;
+  /// Whether or not the output is encoded as HDR10. Default is false.
+  bool hdr10;
 } frame_d3d9_opt_t;
 #endif
 
@@ -437,6 +577,16 @@ typedef struct filter_chain_d3d12_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The shader cache to use. If null, the default shader cache is used.
+  /// The filter chain keeps its own handle to the cache, so the cache may be freed
+  /// after the filter chain is created.
+  libra_shader_cache_t cache;
+  /// The color space of the output. If HDR output is enabled, the final pass should render
+  /// to a target of the matching format, and the HDR options of every frame are set to match.
+  LIBRA_HDR_MODE hdr_mode;
+  /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+  /// Leave this disabled if the shader preset produces HDR output by itself.
+  bool hdr_output_pass;
 } filter_chain_d3d12_opt_t;
 #endif
 
@@ -487,6 +637,25 @@ typedef struct frame_d3d12_opt_t {
   uint32_t total_subframes;
   /// The current sub frame. Default is 1.
   uint32_t current_subframe;
+  /// The aspect ratio of the original input. If this is 0, the aspect ratio
+  /// is calculated from the size of the original input. Default is 0.
+  float aspect_ratio;
+  /// The frame rate of the original input, in frames per second. Default is 60.
+  float frames_per_second;
+  /// The time elapsed since the previous frame, in microseconds. Default is 0.
+  uint32_t frametime_delta;
+  /// Whether or not HDR output is enabled. Default is false.
+  bool enable_hdr;
+  /// The luminance of paper white, in nits. Default is 200.
+  float paper_white_nits;
+  /// The peak luminance of the display, in nits. Default is 1000.
+  float max_nits;
+  /// The gamut expansion mode to use when converting to HDR. Default is 0.
+  uint32_t expand_gamut;
+  /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+  bool inverse_tonemap;
+  /// Whether or not the output is encoded as HDR10. Default is false.
+  bool hdr10;
 } frame_d3d12_opt_t;
 #endif
 
@@ -497,6 +666,19 @@ typedef struct filter_chain_mtl_opt_t {
   LIBRASHADER_API_VERSION version;
   /// Whether or not to explicitly disable mipmap generation regardless of shader preset settings.
   bool force_no_mipmaps;
+  /// Disable the shader object cache. Shaders will be
+  /// recompiled rather than loaded from the cache.
+  bool disable_cache;
+  /// The shader cache to use. If null, the default shader cache is used.
+  /// The filter chain keeps its own handle to the cache, so the cache may be freed
+  /// after the filter chain is created.
+  libra_shader_cache_t cache;
+  /// The color space of the output. If HDR output is enabled, the final pass should render
+  /// to a target of the matching format, and the HDR options of every frame are set to match.
+  LIBRA_HDR_MODE hdr_mode;
+  /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+  /// Leave this disabled if the shader preset produces HDR output by itself.
+  bool hdr_output_pass;
 } filter_chain_mtl_opt_t;
 #endif
 
@@ -520,6 +702,25 @@ typedef struct frame_mtl_opt_t {
   uint32_t total_subframes;
   /// The current sub frame. Default is 1.
   uint32_t current_subframe;
+  /// The aspect ratio of the original input. If this is 0, the aspect ratio
+  /// is calculated from the size of the original input. Default is 0.
+  float aspect_ratio;
+  /// The frame rate of the original input, in frames per second. Default is 60.
+  float frames_per_second;
+  /// The time elapsed since the previous frame, in microseconds. Default is 0.
+  uint32_t frametime_delta;
+  /// Whether or not HDR output is enabled. Default is false.
+  bool enable_hdr;
+  /// The luminance of paper white, in nits. Default is 200.
+  float paper_white_nits;
+  /// The peak luminance of the display, in nits. Default is 1000.
+  float max_nits;
+  /// The gamut expansion mode to use when converting to HDR. Default is 0.
+  uint32_t expand_gamut;
+  /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+  bool inverse_tonemap;
+  /// Whether or not the output is encoded as HDR10. Default is false.
+  bool hdr10;
 } frame_mtl_opt_t;
 #endif
 
@@ -629,6 +830,15 @@ typedef libra_error_t (*PFN_libra_preset_ctx_set_core_aspect_orientation)(libra_
 typedef libra_error_t (*PFN_libra_preset_ctx_set_runtime)(libra_preset_ctx_t *context,
                                                           LIBRA_PRESET_CTX_RUNTIME value);
 
+/// Function pointer definition for
+///libra_shader_cache_open
+typedef libra_error_t (*PFN_libra_shader_cache_open)(const char *directory,
+                                                     libra_shader_cache_t *out);
+
+/// Function pointer definition for
+///libra_shader_cache_free
+typedef libra_error_t (*PFN_libra_shader_cache_free)(libra_shader_cache_t *cache);
+
 /// Function pointer definition for libra_error_errno
 typedef LIBRA_ERRNO (*PFN_libra_error_errno)(libra_error_t error);
 
@@ -1048,7 +1258,12 @@ typedef libra_error_t (*PFN_libra_mtl_filter_chain_free)(libra_mtl_filter_chain_
 ///     - Added rotation, total_subframes, current_subframes to frame options
 ///     - Added preset context API
 ///     - Added Metal runtime API
-#define LIBRASHADER_CURRENT_VERSION 1
+/// - API version 2: 0.2.7
+///     - Added aspect_ratio, frames_per_second, frametime_delta, and HDR options to frame options
+///     - Added shader cache API
+///     - Added cache to filter chain options
+///     - Added hdr_mode and hdr_output_pass to filter chain options
+#define LIBRASHADER_CURRENT_VERSION 2
 
 /// The current version of the librashader ABI.
 /// Used by the loader to check ABI compatibility.
@@ -1106,6 +1321,28 @@ int32_t libra_error_write(libra_error_t error,
 ///     Undefined Behaviour.
 int32_t libra_error_free_string(char **out);
 
+/// Open the shader cache in the given directory, creating it if it does not exist.
+///
+/// The cache can be passed in the options of a filter chain to use it instead of the default
+/// shader cache.
+///
+/// ## Safety
+///  - `directory` must be either null or a valid, aligned pointer to a string path to the cache directory.
+///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_shader_cache_t`.
+/// ## Returns
+///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+libra_error_t libra_shader_cache_open(const char *directory, libra_shader_cache_t *out);
+
+/// Free the shader cache.
+///
+/// Filter chains created with the cache keep using it until they are freed.
+/// If `cache` is null, this function does nothing. The resulting value in `cache` then becomes
+/// null.
+///
+/// ## Safety
+/// - `cache` must be a valid and aligned pointer to a `libra_shader_cache_t`.
+libra_error_t libra_shader_cache_free(libra_shader_cache_t *cache);
+
 /// Load a preset.
 ///
 /// ## Safety
@@ -155,6 +155,17 @@ libra_error_t __librashader__noop_preset_ctx_set_core_aspect_orientation(
     return NULL;
 }
 
+libra_error_t __librashader__noop_shader_cache_open(const char *directory,
+                                                    libra_shader_cache_t *out) {
+    *out = NULL;
+    return NULL;
+}
+
+libra_error_t __librashader__noop_shader_cache_free(
+    libra_shader_cache_t *cache) {
+    return NULL;
+}
+
 libra_error_t __librashader__noop_preset_create_with_context(
     const char *filename, libra_preset_ctx_t *context,
     libra_shader_preset_t *out) {
@@ -645,6 +656,30 @@ typedef struct libra_instance_t {
     PFN_libra_preset_ctx_set_core_aspect_orientation
         preset_ctx_set_core_aspect_orientation;
 
+    /// Open the shader cache in the given directory, creating it if it does
+    /// not exist.
+    ///
+    /// If this function is not loaded, `out` will unconditionally be set to
+    /// null. If this function returns an error, the state of `out` is
+    /// unspecified.
+    ///
+    /// ## Safety
+    /// - `directory` must be either null or a valid, aligned pointer to a
+    /// string path to the cache directory.
+    /// - `out` must be either null, or an aligned pointer to an uninitialized
+    /// or invalid `libra_shader_cache_t`.
+    PFN_libra_shader_cache_open shader_cache_open;
+
+    /// Free the shader cache.
+    ///
+    /// Filter chains created with the cache keep using it until they are
+    /// freed.
+    ///
+    /// ## Safety
+    /// - `cache` must be a valid and aligned pointer to a
+    /// `libra_shader_cache_t`.
+    PFN_libra_shader_cache_free shader_cache_free;
+
     /// Load a preset.
     ///
     /// If this function is not loaded, `out` will unconditionally be set to
@@ -1426,6 +1461,9 @@ libra_instance_t __librashader_make_null_instance(void) {
     instance.preset_ctx_set_core_aspect_orientation =
         __librashader__noop_preset_ctx_set_core_aspect_orientation;
 
+    instance.shader_cache_open = __librashader__noop_shader_cache_open;
+    instance.shader_cache_free = __librashader__noop_shader_cache_free;
+
     instance.preset_create = __librashader__noop_preset_create;
     instance.preset_create_with_context =
         __librashader__noop_preset_create_with_context;
@@ -1603,6 +1641,9 @@ libra_instance_t librashader_load_instance(void) {
     _LIBRASHADER_ASSIGN(librashader, instance,
                         preset_ctx_set_core_aspect_orientation);
 
+    _LIBRASHADER_ASSIGN(librashader, instance, shader_cache_open);
+    _LIBRASHADER_ASSIGN(librashader, instance, shader_cache_free);
+
     _LIBRASHADER_ASSIGN(librashader, instance, preset_create);
     _LIBRASHADER_ASSIGN(librashader, instance, preset_create_with_context);
 
@@ -0,0 +1,368 @@
+//! Portable archives of precompiled shaders.
+use crate::backend::{CacheBackend, CacheEntry};
+use crate::error::CacheError;
+use crate::key::{cache_fingerprint, OutputOptionsKey};
+use crate::output::{decode_output, encode_output, output_key, CacheableOutput};
+use crate::ShaderCache;
+use librashader_preprocess::{Defines, ShaderSource};
+use librashader_presets::ShaderPreset;
+use librashader_reflect::back::glsl::GlslVersion;
+use librashader_reflect::back::hlsl::HlslShaderModel;
+use librashader_reflect::back::msl::MslVersion;
+use librashader_reflect::back::targets::{OutputTarget, GLSL, HLSL, MSL, SPIRV, WGSL};
+use librashader_reflect::back::{CompileShader, FromCompilation, ShaderCompilerOutput};
+use librashader_reflect::error::ShaderCompileError;
+use librashader_reflect::front::SpirvCompilation;
+use librashader_reflect::reflect::cross::SpirvCross;
+use librashader_reflect::reflect::naga::{Naga, NagaLoweringOptions};
+use librashader_reflect::reflect::presets::{CompilePresetTarget, PresetCompileOptions};
+use librashader_reflect::reflect::semantics::ShaderSemantics;
+use librashader_reflect::reflect::ReflectShader;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The magic bytes at the start of a shader archive.
+const ARCHIVE_MAGIC: &[u8; 8] = b"LBRSPACK";
+
+/// The contents of an archive file: the cache fingerprint, and every entry.
+type ArchiveContents = (String, Vec<(String, Vec<u8>, Vec<u8>)>);
+
+/// The outputs to precompile for each shader in a [`ShaderArchive`].
+///
+/// Compiled SPIR-V is always archived, since it is shared by all runtimes that use the cache.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveTargets {
+    /// The GLSL versions to compile to.
+    pub glsl: Vec<GlslVersion>,
+    /// The HLSL shader models to compile to.
+    pub hlsl: Vec<Option<HlslShaderModel>>,
+    /// The MSL versions to compile to.
+    pub msl: Vec<Option<MslVersion>>,
+    /// The lowering options to compile WGSL with.
+    pub wgsl: Vec<NagaLoweringOptions>,
+    /// Whether to archive SPIR-V compiled for Vulkan.
+    pub spirv: bool,
+}
+
+/// A portable archive of precompiled shaders.
+///
+/// An archive is built ahead of time from a list of presets, and can be shipped alongside an
+/// application so that shaders do not need to be compiled on first launch. Entries are keyed
+/// like the shader cache, so a [`ShaderCache`] created with [`ShaderCache::with_archive`] loads
+/// compiled shaders from the archive before compiling them.
+///
+/// Archives can only be loaded by the version of librashader that created them.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderArchive {
+    entries: BTreeMap<(String, Vec<u8>), Vec<u8>>,
+}
+
+impl ShaderArchive {
+    /// Create an empty archive.
+    pub fn new() -> ShaderArchive {
+        ShaderArchive::default()
+    }
+
+    /// Load an archive from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<ShaderArchive, CacheError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| CacheError::IOError(path.to_path_buf(), e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Load an archive from its serialized bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ShaderArchive, CacheError> {
+        let Some(contents) = bytes.strip_prefix(ARCHIVE_MAGIC.as_slice()) else {
+            return Err(CacheError::InvalidArchive);
+        };
+        let ((fingerprint, entries), _): (ArchiveContents, _) =
+            bincode::serde::decode_from_slice(contents, bincode::config::standard())
+                .map_err(|_| CacheError::InvalidArchive)?;
+        if fingerprint != cache_fingerprint() {
+            return Err(CacheError::IncompatibleArchive(fingerprint));
+        }
+
+        Ok(ShaderArchive {
+            entries: entries
+                .into_iter()
+                .map(|(index, key, value)| ((index, key), value))
+                .collect(),
+        })
+    }
+
+    /// Serialize the archive to bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let contents: ArchiveContents = (
+            cache_fingerprint(),
+            self.entries
+                .iter()
+                .map(|((index, key), value)| (index.clone(), key.clone(), value.clone()))
+                .collect(),
+        );
+
+        let mut bytes = ARCHIVE_MAGIC.to_vec();
+        // Encoding strings and byte vectors into a vector can not fail.
+        bincode::serde::encode_into_std_write(&contents, &mut bytes, bincode::config::standard())
+            .expect("failed to encode shader archive");
+        bytes
+    }
+
+    /// Write the archive to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CacheError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes())
+            .map_err(|e| CacheError::IOError(path.to_path_buf(), e))
+    }
+
+    /// The number of entries in the archive.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the archive has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Compile every pass of the preset and add the outputs to the archive.
+    ///
+    /// Passes are reflected before they are archived, so presets that would fail to load in a
+    /// runtime fail here instead.
+    pub fn add_preset(
+        &mut self,
+        preset: &ShaderPreset,
+        targets: &ArchiveTargets,
+    ) -> Result<(), CacheError> {
+        // Compiled SPIR-V is keyed exactly like the shader cache, so compile through one.
+        let cache = ShaderCache::in_memory();
+        let compile = |source: &ShaderSource| {
+            cache
+                .compile::<SpirvCompilation>(source)
+                .map(|compiled| compiled.compilation)
+        };
+
+        let (passes, semantics) =
+            SPIRV::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, CacheError>(
+                preset.shaders.clone(),
+                &preset.textures,
+                &PresetCompileOptions {
+                    compiler: Some(&|source, _, _| compile(source)),
+                    ..Default::default()
+                },
+            )?;
+
+        let mut sources = Vec::new();
+        for (index, (_, source, mut reflect)) in passes.into_iter().enumerate() {
+            reflect.reflect(index, &semantics)?;
+            sources.push(source);
+        }
+
+        self.add_outputs::<GLSL, SpirvCross>(&sources, &semantics, &targets.glsl, &compile)?;
+        self.add_outputs::<HLSL, SpirvCross>(&sources, &semantics, &targets.hlsl, &compile)?;
+        self.add_outputs::<MSL, SpirvCross>(&sources, &semantics, &targets.msl, &compile)?;
+        self.add_outputs::<WGSL, Naga>(&sources, &semantics, &targets.wgsl, &compile)?;
+        if targets.spirv {
+            self.add_outputs::<SPIRV, SpirvCross>(&sources, &semantics, &[None], &compile)?;
+        }
+
+        for CacheEntry { index, key, .. } in cache.entries()? {
+            if let Some(value) = cache.get_blob(&index, &key)? {
+                self.entries.insert((index, key), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn add_outputs<T, R>(
+        &mut self,
+        sources: &[ShaderSource],
+        semantics: &ShaderSemantics,
+        options: &[T::Options],
+        compile: &dyn Fn(&ShaderSource) -> Result<SpirvCompilation, ShaderCompileError>,
+    ) -> Result<(), CacheError>
+    where
+        T: FromCompilation<SpirvCompilation, R>,
+        T::Target: CacheableOutput,
+        T::Options: Clone + OutputOptionsKey,
+        <T::Target as OutputTarget>::Output: Serialize,
+    {
+        for (index, source) in sources.iter().enumerate() {
+            for option in options {
+                // Outputs with options that can not be keyed could never be loaded.
+                let Some(key) = output_key::<T::Target>(source, &Defines::default(), None, option)
+                else {
+                    continue;
+                };
+                // Reflection prepares the compiler for the target like the runtimes do.
+                let mut compiler = T::from_compilation(compile(source)?)?;
+                compiler.reflect(index, semantics)?;
+                let output = CompileShader::<T::Target>::compile(compiler, option.clone())?;
+                let value = encode_output(&output.vertex, &output.fragment)
+                    .ok_or(CacheError::InvalidArchive)?;
+                self.entries
+                    .insert((T::Target::INDEX.to_string(), key), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_output<T: CacheableOutput>(
+        &self,
+        source: &ShaderSource,
+        options: &impl OutputOptionsKey,
+    ) -> Option<ShaderCompilerOutput<T::Output>>
+    where
+        T::Output: DeserializeOwned,
+    {
+        let key = output_key::<T>(source, &Defines::default(), None, options)?;
+        decode_output(self.entries.get(&(T::INDEX.to_string(), key))?)
+    }
+
+    /// Get the archived GLSL for the shader source.
+    pub fn glsl(
+        &self,
+        source: &ShaderSource,
+        version: GlslVersion,
+    ) -> Option<ShaderCompilerOutput<String>> {
+        self.get_output::<GLSL>(source, &version)
+    }
+
+    /// Get the archived HLSL for the shader source.
+    pub fn hlsl(
+        &self,
+        source: &ShaderSource,
+        shader_model: Option<HlslShaderModel>,
+    ) -> Option<ShaderCompilerOutput<String>> {
+        self.get_output::<HLSL>(source, &shader_model)
+    }
+
+    /// Get the archived MSL for the shader source.
+    pub fn msl(
+        &self,
+        source: &ShaderSource,
+        version: Option<MslVersion>,
+    ) -> Option<ShaderCompilerOutput<String>> {
+        self.get_output::<MSL>(source, &version)
+    }
+
+    /// Get the archived WGSL for the shader source.
+    pub fn wgsl(
+        &self,
+        source: &ShaderSource,
+        options: &NagaLoweringOptions,
+    ) -> Option<ShaderCompilerOutput<String>> {
+        self.get_output::<WGSL>(source, options)
+    }
+
+    /// Get the archived SPIR-V for the shader source.
+    pub fn spirv(&self, source: &ShaderSource) -> Option<ShaderCompilerOutput<Vec<u32>>> {
+        self.get_output::<SPIRV>(source, &None::<()>)
+    }
+}
+
+/// A cache backend that loads entries from an archive before the backend it wraps.
+struct ArchivedBackend {
+    archive: ShaderArchive,
+    backend: Arc<dyn CacheBackend>,
+}
+
+impl CacheBackend for ArchivedBackend {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheError> {
+        if let Some(value) = self.archive.entries.get(&(index.to_string(), key.to_vec())) {
+            return Ok(Some(value.clone()));
+        }
+        self.backend.get(index, key)
+    }
+
+    fn put(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheError> {
+        self.backend.put(index, key, value)
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        self.backend.entries()
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<(), CacheError> {
+        self.backend.remove(index, key)
+    }
+
+    fn clear(&self) -> Result<(), CacheError> {
+        self.backend.clear()
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        self.backend.directory()
+    }
+}
+
+impl ShaderCache {
+    /// Get a handle to this cache that loads compiled shaders from the archive first.
+    ///
+    /// Shaders that are not in the archive are loaded from, and stored in this cache.
+    /// Statistics, eviction and clearing only apply to this cache, not the archive.
+    pub fn with_archive(&self, archive: ShaderArchive) -> ShaderCache {
+        ShaderCache {
+            backend: Arc::new(ArchivedBackend {
+                archive,
+                backend: Arc::clone(&self.backend),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ArchiveTargets, CacheError, ShaderArchive, ShaderCache};
+    use librashader_preprocess::ShaderSource;
+    use librashader_presets::ShaderPreset;
+    use librashader_reflect::back::glsl::GlslVersion;
+    use librashader_reflect::front::SpirvCompilation;
+
+    #[test]
+    pub fn precompiles_preset() {
+        let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+        let mut archive = ShaderArchive::new();
+        archive
+            .add_preset(
+                &preset,
+                &ArchiveTargets {
+                    glsl: vec![GlslVersion::V3_30],
+                    spirv: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let archive = ShaderArchive::from_bytes(&archive.to_bytes()).unwrap();
+        let source = ShaderSource::load(&preset.shaders[0].name).unwrap();
+        assert!(archive.glsl(&source, GlslVersion::V3_30).is_some());
+        assert!(archive.glsl(&source, GlslVersion::V4_60).is_none());
+        assert!(archive.spirv(&source).is_some());
+        assert!(archive.hlsl(&source, None).is_none());
+
+        // Compilations in the archive are not stored in the cache.
+        let cache = ShaderCache::in_memory().with_archive(archive);
+        cache.compile::<SpirvCompilation>(&source).unwrap();
+        assert_eq!(cache.stats().unwrap().entries(), 0);
+    }
+
+    #[test]
+    pub fn rejects_invalid_archives() {
+        assert!(matches!(
+            ShaderArchive::from_bytes(b"not an archive"),
+            Err(CacheError::InvalidArchive)
+        ));
+
+        let mut bytes = super::ARCHIVE_MAGIC.to_vec();
+        let contents: super::ArchiveContents = (String::from("0/0.0.0"), Vec::new());
+        bytes
+            .extend(bincode::serde::encode_to_vec(&contents, bincode::config::standard()).unwrap());
+        assert!(matches!(
+            ShaderArchive::from_bytes(&bytes),
+            Err(CacheError::IncompatibleArchive(_))
+        ));
+    }
+}
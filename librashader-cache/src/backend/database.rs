@@ -0,0 +1,186 @@
+use crate::backend::{AccessClock, CacheBackend, CacheEntry};
+use crate::error::CacheError;
+use persy::{ByteVec, Config, Persy, ValueMode};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The name of the database file in the cache directory.
+const DATABASE_FILE: &str = "librashader.db.1";
+
+/// The suffix of the index that tracks when each entry of a cache index was last used.
+const LAST_USED_SUFFIX: &str = ".last_used";
+
+fn last_used_index(index: &str) -> String {
+    format!("{index}{LAST_USED_SUFFIX}")
+}
+
+/// A cache backend that stores entries in a [Persy](https://persy.rs) database.
+///
+/// This is the default cache backend.
+pub struct PersyBackend {
+    directory: PathBuf,
+    db: Persy,
+    clock: AccessClock,
+    /// Entries that were read since the last flush, with the time they were read.
+    accessed: Mutex<Vec<(String, Vec<u8>, u64)>>,
+}
+
+impl PersyBackend {
+    /// Open the database in the given directory, creating it if it does not exist.
+    pub fn open(directory: impl AsRef<Path>) -> Result<PersyBackend, CacheError> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)
+            .map_err(|e| CacheError::IOError(directory.to_path_buf(), e))?;
+
+        let path = directory.join(DATABASE_FILE);
+        let db = match Persy::open_or_create_with(&path, Config::new(), |persy| {
+            let tx = persy.begin()?;
+            tx.commit()?;
+            Ok(())
+        }) {
+            Ok(db) => db,
+            Err(e) => {
+                // The database is likely corrupt, so remove it to try again next time.
+                let _ = std::fs::remove_file(&path);
+                return Err(e.into());
+            }
+        };
+
+        Ok(PersyBackend {
+            directory: directory.to_path_buf(),
+            db,
+            clock: AccessClock::default(),
+            accessed: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Write the access times of entries read since the last flush.
+    fn flush(&self) -> Result<(), CacheError> {
+        let accessed = std::mem::take(&mut *self.accessed.lock().unwrap());
+        if accessed.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.db.begin()?;
+        for (index, key, time) in accessed {
+            let last_used = last_used_index(&index);
+            if !tx.exists_index(&last_used)? {
+                tx.create_index::<ByteVec, u64>(&last_used, ValueMode::Replace)?;
+            }
+            tx.put(&last_used, ByteVec::from(key), time)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get the names of the indices that hold cache entries.
+    fn indices(&self) -> Result<Vec<String>, CacheError> {
+        Ok(self
+            .db
+            .list_indexes()?
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| !name.ends_with(LAST_USED_SUFFIX))
+            .collect())
+    }
+}
+
+impl Drop for PersyBackend {
+    fn drop(&mut self) {
+        // Losing access times only affects the order entries are evicted in.
+        let _ = self.flush();
+    }
+}
+
+impl CacheBackend for PersyBackend {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheError> {
+        if !self.db.exists_index(index)? {
+            return Ok(None);
+        }
+
+        let value = self
+            .db
+            .get::<_, ByteVec>(index, &ByteVec::from(key))?
+            .next();
+        if value.is_some() {
+            // Access times are written in batches, rather than once per read.
+            let time = self.clock.tick();
+            self.accessed
+                .lock()
+                .unwrap()
+                .push((index.to_string(), key.to_vec(), time));
+        }
+        Ok(value.map(|v| v.to_vec()))
+    }
+
+    fn put(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheError> {
+        let last_used = last_used_index(index);
+        let mut tx = self.db.begin()?;
+        if !tx.exists_index(index)? {
+            tx.create_index::<ByteVec, ByteVec>(index, ValueMode::Replace)?;
+        }
+        if !tx.exists_index(&last_used)? {
+            tx.create_index::<ByteVec, u64>(&last_used, ValueMode::Replace)?;
+        }
+
+        tx.put(index, ByteVec::from(key), ByteVec::from(value))?;
+        tx.put(&last_used, ByteVec::from(key), self.clock.tick())?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        self.flush()?;
+
+        let mut entries = Vec::new();
+        for index in self.indices()? {
+            let last_used = last_used_index(&index);
+            let has_last_used = self.db.exists_index(&last_used)?;
+            for (key, values) in self.db.range::<ByteVec, ByteVec, _>(&index, ..)? {
+                let bytes = values.map(|value| value.len() as u64).sum();
+                // Entries without an access time are from an older version.
+                let last_used = if has_last_used {
+                    self.db.get::<_, u64>(&last_used, &key)?.next().unwrap_or(0)
+                } else {
+                    0
+                };
+                entries.push(CacheEntry {
+                    index: index.clone(),
+                    key: key.to_vec(),
+                    bytes,
+                    last_used,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<(), CacheError> {
+        let last_used = last_used_index(index);
+        let key = ByteVec::from(key);
+        let mut tx = self.db.begin()?;
+        if tx.exists_index(index)? {
+            tx.remove::<ByteVec, ByteVec>(index, key.clone(), None)?;
+        }
+        if tx.exists_index(&last_used)? {
+            tx.remove::<ByteVec, u64>(&last_used, key, None)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), CacheError> {
+        self.accessed.lock().unwrap().clear();
+        let mut tx = self.db.begin()?;
+        for (name, _) in tx.list_indexes()? {
+            tx.drop_index(&name)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        Some(&self.directory)
+    }
+}
@@ -0,0 +1,300 @@
+use crate::backend::{CacheBackend, CacheEntry};
+use crate::error::CacheError;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A cache backend that stores each entry as a file on disk.
+///
+/// Entries are stored as `<directory>/<index>/<key>`, where the key is hex encoded. The
+/// modification time of each file is used as the time the entry was last used.
+pub struct FilesBackend {
+    directory: PathBuf,
+    /// Counter used to name temporary files, so concurrent writes do not clobber each other.
+    writes: AtomicU64,
+    read_only: bool,
+}
+
+impl FilesBackend {
+    /// Use the given directory to store entries, creating it if it does not exist.
+    pub fn open(directory: impl AsRef<Path>) -> Result<FilesBackend, CacheError> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)
+            .map_err(|e| CacheError::IOError(directory.to_path_buf(), e))?;
+        Ok(FilesBackend {
+            directory: directory.to_path_buf(),
+            writes: AtomicU64::new(0),
+            read_only: false,
+        })
+    }
+
+    /// Use the entries in the given directory without ever writing to it, such as a cache
+    /// shipped on a read-only filesystem.
+    ///
+    /// The directory does not need to exist. Access times of entries are not updated.
+    pub fn open_read_only(directory: impl AsRef<Path>) -> FilesBackend {
+        FilesBackend {
+            directory: directory.as_ref().to_path_buf(),
+            writes: AtomicU64::new(0),
+            read_only: true,
+        }
+    }
+
+    fn check_writable(&self) -> Result<(), CacheError> {
+        if self.read_only {
+            return Err(CacheError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    fn index_dir(&self, index: &str) -> Result<PathBuf, CacheError> {
+        if !is_valid_index(index) {
+            return Err(CacheError::InvalidIndex(index.to_string()));
+        }
+        Ok(self.directory.join(index))
+    }
+
+    fn entry_path(&self, index: &str, key: &[u8]) -> Result<PathBuf, CacheError> {
+        Ok(self.index_dir(index)?.join(encode_hex(key)))
+    }
+
+    /// Get the names of the indices that hold cache entries.
+    fn indices(&self) -> Result<Vec<String>, CacheError> {
+        let mut indices = Vec::new();
+        for entry in read_dir(&self.directory)? {
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            match entry.file_name().to_str() {
+                Some(name) if is_valid_index(name) => indices.push(name.to_string()),
+                _ => {}
+            }
+        }
+        Ok(indices)
+    }
+}
+
+/// Index names are used as directory names, so only allow a conservative set of characters.
+fn is_valid_index(index: &str) -> bool {
+    !index.is_empty()
+        && index
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Read the entries of a directory, treating a missing directory as empty.
+fn read_dir(path: &Path) -> Result<Vec<std::fs::DirEntry>, CacheError> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CacheError::IOError(path.to_path_buf(), e)),
+    };
+    entries
+        .collect::<Result<_, _>>()
+        .map_err(|e| CacheError::IOError(path.to_path_buf(), e))
+}
+
+impl CacheBackend for FilesBackend {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheError> {
+        let path = self.entry_path(index, key)?;
+        let value = match std::fs::read(&path) {
+            Ok(value) => value,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(CacheError::IOError(path, e)),
+        };
+
+        // The cache may be on a read-only filesystem, in which case access times are not updated.
+        if !self.read_only {
+            if let Ok(file) = File::options().write(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+        }
+        Ok(Some(value))
+    }
+
+    fn put(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheError> {
+        self.check_writable()?;
+        let directory = self.index_dir(index)?;
+        std::fs::create_dir_all(&directory).map_err(|e| CacheError::IOError(directory, e))?;
+
+        // Write to a temporary file first, so readers never see a partially written entry.
+        let path = self.entry_path(index, key)?;
+        let temporary = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            self.writes.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&temporary, value).map_err(|e| CacheError::IOError(temporary.clone(), e))?;
+        std::fs::rename(&temporary, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&temporary);
+            CacheError::IOError(path, e)
+        })
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        let mut entries = Vec::new();
+        for index in self.indices()? {
+            for file in read_dir(&self.directory.join(&index))? {
+                // Skip temporary files, and anything else that is not an entry.
+                let Some(key) = file.file_name().to_str().and_then(decode_hex) else {
+                    continue;
+                };
+                let Ok(metadata) = file.metadata() else {
+                    continue;
+                };
+                let last_used = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_nanos() as u64);
+                entries.push(CacheEntry {
+                    index: index.clone(),
+                    key,
+                    bytes: metadata.len(),
+                    last_used,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<(), CacheError> {
+        self.check_writable()?;
+        let path = self.entry_path(index, key)?;
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(CacheError::IOError(path, e)),
+        }
+    }
+
+    fn clear(&self) -> Result<(), CacheError> {
+        self.check_writable()?;
+        // Only remove entries, in case the directory is shared with other files.
+        for entry in self.entries()? {
+            self.remove(&entry.index, &entry.key)?;
+        }
+        for index in self.indices()? {
+            // Index directories that still hold other files are kept.
+            let _ = std::fs::remove_dir(self.directory.join(index));
+        }
+        Ok(())
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        Some(&self.directory)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FilesBackend;
+    use crate::{CacheBackend, CacheError};
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "librashader-cache-test-backend-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    pub fn reads_and_writes_entries() {
+        let directory = temp_dir("files");
+        let backend = FilesBackend::open(&directory).unwrap();
+        backend.put("spirv", b"a", &[1, 2, 3]).unwrap();
+        assert_eq!(backend.get("spirv", b"a").unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(backend.get("spirv", b"b").unwrap(), None);
+
+        let entries = backend.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, b"a");
+        assert_eq!(entries[0].bytes, 3);
+
+        backend.remove("spirv", b"a").unwrap();
+        assert_eq!(backend.get("spirv", b"a").unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn clear_keeps_other_files() {
+        let directory = temp_dir("clear");
+        let backend = FilesBackend::open(&directory).unwrap();
+        backend.put("spirv", b"a", &[0; 4]).unwrap();
+        backend.put("dxil", b"b", &[0; 4]).unwrap();
+        std::fs::write(directory.join("spirv").join("notes.txt"), b"keep").unwrap();
+        std::fs::write(directory.join("settings.cfg"), b"keep").unwrap();
+
+        backend.clear().unwrap();
+        assert!(backend.entries().unwrap().is_empty());
+        assert!(directory.join("spirv").join("notes.txt").is_file());
+        assert!(directory.join("settings.cfg").is_file());
+        assert!(!directory.join("dxil").exists());
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn read_only_does_not_write() {
+        let directory = temp_dir("read-only");
+        FilesBackend::open(&directory)
+            .unwrap()
+            .put("spirv", b"a", &[1, 2, 3])
+            .unwrap();
+        let path = directory.join("spirv").join("61");
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        let backend = FilesBackend::open_read_only(&directory);
+        assert!(backend.is_read_only());
+        assert_eq!(backend.get("spirv", b"a").unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().modified().unwrap(),
+            modified
+        );
+        assert!(matches!(
+            backend.put("spirv", b"b", &[]),
+            Err(CacheError::ReadOnly)
+        ));
+        assert!(matches!(
+            backend.remove("spirv", b"a"),
+            Err(CacheError::ReadOnly)
+        ));
+        assert!(matches!(backend.clear(), Err(CacheError::ReadOnly)));
+        assert_eq!(backend.entries().unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn read_only_allows_missing_directory() {
+        let directory = temp_dir("read-only-missing");
+        let backend = FilesBackend::open_read_only(&directory);
+        assert_eq!(backend.get("spirv", b"a").unwrap(), None);
+        assert!(backend.entries().unwrap().is_empty());
+        assert!(!directory.exists());
+    }
+}
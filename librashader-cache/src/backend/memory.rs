@@ -0,0 +1,117 @@
+use crate::backend::{AccessClock, CacheBackend, CacheEntry};
+use crate::error::CacheError;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+struct MemoryValue {
+    value: Vec<u8>,
+    last_used: u64,
+}
+
+/// A cache backend that keeps entries in memory.
+///
+/// Entries are lost when the backend is dropped. This is useful where the filesystem is
+/// read-only or sandboxed, and for testing.
+#[derive(Default)]
+pub struct MemoryBackend {
+    entries: Mutex<BTreeMap<(String, Vec<u8>), MemoryValue>>,
+    clock: AccessClock,
+}
+
+impl MemoryBackend {
+    /// Create a new, empty in-memory cache backend.
+    pub fn new() -> MemoryBackend {
+        MemoryBackend::default()
+    }
+}
+
+impl CacheBackend for MemoryBackend {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheError> {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(&(index.to_string(), key.to_vec())) else {
+            return Ok(None);
+        };
+        entry.last_used = self.clock.tick();
+        Ok(Some(entry.value.clone()))
+    }
+
+    fn put(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheError> {
+        self.entries.lock().unwrap().insert(
+            (index.to_string(), key.to_vec()),
+            MemoryValue {
+                value: value.to_vec(),
+                last_used: self.clock.tick(),
+            },
+        );
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((index, key), value)| CacheEntry {
+                index: index.clone(),
+                key: key.clone(),
+                bytes: value.value.len() as u64,
+                last_used: value.last_used,
+            })
+            .collect())
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<(), CacheError> {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(&(index.to_string(), key.to_vec()));
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), CacheError> {
+        self.entries.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MemoryBackend;
+    use crate::CacheBackend;
+
+    #[test]
+    pub fn reads_and_writes_entries() {
+        let backend = MemoryBackend::new();
+        assert!(!backend.is_read_only());
+        backend.put("spirv", b"a", &[1, 2, 3]).unwrap();
+        backend.put("dxil", b"a", &[4]).unwrap();
+        assert_eq!(backend.get("spirv", b"a").unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(backend.get("spirv", b"b").unwrap(), None);
+        assert_eq!(backend.entries().unwrap().len(), 2);
+
+        backend.remove("spirv", b"a").unwrap();
+        assert_eq!(backend.get("spirv", b"a").unwrap(), None);
+
+        backend.clear().unwrap();
+        assert!(backend.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn tracks_last_use() {
+        let backend = MemoryBackend::new();
+        backend.put("spirv", b"a", &[]).unwrap();
+        backend.put("spirv", b"b", &[]).unwrap();
+        backend.get("spirv", b"a").unwrap();
+
+        let entries = backend.entries().unwrap();
+        let last_used = |key: &[u8]| {
+            entries
+                .iter()
+                .find(|entry| entry.key == key)
+                .unwrap()
+                .last_used
+        };
+        assert!(last_used(b"a") > last_used(b"b"));
+    }
+}
@@ -0,0 +1,88 @@
+//! Storage backends for the shader cache.
+mod database;
+mod files;
+mod memory;
+
+pub use database::PersyBackend;
+pub use files::FilesBackend;
+pub use memory::MemoryBackend;
+
+use crate::error::CacheError;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An entry stored in a cache backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The index the entry is stored in, such as `spirv` or `dxil`.
+    pub index: String,
+    /// The key of the entry.
+    pub key: Vec<u8>,
+    /// The size of the value in bytes.
+    pub bytes: u64,
+    /// When the entry was last read or written, in nanoseconds since the Unix epoch.
+    ///
+    /// Entries with an unknown access time report `0`, and are evicted first.
+    pub last_used: u64,
+}
+
+/// Storage for cached shader objects and pipelines.
+///
+/// Values are stored in named indices, such as `spirv` or `dxil`, and looked up by key.
+/// Implementations must update the access time of an entry whenever it is read or written,
+/// so that the least recently used entries can be evicted.
+pub trait CacheBackend: Send + Sync {
+    /// Get the value stored for the key in the index, if any.
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheError>;
+
+    /// Store the value for the key in the index, replacing any existing value.
+    fn put(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheError>;
+
+    /// List every entry stored in the backend.
+    fn entries(&self) -> Result<Vec<CacheEntry>, CacheError>;
+
+    /// Remove the value stored for the key in the index, if any.
+    fn remove(&self, index: &str, key: &[u8]) -> Result<(), CacheError>;
+
+    /// Remove every entry stored in the backend.
+    fn clear(&self) -> Result<(), CacheError>;
+
+    /// The directory the backend stores entries in, if it is stored on disk.
+    fn directory(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Whether the backend can only be read from.
+    ///
+    /// Writes to a read-only backend fail with [`CacheError::ReadOnly`], and the version of
+    /// librashader is not recorded in it when it is opened.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+}
+
+/// A clock that hands out strictly increasing access times.
+#[derive(Debug, Default)]
+pub(crate) struct AccessClock {
+    /// The most recent access time handed out.
+    previous: AtomicU64,
+}
+
+impl AccessClock {
+    /// Get a timestamp for an access, in nanoseconds since the Unix epoch.
+    pub(crate) fn tick(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        // Access times are strictly increasing, even if the clock did not advance.
+        let next = |previous: u64| now.max(previous + 1);
+        let previous = self
+            .previous
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |previous| {
+                Some(next(previous))
+            })
+            .unwrap_or_else(|previous| previous);
+        next(previous)
+    }
+}
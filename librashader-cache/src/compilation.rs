@@ -1,70 +1,137 @@
 //!  Cache helpers for `ShaderCompilation` objects to cache compiled SPIRV.
-use librashader_preprocess::ShaderSource;
+use crate::key::{key_hasher, update_compile_options, update_part};
+use crate::ShaderCache;
+use librashader_preprocess::{Defines, ShaderSource};
 #[cfg(all(target_os = "windows", feature = "d3d"))]
 use librashader_reflect::back::targets::DXIL;
-use librashader_reflect::back::targets::{GLSL, HLSL, SPIRV};
+use librashader_reflect::back::targets::{ESSL, GLSL, HLSL, MSL, SPIRV, WGSL};
 
 use librashader_reflect::back::{CompilerBackend, FromCompilation};
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_reflect::front::{
-    Glslang, ShaderInputCompiler, ShaderReflectObject, SpirvCompilation,
+    Glslang, ShaderInputCompiler, ShaderReflectObject, SpirvCompilation, SpirvOptimization,
 };
 
 pub struct CachedCompilation<T> {
-    compilation: T,
+    pub(crate) compilation: T,
 }
 
 impl<T: ShaderReflectObject> ShaderReflectObject for CachedCompilation<T> {
     type Compiler = T::Compiler;
 }
 
-impl<T: ShaderReflectObject + for<'de> serde::Deserialize<'de> + serde::Serialize + Clone>
-    ShaderInputCompiler<CachedCompilation<T>> for Glslang
-where
-    Glslang: ShaderInputCompiler<T>,
+/// Trait for shader compilations that can be stored in the shader cache.
+pub trait CacheableCompilation:
+    ShaderReflectObject + for<'de> serde::Deserialize<'de> + serde::Serialize + Clone
 {
-    fn compile(source: &ShaderSource) -> Result<CachedCompilation<T>, ShaderCompileError> {
-        let cache = crate::cache::internal::get_cache();
+    /// The tag that identifies the compiler and the layout of the compilation in cache keys.
+    ///
+    /// Tags must be unique, and must change whenever the layout of the compilation changes.
+    const TAG: &'static str;
+
+    /// Compile the shader source, optimizing the compilation if optimizations are given.
+    fn compile_optimized(
+        source: &ShaderSource,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<Self, ShaderCompileError>;
+}
+
+impl CacheableCompilation for SpirvCompilation {
+    const TAG: &'static str = "spirv-glslang";
+
+    fn compile_optimized(
+        source: &ShaderSource,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<Self, ShaderCompileError> {
+        SpirvCompilation::compile_with_options(source, optimization)
+    }
+}
 
-        let Ok(cache) = cache else {
+/// Compile the shader source with the default shader cache.
+///
+/// Use [`ShaderCache::compile`] to compile with a specific cache.
+impl<T: CacheableCompilation> ShaderInputCompiler<CachedCompilation<T>> for Glslang {
+    fn compile(source: &ShaderSource) -> Result<CachedCompilation<T>, ShaderCompileError> {
+        let Ok(cache) = ShaderCache::open_default() else {
             return Ok(CachedCompilation {
-                compilation: Glslang::compile(source)?,
+                compilation: T::compile_optimized(source, None)?,
             });
         };
 
-        let key = {
-            let mut hasher = blake3::Hasher::new();
-            hasher.update(source.vertex.as_bytes());
-            hasher.update(source.fragment.as_bytes());
-            let hash = hasher.finalize();
-            hash
+        cache.compile(source)
+    }
+
+    fn compile_with_options(
+        source: &ShaderSource,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<CachedCompilation<T>, ShaderCompileError> {
+        let Ok(cache) = ShaderCache::open_default() else {
+            return Ok(CachedCompilation {
+                compilation: T::compile_optimized(source, optimization)?,
+            });
         };
 
-        let compilation = 'cached: {
-            if let Ok(Some(cached)) =
-                crate::cache::internal::get_blob(&cache, "spirv", key.as_bytes())
-            {
-                let decoded =
-                    bincode::serde::decode_from_slice(&cached, bincode::config::standard())
-                        .map(|(compilation, _)| CachedCompilation { compilation })
-                        .ok();
-
-                if let Some(compilation) = decoded {
-                    break 'cached compilation;
-                }
-            }
+        cache.compile_with_options(source, &Defines::default(), optimization)
+    }
+}
 
-            CachedCompilation {
-                compilation: Glslang::compile(source)?,
+/// Get the key of the compilation of the shader source with the given options in the
+/// `spirv` index.
+pub(crate) fn compilation_key<T: CacheableCompilation>(
+    source: &ShaderSource,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+) -> blake3::Hash {
+    let mut hasher = key_hasher("spirv");
+    for part in [T::TAG, source.vertex.as_str(), source.fragment.as_str()] {
+        update_part(&mut hasher, part.as_bytes());
+    }
+    update_compile_options(&mut hasher, defines, optimization);
+    hasher.finalize()
+}
+
+impl ShaderCache {
+    /// Compile the shader source with glslang, or load the compilation from the cache
+    /// if available.
+    pub fn compile<T: CacheableCompilation>(
+        &self,
+        source: &ShaderSource,
+    ) -> Result<CachedCompilation<T>, ShaderCompileError> {
+        self.compile_with_options(source, &Defines::default(), None)
+    }
+
+    /// Compile the shader source with glslang and the given options, or load the compilation
+    /// from the cache if available.
+    ///
+    /// - `defines` are the defines the shader source was loaded with.
+    /// - `optimization` are the optimizations to apply to the compiled shader, if any.
+    pub fn compile_with_options<T: CacheableCompilation>(
+        &self,
+        source: &ShaderSource,
+        defines: &Defines,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<CachedCompilation<T>, ShaderCompileError> {
+        let key = compilation_key::<T>(source, defines, optimization);
+
+        if let Ok(Some(cached)) = self.get_blob("spirv", key.as_bytes()) {
+            let decoded = bincode::serde::decode_from_slice(&cached, bincode::config::standard())
+                .map(|(compilation, _)| CachedCompilation { compilation })
+                .ok();
+
+            // Reading the entry already marks it as used, so it does not need to be rewritten.
+            if let Some(compilation) = decoded {
+                return Ok(compilation);
             }
+        }
+
+        let compilation = CachedCompilation {
+            compilation: T::compile_optimized(source, optimization)?,
         };
 
         if let Ok(updated) =
             bincode::serde::encode_to_vec(&compilation.compilation, bincode::config::standard())
         {
-            let Ok(()) =
-                crate::cache::internal::set_blob(&cache, "spirv", key.as_bytes(), &updated)
-            else {
+            let Ok(()) = self.set_blob("spirv", key.as_bytes(), &updated) else {
                 return Ok(compilation);
             };
         }
@@ -106,6 +173,22 @@ where
     }
 }
 
+impl<T> FromCompilation<CachedCompilation<SpirvCompilation>, T> for ESSL
+where
+    ESSL: FromCompilation<SpirvCompilation, T>,
+{
+    type Target = <ESSL as FromCompilation<SpirvCompilation, T>>::Target;
+    type Options = <ESSL as FromCompilation<SpirvCompilation, T>>::Options;
+    type Context = <ESSL as FromCompilation<SpirvCompilation, T>>::Context;
+    type Output = <ESSL as FromCompilation<SpirvCompilation, T>>::Output;
+
+    fn from_compilation(
+        compile: CachedCompilation<SpirvCompilation>,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        ESSL::from_compilation(compile.compilation)
+    }
+}
+
 impl<T> FromCompilation<CachedCompilation<SpirvCompilation>, T> for GLSL
 where
     GLSL: FromCompilation<SpirvCompilation, T>,
@@ -137,3 +220,86 @@ where
         SPIRV::from_compilation(compile.compilation)
     }
 }
+
+impl<T> FromCompilation<CachedCompilation<SpirvCompilation>, T> for MSL
+where
+    MSL: FromCompilation<SpirvCompilation, T>,
+{
+    type Target = <MSL as FromCompilation<SpirvCompilation, T>>::Target;
+    type Options = <MSL as FromCompilation<SpirvCompilation, T>>::Options;
+    type Context = <MSL as FromCompilation<SpirvCompilation, T>>::Context;
+    type Output = <MSL as FromCompilation<SpirvCompilation, T>>::Output;
+
+    fn from_compilation(
+        compile: CachedCompilation<SpirvCompilation>,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        MSL::from_compilation(compile.compilation)
+    }
+}
+
+impl<T> FromCompilation<CachedCompilation<SpirvCompilation>, T> for WGSL
+where
+    WGSL: FromCompilation<SpirvCompilation, T>,
+{
+    type Target = <WGSL as FromCompilation<SpirvCompilation, T>>::Target;
+    type Options = <WGSL as FromCompilation<SpirvCompilation, T>>::Options;
+    type Context = <WGSL as FromCompilation<SpirvCompilation, T>>::Context;
+    type Output = <WGSL as FromCompilation<SpirvCompilation, T>>::Output;
+
+    fn from_compilation(
+        compile: CachedCompilation<SpirvCompilation>,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        WGSL::from_compilation(compile.compilation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::compilation::compilation_key;
+    use librashader_preprocess::{Defines, ShaderSource};
+    use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
+
+    #[test]
+    pub fn keys_compile_options() {
+        let source = ShaderSource::load("../test/basic.slang").unwrap();
+        let key = |defines: &Defines, optimization: Option<&SpirvOptimization>| {
+            compilation_key::<SpirvCompilation>(&source, defines, optimization)
+        };
+
+        let defaults = key(&Defines::default(), None);
+        assert_eq!(defaults, key(&Defines::default(), None));
+
+        let defines = Defines::from_iter([("QUALITY", "2")]);
+        assert_ne!(defaults, key(&defines, None));
+
+        let all = SpirvOptimization::all();
+        assert_ne!(defaults, key(&Defines::default(), Some(&all)));
+        assert_ne!(
+            key(&Defines::default(), Some(&SpirvOptimization::default())),
+            key(&Defines::default(), Some(&all))
+        );
+
+        // Folded parameters are keyed in a stable order.
+        let fold = |names: &[&str]| {
+            let mut optimization = SpirvOptimization::all();
+            for name in names {
+                optimization
+                    .constant_parameters
+                    .insert(name.to_string(), name.len() as f32);
+            }
+            optimization
+        };
+        let folded = fold(&["A", "BB", "CCC", "DDDD"]);
+        assert_eq!(
+            key(&Defines::default(), Some(&folded)),
+            key(
+                &Defines::default(),
+                Some(&fold(&["DDDD", "CCC", "BB", "A"]))
+            )
+        );
+        assert_ne!(
+            key(&Defines::default(), Some(&all)),
+            key(&Defines::default(), Some(&folded))
+        );
+    }
+}
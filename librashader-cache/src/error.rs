@@ -0,0 +1,43 @@
+use librashader_preprocess::PreprocessError;
+use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
+use persy::{PersyError, PE};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Error type for shader cache management.
+#[derive(Error, Debug)]
+pub enum CacheError {
+    /// An IO error occurred when accessing the cache directory.
+    #[error("could not access the cache at {0}")]
+    IOError(PathBuf, #[source] std::io::Error),
+    /// The index name can not be used with the cache backend.
+    #[error("invalid cache index name {0}")]
+    InvalidIndex(String),
+    /// The cache backend is read-only, and can not be written to.
+    #[error("the cache is read-only")]
+    ReadOnly,
+    /// An error occurred in the cache database.
+    #[error("cache database error")]
+    DatabaseError(#[from] PersyError),
+    /// The shader archive is malformed.
+    #[error("invalid shader archive")]
+    InvalidArchive,
+    /// The shader archive was created by a different version of librashader.
+    #[error("shader archive was created by an incompatible version of librashader ({0})")]
+    IncompatibleArchive(String),
+    /// A shader could not be preprocessed when building a shader archive.
+    #[error("shader preprocess error")]
+    ShaderPreprocessError(#[from] PreprocessError),
+    /// A shader could not be compiled when building a shader archive.
+    #[error("shader compile error")]
+    ShaderCompileError(#[from] ShaderCompileError),
+    /// A shader could not be reflected when building a shader archive.
+    #[error("shader reflect error")]
+    ShaderReflectError(#[from] ShaderReflectError),
+}
+
+impl<T: Into<PersyError>> From<PE<T>> for CacheError {
+    fn from(value: PE<T>) -> Self {
+        CacheError::DatabaseError(value.persy_error())
+    }
+}
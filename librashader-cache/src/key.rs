@@ -1,3 +1,10 @@
+use librashader_preprocess::Defines;
+use librashader_reflect::back::glsl::GlslVersion;
+use librashader_reflect::back::hlsl::HlslShaderModel;
+use librashader_reflect::back::msl::MslVersion;
+use librashader_reflect::front::{SpirvOptimization, GLSLANG_VERSION};
+use librashader_reflect::reflect::naga::NagaLoweringOptions;
+
 /// Trait for objects that can be used as part of a key for a cached object.
 pub trait CacheKey {
     /// Get a byte representation of the object that
@@ -41,3 +48,180 @@ impl CacheKey for &str {
         self.as_bytes()
     }
 }
+
+/// Trait for shader compiler options that are part of the key of a compiled output.
+pub trait OutputOptionsKey {
+    /// Get a stable byte representation of the options that will be fed into the hash.
+    ///
+    /// This must not depend on the `Debug` representation of the options, so that keys do
+    /// not change when the formatting of the options does. Returns `None` if the options
+    /// are not known, in which case outputs compiled with them are not cached.
+    fn key_bytes(&self) -> Option<Vec<u8>>;
+}
+
+impl<T: OutputOptionsKey> OutputOptionsKey for Option<T> {
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            None => Some(vec![0]),
+            Some(options) => {
+                let mut bytes = vec![1];
+                bytes.extend(options.key_bytes()?);
+                Some(bytes)
+            }
+        }
+    }
+}
+
+impl OutputOptionsKey for () {
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        Some(Vec::new())
+    }
+}
+
+impl OutputOptionsKey for GlslVersion {
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        let version = match self {
+            GlslVersion::V1_10 => "110",
+            GlslVersion::V1_20 => "120",
+            GlslVersion::V1_30 => "130",
+            GlslVersion::V1_40 => "140",
+            GlslVersion::V1_50 => "150",
+            GlslVersion::V3_30 => "330",
+            GlslVersion::V4_00 => "400",
+            GlslVersion::V4_10 => "410",
+            GlslVersion::V4_20 => "420",
+            GlslVersion::V4_30 => "430",
+            GlslVersion::V4_40 => "440",
+            GlslVersion::V4_50 => "450",
+            GlslVersion::V4_60 => "460",
+            GlslVersion::V1_00Es => "100es",
+            GlslVersion::V3_00Es => "300es",
+            GlslVersion::V3_10Es => "310es",
+            GlslVersion::V3_20Es => "320es",
+            _ => return None,
+        };
+        Some(version.as_bytes().to_vec())
+    }
+}
+
+impl OutputOptionsKey for HlslShaderModel {
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        let shader_model = match self {
+            HlslShaderModel::V3_0 => "3_0",
+            HlslShaderModel::V4_0 => "4_0",
+            HlslShaderModel::V4_0L9_0 => "4_0_level_9_0",
+            HlslShaderModel::V4_0L9_1 => "4_0_level_9_1",
+            HlslShaderModel::V4_0L9_3 => "4_0_level_9_3",
+            HlslShaderModel::V4_1 => "4_1",
+            HlslShaderModel::V5_0 => "5_0",
+            HlslShaderModel::V5_1 => "5_1",
+            HlslShaderModel::V6_0 => "6_0",
+            _ => return None,
+        };
+        Some(shader_model.as_bytes().to_vec())
+    }
+}
+
+impl OutputOptionsKey for MslVersion {
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        let version = match self {
+            MslVersion::V1_0 => "1.0",
+            MslVersion::V1_1 => "1.1",
+            MslVersion::V1_2 => "1.2",
+            MslVersion::V2_0 => "2.0",
+            MslVersion::V2_1 => "2.1",
+            MslVersion::V2_2 => "2.2",
+            MslVersion::V2_3 => "2.3",
+            _ => return None,
+        };
+        Some(version.as_bytes().to_vec())
+    }
+}
+
+impl OutputOptionsKey for NagaLoweringOptions {
+    fn key_bytes(&self) -> Option<Vec<u8>> {
+        let mut bytes = vec![self.write_pcb_as_ubo as u8];
+        bytes.extend(self.sampler_bind_group.to_le_bytes());
+        Some(bytes)
+    }
+}
+
+/// The version of the cache schema.
+///
+/// This must be bumped whenever the layout of a cached object or the contents of a key change.
+pub(crate) const SCHEMA_VERSION: u32 = 2;
+
+/// A fingerprint of the cache schema, the version of librashader, and the version of glslang.
+///
+/// Cached objects are only compatible with caches that have the same fingerprint.
+pub(crate) fn cache_fingerprint() -> String {
+    format!(
+        "{SCHEMA_VERSION}/{}/glslang-{GLSLANG_VERSION}",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// Feed a part of a key into the hasher, prefixed with its length so that adjacent parts
+/// can not run into each other.
+pub(crate) fn update_part(hasher: &mut blake3::Hasher, part: &[u8]) {
+    hasher.update(&(part.len() as u64).to_le_bytes());
+    hasher.update(part);
+}
+
+/// Create a hasher for a key in the given index.
+///
+/// Every key is seeded with the cache fingerprint, so objects cached by a different version
+/// of librashader are never loaded.
+pub(crate) fn key_hasher(index: &str) -> blake3::Hasher {
+    let mut hasher = blake3::Hasher::new();
+    update_part(&mut hasher, cache_fingerprint().as_bytes());
+    update_part(&mut hasher, index.as_bytes());
+    hasher
+}
+
+/// Feed the options a shader source is compiled with into the hasher.
+///
+/// Each option is written out explicitly, so that keys are stable regardless of how the
+/// options are ordered or formatted.
+pub(crate) fn update_compile_options(
+    hasher: &mut blake3::Hasher,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+) {
+    update_part(hasher, &(defines.iter().count() as u64).to_le_bytes());
+    for (name, value) in defines.iter() {
+        update_part(hasher, name.as_bytes());
+        update_part(hasher, value.as_bytes());
+    }
+
+    update_optimization(hasher, optimization);
+}
+
+/// Feed the optimizations a shader source is compiled with into the hasher.
+pub(crate) fn update_optimization(
+    hasher: &mut blake3::Hasher,
+    optimization: Option<&SpirvOptimization>,
+) {
+    let Some(optimization) = optimization else {
+        update_part(hasher, &[0]);
+        return;
+    };
+
+    update_part(
+        hasher,
+        &[
+            1,
+            optimization.inline_functions as u8,
+            optimization.eliminate_dead_code as u8,
+            optimization.remove_unused_resources as u8,
+        ],
+    );
+
+    let mut parameters: Vec<_> = optimization.constant_parameters.iter().collect();
+    parameters.sort_by(|a, b| a.0.cmp(b.0));
+    update_part(hasher, &(parameters.len() as u64).to_le_bytes());
+    for (name, value) in parameters {
+        update_part(hasher, name.as_bytes());
+        update_part(hasher, &value.to_bits().to_le_bytes());
+    }
+}
@@ -3,20 +3,32 @@
 //! This crate is exempt from semantic versioning guarantees and is an implementation
 //! detail of librashader runtimes.
 
+mod archive;
+mod backend;
 mod cache;
 
 mod compilation;
+mod output;
+mod reflection;
 
 mod cacheable;
+mod error;
 mod key;
+mod shader_cache;
 
+pub use archive::{ArchiveTargets, ShaderArchive};
+pub use backend::{CacheBackend, CacheEntry, FilesBackend, MemoryBackend, PersyBackend};
 pub use cacheable::Cacheable;
-pub use key::CacheKey;
+pub use error::CacheError;
+pub use key::{CacheKey, OutputOptionsKey};
+pub use shader_cache::{CacheStats, EvictionPolicy, IndexStats, ShaderCache};
 
-pub use compilation::CachedCompilation;
+pub use compilation::{CacheableCompilation, CachedCompilation};
 
 pub use cache::cache_pipeline;
 pub use cache::cache_shader_object;
+pub use output::{cache_shader_output, CacheableOutput};
+pub use reflection::cache_reflection;
 
 #[cfg(all(target_os = "windows", feature = "d3d"))]
 mod d3d;
@@ -0,0 +1,220 @@
+//! Cache helpers for the compiled outputs of shader passes.
+use crate::compilation::compilation_key;
+use crate::key::{key_hasher, update_part, OutputOptionsKey};
+use crate::ShaderCache;
+use librashader_preprocess::{Defines, ShaderSource};
+use librashader_reflect::back::targets::{OutputTarget, GLSL, HLSL, MSL, SPIRV, WGSL};
+use librashader_reflect::back::{CompileShader, ShaderCompilerOutput};
+use librashader_reflect::error::ShaderCompileError;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Trait for shader compiler targets whose outputs can be stored in the shader cache.
+pub trait CacheableOutput: OutputTarget {
+    /// The index outputs of the target are stored in.
+    const INDEX: &'static str;
+}
+
+impl CacheableOutput for GLSL {
+    const INDEX: &'static str = "glsl";
+}
+
+impl CacheableOutput for HLSL {
+    const INDEX: &'static str = "hlsl";
+}
+
+impl CacheableOutput for MSL {
+    const INDEX: &'static str = "msl";
+}
+
+impl CacheableOutput for WGSL {
+    const INDEX: &'static str = "wgsl";
+}
+
+impl CacheableOutput for SPIRV {
+    const INDEX: &'static str = "spv";
+}
+
+/// Get the key of the output of the shader source compiled with the given options, if the
+/// options can be keyed.
+pub(crate) fn output_key<T: CacheableOutput>(
+    source: &ShaderSource,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    options: &impl OutputOptionsKey,
+) -> Option<Vec<u8>> {
+    let options = options.key_bytes()?;
+    let mut hasher = key_hasher(T::INDEX);
+    let compilation = compilation_key::<SpirvCompilation>(source, defines, optimization);
+    update_part(&mut hasher, compilation.as_bytes());
+    update_part(&mut hasher, &options);
+    Some(hasher.finalize().as_bytes().to_vec())
+}
+
+pub(crate) fn encode_output<O: Serialize>(vertex: &O, fragment: &O) -> Option<Vec<u8>> {
+    bincode::serde::encode_to_vec((vertex, fragment), bincode::config::standard()).ok()
+}
+
+pub(crate) fn decode_output<O: DeserializeOwned>(bytes: &[u8]) -> Option<ShaderCompilerOutput<O>> {
+    let ((vertex, fragment), _) =
+        bincode::serde::decode_from_slice(bytes, bincode::config::standard()).ok()?;
+    Some(ShaderCompilerOutput {
+        vertex,
+        fragment,
+        context: (),
+    })
+}
+
+/// Compile the reflected shader pass to the output target, or load the compiled output from
+/// the cache if available.
+///
+/// Outputs precompiled into a [`ShaderArchive`](crate::ShaderArchive) are loaded from the
+/// archive if the cache was created with [`ShaderCache::with_archive`].
+///
+/// - `source`, `defines` and `optimization` are the shader source the pass was compiled from,
+///   and the options it was compiled with.
+/// - Only the vertex and fragment outputs are cached, so the context of the compiler is not
+///   available.
+/// - If `cache` is `None`, the cache is bypassed.
+pub fn cache_shader_output<T, C>(
+    source: &ShaderSource,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    compiler: C,
+    options: C::Options,
+    cache: Option<&ShaderCache>,
+) -> Result<ShaderCompilerOutput<T::Output>, ShaderCompileError>
+where
+    T: CacheableOutput,
+    T::Output: Serialize + DeserializeOwned,
+    C: CompileShader<T>,
+    C::Options: OutputOptionsKey,
+{
+    let Some((cache, key)) = cache.and_then(|cache| {
+        Some((
+            cache,
+            output_key::<T>(source, defines, optimization, &options)?,
+        ))
+    }) else {
+        let output = compiler.compile(options)?;
+        return Ok(ShaderCompilerOutput {
+            vertex: output.vertex,
+            fragment: output.fragment,
+            context: (),
+        });
+    };
+
+    if let Ok(Some(blob)) = cache.get_blob(T::INDEX, &key) {
+        if let Some(output) = decode_output(&blob) {
+            return Ok(output);
+        }
+    }
+
+    let output = compiler.compile(options)?;
+    if let Some(bytes) = encode_output(&output.vertex, &output.fragment) {
+        // We don't really care if the transaction fails, just try again next time.
+        let _ = cache.set_blob(T::INDEX, &key, &bytes);
+    }
+
+    Ok(ShaderCompilerOutput {
+        vertex: output.vertex,
+        fragment: output.fragment,
+        context: (),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{cache_shader_output, ArchiveTargets, ShaderArchive, ShaderCache};
+    use librashader_preprocess::Defines;
+    use librashader_presets::ShaderPreset;
+    use librashader_reflect::back::targets::WGSL;
+    use librashader_reflect::front::SpirvCompilation;
+    use librashader_reflect::reflect::naga::{Naga, NagaLoweringOptions};
+    use librashader_reflect::reflect::presets::{CompilePresetTarget, PresetCompileOptions};
+    use librashader_reflect::reflect::ReflectShader;
+
+    /// Compile every pass of the preset to WGSL through the cache, returning the number of passes.
+    fn compile_preset(preset: &ShaderPreset, cache: &ShaderCache) -> usize {
+        let (passes, semantics) = WGSL::compile_preset_passes_with_options::<
+            SpirvCompilation,
+            Naga,
+            Box<dyn std::error::Error>,
+        >(
+            preset.shaders.clone(),
+            &preset.textures,
+            &PresetCompileOptions {
+                compiler: Some(&|source, defines, optimization| {
+                    cache
+                        .compile_with_options(source, defines, optimization)
+                        .map(|compiled| compiled.compilation)
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let pass_count = passes.len();
+        for (index, (_, source, mut reflect)) in passes.into_iter().enumerate() {
+            reflect.reflect(index, &semantics).unwrap();
+            let output = cache_shader_output(
+                &source,
+                &Defines::default(),
+                None,
+                reflect,
+                lowering_options(),
+                Some(cache),
+            )
+            .unwrap();
+            assert!(output.vertex.contains("fn main"));
+        }
+        pass_count
+    }
+
+    fn lowering_options() -> NagaLoweringOptions {
+        NagaLoweringOptions {
+            write_pcb_as_ubo: true,
+            sampler_bind_group: 1,
+        }
+    }
+
+    #[test]
+    pub fn caches_outputs() {
+        let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+        let cache = ShaderCache::in_memory();
+        let pass_count = compile_preset(&preset, &cache) as u64;
+
+        let stats = cache.stats().unwrap();
+        let outputs = stats
+            .indices
+            .iter()
+            .find(|index| index.name == "wgsl")
+            .unwrap();
+        assert_eq!(outputs.entries, pass_count);
+
+        // Compiling again loads every output from the cache.
+        compile_preset(&preset, &cache);
+        assert_eq!(cache.stats().unwrap().entries(), stats.entries());
+    }
+
+    #[test]
+    pub fn loads_outputs_from_archive() {
+        let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+        let mut archive = ShaderArchive::new();
+        archive
+            .add_preset(
+                &preset,
+                &ArchiveTargets {
+                    wgsl: vec![lowering_options()],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // Everything is loaded from the archive, so nothing is stored in the cache.
+        let cache = ShaderCache::in_memory().with_archive(archive);
+        compile_preset(&preset, &cache);
+        assert_eq!(cache.stats().unwrap().entries(), 0);
+    }
+}
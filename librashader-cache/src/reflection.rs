@@ -0,0 +1,163 @@
+//! Cache helpers for `ShaderReflection` objects.
+use crate::cacheable::Cacheable;
+use crate::key::{key_hasher, update_optimization};
+use crate::ShaderCache;
+use librashader_preprocess::ShaderSource;
+use librashader_reflect::error::ShaderReflectError;
+use librashader_reflect::front::SpirvOptimization;
+use librashader_reflect::reflect::semantics::ShaderSemantics;
+use librashader_reflect::reflect::{ReflectShader, ShaderReflection};
+
+impl Cacheable for ShaderReflection {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+            .map(|(reflection, _)| reflection)
+            .ok()
+    }
+
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard()).ok()
+    }
+}
+
+/// Get the key of the reflection of the shader source as the given pass in the `reflection` index.
+fn reflection_key<R>(
+    source: &ShaderSource,
+    optimization: Option<&SpirvOptimization>,
+    pass_number: usize,
+    semantics: &ShaderSemantics,
+) -> Option<blake3::Hash> {
+    // The semantic maps are unordered, so sort them to get a stable key.
+    let mut uniforms: Vec<_> = semantics.uniform_semantics.iter().collect();
+    uniforms.sort_by(|a, b| a.0.cmp(b.0));
+    let mut textures: Vec<_> = semantics.texture_semantics.iter().collect();
+    textures.sort_by(|a, b| a.0.cmp(b.0));
+    let semantics =
+        bincode::serde::encode_to_vec((uniforms, textures), bincode::config::standard()).ok()?;
+
+    let mut hasher = key_hasher("reflection");
+    // The reflection depends on the backend that reflects the shader.
+    for part in [
+        std::any::type_name::<R>().as_bytes(),
+        source.vertex.as_bytes(),
+        source.fragment.as_bytes(),
+        (pass_number as u64).to_le_bytes().as_slice(),
+        semantics.as_slice(),
+    ] {
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    // Optimizations can remove resources, which changes the reflection.
+    update_optimization(&mut hasher, optimization);
+    Some(hasher.finalize())
+}
+
+/// Reflect the shader as the given pass within the shader preset, or load the reflection
+/// from the cache if available.
+///
+/// - `source` is the shader source that `reflect` was compiled from.
+/// - `optimization` are the optimizations `reflect` was compiled with, if any.
+/// - If `cache` is `None`, the cache is bypassed.
+pub fn cache_reflection<R: ReflectShader>(
+    reflect: &mut R,
+    source: &ShaderSource,
+    optimization: Option<&SpirvOptimization>,
+    pass_number: usize,
+    semantics: &ShaderSemantics,
+    cache: Option<&ShaderCache>,
+) -> Result<ShaderReflection, ShaderReflectError> {
+    let Some((cache, key)) = cache.and_then(|cache| {
+        Some((
+            cache,
+            reflection_key::<R>(source, optimization, pass_number, semantics)?,
+        ))
+    }) else {
+        return reflect.reflect(pass_number, semantics);
+    };
+
+    if let Ok(Some(blob)) = cache.get_blob("reflection", key.as_bytes()) {
+        if let Some(reflection) = ShaderReflection::from_bytes(&blob) {
+            return Ok(reflection);
+        }
+    }
+
+    let reflection = reflect.reflect(pass_number, semantics)?;
+    if let Some(bytes) = reflection.to_bytes() {
+        // We don't really care if the transaction fails, just try again next time.
+        let _ = cache.set_blob("reflection", key.as_bytes(), &bytes);
+    }
+
+    Ok(reflection)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reflection::{cache_reflection, reflection_key};
+    use crate::{CachedCompilation, ShaderCache};
+    use librashader_preprocess::ShaderSource;
+    use librashader_presets::ShaderPreset;
+    use librashader_reflect::back::targets::GLSL;
+    use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
+    use librashader_reflect::reflect::cross::SpirvCross;
+    use librashader_reflect::reflect::presets::{CompilePresetTarget, PresetCompileOptions};
+    use librashader_reflect::reflect::semantics::ShaderSemantics;
+    use librashader_reflect::reflect::ReflectShader;
+
+    #[test]
+    pub fn caches_reflection() {
+        let cache = ShaderCache::in_memory();
+        let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+        let (passes, semantics) = GLSL::compile_preset_passes_with_options::<
+            CachedCompilation<SpirvCompilation>,
+            SpirvCross,
+            Box<dyn std::error::Error>,
+        >(
+            preset.shaders,
+            &preset.textures,
+            &PresetCompileOptions {
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let pass_count = passes.len() as u64;
+        for (index, (_, source, mut reflect)) in passes.into_iter().enumerate() {
+            let expected = reflect.reflect(index, &semantics).unwrap();
+            let first =
+                cache_reflection(&mut reflect, &source, None, index, &semantics, Some(&cache))
+                    .unwrap();
+            let cached =
+                cache_reflection(&mut reflect, &source, None, index, &semantics, Some(&cache))
+                    .unwrap();
+
+            assert_eq!(format!("{expected:?}"), format!("{first:?}"));
+            assert_eq!(format!("{expected:?}"), format!("{cached:?}"));
+        }
+
+        let stats = cache.stats().unwrap();
+        let reflections = stats
+            .indices
+            .iter()
+            .find(|index| index.name == "reflection")
+            .unwrap();
+        assert_eq!(reflections.entries, pass_count);
+    }
+
+    #[test]
+    pub fn keys_reflection_by_optimization() {
+        let source = ShaderSource::load("../test/basic.slang").unwrap();
+        let semantics = ShaderSemantics {
+            uniform_semantics: Default::default(),
+            texture_semantics: Default::default(),
+        };
+        let key = |optimization: Option<&SpirvOptimization>| {
+            reflection_key::<SpirvCross>(&source, optimization, 0, &semantics).unwrap()
+        };
+
+        assert_eq!(key(None), key(None));
+        assert_ne!(key(None), key(Some(&SpirvOptimization::all())));
+    }
+}
@@ -0,0 +1,481 @@
+//! A handle to the shader cache.
+use crate::backend::{CacheBackend, CacheEntry, FilesBackend, MemoryBackend, PersyBackend};
+use crate::error::CacheError;
+use crate::key::cache_fingerprint;
+use platform_dirs::AppDirs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The index that holds metadata about the cache itself.
+const METADATA_INDEX: &str = "librashader";
+
+/// The key in the metadata index of the fingerprint of the version that wrote the cache.
+const VERSION_KEY: &[u8] = b"version";
+
+/// Databases that are currently open, so the same database is never opened twice.
+static OPEN_CACHES: Mutex<Vec<(PathBuf, Weak<PersyBackend>)>> = Mutex::new(Vec::new());
+
+/// The default cache, kept open once it has been opened for a filter chain.
+static DEFAULT_CACHE: OnceLock<ShaderCache> = OnceLock::new();
+
+/// A handle to a shader cache.
+///
+/// Handles are cheap to clone, and opening the same directory more than once returns a handle
+/// to the same cache.
+#[derive(Clone)]
+pub struct ShaderCache {
+    pub(crate) backend: Arc<dyn CacheBackend>,
+}
+
+impl std::fmt::Debug for ShaderCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShaderCache")
+            .field("directory", &self.backend.directory())
+            .finish()
+    }
+}
+
+/// Statistics for a single index of the shader cache, such as `spirv` or `dxil`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    /// The name of the index.
+    pub name: String,
+    /// The number of entries in the index.
+    pub entries: u64,
+    /// The total size of the entries in bytes.
+    pub bytes: u64,
+}
+
+/// Statistics for the shader cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The statistics of each index in the cache.
+    pub indices: Vec<IndexStats>,
+}
+
+impl CacheStats {
+    /// The total number of entries in the cache.
+    pub fn entries(&self) -> u64 {
+        self.indices.iter().map(|index| index.entries).sum()
+    }
+
+    /// The total size of the entries in the cache in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.indices.iter().map(|index| index.bytes).sum()
+    }
+}
+
+/// Limits on the contents of the shader cache, used to evict entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvictionPolicy {
+    /// Evict entries that have not been used for longer than this duration.
+    pub max_age: Option<Duration>,
+    /// Evict the least recently used entries until the total size of the entries in bytes is
+    /// within this budget.
+    pub max_bytes: Option<u64>,
+}
+
+impl ShaderCache {
+    /// Open the shader cache database in the given directory, creating it if it does not exist.
+    pub fn open(directory: impl AsRef<Path>) -> Result<ShaderCache, CacheError> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)
+            .map_err(|e| CacheError::IOError(directory.to_path_buf(), e))?;
+        let directory = directory
+            .canonicalize()
+            .unwrap_or_else(|_| directory.to_path_buf());
+
+        // The list only holds weak handles, so it is still valid if a panic poisoned the lock.
+        let mut open = OPEN_CACHES.lock().unwrap_or_else(PoisonError::into_inner);
+        open.retain(|(_, cache)| cache.strong_count() > 0);
+        if let Some(backend) = open
+            .iter()
+            .find(|(path, _)| path == &directory)
+            .and_then(|(_, cache)| cache.upgrade())
+        {
+            return Ok(ShaderCache { backend });
+        }
+
+        let backend = Arc::new(PersyBackend::open(&directory)?);
+        check_version(&*backend)?;
+        open.push((directory, Arc::downgrade(&backend)));
+        Ok(ShaderCache { backend })
+    }
+
+    /// Open the shader cache database in the default cache directory for the platform.
+    pub fn open_default() -> Result<ShaderCache, CacheError> {
+        Self::open(default_cache_dir())
+    }
+
+    /// Open a shader cache that stores each entry as a file in the given directory.
+    pub fn open_files(directory: impl AsRef<Path>) -> Result<ShaderCache, CacheError> {
+        Self::with_backend(FilesBackend::open(directory)?)
+    }
+
+    /// Create a shader cache that keeps entries in memory.
+    pub fn in_memory() -> ShaderCache {
+        let backend = MemoryBackend::new();
+        // Writing to memory can not fail.
+        let _ = stamp_version(&backend);
+        ShaderCache {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Create a shader cache with a custom storage backend.
+    ///
+    /// Entries written by a different version of librashader are kept, but never loaded.
+    pub fn with_backend(backend: impl CacheBackend + 'static) -> Result<ShaderCache, CacheError> {
+        check_version(&backend)?;
+        Ok(ShaderCache {
+            backend: Arc::new(backend),
+        })
+    }
+
+    /// Get the cache to use for a filter chain given the cache in the filter chain options.
+    ///
+    /// Returns `None` if the cache is disabled, or if no cache was provided and the default
+    /// cache could not be opened.
+    ///
+    /// The default cache is opened once and shared by every filter chain. If it could not be
+    /// opened, opening it is tried again for the next filter chain.
+    pub fn for_filter_chain(
+        cache: Option<&ShaderCache>,
+        disable_cache: bool,
+    ) -> Option<ShaderCache> {
+        if disable_cache {
+            return None;
+        }
+
+        if let Some(cache) = cache.or_else(|| DEFAULT_CACHE.get()) {
+            return Some(cache.clone());
+        }
+
+        let cache = Self::open_default().ok()?;
+        Some(DEFAULT_CACHE.get_or_init(|| cache).clone())
+    }
+
+    /// The directory the cache is stored in, if it is stored on disk.
+    pub fn directory(&self) -> Option<&Path> {
+        self.backend.directory()
+    }
+
+    pub(crate) fn get_blob(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheError> {
+        self.backend.get(index, key)
+    }
+
+    pub(crate) fn set_blob(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheError> {
+        self.backend.put(index, key, value)
+    }
+
+    /// List the entries of the cache, excluding metadata.
+    pub(crate) fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        let mut entries = self.backend.entries()?;
+        entries.retain(|entry| entry.index != METADATA_INDEX);
+        Ok(entries)
+    }
+
+    /// Collect the statistics of each index in the cache.
+    pub fn stats(&self) -> Result<CacheStats, CacheError> {
+        let mut indices: Vec<IndexStats> = Vec::new();
+        for entry in self.entries()? {
+            let stats = match indices.iter_mut().find(|stats| stats.name == entry.index) {
+                Some(stats) => stats,
+                None => {
+                    indices.push(IndexStats {
+                        name: entry.index,
+                        entries: 0,
+                        bytes: 0,
+                    });
+                    indices.last_mut().unwrap()
+                }
+            };
+            stats.entries += 1;
+            stats.bytes += entry.bytes;
+        }
+        indices.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(CacheStats { indices })
+    }
+
+    /// Evict entries from the cache according to the given policy, returning the number of
+    /// entries evicted.
+    ///
+    /// Entries that are older than the maximum age are evicted first. Then, the least recently
+    /// used entries are evicted until the cache is within the byte budget.
+    pub fn evict(&self, policy: &EvictionPolicy) -> Result<u64, CacheError> {
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|entry| entry.last_used);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let mut total: u64 = entries.iter().map(|entry| entry.bytes).sum();
+        let mut evicted = 0;
+        for entry in entries {
+            let expired = policy.max_age.is_some_and(|max_age| {
+                now.saturating_sub(entry.last_used) >= max_age.as_nanos() as u64
+            });
+            let over_budget = policy.max_bytes.is_some_and(|max_bytes| total > max_bytes);
+            if !expired && !over_budget {
+                continue;
+            }
+
+            self.backend.remove(&entry.index, &entry.key)?;
+            total -= entry.bytes;
+            evicted += 1;
+        }
+
+        Ok(evicted)
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&self) -> Result<(), CacheError> {
+        self.backend.clear()?;
+        stamp_version(&*self.backend)
+    }
+}
+
+/// Record the fingerprint of this version of librashader in the backend.
+fn stamp_version(backend: &dyn CacheBackend) -> Result<(), CacheError> {
+    backend.put(METADATA_INDEX, VERSION_KEY, cache_fingerprint().as_bytes())
+}
+
+/// Record the fingerprint of this version of librashader if the backend was last written by
+/// a different version.
+///
+/// Entries written by other versions are kept, since the cache may be shared by applications
+/// that use different versions of librashader. Keys already include the fingerprint, so they
+/// are never loaded by this version, and are removed by eviction once they are no longer used.
+fn check_version(backend: &dyn CacheBackend) -> Result<(), CacheError> {
+    if backend.is_read_only() {
+        return Ok(());
+    }
+
+    let version = backend.get(METADATA_INDEX, VERSION_KEY)?;
+    if version.as_deref() == Some(cache_fingerprint().as_bytes()) {
+        return Ok(());
+    }
+
+    stamp_version(backend)
+}
+
+/// Get the default cache directory for the platform, falling back to the current directory.
+fn default_cache_dir() -> PathBuf {
+    if let Some(cache_dir) = AppDirs::new(Some("librashader"), false).map(|a| a.cache_dir) {
+        cache_dir
+    } else {
+        let mut current_dir = std::env::current_dir().unwrap_or_default();
+        current_dir.push("librashader");
+        current_dir
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{METADATA_INDEX, VERSION_KEY};
+    use crate::key::cache_fingerprint;
+    use crate::{
+        CacheBackend, CacheError, EvictionPolicy, FilesBackend, MemoryBackend, ShaderCache,
+    };
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn temp_cache(name: &str) -> (PathBuf, ShaderCache) {
+        let directory = std::env::temp_dir().join(format!(
+            "librashader-cache-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let cache = ShaderCache::open(&directory).unwrap();
+        (directory, cache)
+    }
+
+    #[test]
+    pub fn reports_stats_and_clears() {
+        let (directory, cache) = temp_cache("stats");
+        cache.set_blob("spirv", b"a", &[0; 16]).unwrap();
+        cache.set_blob("spirv", b"b", &[0; 8]).unwrap();
+        cache.set_blob("pipeline", b"a", &[0; 4]).unwrap();
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.indices.len(), 2);
+        assert_eq!(stats.indices[0].name, "pipeline");
+        assert_eq!(stats.indices[1].entries, 2);
+        assert_eq!(stats.indices[1].bytes, 24);
+        assert_eq!(stats.bytes(), 28);
+
+        // Opening the same directory returns the same cache.
+        let reopened = ShaderCache::open(&directory).unwrap();
+        assert_eq!(reopened.get_blob("spirv", b"b").unwrap(), Some(vec![0; 8]));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.stats().unwrap().entries(), 0);
+        assert_eq!(cache.get_blob("spirv", b"a").unwrap(), None);
+
+        drop((cache, reopened));
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn opens_after_poisoned_lock() {
+        let _ = std::thread::spawn(|| {
+            let _open = super::OPEN_CACHES.lock();
+            panic!("poisoning the list of open caches");
+        })
+        .join();
+        assert!(super::OPEN_CACHES.is_poisoned());
+
+        let (directory, cache) = temp_cache("poisoned");
+        cache.set_blob("spirv", b"a", &[0; 4]).unwrap();
+        let reopened = ShaderCache::open(&directory).unwrap();
+        assert!(reopened.get_blob("spirv", b"a").unwrap().is_some());
+
+        drop((cache, reopened));
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn opens_read_only_files() {
+        let directory = std::env::temp_dir().join(format!(
+            "librashader-cache-test-read-only-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let cache = ShaderCache::open_files(&directory).unwrap();
+        cache.set_blob("spirv", b"a", &[0; 4]).unwrap();
+        drop(cache);
+
+        let cache = ShaderCache::with_backend(FilesBackend::open_read_only(&directory)).unwrap();
+        assert_eq!(cache.get_blob("spirv", b"a").unwrap(), Some(vec![0; 4]));
+        assert!(matches!(
+            cache.set_blob("spirv", b"b", &[]),
+            Err(CacheError::ReadOnly)
+        ));
+
+        // Opening a missing directory read-only does not create it.
+        let missing = directory.join("missing");
+        let cache = ShaderCache::with_backend(FilesBackend::open_read_only(&missing)).unwrap();
+        assert_eq!(cache.get_blob("spirv", b"a").unwrap(), None);
+        assert!(!missing.exists());
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn evicts_least_recently_used() {
+        let (directory, cache) = temp_cache("evict");
+        cache.set_blob("spirv", b"a", &[0; 10]).unwrap();
+        cache.set_blob("spirv", b"b", &[0; 10]).unwrap();
+        cache.set_blob("dxil", b"c", &[0; 10]).unwrap();
+        cache.get_blob("spirv", b"a").unwrap();
+
+        let evicted = cache
+            .evict(&EvictionPolicy {
+                max_age: None,
+                max_bytes: Some(20),
+            })
+            .unwrap();
+        assert_eq!(evicted, 1);
+        assert_eq!(cache.get_blob("spirv", b"b").unwrap(), None);
+        assert!(cache.get_blob("spirv", b"a").unwrap().is_some());
+        assert!(cache.get_blob("dxil", b"c").unwrap().is_some());
+
+        let evicted = cache
+            .evict(&EvictionPolicy {
+                max_age: Some(Duration::ZERO),
+                max_bytes: None,
+            })
+            .unwrap();
+        assert_eq!(evicted, 2);
+        assert_eq!(cache.stats().unwrap().entries(), 0);
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn caches_shader_objects_in_memory() {
+        let cache = ShaderCache::in_memory();
+        let mut compiled = 0;
+        for _ in 0..2 {
+            let object = crate::cache_shader_object(
+                "dxbc",
+                &[b"source".as_slice()],
+                |_| {
+                    compiled += 1;
+                    Ok::<_, CacheError>(vec![1u8, 2, 3])
+                },
+                Ok,
+                Some(&cache),
+            )
+            .unwrap();
+            assert_eq!(object, vec![1, 2, 3]);
+        }
+
+        assert_eq!(compiled, 1);
+        assert_eq!(cache.stats().unwrap().entries(), 1);
+        assert_eq!(cache.directory(), None);
+    }
+
+    #[test]
+    pub fn stores_entries_as_files() {
+        let directory = std::env::temp_dir().join(format!(
+            "librashader-cache-test-files-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let cache = ShaderCache::open_files(&directory).unwrap();
+
+        cache.set_blob("spirv", &[0xab, 0x01], &[0; 16]).unwrap();
+        cache.set_blob("dxil", b"b", &[0; 8]).unwrap();
+        assert!(directory.join("spirv").join("ab01").is_file());
+        assert_eq!(
+            cache.get_blob("spirv", &[0xab, 0x01]).unwrap(),
+            Some(vec![0; 16])
+        );
+        assert_eq!(cache.get_blob("spirv", b"missing").unwrap(), None);
+        assert!(matches!(
+            cache.set_blob("../spirv", b"a", &[]),
+            Err(CacheError::InvalidIndex(_))
+        ));
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries(), 2);
+        assert_eq!(stats.bytes(), 24);
+
+        cache.clear().unwrap();
+        assert_eq!(cache.stats().unwrap().entries(), 0);
+        assert!(directory.is_dir());
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    pub fn keeps_entries_from_other_versions() {
+        let backend = MemoryBackend::new();
+        backend
+            .put(METADATA_INDEX, VERSION_KEY, b"0/0.0.0")
+            .unwrap();
+        backend.put("spirv", b"other", &[0; 4]).unwrap();
+
+        let cache = ShaderCache::with_backend(backend).unwrap();
+        assert!(cache.get_blob("spirv", b"other").unwrap().is_some());
+        assert_eq!(cache.stats().unwrap().entries(), 1);
+        assert_eq!(
+            cache.get_blob(METADATA_INDEX, VERSION_KEY).unwrap(),
+            Some(cache_fingerprint().into_bytes())
+        );
+
+        // Entries written by the current version are kept.
+        cache.set_blob("spirv", b"fresh", &[0; 4]).unwrap();
+        let backend = MemoryBackend::new();
+        for entry in cache.backend.entries().unwrap() {
+            let value = cache.get_blob(&entry.index, &entry.key).unwrap().unwrap();
+            backend.put(&entry.index, &entry.key, &value).unwrap();
+        }
+        let cache = ShaderCache::with_backend(backend).unwrap();
+        assert!(cache.get_blob("spirv", b"fresh").unwrap().is_some());
+    }
+}
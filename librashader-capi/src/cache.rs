@@ -0,0 +1,60 @@
+//! librashader shader cache C API (`libra_shader_cache_*`).
+use crate::ctypes::libra_shader_cache_t;
+use crate::error::assert_non_null;
+use crate::ffi::extern_fn;
+use librashader::cache::ShaderCache;
+use std::ffi::{c_char, CStr};
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+
+const _: () = crate::assert_thread_safe::<ShaderCache>();
+
+extern_fn! {
+    /// Open the shader cache in the given directory, creating it if it does not exist.
+    ///
+    /// The cache can be passed in the options of a filter chain to use it instead of the default
+    /// shader cache.
+    ///
+    /// ## Safety
+    ///  - `directory` must be either null or a valid, aligned pointer to a string path to the cache directory.
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_shader_cache_t`.
+    /// ## Returns
+    ///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+    fn libra_shader_cache_open(
+        directory: *const c_char,
+        out: *mut MaybeUninit<libra_shader_cache_t>
+    ) {
+        assert_non_null!(directory);
+        assert_non_null!(out);
+
+        let directory = unsafe { CStr::from_ptr(directory) };
+        let directory = directory.to_str()?;
+
+        let cache = ShaderCache::open(directory)?;
+        unsafe {
+            out.write(MaybeUninit::new(NonNull::new(Box::into_raw(Box::new(
+                cache,
+            )))))
+        }
+    }
+}
+
+extern_fn! {
+    /// Free the shader cache.
+    ///
+    /// Filter chains created with the cache keep using it until they are freed.
+    /// If `cache` is null, this function does nothing. The resulting value in `cache` then becomes
+    /// null.
+    ///
+    /// ## Safety
+    /// - `cache` must be a valid and aligned pointer to a `libra_shader_cache_t`.
+    fn libra_shader_cache_free(cache: *mut libra_shader_cache_t) {
+        assert_non_null!(cache);
+        unsafe {
+            let cache_ptr = &mut *cache;
+            if let Some(cache) = cache_ptr.take() {
+                drop(Box::from_raw(cache.as_ptr()));
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 //! Binding types for the librashader C API.
 use crate::error::LibrashaderError;
+use librashader::cache::ShaderCache;
 use librashader::presets::context::{Orientation, VideoDriver, WildcardContext};
 use librashader::presets::ShaderPreset;
+use librashader::runtime::HdrMode;
 use std::mem::MaybeUninit;
 use std::ptr::NonNull;
 
@@ -11,6 +13,9 @@ pub type libra_shader_preset_t = Option<NonNull<ShaderPreset>>;
 /// A handle to a preset wildcard context object.
 pub type libra_preset_ctx_t = Option<NonNull<WildcardContext>>;
 
+/// A handle to a shader cache object.
+pub type libra_shader_cache_t = Option<NonNull<ShaderCache>>;
+
 /// A handle to a librashader error object.
 pub type libra_error_t = Option<NonNull<LibrashaderError>>;
 
@@ -55,6 +60,29 @@ impl From<LIBRA_PRESET_CTX_RUNTIME> for VideoDriver {
     }
 }
 
+/// An enum representing the color space of the output of a filter chain.
+#[repr(u32)]
+#[derive(Default, Debug, Copy, Clone)]
+pub enum LIBRA_HDR_MODE {
+    /// SDR output.
+    #[default]
+    Disabled = 0,
+    /// scRGB output. The final pass should render to a `R16G16B16A16_SFLOAT` target.
+    ScRgb,
+    /// HDR10 output. The final pass should render to a `A2B10G10R10_UNORM_PACK32` target.
+    Hdr10,
+}
+
+impl From<LIBRA_HDR_MODE> for HdrMode {
+    fn from(value: LIBRA_HDR_MODE) -> Self {
+        match value {
+            LIBRA_HDR_MODE::Disabled => HdrMode::Disabled,
+            LIBRA_HDR_MODE::ScRgb => HdrMode::ScRgb,
+            LIBRA_HDR_MODE::Hdr10 => HdrMode::Hdr10,
+        }
+    }
+}
+
 #[cfg(feature = "runtime-opengl")]
 use librashader::runtime::gl::FilterChain as FilterChainGL;
 
@@ -145,9 +173,41 @@ where
     fn from_uninit(value: MaybeUninit<Self>) -> T;
 }
 
+/// Conversion from the value of a field of a config struct to the value of the option.
+pub(crate) trait FromConfigField<T> {
+    /// Convert the value of the config struct field.
+    ///
+    /// ## Safety
+    /// Handles in the field must be null or valid.
+    unsafe fn from_config_field(value: T) -> Self;
+}
+
+impl<T> FromConfigField<T> for T {
+    unsafe fn from_config_field(value: T) -> Self {
+        value
+    }
+}
+
+impl FromConfigField<libra_shader_cache_t> for Option<ShaderCache> {
+    unsafe fn from_config_field(value: libra_shader_cache_t) -> Self {
+        // The filter chain keeps its own handle, so the cache can be freed independently.
+        value.map(|cache| unsafe { cache.as_ref() }.clone())
+    }
+}
+
+impl FromConfigField<LIBRA_HDR_MODE> for HdrMode {
+    unsafe fn from_config_field(value: LIBRA_HDR_MODE) -> Self {
+        value.into()
+    }
+}
+
 macro_rules! config_set_field {
     ($options:ident.$field:ident <- $ptr:ident) => {
-        $options.$field = unsafe { ::std::ptr::addr_of!((*$ptr).$field).read() };
+        $options.$field = unsafe {
+            $crate::ctypes::FromConfigField::from_config_field(
+                ::std::ptr::addr_of!((*$ptr).$field).read(),
+            )
+        };
     };
 }
 
@@ -201,6 +261,8 @@ mod __cbindgen_opaque_forward_declarations {
         WildcardContext;
         /// Opaque struct for a shader preset.
         ShaderPreset;
+        /// Opaque struct for a shader cache.
+        ShaderCache;
         /// Opaque struct for an OpenGL filter chain.
         FilterChainGL;
         /// Opaque struct for a Direct3D 11 filter chain.
@@ -25,6 +25,8 @@ pub enum LibrashaderError {
     ShaderReflectError(#[from] librashader::reflect::ShaderReflectError),
     #[error("The provided parameter name was invalid.")]
     UnknownShaderParameter(*const c_char),
+    #[error("There was an error opening the shader cache.")]
+    CacheError(#[from] librashader::cache::CacheError),
     #[cfg(feature = "runtime-opengl")]
     #[doc(cfg(feature = "runtime-opengl"))]
     #[error("There was an error in the OpenGL filter chain.")]
@@ -62,6 +64,7 @@ pub enum LIBRA_ERRNO {
     SHADER_PARAMETER_ERROR = 5,
     REFLECT_ERROR = 6,
     RUNTIME_ERROR = 7,
+    CACHE_ERROR = 8,
 }
 
 // Nothing here can use extern_fn because they are lower level than libra_error_t.
@@ -187,6 +190,7 @@ impl LibrashaderError {
                 LIBRA_ERRNO::REFLECT_ERROR
             }
             LibrashaderError::UnknownShaderParameter(_) => LIBRA_ERRNO::SHADER_PARAMETER_ERROR,
+            LibrashaderError::CacheError(_) => LIBRA_ERRNO::CACHE_ERROR,
             #[cfg(feature = "runtime-opengl")]
             LibrashaderError::OpenGlFilterError(_) => LIBRA_ERRNO::RUNTIME_ERROR,
             #[cfg(all(target_os = "windows", feature = "runtime-d3d11"))]
@@ -70,6 +70,7 @@
 
 extern crate alloc;
 
+pub mod cache;
 pub mod ctypes;
 pub mod error;
 mod ffi;
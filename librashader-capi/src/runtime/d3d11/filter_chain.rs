@@ -1,5 +1,6 @@
 use crate::ctypes::{
-    config_struct, libra_d3d11_filter_chain_t, libra_shader_preset_t, libra_viewport_t, FromUninit,
+    config_struct, libra_d3d11_filter_chain_t, libra_shader_cache_t, libra_shader_preset_t,
+    libra_viewport_t, FromUninit, LIBRA_HDR_MODE,
 };
 use crate::error::{assert_non_null, assert_some_ptr, LibrashaderError};
 use crate::ffi::extern_fn;
@@ -54,11 +55,22 @@ pub struct filter_chain_d3d11_opt_t {
     /// Disable the shader object cache. Shaders will be
     /// recompiled rather than loaded from the cache.
     pub disable_cache: bool,
+    /// The shader cache to use. If null, the default shader cache is used.
+    /// The filter chain keeps its own handle to the cache, so the cache may be freed
+    /// after the filter chain is created.
+    pub cache: libra_shader_cache_t,
+    /// The color space of the output. If HDR output is enabled, the final pass should render
+    /// to a target of the matching format, and the HDR options of every frame are set to match.
+    pub hdr_mode: LIBRA_HDR_MODE,
+    /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+    /// Leave this disabled if the shader preset produces HDR output by itself.
+    pub hdr_output_pass: bool,
 }
 
 config_struct! {
     impl FilterChainOptions => filter_chain_d3d11_opt_t {
         0 => [force_no_mipmaps, disable_cache];
+        2 => [cache, hdr_mode, hdr_output_pass];
     }
 }
 
@@ -79,12 +91,32 @@ pub struct frame_d3d11_opt_t {
     pub total_subframes: u32,
     /// The current sub frame. Default is 1.
     pub current_subframe: u32,
+    /// The aspect ratio of the original input. If this is 0, the aspect ratio
+    /// is calculated from the size of the original input. Default is 0.
+    pub aspect_ratio: f32,
+    /// The frame rate of the original input, in frames per second. Default is 60.
+    pub frames_per_second: f32,
+    /// The time elapsed since the previous frame, in microseconds. Default is 0.
+    pub frametime_delta: u32,
+    /// Whether or not HDR output is enabled. Default is false.
+    pub enable_hdr: bool,
+    /// The luminance of paper white, in nits. Default is 200.
+    pub paper_white_nits: f32,
+    /// The peak luminance of the display, in nits. Default is 1000.
+    pub max_nits: f32,
+    /// The gamut expansion mode to use when converting to HDR. Default is 0.
+    pub expand_gamut: u32,
+    /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+    pub inverse_tonemap: bool,
+    /// Whether or not the output is encoded as HDR10. Default is false.
+    pub hdr10: bool,
 }
 
 config_struct! {
     impl FrameOptions => frame_d3d11_opt_t {
         0 => [clear_history, frame_direction];
-        1 => [rotation, total_subframes, current_subframe]
+        1 => [rotation, total_subframes, current_subframe];
+        2 => [aspect_ratio, frames_per_second, frametime_delta, enable_hdr, paper_white_nits, max_nits, expand_gamut, inverse_tonemap, hdr10]
     }
 }
 
@@ -17,7 +17,12 @@ pub type LIBRASHADER_ABI_VERSION = usize;
 ///     - Added rotation, total_subframes, current_subframes to frame options
 ///     - Added preset context API
 ///     - Added Metal runtime API
-pub const LIBRASHADER_CURRENT_VERSION: LIBRASHADER_API_VERSION = 1;
+/// - API version 2: 0.2.7
+///     - Added aspect_ratio, frames_per_second, frametime_delta, and HDR options to frame options
+///     - Added shader cache API
+///     - Added cache to filter chain options
+///     - Added hdr_mode and hdr_output_pass to filter chain options
+pub const LIBRASHADER_CURRENT_VERSION: LIBRASHADER_API_VERSION = 2;
 
 /// The current version of the librashader ABI.
 /// Used by the loader to check ABI compatibility.
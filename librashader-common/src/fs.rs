@@ -0,0 +1,135 @@
+//! Pluggable file access for loading shader presets, shader sources, and textures.
+use crate::map::FastHashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+/// A source of files for shader presets, shader sources, and lookup textures.
+///
+/// By default, files are read from the host filesystem with [`StdFileProvider`].
+/// Implement this trait to load files from archives, asset bundles, or other
+/// virtual filesystems.
+pub trait FileProvider: Send + Sync {
+    /// Read the entire contents of the file at the given path.
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>>;
+
+    /// Get the canonical, absolute form of the given path.
+    ///
+    /// Returns an error if the path does not exist.
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf>;
+
+    /// Returns whether the given path points to an existing directory.
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Returns whether the given path points to an existing file or directory.
+    fn exists(&self, path: &Path) -> bool {
+        self.canonicalize(path).is_ok()
+    }
+}
+
+impl std::fmt::Debug for dyn FileProvider + '_ {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FileProvider")
+    }
+}
+
+/// A file provider that reads from the host filesystem.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StdFileProvider;
+
+impl FileProvider for StdFileProvider {
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        matches!(path.try_exists(), Ok(true))
+    }
+}
+
+/// A file provider that serves files from memory.
+///
+/// Paths are normalized lexically, without following symbolic links. Directories
+/// exist implicitly if any file is contained within them.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileProvider {
+    files: FastHashMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryFileProvider {
+    /// Create a new, empty in-memory file provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file with the given contents, replacing any existing file at the path.
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+
+    /// Remove the file at the given path, returning its contents if it existed.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.files.remove(&normalize(path.as_ref()))
+    }
+
+    fn not_found(path: &Path) -> Error {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("{} was not found in memory", path.display()),
+        )
+    }
+}
+
+/// Lexically resolve `.` and `..` components in a path.
+///
+/// A `..` component at the start of a relative path is kept, and one at the root of an
+/// absolute path is dropped.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+impl FileProvider for MemoryFileProvider {
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        self.files
+            .get(&normalize(path))
+            .cloned()
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let normalized = normalize(path);
+        if self.files.contains_key(&normalized) || self.is_dir(&normalized) {
+            Ok(normalized)
+        } else {
+            Err(Self::not_found(path))
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        self.files
+            .keys()
+            .any(|file| file != &path && file.starts_with(&path))
+    }
+}
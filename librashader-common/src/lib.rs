@@ -33,6 +33,8 @@ pub mod metal;
 
 mod viewport;
 
+pub mod fs;
+
 #[doc(hidden)]
 pub mod map;
 
@@ -40,6 +42,7 @@ pub use viewport::Viewport;
 
 use num_traits::AsPrimitive;
 use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 #[repr(u32)]
@@ -126,6 +129,26 @@ impl FromStr for FilterMode {
     }
 }
 
+impl Display for WrapMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WrapMode::ClampToBorder => f.write_str("clamp_to_border"),
+            WrapMode::ClampToEdge => f.write_str("clamp_to_edge"),
+            WrapMode::Repeat => f.write_str("repeat"),
+            WrapMode::MirroredRepeat => f.write_str("mirrored_repeat"),
+        }
+    }
+}
+
+impl Display for FilterMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterMode::Linear => f.write_str("linear"),
+            FilterMode::Nearest => f.write_str("nearest"),
+        }
+    }
+}
+
 #[repr(i32)]
 #[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Hash)]
 /// The wrapping (address) mode for a texture sampler.
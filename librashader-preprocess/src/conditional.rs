@@ -0,0 +1,622 @@
+use crate::PreprocessError;
+use librashader_common::map::FastHashMap;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::iter::Enumerate;
+use std::path::Path;
+use std::str::Lines;
+
+/// The `VULKAN` macro predefined by glslang when compiling for Vulkan, which all slang shaders
+/// are compiled as.
+const VULKAN_VERSION: &str = "100";
+
+/// The maximum depth to expand macros to when evaluating a conditional expression.
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// A set of macros that are defined before preprocessing a shader source.
+///
+/// Defines are visible to `#if` and `#ifdef` directives, and are also inserted into the
+/// resulting shader source after the `#version` header. This allows a single shader to be
+/// compiled into multiple variants, such as for a specific runtime or quality level.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Defines {
+    macros: BTreeMap<String, String>,
+}
+
+impl Defines {
+    /// Create an empty set of defines.
+    pub const fn new() -> Self {
+        Self {
+            macros: BTreeMap::new(),
+        }
+    }
+
+    /// Define a macro with the given value, replacing any previous definition.
+    pub fn define(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.macros.insert(name.into(), value.into());
+        self
+    }
+
+    /// Remove the definition of a macro.
+    pub fn undefine(&mut self, name: &str) -> &mut Self {
+        self.macros.remove(name);
+        self
+    }
+
+    /// Get the value of a defined macro.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.macros.get(name).map(String::as_str)
+    }
+
+    /// Returns whether there are no defines.
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    /// Iterate over the names and values of the defined macros, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.macros.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Defines {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut defines = Defines::new();
+        for (name, value) in iter {
+            defines.define(name, value);
+        }
+        defines
+    }
+}
+
+/// A macro known to the preprocessor.
+#[derive(Clone, PartialEq)]
+enum Macro {
+    Object(String),
+    Function,
+}
+
+/// A single `#if` block.
+struct Block {
+    /// Whether the block that contains this block is active.
+    parent_active: bool,
+    /// Whether the current branch of this block is active.
+    active: bool,
+    /// Whether any branch of this block has been taken.
+    taken: bool,
+    /// Whether the `#else` branch has been seen.
+    seen_else: bool,
+}
+
+/// A line of source, after joining directive lines continued with a trailing `\`.
+pub(crate) struct SourceLine<'a> {
+    /// The number of the first line, starting from 1.
+    pub(crate) line_no: usize,
+    /// The number of lines that were joined.
+    pub(crate) count: usize,
+    /// The text of the line.
+    pub(crate) text: Cow<'a, str>,
+    /// Whether the line starts inside a block comment, and so can not be a directive.
+    pub(crate) in_comment: bool,
+}
+
+/// Iterates over the lines of a source file, joining continued directive lines and tracking
+/// block comments.
+pub(crate) struct SourceLines<'a> {
+    lines: Enumerate<Lines<'a>>,
+    first_line: usize,
+    in_comment: bool,
+}
+
+impl<'a> SourceLines<'a> {
+    pub(crate) fn new(lines: Lines<'a>, first_line: usize) -> Self {
+        Self {
+            lines: lines.enumerate(),
+            first_line,
+            in_comment: false,
+        }
+    }
+}
+
+impl<'a> Iterator for SourceLines<'a> {
+    type Item = SourceLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, line) = self.lines.next()?;
+        let in_comment = self.in_comment;
+        let mut text = Cow::Borrowed(line);
+        let mut count = 1;
+
+        if !in_comment && split_directive(line).is_some() {
+            while text.ends_with('\\') {
+                let Some((_, next)) = self.lines.next() else {
+                    break;
+                };
+                let mut joined = text.into_owned();
+                joined.pop();
+                joined.push_str(next);
+                text = Cow::Owned(joined);
+                count += 1;
+            }
+        }
+
+        self.in_comment = ends_in_comment(&text, in_comment);
+        Some(SourceLine {
+            line_no: self.first_line + index,
+            count,
+            text,
+            in_comment,
+        })
+    }
+}
+
+/// Returns whether a block comment is still open at the end of the line.
+fn ends_in_comment(line: &str, mut in_comment: bool) -> bool {
+    let mut rest = line;
+    loop {
+        if in_comment {
+            let Some(end) = rest.find("*/") else {
+                return true;
+            };
+            rest = &rest[end + 2..];
+            in_comment = false;
+        } else {
+            let line_comment = rest.find("//");
+            match rest.find("/*") {
+                Some(start) if line_comment.is_none_or(|line| start < line) => {
+                    rest = &rest[start + 2..];
+                    in_comment = true;
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// The result of processing a source line.
+pub(crate) enum LineKind<'a> {
+    /// The line is a conditional directive, and should not be emitted.
+    Conditional,
+    /// The line is in an inactive block, and should not be emitted.
+    Inactive,
+    /// The line is active and should be processed.
+    Active(&'a str),
+}
+
+/// The macros defined at some point while preprocessing.
+///
+/// Preprocessing a file twice with the same macros defined gives the same result, so a file
+/// that includes itself without changing any macro would recurse forever.
+#[derive(PartialEq)]
+pub(crate) struct MacroState(FastHashMap<String, Macro>);
+
+/// Conditional compilation state for a shader source and its includes.
+pub(crate) struct Conditionals {
+    macros: FastHashMap<String, Macro>,
+    blocks: Vec<Block>,
+}
+
+impl Conditionals {
+    pub(crate) fn new(version: &str, defines: &Defines) -> Self {
+        let mut macros = FastHashMap::default();
+        macros.insert(
+            String::from("__VERSION__"),
+            Macro::Object(version.trim().to_string()),
+        );
+        macros.insert(
+            String::from("VULKAN"),
+            Macro::Object(VULKAN_VERSION.to_string()),
+        );
+        for (name, value) in defines.iter() {
+            macros.insert(name.to_string(), Macro::Object(value.to_string()));
+        }
+        Self {
+            macros,
+            blocks: Vec::new(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.blocks.last().is_none_or(|block| block.active)
+    }
+
+    /// The macros that are currently defined.
+    pub(crate) fn macro_state(&self) -> MacroState {
+        MacroState(self.macros.clone())
+    }
+
+    /// The number of open blocks, used to check that every file closes the blocks it opens.
+    pub(crate) fn depth(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Process a line of source, evaluating conditional directives and tracking macro
+    /// definitions in active blocks.
+    pub(crate) fn process_line<'a>(
+        &mut self,
+        line: &'a SourceLine,
+        path: &Path,
+    ) -> Result<LineKind<'a>, PreprocessError> {
+        let line_no = line.line_no;
+        let directive = if line.in_comment {
+            None
+        } else {
+            split_directive(&line.text)
+        };
+        let line = line.text.as_ref();
+        let Some((directive, rest)) = directive else {
+            return Ok(if self.is_active() {
+                LineKind::Active(line)
+            } else {
+                LineKind::Inactive
+            });
+        };
+
+        let invalid = |message: String| {
+            PreprocessError::InvalidConditional(path.to_path_buf(), line_no, message)
+        };
+        let unmatched = || PreprocessError::UnmatchedConditional(path.to_path_buf(), line_no);
+
+        match directive {
+            "if" | "ifdef" | "ifndef" => {
+                let parent_active = self.is_active();
+                let active = parent_active
+                    && match directive {
+                        "if" => self.evaluate(rest).map_err(invalid)?,
+                        "ifdef" => self.is_defined(macro_name(rest).map_err(invalid)?.0),
+                        _ => !self.is_defined(macro_name(rest).map_err(invalid)?.0),
+                    };
+                self.blocks.push(Block {
+                    parent_active,
+                    active,
+                    taken: active,
+                    seen_else: false,
+                });
+            }
+            "elif" => {
+                let block = self.blocks.last().ok_or_else(unmatched)?;
+                if block.seen_else {
+                    return Err(unmatched());
+                }
+                let active =
+                    block.parent_active && !block.taken && self.evaluate(rest).map_err(invalid)?;
+                let block = self.blocks.last_mut().ok_or_else(unmatched)?;
+                block.active = active;
+                block.taken |= active;
+            }
+            "else" => {
+                let block = self.blocks.last_mut().ok_or_else(unmatched)?;
+                if block.seen_else {
+                    return Err(unmatched());
+                }
+                block.seen_else = true;
+                block.active = block.parent_active && !block.taken;
+                block.taken = true;
+            }
+            "endif" => {
+                self.blocks.pop().ok_or_else(unmatched)?;
+            }
+            _ => {
+                if !self.is_active() {
+                    return Ok(LineKind::Inactive);
+                }
+                match directive {
+                    "define" => self.define(rest).map_err(invalid)?,
+                    "undef" => {
+                        self.macros.remove(macro_name(rest).map_err(invalid)?.0);
+                    }
+                    _ => {}
+                }
+                return Ok(LineKind::Active(line));
+            }
+        }
+
+        Ok(LineKind::Conditional)
+    }
+
+    /// Check that all blocks opened since the given depth have been closed.
+    pub(crate) fn close(&self, depth: usize, path: &Path) -> Result<(), PreprocessError> {
+        if self.blocks.len() != depth {
+            return Err(PreprocessError::UnterminatedConditional(path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+
+    fn define(&mut self, rest: &str) -> Result<(), String> {
+        let (name, body) = macro_name(rest)?;
+        let definition = if body.starts_with('(') {
+            Macro::Function
+        } else {
+            Macro::Object(strip_comments(body).trim().to_string())
+        };
+        self.macros.insert(name.to_string(), definition);
+        Ok(())
+    }
+
+    fn evaluate(&self, expression: &str) -> Result<bool, String> {
+        let tokens = tokenize(&strip_comments(expression))?;
+        let tokens = self.expand(tokens, 0)?;
+        let mut parser = ExpressionParser { tokens, pos: 0 };
+        let value = parser.ternary()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("unexpected `{token}` in expression"));
+        }
+        Ok(value != 0)
+    }
+
+    /// Replace `defined` operators and macro names in the expression with their values.
+    fn expand(&self, tokens: Vec<Token>, depth: usize) -> Result<Vec<Token>, String> {
+        if depth > MAX_EXPANSION_DEPTH {
+            return Err(String::from("macro expansion is too deep"));
+        }
+
+        let mut output = Vec::with_capacity(tokens.len());
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            let Token::Identifier(name) = token else {
+                output.push(token);
+                continue;
+            };
+
+            if name == "defined" {
+                let parenthesized = tokens.next_if_eq(&Token::Punct("(")).is_some();
+                let Some(Token::Identifier(name)) = tokens.next() else {
+                    return Err(String::from("expected a macro name after `defined`"));
+                };
+                if parenthesized && tokens.next_if_eq(&Token::Punct(")")).is_none() {
+                    return Err(String::from("expected `)` after `defined`"));
+                }
+                output.push(Token::Number(i64::from(self.is_defined(&name))));
+                continue;
+            }
+
+            match self.macros.get(&name) {
+                Some(Macro::Object(value)) => {
+                    output.extend(self.expand(tokenize(value)?, depth + 1)?);
+                }
+                Some(Macro::Function) => {
+                    return Err(format!(
+                        "function-like macro `{name}` can not be used in a conditional expression"
+                    ));
+                }
+                // Undefined identifiers evaluate to 0.
+                None => output.push(Token::Number(0)),
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Split a directive line into the directive name and the rest of the line.
+fn split_directive(line: &str) -> Option<(&str, &str)> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let end = directive
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(directive.len());
+    Some(directive.split_at(end))
+}
+
+/// Split the macro name from the start of a directive.
+fn macro_name(rest: &str) -> Result<(&str, &str), String> {
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| !is_identifier_char(c))
+        .unwrap_or(rest.len());
+    let (name, body) = rest.split_at(end);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!("expected a macro name, found `{}`", rest.trim()));
+    }
+    Ok((name, body))
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Remove a trailing line comment or any block comments from a directive.
+fn strip_comments(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    loop {
+        let line = rest.find("//");
+        let block = rest.find("/*");
+        match (line, block) {
+            (Some(line), block) if block.is_none_or(|block| line < block) => {
+                output.push_str(&rest[..line]);
+                return output;
+            }
+            (_, Some(block)) => {
+                output.push_str(&rest[..block]);
+                output.push(' ');
+                match rest[block + 2..].find("*/") {
+                    Some(end) => rest = &rest[block + 2 + end + 2..],
+                    None => return output,
+                }
+            }
+            _ => {
+                output.push_str(rest);
+                return output;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Identifier(String),
+    Punct(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Identifier(name) => f.write_str(name),
+            Token::Punct(punct) => f.write_str(punct),
+        }
+    }
+}
+
+/// Punctuators allowed in conditional expressions, longest first.
+const PUNCTUATORS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<<", ">>", "(", ")", "!", "~", "+", "-", "*", "/", "%",
+    "<", ">", "&", "^", "|", "?", ":",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !is_identifier_char(c))
+                .unwrap_or(rest.len());
+            let (literal, tail) = rest.split_at(end);
+            tokens.push(Token::Number(parse_integer(literal)?));
+            rest = tail;
+        } else if is_identifier_char(c) {
+            let end = rest
+                .find(|c: char| !is_identifier_char(c))
+                .unwrap_or(rest.len());
+            let (name, tail) = rest.split_at(end);
+            tokens.push(Token::Identifier(name.to_string()));
+            rest = tail;
+        } else if let Some(punct) = PUNCTUATORS.iter().find(|p| rest.starts_with(**p)) {
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(format!("unexpected character `{c}` in expression"));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn parse_integer(literal: &str) -> Result<i64, String> {
+    let digits = literal.trim_end_matches(['u', 'U']);
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        i64::from_str_radix(hex, 16)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse()
+    };
+    value.map_err(|_| format!("invalid integer literal `{literal}`"))
+}
+
+/// A precedence climbing parser that evaluates conditional expressions as it parses them.
+struct ExpressionParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+/// Binary operators by precedence, loosest first.
+const BINARY_OPERATORS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", ">", "<=", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl ExpressionParser {
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn ternary(&mut self) -> Result<i64, String> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.ternary()?;
+        if !self.eat(":") {
+            return Err(String::from("expected `:` in conditional expression"));
+        }
+        let otherwise = self.ternary()?;
+        Ok(if condition != 0 { then } else { otherwise })
+    }
+
+    fn binary(&mut self, precedence: usize) -> Result<i64, String> {
+        let Some(operators) = BINARY_OPERATORS.get(precedence) else {
+            return self.unary();
+        };
+
+        let mut lhs = self.binary(precedence + 1)?;
+        while let Some(&op) = operators.iter().find(|op| self.eat(op)) {
+            let rhs = self.binary(precedence + 1)?;
+            lhs = match op {
+                "||" => i64::from(lhs != 0 || rhs != 0),
+                "&&" => i64::from(lhs != 0 && rhs != 0),
+                "|" => lhs | rhs,
+                "^" => lhs ^ rhs,
+                "&" => lhs & rhs,
+                "==" => i64::from(lhs == rhs),
+                "!=" => i64::from(lhs != rhs),
+                "<" => i64::from(lhs < rhs),
+                ">" => i64::from(lhs > rhs),
+                "<=" => i64::from(lhs <= rhs),
+                ">=" => i64::from(lhs >= rhs),
+                "<<" => lhs.wrapping_shl(rhs as u32),
+                ">>" => lhs.wrapping_shr(rhs as u32),
+                "+" => lhs.wrapping_add(rhs),
+                "-" => lhs.wrapping_sub(rhs),
+                "*" => lhs.wrapping_mul(rhs),
+                "/" | "%" if rhs == 0 => {
+                    return Err(String::from("division by zero in expression"))
+                }
+                "/" => lhs.wrapping_div(rhs),
+                _ => lhs.wrapping_rem(rhs),
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        if self.eat("!") {
+            return Ok(i64::from(self.unary()? == 0));
+        }
+        if self.eat("~") {
+            return Ok(!self.unary()?);
+        }
+        if self.eat("-") {
+            return Ok(self.unary()?.wrapping_neg());
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("(") {
+            let value = self.ternary()?;
+            if !self.eat(")") {
+                return Err(String::from("expected `)` in expression"));
+            }
+            return Ok(value);
+        }
+
+        match self.tokens.get(self.pos) {
+            Some(Token::Number(value)) => {
+                self.pos += 1;
+                Ok(*value)
+            }
+            Some(token) => Err(format!("unexpected `{token}` in expression")),
+            None => Err(String::from("unexpected end of expression")),
+        }
+    }
+}
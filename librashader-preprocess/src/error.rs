@@ -34,6 +34,55 @@ pub enum PreprocessError {
     /// The stage declared by the shader source was not `vertex` or `fragment`.
     #[error("stage must be either vertex or fragment")]
     InvalidStage,
+    /// An `#elif`, `#else` or `#endif` directive was found without a matching `#if`.
+    #[error("conditional directive without a matching #if at {0}:{1}")]
+    UnmatchedConditional(PathBuf, usize),
+    /// An `#if` block was not closed by an `#endif` before the end of the file.
+    #[error("unterminated conditional directive in {0}")]
+    UnterminatedConditional(PathBuf),
+    /// A conditional directive or macro definition could not be evaluated.
+    #[error("invalid conditional directive at {0}:{1}: {2}")]
+    InvalidConditional(PathBuf, usize, String),
+    /// A file includes itself, either directly or through other includes. The cycle starts and
+    /// ends with the same file.
+    #[error("cyclic include: {}", display_cycle(.0))]
+    IncludeCycle(Vec<PathBuf>),
+    /// One or more parameters were malformed or inconsistent when loading in strict mode.
+    #[error("invalid parameters: {}", display_issues(.0))]
+    InvalidParameters(Vec<ParameterIssue>),
+}
+
+/// A problem with a `#pragma parameter` declaration.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParameterIssue {
+    /// The values of the parameter could not be parsed, so they default to 0.
+    #[error("the values of parameter `{0}` could not be parsed")]
+    Malformed(String),
+    /// The minimum value of the parameter is greater than its maximum value.
+    #[error("the minimum of parameter `{0}` is greater than its maximum")]
+    MinimumExceedsMaximum(String),
+    /// The initial value of the parameter is not between its minimum and maximum values.
+    #[error("the initial value of parameter `{0}` is out of range")]
+    InitialOutOfRange(String),
+    /// The step of the parameter is zero or negative, so it can not be adjusted.
+    #[error("the step of parameter `{0}` is not positive")]
+    InvalidStep(String),
+}
+
+fn display_issues(issues: &[ParameterIssue]) -> String {
+    issues
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn display_cycle(cycle: &[PathBuf]) -> String {
+    cycle
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
 }
 
 impl From<Infallible> for PreprocessError {
@@ -1,22 +1,21 @@
-use crate::{PreprocessError, SourceOutput};
+use crate::conditional::{Conditionals, LineKind, MacroState, SourceLines};
+use crate::{Defines, IncludeTree, PreprocessError, SourceOutput};
 use encoding_rs::{DecoderResult, WINDOWS_1252};
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use librashader_common::fs::{normalize, FileProvider};
+use std::path::{Path, PathBuf};
 use std::str::Lines;
 
 #[cfg(feature = "line_directives")]
 const GL_GOOGLE_CPP_STYLE_LINE_DIRECTIVE: &str =
     "#extension GL_GOOGLE_cpp_style_line_directive : require";
 
-fn read_file(path: impl AsRef<Path>) -> Result<String, PreprocessError> {
+fn read_file(
+    path: impl AsRef<Path>,
+    provider: &dyn FileProvider,
+) -> Result<String, PreprocessError> {
     let path = path.as_ref();
-    let mut buf = Vec::new();
-    File::open(path)
-        .and_then(|mut f| {
-            f.read_to_end(&mut buf)?;
-            Ok(())
-        })
+    let buf = provider
+        .read(path)
         .map_err(|e| PreprocessError::IOError(path.to_path_buf(), e))?;
 
     match String::from_utf8(buf) {
@@ -42,52 +41,116 @@ fn read_file(path: impl AsRef<Path>) -> Result<String, PreprocessError> {
     }
 }
 
-pub fn read_source(path: impl AsRef<Path>) -> Result<String, PreprocessError> {
+pub fn read_source(
+    path: impl AsRef<Path>,
+    defines: &Defines,
+    provider: &dyn FileProvider,
+) -> Result<(String, IncludeTree), PreprocessError> {
     let path = path.as_ref();
-    let source = read_file(path)?;
+    let source = read_file(path, provider)?;
     let mut output = String::new();
 
     let source = source.trim();
     let mut lines = source.lines();
 
-    if let Some(header) = lines.next() {
-        if !header.starts_with("#version ") {
-            return Err(PreprocessError::MissingVersionHeader);
-        }
-        output.push_line(header);
-    } else {
+    let Some(header) = lines.next() else {
         return Err(PreprocessError::UnexpectedEof);
-    }
+    };
+    let Some(version) = header.strip_prefix("#version ") else {
+        return Err(PreprocessError::MissingVersionHeader);
+    };
+    output.push_line(header);
 
     #[cfg(feature = "line_directives")]
     output.push_line(GL_GOOGLE_CPP_STYLE_LINE_DIRECTIVE);
 
-    output.mark_line(2, path.file_name().and_then(|f| f.to_str()).unwrap_or(""));
-    preprocess(lines, path, &mut output)?;
+    for (name, value) in defines.iter() {
+        output.push_line(&format!("#define {name} {value}"));
+    }
 
-    Ok(output)
+    let mut conditionals = Conditionals::new(version, defines);
+    let mut tree = IncludeTree::new(normalize(path));
+    let mut stack = vec![(tree.path.clone(), conditionals.macro_state())];
+    output.mark_line(2, path.file_name().and_then(|f| f.to_str()).unwrap_or(""));
+    preprocess(
+        lines,
+        2,
+        &mut tree,
+        &mut stack,
+        &mut output,
+        &mut conditionals,
+        provider,
+    )?;
+
+    Ok((output, tree))
 }
 
+/// Preprocess the lines of the file at the root of the given include tree, adding the files it
+/// includes to the tree. The stack holds the chain of files currently being included, with the
+/// macros that were defined when each file was entered.
+///
+/// A file may include a file that is still being included, as long as some macro changed since,
+/// which allows mutual includes behind include guards.
 fn preprocess(
     lines: Lines,
-    file_name: impl AsRef<Path>,
+    first_line: usize,
+    tree: &mut IncludeTree,
+    stack: &mut Vec<(PathBuf, MacroState)>,
     output: &mut String,
+    conditionals: &mut Conditionals,
+    provider: &dyn FileProvider,
 ) -> Result<(), PreprocessError> {
-    let file_name = file_name.as_ref();
-    let include_path = file_name.parent().unwrap();
-    let file_name = file_name.file_name().and_then(|f| f.to_str()).unwrap_or("");
+    let path = tree.path.clone();
+    let path = path.as_path();
+    let include_path = path.parent().unwrap();
+    let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+    let depth = conditionals.depth();
+
+    // Whether lines were skipped, and the line number needs to be marked again.
+    let mut skipped = false;
+    for source_line in SourceLines::new(lines, first_line) {
+        let line_no = source_line.line_no;
+        // The line number of the line after this one, which may have been joined with others.
+        let next_line_no = line_no + source_line.count;
+        let line = match conditionals.process_line(&source_line, path)? {
+            LineKind::Active(line) => line,
+            LineKind::Conditional | LineKind::Inactive => {
+                skipped = true;
+                continue;
+            }
+        };
 
-    for (line_no, line) in lines.enumerate() {
-        if let Some(include_file) = line.strip_prefix("#include ") {
+        if skipped {
+            output.mark_line(line_no, file_name);
+            skipped = false;
+        }
+
+        let include_file = if source_line.in_comment {
+            None
+        } else {
+            line.strip_prefix("#include ")
+        };
+        if let Some(include_file) = include_file {
             let include_file = include_file.trim().trim_matches('"');
             if include_file.is_empty() {
                 return Err(PreprocessError::UnexpectedEol(line_no));
             }
 
-            let mut include_path = include_path.to_path_buf();
-            include_path.push(include_file);
+            let include_path = normalize(&include_path.join(include_file));
+            let state = conditionals.macro_state();
+            if let Some(start) = stack
+                .iter()
+                .position(|(path, entered)| path == &include_path && entered == &state)
+            {
+                let mut cycle: Vec<PathBuf> = stack[start..]
+                    .iter()
+                    .map(|(path, _)| path.clone())
+                    .collect();
+                cycle.push(include_path);
+                return Err(PreprocessError::IncludeCycle(cycle));
+            }
 
-            let source = read_file(&include_path)?;
+            let source = read_file(&include_path, provider)?;
             let source = source.trim();
             let lines = source.lines();
 
@@ -96,17 +159,34 @@ fn preprocess(
                 .and_then(|f| f.to_str())
                 .unwrap_or("");
             output.mark_line(1, include_file);
-            preprocess(lines, include_path, output)?;
-            output.mark_line(line_no + 1, file_name);
+
+            let mut include = IncludeTree::new(include_path);
+            stack.push((include.path.clone(), state));
+            preprocess(
+                lines,
+                1,
+                &mut include,
+                stack,
+                output,
+                conditionals,
+                provider,
+            )?;
+            stack.pop();
+            tree.includes.push(include);
+
+            output.mark_line(next_line_no, file_name);
             continue;
         }
-        if line.starts_with("#endif") || line.starts_with("#pragma") {
+        if line.starts_with("#pragma") {
             output.push_line(line);
-            output.mark_line(line_no + 2, file_name);
+            output.mark_line(next_line_no, file_name);
             continue;
         }
 
-        output.push_line(line)
+        output.push_line(line);
+        // Joined lines are written as one line, so the following line is marked again.
+        skipped = source_line.count > 1;
     }
-    Ok(())
+
+    conditionals.close(depth, path)
 }
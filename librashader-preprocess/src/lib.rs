@@ -4,20 +4,27 @@
 //! into a single compilation unit. `#pragma` directives are also parsed and resolved as
 //! [`ShaderParameter`](crate::ShaderParameter) structs.
 //!
+//! Conditional directives such as `#if` and `#ifdef` are evaluated while resolving includes,
+//! so that includes and `#pragma` directives in disabled blocks are ignored. Additional macros
+//! can be defined with [`Defines`](crate::Defines) to compile variants of a shader.
+//!
 //! The resulting [`ShaderSource`](crate::ShaderSource) can then be passed into a
 //! reflection target for reflection and compilation into the target shader format.
 //!
 //! Re-exported as [`librashader::preprocess`](https://docs.rs/librashader/latest/librashader/preprocess/index.html).
+mod conditional;
 mod error;
 mod include;
 mod pragma;
 mod stage;
 
 use crate::include::read_source;
+pub use conditional::Defines;
 pub use error::*;
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
 use librashader_common::ImageFormat;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// The source file for a single shader pass.
 #[derive(Debug, Clone, PartialEq)]
@@ -34,8 +41,64 @@ pub struct ShaderSource {
     /// The list of shader parameters found in the shader source.
     pub parameters: FastHashMap<String, ShaderParameter>,
 
+    /// The ids of the shader parameters in the order they were declared, grouped by
+    /// section header.
+    pub parameter_groups: Vec<ParameterGroup>,
+
+    /// Malformed or inconsistent parameter declarations found in the shader source.
+    pub parameter_issues: Vec<ParameterIssue>,
+
     /// The image format the shader expects.
     pub format: ImageFormat,
+
+    /// The source file and the files it includes.
+    include_tree: IncludeTree,
+}
+
+/// Shader parameters that follow a section header, in the order they were declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterGroup {
+    /// The id of the section header parameter, or `None` for parameters declared before
+    /// the first section header.
+    pub header: Option<String>,
+    /// The ids of the parameters in this group.
+    pub parameters: Vec<String>,
+}
+
+/// A source file and the files it includes, in the order they are included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeTree {
+    /// The path to the file.
+    pub path: PathBuf,
+    /// The files included by this file.
+    pub includes: Vec<IncludeTree>,
+}
+
+impl IncludeTree {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            includes: Vec::new(),
+        }
+    }
+
+    /// Get the paths to all files in the tree, in the order they are first included.
+    ///
+    /// Files included more than once are only returned once.
+    pub fn files(&self) -> Vec<&Path> {
+        fn visit<'a>(tree: &'a IncludeTree, files: &mut Vec<&'a Path>) {
+            if !files.contains(&tree.path.as_path()) {
+                files.push(&tree.path);
+            }
+            for include in &tree.includes {
+                visit(include, files);
+            }
+        }
+
+        let mut files = Vec::new();
+        visit(self, &mut files);
+        files
+    }
 }
 
 /// A user tweakable parameter for the shader as declared in source.
@@ -55,11 +118,65 @@ pub struct ShaderParameter {
     pub step: f32,
 }
 
+impl ShaderParameter {
+    /// Returns whether the parameter is a section header, which shader authors use to group the
+    /// parameters following it.
+    ///
+    /// Section headers have equal minimum and maximum values, and can not be adjusted.
+    pub fn is_section_header(&self) -> bool {
+        self.minimum == self.maximum
+    }
+}
+
 impl ShaderSource {
+    /// Get the paths to all files the shader source was built from, starting with the source file.
+    pub fn files(&self) -> Vec<&Path> {
+        self.include_tree.files()
+    }
+
+    /// Get the source file and the files it includes.
+    pub fn include_tree(&self) -> &IncludeTree {
+        &self.include_tree
+    }
+
     /// Load the source file at the given path, resolving includes relative to the location of the
     /// source file.
     pub fn load(path: impl AsRef<Path>) -> Result<ShaderSource, PreprocessError> {
-        load_shader_source(path)
+        load_shader_source(path, &Defines::default(), &StdFileProvider)
+    }
+
+    /// Load the source file at the given path, resolving includes relative to the location of the
+    /// source file, reading all files through the given file provider.
+    pub fn load_with_provider(
+        path: impl AsRef<Path>,
+        provider: &dyn FileProvider,
+    ) -> Result<ShaderSource, PreprocessError> {
+        load_shader_source(path, &Defines::default(), provider)
+    }
+
+    /// Load the source file at the given path with the given macros defined, resolving includes
+    /// relative to the location of the source file, reading all files through the given
+    /// file provider.
+    pub fn load_with_defines(
+        path: impl AsRef<Path>,
+        defines: &Defines,
+        provider: &dyn FileProvider,
+    ) -> Result<ShaderSource, PreprocessError> {
+        load_shader_source(path, defines, provider)
+    }
+
+    /// Load the source file at the given path like [`ShaderSource::load_with_defines`], failing
+    /// if any parameter declaration is malformed or inconsistent.
+    pub fn load_strict(
+        path: impl AsRef<Path>,
+        defines: &Defines,
+        provider: &dyn FileProvider,
+    ) -> Result<ShaderSource, PreprocessError> {
+        let source = load_shader_source(path, defines, provider)?;
+        if !source.parameter_issues.is_empty() {
+            return Err(PreprocessError::InvalidParameters(source.parameter_issues));
+        }
+        Ok(source)
     }
 }
 
@@ -78,8 +195,12 @@ impl SourceOutput for String {
     }
 }
 
-pub(crate) fn load_shader_source(path: impl AsRef<Path>) -> Result<ShaderSource, PreprocessError> {
-    let source = read_source(path)?;
+pub(crate) fn load_shader_source(
+    path: impl AsRef<Path>,
+    defines: &Defines,
+    provider: &dyn FileProvider,
+) -> Result<ShaderSource, PreprocessError> {
+    let (source, include_tree) = read_source(path, defines, provider)?;
     let meta = pragma::parse_pragma_meta(&source)?;
     let text = stage::process_stages(&source)?;
     let parameters = FastHashMap::from_iter(meta.parameters.into_iter().map(|p| (p.id.clone(), p)));
@@ -89,19 +210,35 @@ pub(crate) fn load_shader_source(path: impl AsRef<Path>) -> Result<ShaderSource,
         fragment: text.fragment,
         name: meta.name,
         parameters,
+        parameter_groups: meta.groups,
+        parameter_issues: meta.issues,
         format: meta.format,
+        include_tree,
     })
 }
 
 #[cfg(test)]
 mod test {
     use crate::include::read_source;
-    use crate::{load_shader_source, pragma};
+    use crate::{
+        load_shader_source, pragma, Defines, ParameterIssue, PreprocessError, ShaderSource,
+    };
+    use librashader_common::fs::{MemoryFileProvider, StdFileProvider};
+    use std::path::PathBuf;
+
+    /// An absolute path from a `/`-separated path relative to the filesystem root of the platform.
+    fn absolute(path: &str) -> PathBuf {
+        let root = if cfg!(windows) { r"C:\" } else { "/" };
+        path.split('/')
+            .fold(PathBuf::from(root), |path, component| path.join(component))
+    }
 
     #[test]
     pub fn load_file() {
         let result = load_shader_source(
             "../test/slang-shaders/blurs/shaders/royale/blur3x3-last-pass.slang",
+            &Defines::default(),
+            &StdFileProvider,
         )
         .unwrap();
         eprintln!("{:#}", result.vertex)
@@ -109,20 +246,351 @@ mod test {
 
     #[test]
     pub fn preprocess_file() {
-        let result =
-            read_source("../test/slang-shaders/blurs/shaders/royale/blur3x3-last-pass.slang")
-                .unwrap();
+        let (result, _) = read_source(
+            "../test/slang-shaders/blurs/shaders/royale/blur3x3-last-pass.slang",
+            &Defines::default(),
+            &StdFileProvider,
+        )
+        .unwrap();
         eprintln!("{result}")
     }
 
     #[test]
     pub fn get_param_pragmas() {
-        let result = read_source(
+        let (result, _) = read_source(
             "../test/slang-shaders/crt/shaders/crt-maximus-royale/src/ntsc_pass1.slang",
+            &Defines::default(),
+            &StdFileProvider,
         )
         .unwrap();
 
         let params = pragma::parse_pragma_meta(result).unwrap();
         eprintln!("{params:?}")
     }
+
+    #[test]
+    pub fn load_file_from_provider() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#include \"include/common.inc\"\n#pragma stage vertex\nvoid main() {}\n",
+        );
+        provider.insert(
+            absolute("shaders/include/common.inc"),
+            "#pragma parameter Strength \"Strength\" 0.5 0.0 1.0 0.1\n",
+        );
+
+        let result = load_shader_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap();
+        assert!(result.parameters.contains_key("Strength"));
+    }
+
+    #[test]
+    pub fn strict_mode_rejects_invalid_parameters() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#pragma name Pass\n#pragma parameter Inverted \"Inverted\" 0.5 1.0 0.0 0.1\n",
+        );
+
+        let defines = Defines::default();
+        let source =
+            ShaderSource::load_with_defines(absolute("shaders/pass.slang"), &defines, &provider)
+                .unwrap();
+        assert_eq!(source.name.as_deref(), Some("Pass"));
+        assert_eq!(
+            source.parameter_issues,
+            vec![ParameterIssue::MinimumExceedsMaximum(String::from(
+                "Inverted"
+            ))]
+        );
+
+        let error = ShaderSource::load_strict(absolute("shaders/pass.slang"), &defines, &provider)
+            .unwrap_err();
+        assert!(matches!(error, PreprocessError::InvalidParameters(issues) if issues.len() == 1));
+    }
+
+    fn conditional_provider() -> MemoryFileProvider {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            r#"#version 450
+#define USE_MASK 1
+#if 0
+#pragma parameter Disabled "Disabled" 0.5 0.0 1.0 0.1
+#endif
+#if USE_MASK && QUALITY >= 2
+#include "mask.inc"
+#elif defined(QUALITY)
+#pragma parameter Low "Low" 0.5 0.0 1.0 0.1
+#else
+#include "missing.inc"
+#endif
+#ifndef VULKAN
+#pragma parameter NotVulkan "Not Vulkan" 0.5 0.0 1.0 0.1
+#endif
+#pragma stage fragment
+void main() {}
+"#,
+        );
+        provider.insert(
+            absolute("shaders/mask.inc"),
+            "#ifdef USE_MASK
+#pragma parameter Mask \"Mask\" 0.5 0.0 1.0 0.1
+#endif
+",
+        );
+        provider
+    }
+
+    #[test]
+    pub fn evaluates_conditionals_with_defines() {
+        let provider = conditional_provider();
+
+        let mut defines = Defines::new();
+        defines.define("QUALITY", "2");
+        let result =
+            load_shader_source(absolute("shaders/pass.slang"), &defines, &provider).unwrap();
+        let mut parameters: Vec<&str> = result.parameters.keys().map(String::as_str).collect();
+        parameters.sort();
+        assert_eq!(parameters, vec!["Mask"]);
+        assert!(result.fragment.contains("#define QUALITY 2\n"));
+        assert!(result.fragment.contains("#define USE_MASK 1\n"));
+        assert!(!result.fragment.contains("#if"));
+
+        defines.define("QUALITY", "1");
+        let result =
+            load_shader_source(absolute("shaders/pass.slang"), &defines, &provider).unwrap();
+        let parameters: Vec<&str> = result.parameters.keys().map(String::as_str).collect();
+        assert_eq!(parameters, vec!["Low"]);
+    }
+
+    #[test]
+    pub fn skips_disabled_includes() {
+        let provider = conditional_provider();
+
+        let error = load_shader_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, PreprocessError::IOError(path, _) if path.ends_with("missing.inc"))
+        );
+    }
+
+    #[test]
+    pub fn marks_lines_after_disabled_blocks() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#ifdef DISABLED\nfloat a;\n#endif\nfloat b;\n",
+        );
+
+        let (source, _) = read_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap();
+        #[cfg(feature = "line_directives")]
+        assert!(source.ends_with("#line 5 \"pass.slang\"\nfloat b;\n"));
+        assert!(!source.contains("float a;"));
+    }
+
+    #[test]
+    pub fn ignores_directives_in_block_comments() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n/* disabled:\n#if 0\n*/\nfloat a; /* #endif\n#include \"missing.inc\"\n*/\nfloat b;\n",
+        );
+
+        let (source, _) = read_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap();
+        assert!(source.contains("float a;"));
+        assert!(source.contains("float b;"));
+    }
+
+    #[test]
+    pub fn joins_continued_directives() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#if defined(A) && \\\n    defined(B)\nfloat a;\n#endif\nfloat b;\n",
+        );
+
+        let mut defines = Defines::new();
+        defines.define("A", "1");
+        let (source, _) = read_source(absolute("shaders/pass.slang"), &defines, &provider).unwrap();
+        assert!(!source.contains("float a;"));
+        #[cfg(feature = "line_directives")]
+        assert!(source.ends_with("#line 6 \"pass.slang\"\nfloat b;\n"));
+
+        defines.define("B", "1");
+        let (source, _) = read_source(absolute("shaders/pass.slang"), &defines, &provider).unwrap();
+        assert!(source.contains("float a;"));
+    }
+
+    #[test]
+    pub fn rejects_unbalanced_conditionals() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(absolute("shaders/open.slang"), "#version 450\n#if 1\n");
+        provider.insert(absolute("shaders/close.slang"), "#version 450\n#endif\n");
+        provider.insert(
+            absolute("shaders/invalid.slang"),
+            "#version 450\n#if 1 +\n#endif\n",
+        );
+
+        let defines = Defines::default();
+        assert!(matches!(
+            load_shader_source(absolute("shaders/open.slang"), &defines, &provider),
+            Err(PreprocessError::UnterminatedConditional(_))
+        ));
+        assert!(matches!(
+            load_shader_source(absolute("shaders/close.slang"), &defines, &provider),
+            Err(PreprocessError::UnmatchedConditional(_, 2))
+        ));
+        assert!(matches!(
+            load_shader_source(absolute("shaders/invalid.slang"), &defines, &provider),
+            Err(PreprocessError::InvalidConditional(_, 2, _))
+        ));
+    }
+
+    #[test]
+    pub fn reports_include_tree() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#include \"include/a.inc\"\n#include \"include/b.inc\"\n",
+        );
+        provider.insert(
+            absolute("shaders/include/a.inc"),
+            "#include \"common.inc\"\n",
+        );
+        provider.insert(
+            absolute("shaders/include/b.inc"),
+            "#include \"../include/common.inc\"\n",
+        );
+        provider.insert(absolute("shaders/include/common.inc"), "float common;\n");
+
+        let result = load_shader_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap();
+        let tree = result.include_tree();
+        assert_eq!(tree.path, absolute("shaders/pass.slang"));
+        assert_eq!(tree.includes.len(), 2);
+        assert_eq!(tree.includes[1].path, absolute("shaders/include/b.inc"));
+        assert_eq!(
+            tree.includes[1].includes[0].path,
+            absolute("shaders/include/common.inc")
+        );
+        assert_eq!(
+            result.files(),
+            vec![
+                absolute("shaders/pass.slang").as_path(),
+                absolute("shaders/include/a.inc").as_path(),
+                absolute("shaders/include/common.inc").as_path(),
+                absolute("shaders/include/b.inc").as_path(),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn rejects_include_cycle() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#include \"a.inc\"\n",
+        );
+        provider.insert(absolute("shaders/a.inc"), "#include \"include/b.inc\"\n");
+        provider.insert(absolute("shaders/include/b.inc"), "#include \"../a.inc\"\n");
+
+        let error = load_shader_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap_err();
+        let PreprocessError::IncludeCycle(cycle) = &error else {
+            panic!("expected an include cycle, got {error:?}");
+        };
+        assert_eq!(
+            cycle,
+            &[
+                absolute("shaders/a.inc"),
+                absolute("shaders/include/b.inc"),
+                absolute("shaders/a.inc"),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "cyclic include: {a} -> {b} -> {a}",
+                a = absolute("shaders/a.inc").display(),
+                b = absolute("shaders/include/b.inc").display(),
+            )
+        );
+    }
+
+    #[test]
+    pub fn allows_guarded_mutual_includes() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#include \"a.inc\"\n",
+        );
+        provider.insert(
+            absolute("shaders/a.inc"),
+            "#ifndef A_INC\n#define A_INC\n#include \"b.inc\"\nfloat a;\n#endif\n",
+        );
+        provider.insert(
+            absolute("shaders/b.inc"),
+            "#ifndef B_INC\n#define B_INC\n#include \"a.inc\"\nfloat b;\n#endif\n",
+        );
+
+        let (source, _) = read_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap();
+        assert_eq!(source.matches("float a;").count(), 1);
+        assert_eq!(source.matches("float b;").count(), 1);
+        assert!(source.find("float b;") < source.find("float a;"));
+    }
+
+    #[test]
+    pub fn allows_repeated_guarded_include() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("shaders/pass.slang"),
+            "#version 450\n#include \"common.inc\"\n#include \"common.inc\"\n",
+        );
+        provider.insert(
+            absolute("shaders/common.inc"),
+            "#ifndef COMMON_INC\n#define COMMON_INC\nfloat common;\n#endif\n",
+        );
+
+        let result = load_shader_source(
+            absolute("shaders/pass.slang"),
+            &Defines::default(),
+            &provider,
+        )
+        .unwrap();
+        assert_eq!(result.fragment.matches("float common;").count(), 1);
+        assert_eq!(result.files().len(), 2);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{PreprocessError, ShaderParameter};
+use crate::{ParameterGroup, ParameterIssue, PreprocessError, ShaderParameter};
 use librashader_common::ImageFormat;
 use nom::bytes::complete::{is_not, tag, take_while};
 
@@ -13,9 +13,15 @@ pub(crate) struct ShaderMeta {
     pub(crate) format: ImageFormat,
     pub(crate) parameters: Vec<ShaderParameter>,
     pub(crate) name: Option<String>,
+    pub(crate) groups: Vec<ParameterGroup>,
+    pub(crate) issues: Vec<ParameterIssue>,
 }
 
-fn parse_parameter_string(input: &str) -> Result<ShaderParameter, PreprocessError> {
+/// Parse a parameter pragma, returning the parameter and an issue if its values could
+/// not be parsed.
+fn parse_parameter_string(
+    input: &str,
+) -> Result<(ShaderParameter, Option<ParameterIssue>), PreprocessError> {
     fn parse_parameter_string_name(input: &str) -> IResult<&str, (&str, &str)> {
         let (input, _) = tag("#pragma parameter ")(input)?;
         let (input, name) = take_while(|c| c != ' ' && c != '\t')(input)?;
@@ -57,16 +63,38 @@ fn parse_parameter_string(input: &str) -> Result<ShaderParameter, PreprocessErro
     // some shaders do some really funky things with their pragmas so we need to be lenient and ignore
     // that it can be set at all.
     if let Ok((_, param)) = parse_parameter_string_inner(name, description, params) {
-        Ok(param)
+        Ok((param, None))
     } else {
-        Ok(ShaderParameter {
-            id: name.to_string(),
-            description: description.to_string(),
-            initial: 0f32,
-            minimum: 0f32,
-            maximum: 0f32,
-            step: 0f32,
-        })
+        Ok((
+            ShaderParameter {
+                id: name.to_string(),
+                description: description.to_string(),
+                initial: 0f32,
+                minimum: 0f32,
+                maximum: 0f32,
+                step: 0f32,
+            },
+            Some(ParameterIssue::Malformed(name.to_string())),
+        ))
+    }
+}
+
+/// Check that the range and step of a parameter are consistent.
+fn check_parameter(parameter: &ShaderParameter) -> Option<ParameterIssue> {
+    // Section headers can not be adjusted, so their values are meaningless.
+    if parameter.is_section_header() {
+        return None;
+    }
+
+    let id = parameter.id.clone();
+    if parameter.minimum > parameter.maximum {
+        Some(ParameterIssue::MinimumExceedsMaximum(id))
+    } else if parameter.initial < parameter.minimum || parameter.initial > parameter.maximum {
+        Some(ParameterIssue::InitialOutOfRange(id))
+    } else if parameter.step <= 0f32 {
+        Some(ParameterIssue::InvalidStep(id))
+    } else {
+        None
     }
 }
 
@@ -75,14 +103,35 @@ pub(crate) fn parse_pragma_meta(source: impl AsRef<str>) -> Result<ShaderMeta, P
     let mut parameters: Vec<ShaderParameter> = Vec::new();
     let mut format = ImageFormat::default();
     let mut name = None;
+    let mut groups: Vec<ParameterGroup> = Vec::new();
+    let mut issues = Vec::new();
     for line in source.lines() {
         if line.starts_with("#pragma parameter ") {
-            let parameter = parse_parameter_string(line)?;
+            let (parameter, issue) = parse_parameter_string(line)?;
             if let Some(existing) = parameters.iter().find(|&p| p.id == parameter.id) {
                 if existing != &parameter {
                     return Err(PreprocessError::DuplicatePragmaError(parameter.id));
                 }
             } else {
+                // The fallback values of a malformed parameter would make it look like a header.
+                let malformed = issue.is_some();
+                if let Some(issue) = issue.or_else(|| check_parameter(&parameter)) {
+                    issues.push(issue);
+                }
+
+                if !malformed && parameter.is_section_header() {
+                    groups.push(ParameterGroup {
+                        header: Some(parameter.id.clone()),
+                        parameters: Vec::new(),
+                    });
+                } else if let Some(group) = groups.last_mut() {
+                    group.parameters.push(parameter.id.clone());
+                } else {
+                    groups.push(ParameterGroup {
+                        header: None,
+                        parameters: vec![parameter.id.clone()],
+                    });
+                }
                 parameters.push(parameter);
             }
         }
@@ -100,12 +149,12 @@ pub(crate) fn parse_pragma_meta(source: impl AsRef<str>) -> Result<ShaderMeta, P
             }
         }
 
-        if line.starts_with("#pragma name ") {
+        if let Some(name_string) = line.strip_prefix("#pragma name ") {
             if name.is_some() {
                 return Err(PreprocessError::DuplicatePragmaError(line.to_string()));
             }
 
-            name = Some(line.trim().to_string())
+            name = Some(name_string.trim().to_string())
         }
     }
 
@@ -113,13 +162,15 @@ pub(crate) fn parse_pragma_meta(source: impl AsRef<str>) -> Result<ShaderMeta, P
         name,
         format,
         parameters,
+        groups,
+        issues,
     })
 }
 
 #[cfg(test)]
 mod test {
-    use crate::pragma::parse_parameter_string;
-    use crate::ShaderParameter;
+    use crate::pragma::{parse_parameter_string, parse_pragma_meta};
+    use crate::{ParameterGroup, ParameterIssue, ShaderParameter};
 
     #[test]
     fn parses_parameter_pragma() {
@@ -130,7 +181,7 @@ mod test {
             minimum: -10.0,
             maximum: 10.0,
             step: 0.25
-        }, parse_parameter_string(r#"#pragma parameter exc "orizontal correction hack (games where players stay at center)" 0.0 -10.0 10.0 0.25"#).unwrap())
+        }, parse_parameter_string(r#"#pragma parameter exc "orizontal correction hack (games where players stay at center)" 0.0 -10.0 10.0 0.25"#).unwrap().0)
     }
 
     #[test]
@@ -142,6 +193,90 @@ mod test {
             minimum: 25.0,
             maximum: 1600.0,
             step: 25.0
-        }, parse_parameter_string(r#"#pragma parameter HSM_CORE_RES_SAMPLING_MULT_SCANLINE_DIR			"          Scanline Dir Multiplier"  100 25 1600 25"#).unwrap())
+        }, parse_parameter_string(r#"#pragma parameter HSM_CORE_RES_SAMPLING_MULT_SCANLINE_DIR			"          Scanline Dir Multiplier"  100 25 1600 25"#).unwrap().0)
+    }
+
+    #[test]
+    fn parses_name_pragma() {
+        let meta = parse_pragma_meta("#pragma name PassAlias\n").unwrap();
+        assert_eq!(meta.name.as_deref(), Some("PassAlias"));
+    }
+
+    #[test]
+    fn reports_parameter_issues() {
+        let meta = parse_pragma_meta(
+            r#"#pragma parameter Malformed "Malformed" 0.5 0.0 one 0.1
+#pragma parameter Inverted "Inverted" 0.5 1.0 0.0 0.1
+#pragma parameter OutOfRange "Out of Range" 2.0 0.0 1.0 0.1
+#pragma parameter ZeroStep "Zero Step" 0.5 0.0 1.0 0.0
+#pragma parameter Header "[ Header ]" 0.0 0.0 0.0 0.0
+#pragma parameter Valid "Valid" 0.5 0.0 1.0 0.1
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.issues,
+            vec![
+                ParameterIssue::Malformed(String::from("Malformed")),
+                ParameterIssue::MinimumExceedsMaximum(String::from("Inverted")),
+                ParameterIssue::InitialOutOfRange(String::from("OutOfRange")),
+                ParameterIssue::InvalidStep(String::from("ZeroStep")),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_parameters_by_section_header() {
+        let meta = parse_pragma_meta(
+            r#"#pragma parameter First "First" 0.5 0.0 1.0 0.1
+#pragma parameter ColorHeader "--- Color ---" 0.0 0.0 0.0 0.0
+#pragma parameter Gamma "Gamma" 2.2 1.0 3.0 0.1
+#pragma parameter Saturation "Saturation" 1.0 0.0 2.0 0.1
+#pragma parameter EmptyHeader "--- Empty ---" 1.0 1.0 1.0 1.0
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.groups,
+            vec![
+                ParameterGroup {
+                    header: None,
+                    parameters: vec![String::from("First")],
+                },
+                ParameterGroup {
+                    header: Some(String::from("ColorHeader")),
+                    parameters: vec![String::from("Gamma"), String::from("Saturation")],
+                },
+                ParameterGroup {
+                    header: Some(String::from("EmptyHeader")),
+                    parameters: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_group_malformed_parameters_as_headers() {
+        let meta = parse_pragma_meta(
+            r#"#pragma parameter ColorHeader "--- Color ---" 0.0 0.0 0.0 0.0
+#pragma parameter Malformed "Malformed" 0.5 0.0 one 0.1
+#pragma parameter Gamma "Gamma" 2.2 1.0 3.0 0.1
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.issues,
+            vec![ParameterIssue::Malformed(String::from("Malformed"))]
+        );
+        assert_eq!(
+            meta.groups,
+            vec![ParameterGroup {
+                header: Some(String::from("ColorHeader")),
+                parameters: vec![String::from("Malformed"), String::from("Gamma")],
+            }]
+        );
     }
 }
@@ -4,6 +4,7 @@
 //!
 //! Implements wildcard replacement of shader paths specified in
 //! [RetroArch#15023](https://github.com/libretro/RetroArch/pull/15023).
+use librashader_common::fs::FileProvider;
 use librashader_common::map::FastHashMap;
 use once_cell::sync::Lazy;
 use regex::bytes::Regex;
@@ -52,7 +53,7 @@ impl Display for VideoDriver {
 
 /// Valid extensions for shader extensions.
 #[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ShaderExtension {
     /// `.slang`
     Slang = 0,
@@ -62,6 +63,19 @@ pub enum ShaderExtension {
     Cg,
 }
 
+impl ShaderExtension {
+    /// Get the shader extension of the file at the given path, if it is a known shader extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "slang" => Some(ShaderExtension::Slang),
+            "glsl" => Some(ShaderExtension::Glsl),
+            "cg" => Some(ShaderExtension::Cg),
+            _ => None,
+        }
+    }
+}
+
 impl Display for ShaderExtension {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -74,7 +88,7 @@ impl Display for ShaderExtension {
 
 /// Valid extensions for shader presets
 #[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PresetExtension {
     /// `.slangp`
     Slangp = 0,
@@ -84,6 +98,28 @@ pub enum PresetExtension {
     Cgp,
 }
 
+impl PresetExtension {
+    /// Get the preset extension of the file at the given path, if it is a known preset extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "slangp" => Some(PresetExtension::Slangp),
+            "glslp" => Some(PresetExtension::Glslp),
+            "cgp" => Some(PresetExtension::Cgp),
+            _ => None,
+        }
+    }
+
+    /// Get the extension of the shaders that presets with this extension refer to.
+    pub fn shader_extension(&self) -> ShaderExtension {
+        match self {
+            PresetExtension::Slangp => ShaderExtension::Slang,
+            PresetExtension::Glslp => ShaderExtension::Glsl,
+            PresetExtension::Cgp => ShaderExtension::Cg,
+        }
+    }
+}
+
 impl Display for PresetExtension {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -324,6 +360,17 @@ impl WildcardContext {
             let preset_dir_name = preset_dir_name.to_string_lossy();
             self.prepend_item(ContextItem::PresetDirectory(preset_dir_name.into()))
         }
+
+        // Legacy presets should resolve wildcards to other legacy presets and shaders.
+        #[cfg(feature = "parse_legacy_glsl")]
+        if let Some(extension) = PresetExtension::from_path(path) {
+            if extension != PresetExtension::Slangp {
+                self.prepend_item(ContextItem::VideoDriverPresetExtension(extension));
+                self.prepend_item(ContextItem::VideoDriverShaderExtension(
+                    extension.shader_extension(),
+                ));
+            }
+        }
     }
 
     pub fn to_hashmap(mut self) -> FastHashMap<String, String> {
@@ -361,7 +408,11 @@ impl WildcardContext {
 }
 
 #[rustversion::since(1.74)]
-pub(crate) fn apply_context(path: &mut PathBuf, context: &FastHashMap<String, String>) {
+pub(crate) fn apply_context(
+    path: &mut PathBuf,
+    context: &FastHashMap<String, String>,
+    provider: &dyn FileProvider,
+) {
     use std::ffi::{OsStr, OsString};
 
     static WILDCARD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\\$([A-Z-_]+)\\$").unwrap());
@@ -403,13 +454,17 @@ pub(crate) fn apply_context(path: &mut PathBuf, context: &FastHashMap<String, St
     }
 
     // If no wildcards are found within the path, or the path after replacing the wildcards does not exist on disk, the path returned will be unaffected.
-    if let Ok(true) = new_path.try_exists() {
+    if provider.exists(&new_path) {
         *path = new_path;
     }
 }
 
 #[rustversion::before(1.74)]
-pub(crate) fn apply_context(path: &mut PathBuf, context: &FastHashMap<String, String>) {
+pub(crate) fn apply_context(
+    path: &mut PathBuf,
+    context: &FastHashMap<String, String>,
+    provider: &dyn FileProvider,
+) {
     use os_str_bytes::RawOsStr;
     static WILDCARD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\\$([A-Z-_]+)\\$").unwrap());
     if context.is_empty() {
@@ -453,7 +508,7 @@ pub(crate) fn apply_context(path: &mut PathBuf, context: &FastHashMap<String, St
     }
 
     // If no wildcards are found within the path, or the path after replacing the wildcards does not exist on disk, the path returned will be unaffected.
-    if let Ok(true) = new_path.try_exists() {
+    if provider.exists(&new_path) {
         *path = new_path;
     }
 }
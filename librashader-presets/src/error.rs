@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use crate::parse::Span;
+use std::fmt::{Display, Formatter, Write};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Error type for preset parsing.
@@ -6,14 +8,20 @@ use thiserror::Error;
 pub enum ParsePresetError {
     /// An error occurred when tokenizing the preset file.
     #[error("shader preset lexing error")]
-    LexerError { offset: usize, row: u32, col: usize },
+    LexerError {
+        offset: usize,
+        row: u32,
+        col: usize,
+        location: Box<ParseErrorLocation>,
+    },
     /// An error occurred when parsing the preset file.
-    #[error("shader preset parse error")]
+    #[error("shader preset parse error: {kind}")]
     ParserError {
         offset: usize,
         row: u32,
         col: usize,
         kind: ParseErrorKind,
+        location: Box<ParseErrorLocation>,
     },
     /// The scale type was invalid.
     #[error("invalid scale type")]
@@ -27,6 +35,14 @@ pub enum ParsePresetError {
     /// An IO error occurred when reading the shader preset.
     #[error("the file was not found during resolution")]
     IOError(PathBuf, std::io::Error),
+    /// A shader, texture, or reference path in the shader preset could not be resolved.
+    #[error("the path referenced by the shader preset was not found during resolution")]
+    UnresolvedPath {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+        location: Box<ParseErrorLocation>,
+    },
     /// The shader preset did not contain valid UTF-8 bytes.
     #[error("expected utf8 bytes but got invalid utf8")]
     Utf8Error(Vec<u8>),
@@ -45,4 +61,288 @@ pub enum ParseErrorKind {
     Float,
     /// Expected a boolean.
     Bool,
+    /// Expected a scale type.
+    ScaleType,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Index(key) => write!(f, "expected an indexed key `{key}N`"),
+            ParseErrorKind::Int => f.write_str("expected a signed integer"),
+            ParseErrorKind::UnsignedInt => f.write_str("expected an unsigned integer"),
+            ParseErrorKind::Float => f.write_str("expected a float"),
+            ParseErrorKind::Bool => f.write_str("expected a boolean"),
+            ParseErrorKind::ScaleType => {
+                f.write_str("expected one of `source`, `viewport`, `absolute`, or `original`")
+            }
+        }
+    }
+}
+
+/// The location in a shader preset file where an error occurred.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseErrorLocation {
+    /// The path to the preset file the error occurred in, if known.
+    ///
+    /// When the preset uses `#reference`, this is the file in the reference chain
+    /// that contains the offending text.
+    pub path: Option<PathBuf>,
+    /// The key of the entry the error occurred in, if known.
+    pub key: Option<String>,
+    /// The byte offset of the offending text from the start of the file.
+    pub offset: usize,
+    /// The line of the offending text, starting from 1.
+    pub row: u32,
+    /// The byte column of the offending text, starting from 1.
+    pub col: usize,
+    /// The length of the offending text in bytes.
+    pub len: usize,
+    /// The full line of source text containing the offending text.
+    pub line: String,
+}
+
+impl ParseErrorLocation {
+    pub(crate) fn from_span(span: Span) -> Box<Self> {
+        let fragment = span.fragment();
+        Box::new(Self {
+            path: None,
+            key: None,
+            offset: span.location_offset(),
+            row: span.location_line(),
+            col: span.get_column(),
+            len: fragment.find(['\r', '\n']).unwrap_or(fragment.len()),
+            line: String::new(),
+        })
+    }
+
+    /// Attach the preset file and entry key, if not already known.
+    pub(crate) fn attach(&mut self, path: &Path, key: Option<&str>, contents: &str) {
+        if self.path.is_none() {
+            self.path = Some(path.to_path_buf());
+            self.line = contents
+                .lines()
+                .nth(self.row.saturating_sub(1) as usize)
+                .unwrap_or_default()
+                .to_string();
+        }
+
+        if self.key.is_none() {
+            self.key = key.map(str::to_string);
+        }
+    }
+
+    /// Write the location and the offending source line, underlined in the style of rustc.
+    fn render_into(&self, output: &mut String, note: Option<String>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.row.to_string().len());
+        match &self.path {
+            Some(path) => writeln!(
+                output,
+                "{gutter}--> {}:{}:{}",
+                path.display(),
+                self.row,
+                self.col
+            )?,
+            None => writeln!(output, "{gutter}--> {}:{}", self.row, self.col)?,
+        }
+
+        if !self.line.is_empty() {
+            let start = self.col.saturating_sub(1).min(self.line.len());
+            let end = (start + self.len).min(self.line.len());
+            let prefix = self.line.get(..start).unwrap_or_default();
+            let highlighted = self.line.get(start..end).unwrap_or_default();
+
+            // Tabs are expanded so the caret lines up regardless of the terminal tab width.
+            let padding: String = prefix
+                .chars()
+                .map(|c| if c == '\t' { "    " } else { " " })
+                .collect();
+            let carets = "^".repeat(highlighted.chars().count().max(1));
+
+            writeln!(output, "{gutter} |")?;
+            writeln!(output, "{} | {}", self.row, self.line.replace('\t', "    "))?;
+            writeln!(output, "{gutter} | {padding}{carets}")?;
+        }
+
+        if let Some(key) = &self.key {
+            writeln!(output, "{gutter} |")?;
+            writeln!(output, "{gutter} = note: in the value of `{key}`")?;
+        }
+
+        if let Some(note) = note {
+            writeln!(output, "{gutter} = note: {note}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ParsePresetError {
+    pub(crate) fn lexer_error(span: Span) -> Self {
+        let location = ParseErrorLocation::from_span(span);
+        ParsePresetError::LexerError {
+            offset: location.offset,
+            row: location.row,
+            col: location.col,
+            location,
+        }
+    }
+
+    pub(crate) fn parser_error(kind: ParseErrorKind, span: Span) -> Self {
+        let location = ParseErrorLocation::from_span(span);
+        ParsePresetError::ParserError {
+            offset: location.offset,
+            row: location.row,
+            col: location.col,
+            kind,
+            location,
+        }
+    }
+
+    /// Get the location in the preset file where the error occurred, if applicable.
+    pub fn location(&self) -> Option<&ParseErrorLocation> {
+        match self {
+            ParsePresetError::LexerError { location, .. }
+            | ParsePresetError::ParserError { location, .. }
+            | ParsePresetError::UnresolvedPath { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Attach the preset file and entry key to the location of the error, if it does not
+    /// already have one.
+    pub(crate) fn located(mut self, path: &Path, key: Option<&str>, contents: &str) -> Self {
+        let location = match &mut self {
+            ParsePresetError::LexerError { location, .. }
+            | ParsePresetError::ParserError { location, .. }
+            | ParsePresetError::UnresolvedPath { location, .. } => location,
+            _ => return self,
+        };
+
+        location.attach(path, key, contents);
+        self
+    }
+
+    /// Render the error as a human-readable diagnostic, with the offending source text
+    /// underlined.
+    ///
+    /// ```text
+    /// error: shader preset parse error: expected a float
+    ///   --> crt/crt-royale.slangp:12:10
+    ///    |
+    /// 12 | scale0 = 2.0x
+    ///    |          ^^^^
+    ///    |
+    ///    = note: in the value of `scale0`
+    /// ```
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        // NOPANIC: writing to a String is infallible.
+        self.render_into(&mut output).unwrap();
+        output
+    }
+
+    fn render_into(&self, output: &mut String) -> std::fmt::Result {
+        writeln!(output, "error: {self}")?;
+
+        match self {
+            ParsePresetError::UnresolvedPath {
+                path,
+                error,
+                location,
+            } => location.render_into(output, Some(format!("{}: {error}", path.display()))),
+            ParsePresetError::LexerError { location, .. }
+            | ParsePresetError::ParserError { location, .. } => location.render_into(output, None),
+            ParsePresetError::IOError(path, error) => {
+                writeln!(output, " --> {}", path.display())?;
+                writeln!(output, "  = note: {error}")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A non-fatal issue found when parsing a shader preset leniently.
+///
+/// Passes and textures with missing paths are kept in the preset with their unresolved path, so
+/// the caller can decide whether to drop them, substitute them, or refuse to load the preset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresetWarning {
+    /// The shader source of a pass was not found.
+    MissingShader {
+        /// The index of the shader pass.
+        index: i32,
+        /// The path that could not be resolved.
+        path: PathBuf,
+        /// The location of the entry in the preset.
+        location: Box<ParseErrorLocation>,
+    },
+    /// A lookup texture was not found.
+    MissingTexture {
+        /// The name of the texture.
+        name: String,
+        /// The path that could not be resolved.
+        path: PathBuf,
+        /// The location of the entry in the preset.
+        location: Box<ParseErrorLocation>,
+    },
+    /// A `#reference` preset was not found, and was skipped.
+    MissingReference {
+        /// The path that could not be resolved.
+        path: PathBuf,
+        /// The location of the `#reference` directive.
+        location: Box<ParseErrorLocation>,
+    },
+}
+
+impl Display for PresetWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresetWarning::MissingShader { index, .. } => {
+                write!(f, "the shader for pass {index} was not found")
+            }
+            PresetWarning::MissingTexture { name, .. } => {
+                write!(f, "the texture `{name}` was not found")
+            }
+            PresetWarning::MissingReference { .. } => {
+                f.write_str("the referenced shader preset was not found")
+            }
+        }
+    }
+}
+
+impl PresetWarning {
+    /// Get the path that could not be resolved.
+    pub fn path(&self) -> &Path {
+        match self {
+            PresetWarning::MissingShader { path, .. }
+            | PresetWarning::MissingTexture { path, .. }
+            | PresetWarning::MissingReference { path, .. } => path,
+        }
+    }
+
+    /// Get the location in the preset file the warning refers to.
+    pub fn location(&self) -> &ParseErrorLocation {
+        match self {
+            PresetWarning::MissingShader { location, .. }
+            | PresetWarning::MissingTexture { location, .. }
+            | PresetWarning::MissingReference { location, .. } => location,
+        }
+    }
+
+    /// Render the warning as a human-readable diagnostic, in the same format as
+    /// [`ParsePresetError::render`].
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        // NOPANIC: writing to a String is infallible.
+        writeln!(output, "warning: {self}")
+            .and_then(|_| {
+                self.location().render_into(
+                    &mut output,
+                    Some(format!("`{}` was not found", self.path().display())),
+                )
+            })
+            .unwrap();
+        output
+    }
 }
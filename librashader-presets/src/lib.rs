@@ -1,6 +1,6 @@
 //! Shader preset definition parsing for librashader.
 //!
-//! This crate contains facilities and types for parsing `.slangp` shader presets files.
+//! This crate contains facilities and types for parsing and writing `.slangp` shader presets files.
 //!
 //! Shader presets contain shader and texture parameters, and the order in which to apply a set of
 //! shaders in a filter chain. A librashader runtime takes a resulting [`ShaderPreset`](crate::ShaderPreset)
@@ -16,7 +16,11 @@ mod error;
 mod extract_if;
 mod parse;
 mod preset;
+mod validate;
+mod write;
 
 pub use context::WildcardContext;
 pub use error::*;
 pub use preset::*;
+pub use validate::{Severity, ValidationIssue};
+pub use write::PresetEntry;
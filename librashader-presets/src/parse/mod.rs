@@ -13,8 +13,9 @@ pub(crate) use token::Token;
 use crate::context::{VideoDriver, WildcardContext};
 use crate::error::ParsePresetError;
 use crate::parse::preset::resolve_values;
-use crate::parse::value::parse_preset;
+use crate::parse::value::{parse_preset, parse_preset_str};
 use crate::ShaderPreset;
+use librashader_common::fs::{FileProvider, StdFileProvider};
 
 pub(crate) fn remove_if<T>(values: &mut Vec<T>, f: impl FnMut(&T) -> bool) -> Option<T> {
     values.iter().position(f).map(|idx| values.remove(idx))
@@ -27,8 +28,9 @@ impl ShaderPreset {
     pub fn try_parse(path: impl AsRef<Path>) -> Result<ShaderPreset, ParsePresetError> {
         let mut context = WildcardContext::new();
         context.add_path_defaults(path.as_ref());
-        let values = parse_preset(path, WildcardContext::new())?;
-        Ok(resolve_values(values))
+        let (values, warnings, files) =
+            parse_preset(path, WildcardContext::new(), &StdFileProvider, false)?;
+        Ok(resolve_values(values, warnings, files))
     }
 
     /// Try to parse the shader preset at the given path.
@@ -41,8 +43,8 @@ impl ShaderPreset {
         let mut context = WildcardContext::new();
         context.add_path_defaults(path.as_ref());
         context.add_video_driver_defaults(driver);
-        let values = parse_preset(path, context)?;
-        Ok(resolve_values(values))
+        let (values, warnings, files) = parse_preset(path, context, &StdFileProvider, false)?;
+        Ok(resolve_values(values, warnings, files))
     }
 
     /// Try to parse the shader preset at the given path, with the exact provided context.
@@ -53,8 +55,50 @@ impl ShaderPreset {
         path: impl AsRef<Path>,
         context: WildcardContext,
     ) -> Result<ShaderPreset, ParsePresetError> {
-        let values = parse_preset(path, context)?;
-        Ok(resolve_values(values))
+        let (values, warnings, files) = parse_preset(path, context, &StdFileProvider, false)?;
+        Ok(resolve_values(values, warnings, files))
+    }
+
+    /// Try to parse the shader preset at the given path, with the exact provided context,
+    /// reading the preset and any referenced files through the given file provider.
+    pub fn try_parse_with_provider(
+        path: impl AsRef<Path>,
+        context: WildcardContext,
+        provider: &dyn FileProvider,
+    ) -> Result<ShaderPreset, ParsePresetError> {
+        let (values, warnings, files) = parse_preset(path, context, provider, false)?;
+        Ok(resolve_values(values, warnings, files))
+    }
+
+    /// Try to parse the shader preset at the given path, with the exact provided context,
+    /// tolerating missing shaders, textures, and referenced presets.
+    ///
+    /// Instead of failing, paths that could not be resolved are collected in
+    /// [`ShaderPreset::warnings`]. Passes and textures with missing paths are kept with the
+    /// unresolved path, so the caller can decide whether to drop or substitute them before
+    /// creating a filter chain. Missing `#reference` presets are skipped.
+    pub fn try_parse_lenient(
+        path: impl AsRef<Path>,
+        context: WildcardContext,
+        provider: &dyn FileProvider,
+    ) -> Result<ShaderPreset, ParsePresetError> {
+        let (values, warnings, files) = parse_preset(path, context, provider, true)?;
+        Ok(resolve_values(values, warnings, files))
+    }
+
+    /// Try to parse a shader preset from a string, with the exact provided context.
+    ///
+    /// Relative paths in the preset are resolved as if the preset were located at `path`.
+    /// The preset itself does not need to exist at `path`, but any referenced presets,
+    /// shaders and textures are resolved through the given file provider.
+    pub fn try_parse_str(
+        source: &str,
+        path: impl AsRef<Path>,
+        context: WildcardContext,
+        provider: &dyn FileProvider,
+    ) -> Result<ShaderPreset, ParsePresetError> {
+        let (values, warnings, files) = parse_preset_str(source, path, context, provider, false)?;
+        Ok(resolve_values(values, warnings, files))
     }
 }
 
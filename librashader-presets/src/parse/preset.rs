@@ -1,9 +1,18 @@
+#[cfg(feature = "parse_legacy_glsl")]
+use crate::context::ShaderExtension;
 use crate::extract_if::MakeExtractIf;
 use crate::parse::remove_if;
 use crate::parse::value::Value;
-use crate::{ParameterConfig, Scale2D, Scaling, ShaderPassConfig, ShaderPreset, TextureConfig};
+use crate::{
+    ParameterConfig, PresetWarning, Scale2D, Scaling, ShaderPassConfig, ShaderPreset, TextureConfig,
+};
+use std::path::PathBuf;
 
-pub fn resolve_values(mut values: Vec<Value>) -> ShaderPreset {
+pub fn resolve_values(
+    mut values: Vec<Value>,
+    warnings: Vec<PresetWarning>,
+    files: Vec<PathBuf>,
+) -> ShaderPreset {
     let textures: Vec<TextureConfig> = values
         .extract_if(|f| matches!(*f, Value::Texture { .. }))
         .map(|value| {
@@ -57,7 +66,20 @@ pub fn resolve_values(mut values: Vec<Value>) -> ShaderPreset {
                 unreachable!("value should be of type feedback_pass")
             }
         })
-        .unwrap_or(0);
+        // RetroArch disables the feedback pass unless one is specified.
+        .unwrap_or(-1);
+
+    #[cfg(feature = "parse_legacy_glsl")]
+    let imports: Vec<crate::ImportConfig> = values
+        .extract_if(|f| matches!(*f, Value::Import(_)))
+        .map(|value| {
+            if let Value::Import(import) = value {
+                import
+            } else {
+                unreachable!("values should be all of type import")
+            }
+        })
+        .collect();
 
     for shader in 0..shader_count {
         if let Some(Value::Shader(id, name)) = remove_if(
@@ -175,6 +197,9 @@ pub fn resolve_values(mut values: Vec<Value>) -> ShaderPreset {
                 },
             };
 
+            #[cfg(feature = "parse_legacy_glsl")]
+            let shader = legacy_framebuffer_defaults(shader);
+
             shaders.push(shader)
         }
     }
@@ -186,5 +211,21 @@ pub fn resolve_values(mut values: Vec<Value>) -> ShaderPreset {
         shaders,
         textures,
         parameters,
+        #[cfg(feature = "parse_legacy_glsl")]
+        imports,
+        warnings,
+        files,
+    }
+}
+
+/// Legacy Cg and GLSL shaders can not declare the format of their framebuffer, and RetroArch
+/// only reads `float_framebufferN` and `srgb_framebufferN` for legacy passes that set a scale type.
+/// Other legacy passes render to the default RGBA8 framebuffer.
+#[cfg(feature = "parse_legacy_glsl")]
+fn legacy_framebuffer_defaults(mut shader: ShaderPassConfig) -> ShaderPassConfig {
+    if !shader.scaling.valid && shader.shader_extension() != Some(ShaderExtension::Slang) {
+        shader.float_framebuffer = false;
+        shader.srgb_framebuffer = false;
     }
+    shader
 }
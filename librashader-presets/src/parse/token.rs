@@ -5,7 +5,7 @@ use nom::bytes::complete::{is_not, take_until};
 use nom::character::complete::{char, line_ending, multispace1, not_line_ending};
 use std::ops::RangeFrom;
 
-use nom::combinator::{eof, map_res, value};
+use nom::combinator::{eof, map_res, opt, recognize, value};
 use nom::error::{ErrorKind, ParseError};
 
 use nom::sequence::delimited;
@@ -69,7 +69,9 @@ where
 
 fn extract_from_quotes(input: Span) -> IResult<Span, Span> {
     // Allow unbalanced quotes because some presets just leave an open quote.
-    let (input, between) = delimited(char('"'), is_not("\""), unbalanced_quote)(input)?;
+    // Empty quotes are an empty value, which unsets the key.
+    let (input, between) =
+        delimited(char('"'), recognize(opt(is_not("\""))), unbalanced_quote)(input)?;
     let (input, _) = opt_whitespace(input)?;
     let (input, _) = eof(input)?;
     Ok((input, between))
@@ -151,19 +153,12 @@ fn parse_tokens(mut span: Span) -> IResult<Span, Vec<Token>> {
 pub fn do_lex(input: &str) -> Result<Vec<Token>, ParsePresetError> {
     let span = Span::new(input.trim_end());
     let (_, tokens) = parse_tokens(span).map_err(|e| match e {
-        nom::Err::Error(e) | nom::Err::Failure(e) => {
-            let input: Span = e.input;
-            println!("{:?}", input);
-            ParsePresetError::LexerError {
-                offset: input.location_offset(),
-                row: input.location_line(),
-                col: input.get_column(),
-            }
-        }
+        nom::Err::Error(e) | nom::Err::Failure(e) => ParsePresetError::lexer_error(e.input),
         _ => ParsePresetError::LexerError {
             offset: 0,
             row: 0,
             col: 0,
+            location: Box::default(),
         },
     })?;
     Ok(tokens)
@@ -1,5 +1,7 @@
-use crate::error::{ParseErrorKind, ParsePresetError};
+use crate::error::{ParseErrorKind, ParseErrorLocation, ParsePresetError, PresetWarning};
 use crate::parse::{remove_if, Span, Token};
+#[cfg(feature = "parse_legacy_glsl")]
+use crate::ImportConfig;
 use crate::{ScaleFactor, ScaleType};
 use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
@@ -10,10 +12,9 @@ use nom::IResult;
 use num_traits::cast::ToPrimitive;
 
 use crate::parse::token::do_lex;
+use librashader_common::fs::FileProvider;
 use librashader_common::map::FastHashMap;
 use librashader_common::{FilterMode, WrapMode};
-use std::fs::File;
-use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -39,6 +40,8 @@ pub enum Value {
     MipmapInput(i32, bool),
     Alias(i32, String),
     Parameter(String, f32),
+    #[cfg(feature = "parse_legacy_glsl")]
+    Import(ImportConfig),
     Texture {
         name: String,
         filter_mode: FilterMode,
@@ -76,24 +79,14 @@ fn from_int(input: Span) -> Result<i32, ParsePresetError> {
     // semicolon crimes or a valid param/texture name listing.
     let to_parse = input.trim().trim_end_matches(";");
     i32::from_str(to_parse)
-        .map_err(|_| ParsePresetError::ParserError {
-            offset: input.location_offset(),
-            row: input.location_line(),
-            col: input.get_column(),
-            kind: ParseErrorKind::Int,
-        })
+        .map_err(|_| ParsePresetError::parser_error(ParseErrorKind::Int, input))
         .or_else(|e| {
             // An even more egregious ✨CRIME✨ is using a float as a shader index.
             let result = f32::from_str(to_parse).map_err(|_| e)?;
             let result = result
                 .trunc()
                 .to_i32()
-                .ok_or(ParsePresetError::ParserError {
-                    offset: input.location_offset(),
-                    row: input.location_line(),
-                    col: input.get_column(),
-                    kind: ParseErrorKind::Int,
-                })?;
+                .ok_or(ParsePresetError::parser_error(ParseErrorKind::Int, input))?;
             Ok(result)
         })
 }
@@ -102,24 +95,16 @@ fn from_ul(input: Span) -> Result<u32, ParsePresetError> {
     // Presets like to commit ✨CRIMES✨ and end their lines with a ";".
     // It's too hard to put this in the lexer because we can't tell between
     // semicolon crimes or a valid param/texture name listing.
-    u32::from_str(input.trim().trim_end_matches(";")).map_err(|_| ParsePresetError::ParserError {
-        offset: input.location_offset(),
-        row: input.location_line(),
-        col: input.get_column(),
-        kind: ParseErrorKind::UnsignedInt,
-    })
+    u32::from_str(input.trim().trim_end_matches(";"))
+        .map_err(|_| ParsePresetError::parser_error(ParseErrorKind::UnsignedInt, input))
 }
 
 fn from_float(input: Span) -> Result<f32, ParsePresetError> {
     // Presets like to commit ✨CRIMES✨ and end their lines with a ";".
     // It's too hard to put this in the lexer because we can't tell between
     // semicolon crimes or a valid param/texture name listing.
-    f32::from_str(input.trim().trim_end_matches(";")).map_err(|_| ParsePresetError::ParserError {
-        offset: input.location_offset(),
-        row: input.location_line(),
-        col: input.get_column(),
-        kind: ParseErrorKind::Float,
-    })
+    f32::from_str(input.trim().trim_end_matches(";"))
+        .map_err(|_| ParsePresetError::parser_error(ParseErrorKind::Float, input))
 }
 
 fn from_bool(input: Span) -> Result<bool, ParsePresetError> {
@@ -127,20 +112,111 @@ fn from_bool(input: Span) -> Result<bool, ParsePresetError> {
         return match i {
             1 => Ok(true),
             0 => Ok(false),
-            _ => Err(ParsePresetError::ParserError {
-                offset: input.location_offset(),
-                row: input.location_line(),
-                col: input.get_column(),
-                kind: ParseErrorKind::Bool,
-            }),
+            _ => Err(ParsePresetError::parser_error(ParseErrorKind::Bool, input)),
         };
     }
-    bool::from_str(input.trim()).map_err(|_| ParsePresetError::ParserError {
-        offset: input.location_offset(),
-        row: input.location_line(),
-        col: input.get_column(),
-        kind: ParseErrorKind::Bool,
-    })
+    bool::from_str(input.trim())
+        .map_err(|_| ParsePresetError::parser_error(ParseErrorKind::Bool, input))
+}
+
+#[cfg(feature = "parse_legacy_glsl")]
+fn from_hex(input: Span) -> Result<u32, ParsePresetError> {
+    let to_parse = input.trim().trim_end_matches(";");
+    let to_parse = to_parse
+        .strip_prefix("0x")
+        .or_else(|| to_parse.strip_prefix("0X"))
+        .unwrap_or(to_parse);
+    u32::from_str_radix(to_parse, 16)
+        .map_err(|_| ParsePresetError::parser_error(ParseErrorKind::UnsignedInt, input))
+}
+
+fn from_scale_type(input: Span) -> Result<ScaleType, ParsePresetError> {
+    ScaleType::from_str(input.trim())
+        .map_err(|_| ParsePresetError::parser_error(ParseErrorKind::ScaleType, input))
+}
+
+/// A preset file in the `#reference` chain that tokens were lexed from.
+#[derive(Debug, Copy, Clone)]
+struct SourceFile<'a> {
+    /// The path to the preset file.
+    path: &'a Path,
+    /// The directory that relative paths in the preset file are resolved against.
+    root: &'a Path,
+    /// The contents of the preset file.
+    contents: &'a str,
+}
+
+impl<'a> SourceFile<'a> {
+    fn new(path: &'a Path, contents: &'a str, provider: &dyn FileProvider) -> Self {
+        // we don't really care if this doesn't do anything because a non-canonical root path will
+        // fail at a later stage during resolution.
+        let root = if provider.is_dir(path) {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+
+        Self {
+            path,
+            root,
+            contents,
+        }
+    }
+
+    /// Attach this file and the key of the entry to an error that occurred within it.
+    fn locate(self, key: Span, error: ParsePresetError) -> ParsePresetError {
+        error.located(self.path, Some(key.fragment()), self.contents)
+    }
+
+    /// Lex the tokens of the preset file.
+    fn lex(self) -> Result<Vec<Token<'a>>, ParsePresetError> {
+        do_lex(self.contents).map_err(|e| e.located(self.path, None, self.contents))
+    }
+
+    /// Resolve the path in the value of the token relative to this file.
+    fn resolve(
+        self,
+        token: &Token,
+        provider: &dyn FileProvider,
+    ) -> Result<PathBuf, ParsePresetError> {
+        let mut relative_path = self.root.to_path_buf();
+        relative_path.push(*token.value.fragment());
+        match provider.canonicalize(&relative_path) {
+            Ok(_) => Ok(relative_path),
+            Err(error) => Err(self.locate(
+                token.key,
+                ParsePresetError::UnresolvedPath {
+                    path: relative_path,
+                    error,
+                    location: ParseErrorLocation::from_span(token.value),
+                },
+            )),
+        }
+    }
+}
+
+/// Non-fatal issues collected when parsing a preset leniently.
+struct Warnings {
+    lenient: bool,
+    warnings: Vec<PresetWarning>,
+}
+
+impl Warnings {
+    /// When parsing leniently, record an unresolved path as a warning and continue
+    /// with the unresolved path instead of failing.
+    fn tolerate(
+        &mut self,
+        result: Result<PathBuf, ParsePresetError>,
+        warning: impl FnOnce(PathBuf, Box<ParseErrorLocation>) -> PresetWarning,
+    ) -> Result<PathBuf, ParsePresetError> {
+        match result {
+            Err(ParsePresetError::UnresolvedPath { path, location, .. }) if self.lenient => {
+                self.warnings.push(warning(path.clone(), location));
+                Ok(path)
+            }
+            result => result,
+        }
+    }
 }
 
 fn parse_indexed_key<'a>(key: &'static str, input: Span<'a>) -> IResult<Span<'a>, i32> {
@@ -150,13 +226,78 @@ fn parse_indexed_key<'a>(key: &'static str, input: Span<'a>) -> IResult<Span<'a>
     Ok((input, idx))
 }
 
+/// Resolve the state tracking imports of legacy presets, removing their entries from the tokens.
+#[cfg(feature = "parse_legacy_glsl")]
+fn resolve_imports(
+    import_names: &[&str],
+    tokens: &mut Vec<(SourceFile, Token)>,
+) -> Result<Vec<Value>, ParsePresetError> {
+    let mut imports = Vec::new();
+    for name in import_names {
+        let mut take = |suffix: &str| {
+            remove_if(tokens, |(_, t)| {
+                t.key.fragment().strip_prefix(*name) == Some(suffix)
+            })
+        };
+
+        let semantic = take("_semantic").map(|(_, t)| t.value.trim().to_string());
+        let wram = take("_wram")
+            .map(|(source, t)| from_hex(t.value).map_err(|e| source.locate(t.key, e)))
+            .transpose()?;
+        let input_slot = take("_input_slot")
+            .map(|(source, t)| from_ul(t.value).map_err(|e| source.locate(t.key, e)))
+            .transpose()?;
+        let mask = take("_mask")
+            .map(|(source, t)| from_hex(t.value).map_err(|e| source.locate(t.key, e)))
+            .transpose()?;
+        let equal = take("_equal")
+            .map(|(source, t)| from_hex(t.value).map_err(|e| source.locate(t.key, e)))
+            .transpose()?;
+
+        imports.push(Value::Import(ImportConfig {
+            name: name.to_string(),
+            semantic,
+            wram,
+            input_slot,
+            mask,
+            equal,
+        }));
+    }
+    Ok(imports)
+}
+
 pub const SHADER_MAX_REFERENCE_DEPTH: usize = 16;
 
+fn read_to_string(path: &Path, provider: &dyn FileProvider) -> Result<String, ParsePresetError> {
+    let bytes = provider
+        .read(path)
+        .map_err(|e| ParsePresetError::IOError(path.to_path_buf(), e))?;
+    String::from_utf8(bytes).map_err(|e| ParsePresetError::Utf8Error(e.into_bytes()))
+}
+
+/// Extract the `#reference` paths from the tokens of a preset file, along with the location
+/// they were referenced at.
+fn extract_references(
+    tokens: &mut Vec<Token>,
+    source: SourceFile,
+) -> Vec<(PathBuf, Box<ParseErrorLocation>)> {
+    tokens
+        .extract_if(|token| *token.key.fragment() == "#reference")
+        .map(|token| {
+            let mut location = ParseErrorLocation::from_span(token.value);
+            location.attach(source.path, Some(token.key.fragment()), source.contents);
+            (PathBuf::from(*token.value.fragment()), location)
+        })
+        .collect()
+}
+
 // prereq: root_path must be contextualized
 fn load_child_reference_strings(
-    root_references: Vec<PathBuf>,
+    root_references: Vec<(PathBuf, Box<ParseErrorLocation>)>,
     root_path: impl AsRef<Path>,
     context: &FastHashMap<String, String>,
+    provider: &dyn FileProvider,
+    warnings: &mut Warnings,
 ) -> Result<Vec<(PathBuf, String)>, ParsePresetError> {
     let root_path = root_path.as_ref();
 
@@ -172,38 +313,41 @@ fn load_child_reference_strings(
         // enter the current root
         reference_depth += 1;
         // canonicalize current root
-        apply_context(&mut reference_root, context);
-        let reference_root = reference_root
-            .canonicalize()
+        apply_context(&mut reference_root, context, provider);
+        let reference_root = provider
+            .canonicalize(&reference_root)
             .map_err(|e| ParsePresetError::IOError(reference_root.to_path_buf(), e))?;
 
         // resolve all referenced paths against root
-        // println!("Resolving {referenced_paths:?} against {reference_root:?}.");
-
-        for path in referenced_paths {
-            let mut path = reference_root.join(path.clone());
-            apply_context(&mut path, context);
-
-            let mut path = path
-                .canonicalize()
-                .map_err(|e| ParsePresetError::IOError(path.clone(), e))?;
-            // println!("Opening {:?}", path);
-            let mut reference_contents = String::new();
-            File::open(&path)
-                .map_err(|e| ParsePresetError::IOError(path.clone(), e))?
-                .read_to_string(&mut reference_contents)
-                .map_err(|e| ParsePresetError::IOError(path.clone(), e))?;
-
-            let mut new_tokens = do_lex(&reference_contents)?;
-            let new_references: Vec<PathBuf> = new_tokens
-                .extract_if(|token| *token.key.fragment() == "#reference")
-                .map(|value| PathBuf::from(*value.value.fragment()))
-                .collect();
-
-            path.pop();
-            reference_strings.push_front((path.clone(), reference_contents));
+        for (path, location) in referenced_paths {
+            let mut path = reference_root.join(path);
+            apply_context(&mut path, context, provider);
+
+            let path = match provider.canonicalize(&path) {
+                Ok(path) => path,
+                Err(_) if warnings.lenient => {
+                    warnings
+                        .warnings
+                        .push(PresetWarning::MissingReference { path, location });
+                    continue;
+                }
+                Err(error) => {
+                    return Err(ParsePresetError::UnresolvedPath {
+                        path,
+                        error,
+                        location,
+                    })
+                }
+            };
+            let reference_contents = read_to_string(&path, provider)?;
+
+            let source = SourceFile::new(&path, &reference_contents, provider);
+            let new_references = extract_references(&mut source.lex()?, source);
+            let reference_directory = source.root.to_path_buf();
+
+            reference_strings.push_front((path, reference_contents));
             if !new_references.is_empty() {
-                root_references.push_front((path, new_references));
+                root_references.push_front((reference_directory, new_references));
             }
         }
     }
@@ -211,66 +355,140 @@ fn load_child_reference_strings(
     Ok(reference_strings.into())
 }
 
+/// The values of a preset, the warnings found when parsing it, and the presets that were read.
+type ParsedValues = (Vec<Value>, Vec<PresetWarning>, Vec<PathBuf>);
+
 pub(crate) fn parse_preset(
     path: impl AsRef<Path>,
     context: WildcardContext,
-) -> Result<Vec<Value>, ParsePresetError> {
+    provider: &dyn FileProvider,
+    lenient: bool,
+) -> Result<ParsedValues, ParsePresetError> {
     let path = path.as_ref();
     let mut path = path.to_path_buf();
     let context = context.to_hashmap();
 
-    apply_context(&mut path, &context);
+    apply_context(&mut path, &context, provider);
 
-    let path = path
-        .canonicalize()
+    let path = provider
+        .canonicalize(&path)
         .map_err(|e| ParsePresetError::IOError(path.to_path_buf(), e))?;
 
-    let mut contents = String::new();
-    File::open(&path)
-        .and_then(|mut f| f.read_to_string(&mut contents))
-        .map_err(|e| ParsePresetError::IOError(path.to_path_buf(), e))?;
+    let contents = read_to_string(&path, provider)?;
+    let (values, warnings, mut files) = parse_values(&contents, &path, context, provider, lenient)?;
+    files.insert(0, path);
+    Ok((values, warnings, files))
+}
+
+pub(crate) fn parse_preset_str(
+    contents: &str,
+    path: impl AsRef<Path>,
+    context: WildcardContext,
+    provider: &dyn FileProvider,
+    lenient: bool,
+) -> Result<ParsedValues, ParsePresetError> {
+    let mut path = path.as_ref().to_path_buf();
+    let context = context.to_hashmap();
+
+    apply_context(&mut path, &context, provider);
+
+    // The preset itself does not need to exist, only the directory it is resolved against.
+    if provider.is_dir(&path) {
+        let path = provider
+            .canonicalize(&path)
+            .map_err(|e| ParsePresetError::IOError(path.to_path_buf(), e))?;
+        return parse_values(contents, path, context, provider, lenient);
+    }
+
+    let mut root_path = path.clone();
+    root_path.pop();
+    let mut root_path = provider
+        .canonicalize(&root_path)
+        .map_err(|e| ParsePresetError::IOError(root_path.to_path_buf(), e))?;
+    if let Some(file_name) = path.file_name() {
+        root_path.push(file_name);
+    }
 
-    let tokens = super::token::do_lex(&contents)?;
-    parse_values(tokens, path, context)
+    parse_values(contents, root_path, context, provider, lenient)
 }
 
-// prereq: root_path must be contextualized
+/// Keys that declare names, which are collected from every preset instead of overridden.
+const DECLARATION_KEYS: [&str; 3] = ["parameters", "textures", "imports"];
+
+/// Apply the overrides of presets that reference other presets.
+///
+/// Within a single preset, the first occurrence of a key takes precedence. A key set by a
+/// preset replaces every occurrence of that key in the presets it references, which must be
+/// ordered before it. A key with an empty value that overrides a key set by a referenced
+/// preset is then removed, so that the empty value unsets the key.
+fn apply_overrides(all_tokens: &mut [(SourceFile, Vec<Token>)]) {
+    let set_keys: Vec<Vec<&str>> = all_tokens
+        .iter()
+        .map(|(_, tokens)| tokens.iter().map(|token| *token.key.fragment()).collect())
+        .collect();
+
+    let mut overridden: Vec<&str> = Vec::new();
+    for (index, (_, tokens)) in all_tokens.iter_mut().enumerate().rev() {
+        let keys: Vec<&str> = tokens
+            .iter()
+            .map(|token| *token.key.fragment())
+            .filter(|key| !DECLARATION_KEYS.contains(key))
+            .collect();
+        let referenced = &set_keys[..index];
+        tokens.retain(|token| {
+            let key = token.key.fragment();
+            if overridden.contains(key) {
+                return false;
+            }
+
+            !token.value.fragment().is_empty() || !referenced.iter().any(|keys| keys.contains(key))
+        });
+        overridden.extend(keys);
+    }
+}
+
+// prereq: path must be contextualized
+// returns the values of the preset, along with the paths of the presets it references.
 pub fn parse_values(
-    mut tokens: Vec<Token>,
-    root_path: impl AsRef<Path>,
+    contents: &str,
+    path: impl AsRef<Path>,
     context: FastHashMap<String, String>,
-) -> Result<Vec<Value>, ParsePresetError> {
-    let mut root_path = root_path.as_ref().to_path_buf();
-    if root_path.is_relative() {
+    provider: &dyn FileProvider,
+    lenient: bool,
+) -> Result<ParsedValues, ParsePresetError> {
+    let path = path.as_ref();
+    if path.is_relative() {
         return Err(ParsePresetError::RootPathWasNotAbsolute);
     }
-    if !root_path.is_dir() {
-        // we don't really care if this doesn't do anything because a non-canonical root path will
-        // fail at a later stage during resolution.
-        root_path.pop();
-    }
 
-    let references: Vec<PathBuf> = tokens
-        .extract_if(|token| *token.key.fragment() == "#reference")
-        .map(|value| PathBuf::from(*value.value.fragment()))
-        .collect();
+    let mut warnings = Warnings {
+        lenient,
+        warnings: Vec::new(),
+    };
+
+    let root = SourceFile::new(path, contents, provider);
+    let mut tokens = root.lex()?;
+    let references = extract_references(&mut tokens, root);
 
     // unfortunately we need to lex twice because there's no way to know the references ahead of time.
     // the returned references should have context applied
 
-    let child_strings = load_child_reference_strings(references, &root_path, &context)?;
-    let mut all_tokens: Vec<(&Path, Vec<Token>)> = Vec::new();
+    let child_strings =
+        load_child_reference_strings(references, root.root, &context, provider, &mut warnings)?;
+    let files = child_strings.iter().map(|(path, _)| path.clone()).collect();
+    let mut all_tokens: Vec<(SourceFile, Vec<Token>)> = Vec::new();
 
     for (path, string) in child_strings.iter() {
         // lex the child tokens
-        let mut tokens = do_lex(string.as_ref())?;
+        let source = SourceFile::new(path, string, provider);
+        let mut tokens = source.lex()?;
         tokens.retain(|token| *token.key.fragment() != "#reference");
-        all_tokens.push((path.as_path(), tokens))
+        all_tokens.push((source, tokens))
     }
 
     // load depth first, so all child tokens are first.
-    // Later tokens take precedence.
-    all_tokens.push((root_path.as_path(), tokens));
+    all_tokens.push((root, tokens));
+    apply_overrides(&mut all_tokens);
 
     // collect all possible parameter names.
     let mut parameter_names: Vec<&str> = Vec::new();
@@ -294,69 +512,96 @@ pub fn parse_values(
         }
     }
 
+    // collect all possible state tracking import names.
+    #[cfg(feature = "parse_legacy_glsl")]
+    let import_names = {
+        let mut import_names: Vec<&str> = Vec::new();
+        for (_, tokens) in all_tokens.iter_mut() {
+            for token in tokens.extract_if(|token| *token.key.fragment() == "imports") {
+                let import_name_string: &str = token.value.fragment();
+                // A trailing or doubled ";" does not declare an import.
+                for import_name in import_name_string
+                    .split(';')
+                    .filter(|name| !name.is_empty())
+                {
+                    // Imports may be declared again by a preset that references another.
+                    if !import_names.contains(&import_name) {
+                        import_names.push(import_name);
+                    }
+                }
+            }
+        }
+        import_names
+    };
+
     let mut values = Vec::new();
     // resolve shader paths.
-    for (path, tokens) in all_tokens.iter_mut() {
+    for (source, tokens) in all_tokens.iter_mut() {
         for token in tokens.extract_if(|token| parse_indexed_key("shader", token.key).is_ok()) {
-            let (_, index) = parse_indexed_key("shader", token.key).map_err(|e| match e {
-                nom::Err::Error(e) | nom::Err::Failure(e) => {
-                    let input: Span = e.input;
-                    ParsePresetError::ParserError {
-                        offset: input.location_offset(),
-                        row: input.location_line(),
-                        col: input.get_column(),
-                        kind: ParseErrorKind::Index("shader"),
+            let (_, index) = parse_indexed_key("shader", token.key)
+                .map_err(|e| match e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => {
+                        let input: Span = e.input;
+                        ParsePresetError::parser_error(ParseErrorKind::Index("shader"), input)
                     }
+                    _ => ParsePresetError::parser_error(ParseErrorKind::Index("shader"), token.key),
+                })
+                .map_err(|e| source.locate(token.key, e))?;
+
+            let path = warnings.tolerate(source.resolve(&token, provider), |path, location| {
+                PresetWarning::MissingShader {
+                    index,
+                    path,
+                    location,
                 }
-                _ => ParsePresetError::ParserError {
-                    offset: 0,
-                    row: 0,
-                    col: 0,
-                    kind: ParseErrorKind::Index("shader"),
-                },
             })?;
-
-            let mut relative_path = path.to_path_buf();
-            relative_path.push(*token.value.fragment());
-            relative_path
-                .canonicalize()
-                .map_err(|e| ParsePresetError::IOError(relative_path.clone(), e))?;
-            values.push(Value::Shader(index, relative_path))
+            values.push(Value::Shader(index, path))
         }
     }
 
     // resolve texture paths
     let mut textures = Vec::new();
-    for (path, tokens) in all_tokens.iter_mut() {
+    for (source, tokens) in all_tokens.iter_mut() {
         for token in tokens.extract_if(|token| texture_names.contains(token.key.fragment())) {
-            let mut relative_path = path.to_path_buf();
-            relative_path.push(*token.value.fragment());
-            relative_path
-                .canonicalize()
-                .map_err(|e| ParsePresetError::IOError(relative_path.clone(), e))?;
-            textures.push((token.key, relative_path))
+            let path = warnings.tolerate(source.resolve(&token, provider), |path, location| {
+                PresetWarning::MissingTexture {
+                    name: token.key.to_string(),
+                    path,
+                    location,
+                }
+            })?;
+            textures.push((token.key, path))
         }
     }
 
-    let mut tokens: Vec<(&Path, Token)> = all_tokens
+    let mut tokens: Vec<(SourceFile, Token)> = all_tokens
         .into_iter()
         .flat_map(|(p, token)| token.into_iter().map(move |t| (p, t)))
         .collect();
 
+    #[cfg(feature = "parse_legacy_glsl")]
+    values.extend(resolve_imports(&import_names, &mut tokens)?);
+
     for (texture, path) in textures {
         let mipmap = remove_if(&mut tokens, |(_, t)| {
             t.key.starts_with(*texture)
                 && t.key.ends_with("_mipmap")
                 && t.key.len() == texture.len() + "_mipmap".len()
         })
-        .map_or_else(|| Ok(false), |(_, v)| from_bool(v.value))?;
+        .map_or_else(
+            || Ok(false),
+            |(source, v)| from_bool(v.value).map_err(|e| source.locate(v.key, e)),
+        )?;
 
         let linear = remove_if(&mut tokens, |(_, t)| {
             t.key.starts_with(*texture)
                 && t.key.ends_with("_linear")
                 && t.key.len() == texture.len() + "_linear".len()
         })
-        .map_or_else(|| Ok(false), |(_, v)| from_bool(v.value))?;
+        .map_or_else(
+            || Ok(false),
+            |(source, v)| from_bool(v.value).map_err(|e| source.locate(v.key, e)),
+        )?;
 
         let wrap_mode = remove_if(&mut tokens, |(_, t)| {
             t.key.starts_with(*texture)
@@ -394,7 +639,7 @@ pub fn parse_values(
 
     let mut rest_tokens = Vec::new();
     // hopefully no more textures left in the token tree
-    for (p, token) in tokens {
+    for (source, token) in tokens {
         if parameter_names.contains(token.key.fragment()) {
             let param_val = from_float(token.value)
                 // This is literally just to work around BEAM_PROFILE in crt-hyllian-sinc-glow.slangp
@@ -409,17 +654,25 @@ pub fn parse_values(
             continue;
         }
         if token.key.fragment() == &"shaders" {
-            let shader_count = from_int(token.value)?;
+            let shader_count = from_int(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::ShaderCount(shader_count));
             continue;
         }
+        // Python state tracking scripts were removed from RetroArch, so they are ignored.
+        #[cfg(feature = "parse_legacy_glsl")]
+        if matches!(
+            *token.key.fragment(),
+            "import_script" | "import_script_class"
+        ) {
+            continue;
+        }
         if token.key.fragment() == &"feedback_pass" {
-            let feedback_pass = from_int(token.value)?;
+            let feedback_pass = from_int(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::FeedbackPass(feedback_pass));
             continue;
         }
         if let Ok((_, idx)) = parse_indexed_key("filter_linear", token.key) {
-            let linear = from_bool(token.value)?;
+            let linear = from_bool(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::FilterMode(
                 idx,
                 if linear {
@@ -453,32 +706,32 @@ pub fn parse_values(
         }
 
         if let Ok((_, idx)) = parse_indexed_key("frame_count_mod", token.key) {
-            let frame_count_mod = from_ul(token.value)?;
+            let frame_count_mod = from_ul(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::FrameCountMod(idx, frame_count_mod));
             continue;
         }
 
         if let Ok((_, idx)) = parse_indexed_key("srgb_framebuffer", token.key) {
-            let enabled = from_bool(token.value)?;
+            let enabled = from_bool(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::SrgbFramebuffer(idx, enabled));
             continue;
         }
 
         if let Ok((_, idx)) = parse_indexed_key("float_framebuffer", token.key) {
-            let enabled = from_bool(token.value)?;
+            let enabled = from_bool(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::FloatFramebuffer(idx, enabled));
             continue;
         }
 
         if let Ok((_, idx)) = parse_indexed_key("mipmap_input", token.key) {
-            let enabled = from_bool(token.value)?;
+            let enabled = from_bool(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::MipmapInput(idx, enabled));
             continue;
         }
 
         // vector-glow-alt-render.slangp uses "mipmap" for pass 1, but "mipmap_input" for everything else.
         if let Ok((_, idx)) = parse_indexed_key("mipmap", token.key) {
-            let enabled = from_bool(token.value)?;
+            let enabled = from_bool(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::MipmapInput(idx, enabled));
             continue;
         }
@@ -488,31 +741,34 @@ pub fn parse_values(
             continue;
         }
         if let Ok((_, idx)) = parse_indexed_key("scale_type", token.key) {
-            let scale_type = ScaleType::from_str(token.value.trim())?;
+            let scale_type =
+                from_scale_type(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::ScaleType(idx, scale_type));
             continue;
         }
         if let Ok((_, idx)) = parse_indexed_key("scale_type_x", token.key) {
-            let scale_type = ScaleType::from_str(token.value.trim())?;
+            let scale_type =
+                from_scale_type(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::ScaleTypeX(idx, scale_type));
             continue;
         }
         if let Ok((_, idx)) = parse_indexed_key("scale_type_y", token.key) {
-            let scale_type = ScaleType::from_str(token.value.trim())?;
+            let scale_type =
+                from_scale_type(token.value).map_err(|e| source.locate(token.key, e))?;
             values.push(Value::ScaleTypeY(idx, scale_type));
             continue;
         }
-        rest_tokens.push((p, token))
+        rest_tokens.push((source, token))
     }
 
     let mut undeclared_textures = Vec::new();
-    for (path, token) in &rest_tokens {
+    for (source, token) in &rest_tokens {
         if let Ok((_, idx)) = parse_indexed_key("scale", token.key) {
             let scale = if values.iter().any(|t| matches!(*t, Value::ScaleType(match_idx, ScaleType::Absolute) if match_idx == idx)) {
-                let scale = from_int(token.value)?;
+                let scale = from_int(token.value).map_err(|e| source.locate(token.key, e))?;
                 ScaleFactor::Absolute(scale)
             } else {
-                let scale = from_float(token.value)?;
+                let scale = from_float(token.value).map_err(|e| source.locate(token.key, e))?;
                 ScaleFactor::Float(scale)
             };
 
@@ -521,10 +777,10 @@ pub fn parse_values(
         }
         if let Ok((_, idx)) = parse_indexed_key("scale_x", token.key) {
             let scale = if values.iter().any(|t| matches!(*t, Value::ScaleType(match_idx, ScaleType::Absolute) | Value::ScaleTypeX(match_idx, ScaleType::Absolute) if match_idx == idx)) {
-                let scale = from_int(token.value)?;
+                let scale = from_int(token.value).map_err(|e| source.locate(token.key, e))?;
                 ScaleFactor::Absolute(scale)
             } else {
-                let scale = from_float(token.value)?;
+                let scale = from_float(token.value).map_err(|e| source.locate(token.key, e))?;
                 ScaleFactor::Float(scale)
             };
 
@@ -533,10 +789,10 @@ pub fn parse_values(
         }
         if let Ok((_, idx)) = parse_indexed_key("scale_y", token.key) {
             let scale = if values.iter().any(|t| matches!(*t, Value::ScaleType(match_idx, ScaleType::Absolute) | Value::ScaleTypeY(match_idx, ScaleType::Absolute) if match_idx == idx)) {
-                let scale = from_int(token.value)?;
+                let scale = from_int(token.value).map_err(|e| source.locate(token.key, e))?;
                 ScaleFactor::Absolute(scale)
             } else {
-                let scale = from_float(token.value)?;
+                let scale = from_float(token.value).map_err(|e| source.locate(token.key, e))?;
                 ScaleFactor::Float(scale)
             };
 
@@ -557,12 +813,14 @@ pub fn parse_values(
                 .iter()
                 .all(|k| !token.key.ends_with(k))
         {
-            let mut relative_path = path.to_path_buf();
-            relative_path.push(*token.value.fragment());
-            relative_path
-                .canonicalize()
-                .map_err(|e| ParsePresetError::IOError(relative_path.clone(), e))?;
-            undeclared_textures.push((token.key, relative_path));
+            let path = warnings.tolerate(source.resolve(token, provider), |path, location| {
+                PresetWarning::MissingTexture {
+                    name: token.key.to_string(),
+                    path,
+                    location,
+                }
+            })?;
+            undeclared_textures.push((token.key, path));
         }
 
         // we tried our best
@@ -575,14 +833,20 @@ pub fn parse_values(
                 && t.key.ends_with("_mipmap")
                 && t.key.len() == texture.len() + "_mipmap".len()
         })
-        .map_or_else(|| Ok(false), |(_, v)| from_bool(v.value))?;
+        .map_or_else(
+            || Ok(false),
+            |(source, v)| from_bool(v.value).map_err(|e| source.locate(v.key, e)),
+        )?;
 
         let linear = remove_if(&mut rest_tokens, |(_, t)| {
             t.key.starts_with(*texture)
                 && t.key.ends_with("_linear")
                 && t.key.len() == texture.len() + "_linear".len()
         })
-        .map_or_else(|| Ok(false), |(_, v)| from_bool(v.value))?;
+        .map_or_else(
+            || Ok(false),
+            |(source, v)| from_bool(v.value).map_err(|e| source.locate(v.key, e)),
+        )?;
 
         let wrap_mode = remove_if(&mut rest_tokens, |(_, t)| {
             t.key.starts_with(*texture)
@@ -609,20 +873,21 @@ pub fn parse_values(
     }
 
     // all tokens should be ok to process now.
-    Ok(values)
+    Ok((values, warnings.warnings, files))
 }
 
 #[cfg(test)]
 mod test {
     use crate::parse::value::parse_preset;
     use crate::WildcardContext;
+    use librashader_common::fs::StdFileProvider;
     use std::path::PathBuf;
 
     #[test]
     pub fn parse_basic() {
         let root =
             PathBuf::from("../test/slang-shaders/bezel/Mega_Bezel/Presets/Base_CRT_Presets/MBZ__3__STD__MEGATRON-NTSC.slangp");
-        let basic = parse_preset(root, WildcardContext::new());
+        let basic = parse_preset(root, WildcardContext::new(), &StdFileProvider, false);
         eprintln!("{basic:?}");
         assert!(basic.is_ok());
     }
@@ -1,11 +1,14 @@
-use crate::error::ParsePresetError;
+#[cfg(feature = "parse_legacy_glsl")]
+use crate::context::ShaderExtension;
+use crate::error::{ParsePresetError, PresetWarning};
 use librashader_common::{FilterMode, ImageFormat, WrapMode};
+use std::fmt::{Display, Formatter};
 use std::ops::Mul;
 use std::path::PathBuf;
 use std::str::FromStr;
 
 /// The configuration for a single shader pass.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShaderPassConfig {
     /// The index of the shader pass relative to its parent preset.
     pub id: i32,
@@ -53,7 +56,7 @@ impl ShaderPassConfig {
 }
 
 #[repr(i32)]
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
 /// The scaling type for the shader pass.
 pub enum ScaleType {
     #[default]
@@ -68,7 +71,7 @@ pub enum ScaleType {
 }
 
 /// The scaling factor for framebuffer scaling.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ScaleFactor {
     /// Scale by a fractional float factor.
     Float(f32),
@@ -127,8 +130,28 @@ impl FromStr for ScaleType {
     }
 }
 
+impl Display for ScaleType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScaleType::Input => f.write_str("source"),
+            ScaleType::Viewport => f.write_str("viewport"),
+            ScaleType::Absolute => f.write_str("absolute"),
+            ScaleType::Original => f.write_str("original"),
+        }
+    }
+}
+
+impl Display for ScaleFactor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScaleFactor::Float(factor) => write!(f, "{factor}"),
+            ScaleFactor::Absolute(factor) => write!(f, "{factor}"),
+        }
+    }
+}
+
 /// Framebuffer scaling parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Scaling {
     /// The method to scale the framebuffer with.
     pub scale_type: ScaleType,
@@ -137,7 +160,7 @@ pub struct Scaling {
 }
 
 /// 2D quad scaling parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Scale2D {
     /// Whether or not this combination of scaling factors is valid.
     pub valid: bool,
@@ -148,7 +171,7 @@ pub struct Scale2D {
 }
 
 /// Configuration options for a lookup texture used in the shader.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TextureConfig {
     /// The name of the texture.
     pub name: String,
@@ -163,7 +186,7 @@ pub struct TextureConfig {
 }
 
 /// Configuration options for a shader parameter.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParameterConfig {
     /// The name of the parameter.
     pub name: String,
@@ -171,11 +194,31 @@ pub struct ParameterConfig {
     pub value: f32,
 }
 
+/// A RetroArch state tracking import, used by legacy Cg and GLSL shaders to read
+/// values from emulated memory.
+#[cfg(feature = "parse_legacy_glsl")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportConfig {
+    /// The name of the uniform the value is bound to.
+    pub name: String,
+    /// How the value is tracked, i.e. `capture`, `capture_previous`, `transition`,
+    /// `transition_count`, or `transition_previous`.
+    pub semantic: Option<String>,
+    /// The address of the value in emulated work RAM.
+    pub wram: Option<u32>,
+    /// The input slot of the joypad to read from, instead of work RAM.
+    pub input_slot: Option<u32>,
+    /// The bitmask to apply to the value.
+    pub mask: Option<u32>,
+    /// The value to compare the masked value against.
+    pub equal: Option<u32>,
+}
+
 /// A shader preset including all specified parameters, textures, and paths to specified shaders.
 ///
 /// A shader preset can be used to create a filter chain runtime instance, or reflected to get
 /// parameter metadata.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShaderPreset {
     /// Used in legacy GLSL shader semantics. If < 0, no feedback pass is used.
     /// Otherwise, the FBO after pass #N is passed a texture to next frame
@@ -193,4 +236,53 @@ pub struct ShaderPreset {
 
     /// Preset information for each user parameter.
     pub parameters: Vec<ParameterConfig>,
+
+    /// State tracking imports used in legacy Cg and GLSL presets.
+    #[cfg(feature = "parse_legacy_glsl")]
+    pub imports: Vec<ImportConfig>,
+
+    pub(crate) warnings: Vec<PresetWarning>,
+    pub(crate) files: Vec<PathBuf>,
+}
+
+impl ShaderPreset {
+    /// Get the non-fatal issues found when parsing the preset with [`ShaderPreset::try_parse_lenient`].
+    ///
+    /// This is always empty for presets that were not parsed leniently.
+    pub fn warnings(&self) -> &[PresetWarning] {
+        &self.warnings
+    }
+
+    /// Get the paths of the preset files that were read to parse the preset.
+    ///
+    /// This is the preset itself followed by every preset it references with `#reference`,
+    /// directly or through another referenced preset. A preset parsed from a string with
+    /// [`ShaderPreset::try_parse_str`] only lists the presets it references.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+}
+
+#[cfg(feature = "parse_legacy_glsl")]
+impl ShaderPassConfig {
+    /// Get the shader language of the pass from the extension of its source file.
+    pub fn shader_extension(&self) -> Option<ShaderExtension> {
+        ShaderExtension::from_path(&self.name)
+    }
+}
+
+#[cfg(feature = "parse_legacy_glsl")]
+impl ShaderPreset {
+    /// Get the passes in the preset that are not slang shaders, i.e. legacy `.glsl` or `.cg`
+    /// shaders that need to be converted or handled by a legacy runtime.
+    pub fn legacy_passes(&self) -> impl Iterator<Item = &ShaderPassConfig> {
+        self.shaders
+            .iter()
+            .filter(|pass| pass.shader_extension() != Some(ShaderExtension::Slang))
+    }
+
+    /// Returns whether all passes in the preset are slang shaders.
+    pub fn is_slang(&self) -> bool {
+        self.legacy_passes().next().is_none()
+    }
 }
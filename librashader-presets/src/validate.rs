@@ -0,0 +1,223 @@
+use crate::{ScaleFactor, ScaleType, ShaderPreset};
+use librashader_common::map::FastHashMap;
+use std::fmt::{Display, Formatter};
+
+/// Texture names that are bound by the runtime and can not be used as a pass alias or
+/// lookup texture name.
+const RESERVED_NAMES: &[&str] = &["Original", "Source"];
+
+/// Texture names that are bound by the runtime when followed by an index, i.e. `PassOutput1`.
+const RESERVED_INDEXED_NAMES: &[&str] = &["OriginalHistory", "PassOutput", "PassFeedback"];
+
+/// Returns whether the name is bound to a builtin texture semantic.
+fn is_reserved_name(name: &str) -> bool {
+    RESERVED_NAMES.contains(&name)
+        || RESERVED_INDEXED_NAMES.iter().any(|prefix| {
+            name.strip_prefix(prefix)
+                .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
+
+/// How severe a problem found when validating a shader preset is.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    /// The preset will load, but likely not behave as intended.
+    Warning,
+    /// The preset will fail to load, or behave incorrectly.
+    Error,
+}
+
+/// A problem found when validating a shader preset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// `shaders` declares a pass that has no `shaderN` entry.
+    MissingPass {
+        /// The index of the missing pass.
+        index: i32,
+    },
+    /// A pass alias is empty, and will be ignored.
+    EmptyAlias {
+        /// The index of the pass.
+        pass: i32,
+    },
+    /// More than one pass uses the same alias.
+    DuplicateAlias {
+        /// The alias.
+        alias: String,
+        /// The index of the first pass using the alias.
+        first: i32,
+        /// The index of the pass that redefines the alias.
+        second: i32,
+    },
+    /// A pass alias or lookup texture name collides with a builtin texture semantic,
+    /// such as `Original` or `Source`.
+    ReservedName {
+        /// The colliding name.
+        name: String,
+    },
+    /// A lookup texture has the same name as a pass alias, and will shadow the pass output.
+    TextureShadowsAlias {
+        /// The name of the texture.
+        name: String,
+        /// The index of the pass with the alias.
+        pass: i32,
+    },
+    /// A parameter is set in the preset, but not declared by any pass.
+    UndeclaredParameter {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// A pass uses absolute scaling on only one axis.
+    MixedAbsoluteScale {
+        /// The index of the pass.
+        pass: i32,
+    },
+    /// A pass sets a scale factor without a scale type, so the scale factor is ignored.
+    InvalidScale {
+        /// The index of the pass.
+        pass: i32,
+    },
+}
+
+impl ValidationIssue {
+    /// Get the severity of the issue.
+    pub fn severity(&self) -> Severity {
+        match self {
+            ValidationIssue::MissingPass { .. }
+            | ValidationIssue::DuplicateAlias { .. }
+            | ValidationIssue::ReservedName { .. }
+            | ValidationIssue::TextureShadowsAlias { .. } => Severity::Error,
+            ValidationIssue::EmptyAlias { .. }
+            | ValidationIssue::UndeclaredParameter { .. }
+            | ValidationIssue::MixedAbsoluteScale { .. }
+            | ValidationIssue::InvalidScale { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::MissingPass { index } => {
+                write!(
+                    f,
+                    "pass {index} is declared by `shaders` but `shader{index}` is not set"
+                )
+            }
+            ValidationIssue::EmptyAlias { pass } => {
+                write!(f, "the alias of pass {pass} is empty and will be ignored")
+            }
+            ValidationIssue::DuplicateAlias {
+                alias,
+                first,
+                second,
+            } => write!(
+                f,
+                "the alias `{alias}` of pass {second} is already used by pass {first}"
+            ),
+            ValidationIssue::ReservedName { name } => {
+                write!(f, "`{name}` is the name of a builtin texture semantic")
+            }
+            ValidationIssue::TextureShadowsAlias { name, pass } => {
+                write!(f, "the texture `{name}` shadows the alias of pass {pass}")
+            }
+            ValidationIssue::UndeclaredParameter { name } => {
+                write!(f, "the parameter `{name}` is not declared by any pass")
+            }
+            ValidationIssue::MixedAbsoluteScale { pass } => {
+                write!(f, "pass {pass} uses absolute scaling on only one axis")
+            }
+            ValidationIssue::InvalidScale { pass } => write!(
+                f,
+                "pass {pass} sets a scale factor without a scale type, so it is ignored"
+            ),
+        }
+    }
+}
+
+impl ShaderPreset {
+    /// Check the preset for mistakes that would otherwise be silently ignored, or only fail
+    /// when creating a filter chain.
+    ///
+    /// This does not check parameters, since the parameters declared by each pass are only known
+    /// after loading the shader sources. Use [`ShaderPreset::validate_with_parameters`] to also
+    /// check for parameters that are set in the preset but never declared.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        for index in 0..self.shader_count {
+            if !self.shaders.iter().any(|pass| pass.id == index) {
+                issues.push(ValidationIssue::MissingPass { index });
+            }
+        }
+
+        let mut aliases: FastHashMap<&str, i32> = FastHashMap::default();
+        for pass in &self.shaders {
+            if let Some(alias) = &pass.alias {
+                if alias.trim().is_empty() {
+                    issues.push(ValidationIssue::EmptyAlias { pass: pass.id });
+                } else if let Some(first) = aliases.get(alias.as_str()) {
+                    issues.push(ValidationIssue::DuplicateAlias {
+                        alias: alias.clone(),
+                        first: *first,
+                        second: pass.id,
+                    });
+                } else {
+                    if is_reserved_name(alias) {
+                        issues.push(ValidationIssue::ReservedName {
+                            name: alias.clone(),
+                        });
+                    }
+                    aliases.insert(alias, pass.id);
+                }
+            }
+
+            let scaling = &pass.scaling;
+            if scaling.valid {
+                if (scaling.x.scale_type == ScaleType::Absolute)
+                    != (scaling.y.scale_type == ScaleType::Absolute)
+                {
+                    issues.push(ValidationIssue::MixedAbsoluteScale { pass: pass.id });
+                }
+            } else if scaling.x.factor != ScaleFactor::default()
+                || scaling.y.factor != ScaleFactor::default()
+            {
+                issues.push(ValidationIssue::InvalidScale { pass: pass.id });
+            }
+        }
+
+        for texture in &self.textures {
+            if is_reserved_name(&texture.name) {
+                issues.push(ValidationIssue::ReservedName {
+                    name: texture.name.clone(),
+                });
+            }
+            if let Some(pass) = aliases.get(texture.name.as_str()) {
+                issues.push(ValidationIssue::TextureShadowsAlias {
+                    name: texture.name.clone(),
+                    pass: *pass,
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Check the preset for mistakes like [`ShaderPreset::validate`], and for parameters that are
+    /// set in the preset but not in the given list of parameters declared by the passes.
+    pub fn validate_with_parameters<'a>(
+        &self,
+        declared: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<ValidationIssue> {
+        let mut issues = self.validate();
+        let declared: Vec<&str> = declared.into_iter().collect();
+        for parameter in &self.parameters {
+            if !declared.contains(&parameter.name.as_str()) {
+                issues.push(ValidationIssue::UndeclaredParameter {
+                    name: parameter.name.clone(),
+                });
+            }
+        }
+        issues
+    }
+}
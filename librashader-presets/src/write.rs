@@ -0,0 +1,368 @@
+use crate::{ParameterConfig, ParsePresetError, ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_common::fs::normalize;
+use librashader_common::map::FastHashMap;
+use librashader_common::FilterMode;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// A single `key = value` entry in a shader preset.
+pub type PresetEntry = (String, String);
+
+/// Get the parent directory a preset at the given path would resolve relative paths against.
+fn preset_directory(path: &Path) -> PathBuf {
+    let mut directory = path.to_path_buf();
+    if !directory.is_dir() {
+        directory.pop();
+    }
+    directory
+}
+
+/// Make the given path absolute and lexically normalized, without touching the filesystem
+/// unless the path exists.
+fn normalize_path(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+
+    if path.is_relative() {
+        if let Ok(cwd) = std::env::current_dir() {
+            return normalize(&cwd.join(path));
+        }
+    }
+    normalize(path)
+}
+
+/// Get the path of `path` relative to the directory `base`, using forward slashes.
+///
+/// Relative paths are resolved against the current working directory. If no relative path can
+/// be formed (i.e. the paths are on different drives), the absolute path is returned instead.
+pub(crate) fn relative_path(path: &Path, base: &Path) -> String {
+    let path = normalize_path(path);
+    let base = normalize_path(base);
+
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+
+    // The paths must share a root to be relative to each other.
+    if path_components.peek() != base_components.peek() {
+        return path.to_string_lossy().replace('\\', "/");
+    }
+
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+
+    let mut relative: Vec<String> = base_components.map(|_| String::from("..")).collect();
+    relative.extend(path_components.map(|c| c.as_os_str().to_string_lossy().into_owned()));
+    relative.join("/")
+}
+
+impl ShaderPassConfig {
+    /// Get the `.slangp` entries for this shader pass at the given pass index,
+    /// with the shader path relative to the directory `base`.
+    pub fn to_preset_entries(&self, index: usize, base: impl AsRef<Path>) -> Vec<PresetEntry> {
+        let mut entries = vec![
+            (
+                format!("shader{index}"),
+                relative_path(&self.name, base.as_ref()),
+            ),
+            (
+                format!("filter_linear{index}"),
+                (self.filter == FilterMode::Linear).to_string(),
+            ),
+            (format!("wrap_mode{index}"), self.wrap_mode.to_string()),
+            (
+                format!("mipmap_input{index}"),
+                self.mipmap_input.to_string(),
+            ),
+        ];
+
+        if let Some(alias) = &self.alias {
+            entries.push((format!("alias{index}"), alias.clone()));
+        }
+
+        entries.push((
+            format!("float_framebuffer{index}"),
+            self.float_framebuffer.to_string(),
+        ));
+        entries.push((
+            format!("srgb_framebuffer{index}"),
+            self.srgb_framebuffer.to_string(),
+        ));
+
+        if self.frame_count_mod > 0 {
+            entries.push((
+                format!("frame_count_mod{index}"),
+                self.frame_count_mod.to_string(),
+            ));
+        }
+
+        // Passes without a valid scale are left unset, so the runtime picks the default.
+        if self.scaling.valid {
+            let scaling = &self.scaling;
+            if scaling.x == scaling.y {
+                entries.push((
+                    format!("scale_type{index}"),
+                    scaling.x.scale_type.to_string(),
+                ));
+                entries.push((format!("scale{index}"), scaling.x.factor.to_string()));
+            } else {
+                entries.push((
+                    format!("scale_type_x{index}"),
+                    scaling.x.scale_type.to_string(),
+                ));
+                entries.push((format!("scale_x{index}"), scaling.x.factor.to_string()));
+                entries.push((
+                    format!("scale_type_y{index}"),
+                    scaling.y.scale_type.to_string(),
+                ));
+                entries.push((format!("scale_y{index}"), scaling.y.factor.to_string()));
+            }
+        }
+
+        entries
+    }
+}
+
+impl TextureConfig {
+    /// Get the `.slangp` entries for this lookup texture, with the texture path relative
+    /// to the directory `base`.
+    ///
+    /// This does not include the `textures` entry declaring the texture name.
+    pub fn to_preset_entries(&self, base: impl AsRef<Path>) -> Vec<PresetEntry> {
+        vec![
+            (self.name.clone(), relative_path(&self.path, base.as_ref())),
+            (
+                format!("{}_linear", self.name),
+                (self.filter_mode == FilterMode::Linear).to_string(),
+            ),
+            (
+                format!("{}_wrap_mode", self.name),
+                self.wrap_mode.to_string(),
+            ),
+            (format!("{}_mipmap", self.name), self.mipmap.to_string()),
+        ]
+    }
+}
+
+impl ParameterConfig {
+    /// Get the `.slangp` entry for this parameter.
+    ///
+    /// This does not include the `parameters` entry declaring the parameter name.
+    pub fn to_preset_entry(&self) -> PresetEntry {
+        (self.name.clone(), self.value.to_string())
+    }
+}
+
+#[cfg(feature = "parse_legacy_glsl")]
+impl crate::ImportConfig {
+    /// Get the preset entries for this state tracking import.
+    ///
+    /// This does not include the `imports` entry declaring the import name.
+    pub fn to_preset_entries(&self) -> Vec<PresetEntry> {
+        let mut entries = Vec::new();
+        if let Some(semantic) = &self.semantic {
+            entries.push((format!("{}_semantic", self.name), semantic.clone()));
+        }
+        if let Some(wram) = self.wram {
+            entries.push((format!("{}_wram", self.name), format!("{wram:x}")));
+        }
+        if let Some(input_slot) = self.input_slot {
+            entries.push((format!("{}_input_slot", self.name), input_slot.to_string()));
+        }
+        if let Some(mask) = self.mask {
+            entries.push((format!("{}_mask", self.name), format!("{mask:x}")));
+        }
+        if let Some(equal) = self.equal {
+            entries.push((format!("{}_equal", self.name), format!("{equal:x}")));
+        }
+        entries
+    }
+}
+
+impl ShaderPreset {
+    /// Get the `.slangp` entries for this preset, with all paths relative to the directory `base`.
+    ///
+    /// Passes are renumbered in the order they appear in [`ShaderPreset::shaders`](crate::ShaderPreset::shaders).
+    pub fn to_preset_entries(&self, base: impl AsRef<Path>) -> Vec<PresetEntry> {
+        let base = base.as_ref();
+        let mut entries = vec![(String::from("shaders"), self.shaders.len().to_string())];
+
+        for (index, pass) in self.shaders.iter().enumerate() {
+            entries.extend(pass.to_preset_entries(index, base));
+        }
+
+        if !self.textures.is_empty() {
+            let names: Vec<&str> = self.textures.iter().map(|t| t.name.as_str()).collect();
+            entries.push((String::from("textures"), names.join(";")));
+            for texture in &self.textures {
+                entries.extend(texture.to_preset_entries(base));
+            }
+        }
+
+        if !self.parameters.is_empty() {
+            let names: Vec<&str> = self.parameters.iter().map(|p| p.name.as_str()).collect();
+            entries.push((String::from("parameters"), names.join(";")));
+            for parameter in &self.parameters {
+                entries.push(parameter.to_preset_entry());
+            }
+        }
+
+        #[cfg(feature = "parse_legacy_glsl")]
+        {
+            if self.feedback_pass >= 0 {
+                entries.push((
+                    String::from("feedback_pass"),
+                    self.feedback_pass.to_string(),
+                ));
+            }
+
+            if !self.imports.is_empty() {
+                let names: Vec<&str> = self.imports.iter().map(|i| i.name.as_str()).collect();
+                entries.push((String::from("imports"), names.join(";")));
+                for import in &self.imports {
+                    entries.extend(import.to_preset_entries());
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// Serialize the shader preset to `.slangp` text, with all paths made relative to
+    /// the location the preset will be saved to.
+    pub fn to_preset_string(&self, path: impl AsRef<Path>) -> String {
+        let base = preset_directory(path.as_ref());
+        write_entries(&self.to_preset_entries(base))
+    }
+
+    /// Write the shader preset to the given path as a `.slangp` file.
+    pub fn write_preset(&self, path: impl AsRef<Path>) -> Result<(), ParsePresetError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_preset_string(path))
+            .map_err(|e| ParsePresetError::IOError(path.to_path_buf(), e))
+    }
+
+    /// Get the `.slangp` entries that differ between this preset and the `original` preset,
+    /// with all paths relative to the directory `base`.
+    ///
+    /// Applying the returned entries over `original` results in this preset. Parameter
+    /// declarations are not included since they are inherited from the original preset.
+    /// Settings of the original preset that this preset leaves unset, and lookup textures
+    /// that were removed, are unset with an empty value.
+    pub fn to_delta_entries(
+        &self,
+        original: &ShaderPreset,
+        base: impl AsRef<Path>,
+    ) -> Vec<PresetEntry> {
+        let base = base.as_ref();
+        let original_entries: FastHashMap<String, String> =
+            original.to_preset_entries(base).into_iter().collect();
+
+        let mut entries: Vec<PresetEntry> = self
+            .to_preset_entries(base)
+            .into_iter()
+            .filter(|(key, value)| key != "parameters" && original_entries.get(key) != Some(value))
+            .collect();
+
+        // Entries that are omitted when left at their default can't be detected by comparing
+        // keys, so they need to be explicitly unset with an empty value if the original preset
+        // sets them.
+        for (index, (pass, original_pass)) in
+            self.shaders.iter().zip(original.shaders.iter()).enumerate()
+        {
+            if pass.alias.is_none() && original_pass.alias.is_some() {
+                entries.push((format!("alias{index}"), String::new()));
+            }
+            if pass.frame_count_mod == 0 && original_pass.frame_count_mod != 0 {
+                entries.push((format!("frame_count_mod{index}"), String::new()));
+            }
+
+            // The uniform scale takes priority over the per-axis scale, so every scale
+            // entry of the original preset that the pass does not use has to be unset.
+            if pass.scaling != original_pass.scaling {
+                let pass_entries = pass.to_preset_entries(index, base);
+                for key in [
+                    "scale_type",
+                    "scale",
+                    "scale_type_x",
+                    "scale_x",
+                    "scale_type_y",
+                    "scale_y",
+                ] {
+                    let key = format!("{key}{index}");
+                    if entries.iter().any(|(entry, _)| *entry == key) {
+                        continue;
+                    }
+
+                    match pass_entries.iter().find(|(entry, _)| *entry == key) {
+                        Some((_, value)) => entries.push((key, value.clone())),
+                        None if original_entries.contains_key(&key) => {
+                            entries.push((key, String::new()))
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        for texture in &original.textures {
+            if !self.textures.iter().any(|t| t.name == texture.name) {
+                entries.push((texture.name.clone(), String::new()));
+            }
+        }
+
+        entries
+    }
+
+    /// Serialize the shader preset as a `.slangp` that references the preset at `reference`,
+    /// with only the entries that differ from the referenced preset.
+    ///
+    /// This is how RetroArch saves simple presets for core, directory, and game overrides.
+    /// All paths are made relative to the location the preset will be saved to.
+    pub fn to_reference_preset_string(
+        &self,
+        reference: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+    ) -> Result<String, ParsePresetError> {
+        let reference = reference.as_ref();
+        let original = ShaderPreset::try_parse(reference)?;
+        let base = preset_directory(path.as_ref());
+
+        let mut string = format!("#reference \"{}\"\n", relative_path(reference, &base));
+        string.push_str(&write_entries(&self.to_delta_entries(&original, &base)));
+        Ok(string)
+    }
+
+    /// Write the shader preset to the given path as a `.slangp` file that references the preset
+    /// at `reference`, with only the entries that differ from the referenced preset.
+    pub fn write_reference_preset(
+        &self,
+        reference: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+    ) -> Result<(), ParsePresetError> {
+        let path = path.as_ref();
+        let string = self.to_reference_preset_string(reference, path)?;
+        std::fs::write(path, string).map_err(|e| ParsePresetError::IOError(path.to_path_buf(), e))
+    }
+}
+
+/// Format entries as `key = "value"` lines.
+///
+/// Values containing quotes can not be quoted, so they are written as-is.
+pub(crate) fn write_entries(entries: &[PresetEntry]) -> String {
+    let mut string = String::new();
+    for (key, value) in entries {
+        // NOPANIC: writing to a String is infallible.
+        if value.contains('"') {
+            writeln!(string, "{key} = {value}").unwrap();
+        } else {
+            writeln!(string, "{key} = \"{value}\"").unwrap();
+        }
+    }
+    string
+}
@@ -0,0 +1,8 @@
+use std::path::PathBuf;
+
+/// An absolute path from a `/`-separated path relative to the filesystem root of the platform.
+pub fn absolute(path: &str) -> PathBuf {
+    let root = if cfg!(windows) { r"C:\" } else { "/" };
+    path.split('/')
+        .fold(PathBuf::from(root), |path, component| path.join(component))
+}
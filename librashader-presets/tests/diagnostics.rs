@@ -0,0 +1,112 @@
+use librashader_common::fs::MemoryFileProvider;
+use librashader_presets::{ParseErrorKind, ParsePresetError, ShaderPreset, WildcardContext};
+
+mod common;
+
+use common::absolute;
+use std::path::PathBuf;
+
+fn memory_provider() -> MemoryFileProvider {
+    let mut provider = MemoryFileProvider::new();
+    provider.insert(
+        absolute("presets/base.slangp"),
+        "shaders = 1\nshader0 = shaders/pass.slang\nscale_type0 = source\nscale0 = 2.0x\n",
+    );
+    provider.insert(absolute("presets/shaders/pass.slang"), "#version 450\n");
+    provider
+}
+
+fn parse(path: PathBuf, provider: &MemoryFileProvider) -> ParsePresetError {
+    ShaderPreset::try_parse_with_provider(path, WildcardContext::new(), provider).unwrap_err()
+}
+
+#[test]
+fn locates_error_in_referenced_preset() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/override/override.slangp"),
+        "#reference \"../base.slangp\"\nfilter_linear0 = true\n",
+    );
+
+    let error = parse(absolute("presets/override/override.slangp"), &provider);
+    assert!(matches!(
+        error,
+        ParsePresetError::ParserError {
+            kind: ParseErrorKind::Float,
+            row: 4,
+            col: 10,
+            ..
+        }
+    ));
+
+    let location = error.location().unwrap();
+    assert_eq!(location.path, Some(absolute("presets/base.slangp")));
+    assert_eq!(location.key.as_deref(), Some("scale0"));
+    assert_eq!(location.row, 4);
+    assert_eq!(location.col, 10);
+    assert_eq!(location.len, 4);
+    assert_eq!(location.line, "scale0 = 2.0x");
+}
+
+#[test]
+fn renders_error_with_caret() {
+    let provider = memory_provider();
+    let error = parse(absolute("presets/base.slangp"), &provider);
+
+    assert_eq!(
+        error.render(),
+        format!(
+            "error: shader preset parse error: expected a float
+ --> {}:4:10
+  |
+4 | scale0 = 2.0x
+  |          ^^^^
+  |
+  = note: in the value of `scale0`
+",
+            absolute("presets/base.slangp").display()
+        )
+    );
+}
+
+#[test]
+fn locates_unresolved_shader_path() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/missing.slangp"),
+        "shaders = 1\n\tshader0 = \"shaders/missing.slang\"\n",
+    );
+
+    let error = parse(absolute("presets/missing.slangp"), &provider);
+    let ParsePresetError::UnresolvedPath { path, location, .. } = &error else {
+        panic!("expected an unresolved path error, got {error:?}");
+    };
+
+    assert_eq!(*path, absolute("presets/shaders/missing.slang"));
+    assert_eq!(location.key.as_deref(), Some("shader0"));
+    assert_eq!(location.row, 2);
+
+    let rendered = error.render();
+    assert!(rendered.contains(&format!(
+        " --> {}:2:13\n",
+        absolute("presets/missing.slangp").display()
+    )));
+    assert!(rendered.contains("2 |     shader0 = \"shaders/missing.slang\"\n"));
+    assert!(rendered.contains("  |                ^^^^^^^^^^^^^^^^^^^^^\n"));
+}
+
+#[test]
+fn locates_unresolved_reference() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/override.slangp"),
+        "// override\n#reference \"missing.slangp\"\n",
+    );
+
+    let error = parse(absolute("presets/override.slangp"), &provider);
+    let location = error.location().unwrap();
+    assert!(matches!(error, ParsePresetError::UnresolvedPath { .. }));
+    assert_eq!(location.path, Some(absolute("presets/override.slangp")));
+    assert_eq!(location.key.as_deref(), Some("#reference"));
+    assert_eq!(location.line, "#reference \"missing.slangp\"");
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "parse_legacy_glsl")]
+
+use librashader_common::fs::MemoryFileProvider;
+use librashader_presets::context::ShaderExtension;
+use librashader_presets::{ShaderPreset, WildcardContext};
+use std::path::PathBuf;
+
+mod common;
+
+use common::absolute;
+
+fn memory_provider() -> MemoryFileProvider {
+    let mut provider = MemoryFileProvider::new();
+    provider.insert(
+        absolute("presets/base.glslp"),
+        "shaders = 2\nshader0 = shaders/crt.glsl\nscale_type0 = source\nfloat_framebuffer0 = true\nshader1 = shaders/stock.slang\nfeedback_pass = 0\n",
+    );
+    provider.insert(
+        absolute("presets/mario.cgp"),
+        r#"shaders = 1
+shader0 = shaders/mario.cg
+imports = "mario_x;;mario_jump;"
+mario_x_semantic = capture
+mario_x_wram = 7e0094
+mario_jump_semantic = transition
+mario_jump_wram = "0x7E0072"
+mario_jump_mask = ff
+mario_jump_equal = 1
+import_script = script.py
+import_script_class = Mario
+"#,
+    );
+    provider.insert(absolute("presets/shaders/crt.glsl"), "");
+    provider.insert(absolute("presets/shaders/mario.cg"), "");
+    provider.insert(absolute("presets/shaders/stock.slang"), "#version 450\n");
+    provider
+}
+
+fn parse(path: PathBuf, provider: &MemoryFileProvider) -> ShaderPreset {
+    ShaderPreset::try_parse_with_provider(path, WildcardContext::new(), provider).unwrap()
+}
+
+#[test]
+fn parses_referenced_glslp() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/override.glslp"),
+        "#reference \"base.glslp\"\nfeedback_pass = 1\n",
+    );
+
+    let preset = parse(absolute("presets/override.glslp"), &provider);
+    assert_eq!(preset.shaders.len(), 2);
+    assert_eq!(preset.feedback_pass, 1);
+    assert!(preset.shaders[0].float_framebuffer);
+    assert!(!preset.shaders[1].float_framebuffer);
+}
+
+#[test]
+fn ignores_framebuffer_format_of_unscaled_legacy_passes() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/formats.glslp"),
+        r#"shaders = 3
+shader0 = shaders/crt.glsl
+float_framebuffer0 = true
+shader1 = shaders/crt.glsl
+scale_type1 = viewport
+float_framebuffer1 = true
+srgb_framebuffer1 = true
+shader2 = shaders/stock.slang
+float_framebuffer2 = true
+"#,
+    );
+
+    let preset = parse(absolute("presets/formats.glslp"), &provider);
+    assert!(!preset.shaders[0].float_framebuffer);
+    assert_eq!(preset.shaders[0].get_format_override(), None);
+    assert!(preset.shaders[1].float_framebuffer);
+    assert!(preset.shaders[1].srgb_framebuffer);
+    assert!(preset.shaders[2].float_framebuffer);
+}
+
+#[test]
+fn reports_legacy_passes() {
+    let provider = memory_provider();
+    let preset = parse(absolute("presets/base.glslp"), &provider);
+
+    assert!(!preset.is_slang());
+    let legacy: Vec<i32> = preset.legacy_passes().map(|pass| pass.id).collect();
+    assert_eq!(legacy, vec![0]);
+    assert_eq!(
+        preset.shaders[0].shader_extension(),
+        Some(ShaderExtension::Glsl)
+    );
+}
+
+#[test]
+fn parses_state_tracking_imports() {
+    let provider = memory_provider();
+    let preset = parse(absolute("presets/mario.cgp"), &provider);
+
+    assert_eq!(preset.feedback_pass, -1);
+    assert!(preset.parameters.is_empty());
+    assert!(preset.textures.is_empty());
+    assert_eq!(preset.imports.len(), 2);
+
+    let mario_x = &preset.imports[0];
+    assert_eq!(mario_x.name, "mario_x");
+    assert_eq!(mario_x.semantic.as_deref(), Some("capture"));
+    assert_eq!(mario_x.wram, Some(0x7e0094));
+    assert_eq!(mario_x.mask, None);
+
+    let mario_jump = &preset.imports[1];
+    assert_eq!(mario_jump.semantic.as_deref(), Some("transition"));
+    assert_eq!(mario_jump.wram, Some(0x7e0072));
+    assert_eq!(mario_jump.mask, Some(0xff));
+    assert_eq!(mario_jump.equal, Some(1));
+}
+
+#[test]
+fn round_trips_legacy_keys() {
+    let provider = memory_provider();
+    let preset = parse(absolute("presets/mario.cgp"), &provider);
+
+    let mut written = MemoryFileProvider::new();
+    written.insert(absolute("presets/shaders/mario.cg"), "");
+    written.insert(
+        absolute("presets/written.cgp"),
+        preset.to_preset_string(absolute("presets/written.cgp")),
+    );
+
+    let reparsed = parse(absolute("presets/written.cgp"), &written);
+    assert_eq!(reparsed.imports, preset.imports);
+    assert_eq!(reparsed.feedback_pass, preset.feedback_pass);
+}
@@ -0,0 +1,90 @@
+use librashader_common::fs::MemoryFileProvider;
+use librashader_presets::{ParsePresetError, PresetWarning, ShaderPreset, WildcardContext};
+
+mod common;
+
+use common::absolute;
+
+fn memory_provider() -> MemoryFileProvider {
+    let mut provider = MemoryFileProvider::new();
+    provider.insert(
+        absolute("presets/broken.slangp"),
+        "#reference \"missing.slangp\"\nshaders = 2\nshader0 = shaders/pass.slang\nshader1 = shaders/missing.slang\ntextures = LUT;Decor\nLUT = textures/lut.png\nDecor = textures/missing.png\n",
+    );
+    provider.insert(absolute("presets/shaders/pass.slang"), "#version 450\n");
+    provider.insert(absolute("presets/textures/lut.png"), []);
+    provider
+}
+
+#[test]
+fn strict_mode_fails_on_missing_path() {
+    let provider = memory_provider();
+    let error = ShaderPreset::try_parse_with_provider(
+        absolute("presets/broken.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap_err();
+
+    assert!(matches!(error, ParsePresetError::UnresolvedPath { .. }));
+}
+
+#[test]
+fn lenient_mode_collects_missing_paths() {
+    let provider = memory_provider();
+    let preset = ShaderPreset::try_parse_lenient(
+        absolute("presets/broken.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap();
+
+    assert_eq!(preset.shaders.len(), 2);
+    assert_eq!(
+        preset.shaders[1].name,
+        absolute("presets/shaders/missing.slang")
+    );
+    assert_eq!(preset.textures.len(), 2);
+
+    assert_eq!(preset.warnings().len(), 3);
+    assert!(matches!(
+        &preset.warnings()[0],
+        PresetWarning::MissingReference { path, .. } if *path == absolute("presets/missing.slangp")
+    ));
+    assert!(matches!(
+        &preset.warnings()[1],
+        PresetWarning::MissingShader { index: 1, path, .. } if *path == absolute("presets/shaders/missing.slang")
+    ));
+    assert!(matches!(
+        &preset.warnings()[2],
+        PresetWarning::MissingTexture { name, .. } if name == "Decor"
+    ));
+
+    assert_eq!(
+        preset.warnings()[1].location().key.as_deref(),
+        Some("shader1")
+    );
+    assert!(preset.warnings()[2].render().starts_with(&format!(
+        "warning: the texture `Decor` was not found\n --> {}:7:9\n",
+        absolute("presets/broken.slangp").display()
+    )));
+}
+
+#[test]
+fn lenient_mode_has_no_warnings_for_valid_preset() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/valid.slangp"),
+        "shaders = 1\nshader0 = shaders/pass.slang\n",
+    );
+
+    let preset = ShaderPreset::try_parse_lenient(
+        absolute("presets/valid.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap();
+
+    assert_eq!(preset.shaders.len(), 1);
+    assert!(preset.warnings().is_empty());
+}
@@ -0,0 +1,124 @@
+use librashader_common::fs::MemoryFileProvider;
+use librashader_common::FilterMode;
+use librashader_presets::{ShaderPreset, WildcardContext};
+
+mod common;
+
+use common::absolute;
+
+fn memory_provider() -> MemoryFileProvider {
+    let mut provider = MemoryFileProvider::new();
+    provider.insert(
+        absolute("presets/base.slangp"),
+        "shaders = 1\nshader0 = shaders/pass.slang\ntextures = LUT\nLUT = textures/lut.png\n",
+    );
+    provider.insert(absolute("presets/shaders/pass.slang"), "#version 450\n");
+    provider.insert(absolute("presets/textures/lut.png"), []);
+    provider
+}
+
+#[test]
+fn parses_from_provider() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/override/override.slangp"),
+        "#reference \"../base.slangp\"\nLUT_linear = true\n",
+    );
+
+    let preset = ShaderPreset::try_parse_with_provider(
+        absolute("presets/override/override.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap();
+
+    assert_eq!(preset.shaders.len(), 1);
+    assert_eq!(
+        preset.shaders[0].name,
+        absolute("presets/shaders/pass.slang")
+    );
+    assert_eq!(preset.textures.len(), 1);
+    assert_eq!(
+        preset.textures[0].path,
+        absolute("presets/textures/lut.png")
+    );
+    assert_eq!(preset.textures[0].filter_mode, FilterMode::Linear);
+}
+
+#[test]
+fn parses_from_str() {
+    let provider = memory_provider();
+    let preset = ShaderPreset::try_parse_str(
+        "#reference \"base.slangp\"\nshaders = 2\nshader1 = shaders/pass.slang\n",
+        absolute("presets/unsaved.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap();
+
+    assert_eq!(preset.shaders.len(), 2);
+    assert_eq!(preset.textures.len(), 1);
+}
+
+#[test]
+fn lists_referenced_files() {
+    let mut provider = memory_provider();
+    provider.insert(
+        absolute("presets/middle.slangp"),
+        "#reference \"base.slangp\"\nLUT_linear = true\n",
+    );
+    provider.insert(
+        absolute("presets/override/override.slangp"),
+        "#reference \"../middle.slangp\"\nLUT_mipmap = true\n",
+    );
+
+    let preset = ShaderPreset::try_parse_with_provider(
+        absolute("presets/override/override.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap();
+
+    let mut files = preset.files().to_vec();
+    assert_eq!(files[0], absolute("presets/override/override.slangp"));
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            absolute("presets/base.slangp"),
+            absolute("presets/middle.slangp"),
+            absolute("presets/override/override.slangp"),
+        ]
+    );
+
+    let preset = ShaderPreset::try_parse_str(
+        "#reference \"middle.slangp\"\n",
+        absolute("presets/unsaved.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap();
+
+    let mut files = preset.files().to_vec();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            absolute("presets/base.slangp"),
+            absolute("presets/middle.slangp"),
+        ]
+    );
+}
+
+#[test]
+fn fails_on_missing_file_in_provider() {
+    let provider = memory_provider();
+    let preset = ShaderPreset::try_parse_str(
+        "shaders = 1\nshader0 = shaders/missing.slang\n",
+        absolute("presets/unsaved.slangp"),
+        WildcardContext::new(),
+        &provider,
+    );
+
+    assert!(preset.is_err());
+}
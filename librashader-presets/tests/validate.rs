@@ -0,0 +1,103 @@
+use librashader_common::fs::MemoryFileProvider;
+use librashader_presets::{Severity, ShaderPreset, ValidationIssue, WildcardContext};
+
+mod common;
+
+use common::absolute;
+
+fn parse(preset: &str) -> ShaderPreset {
+    let mut provider = MemoryFileProvider::new();
+    provider.insert(absolute("presets/shaders/pass.slang"), "#version 450\n");
+    provider.insert(absolute("presets/textures/lut.png"), []);
+    ShaderPreset::try_parse_str(
+        preset,
+        absolute("presets/preset.slangp"),
+        WildcardContext::new(),
+        &provider,
+    )
+    .unwrap()
+}
+
+#[test]
+fn valid_preset_has_no_issues() {
+    let preset = parse(
+        "shaders = 2\nshader0 = shaders/pass.slang\nalias0 = First\nshader1 = shaders/pass.slang\nscale_type1 = viewport\nscale1 = 1.0\ntextures = LUT\nLUT = textures/lut.png\nparameters = Brightness\nBrightness = 0.5\n",
+    );
+
+    assert!(preset.validate().is_empty());
+    assert!(preset.validate_with_parameters(["Brightness"]).is_empty());
+}
+
+#[test]
+fn reports_structural_issues() {
+    let preset = parse(
+        r#"shaders = 4
+shader0 = shaders/pass.slang
+alias0 = Shared
+shader1 = shaders/pass.slang
+alias1 = Shared
+scale1 = 2.0
+shader2 = shaders/pass.slang
+alias2 = ""
+scale_type_x2 = absolute
+scale_type_y2 = source
+scale_x2 = 320
+textures = "Source;Shared"
+Source = textures/lut.png
+Shared = textures/lut.png
+"#,
+    );
+
+    let issues = preset.validate();
+    assert_eq!(
+        issues,
+        vec![
+            ValidationIssue::MissingPass { index: 3 },
+            ValidationIssue::DuplicateAlias {
+                alias: String::from("Shared"),
+                first: 0,
+                second: 1,
+            },
+            ValidationIssue::InvalidScale { pass: 1 },
+            ValidationIssue::EmptyAlias { pass: 2 },
+            ValidationIssue::MixedAbsoluteScale { pass: 2 },
+            ValidationIssue::ReservedName {
+                name: String::from("Source"),
+            },
+            ValidationIssue::TextureShadowsAlias {
+                name: String::from("Shared"),
+                pass: 0,
+            },
+        ]
+    );
+    assert_eq!(issues[0].severity(), Severity::Error);
+    assert_eq!(issues[2].severity(), Severity::Warning);
+}
+
+#[test]
+fn reports_reserved_indexed_alias() {
+    let preset = parse("shaders = 1\nshader0 = shaders/pass.slang\nalias0 = PassOutput0\n");
+    assert_eq!(
+        preset.validate(),
+        vec![ValidationIssue::ReservedName {
+            name: String::from("PassOutput0"),
+        }]
+    );
+
+    let preset = parse("shaders = 1\nshader0 = shaders/pass.slang\nalias0 = PassOutputs\n");
+    assert!(preset.validate().is_empty());
+}
+
+#[test]
+fn reports_undeclared_parameters() {
+    let preset = parse(
+        "shaders = 1\nshader0 = shaders/pass.slang\nparameters = \"Declared;Undeclared\"\nDeclared = 1.0\nUndeclared = 2.0\n",
+    );
+
+    assert_eq!(
+        preset.validate_with_parameters(["Declared"]),
+        vec![ValidationIssue::UndeclaredParameter {
+            name: String::from("Undeclared"),
+        }]
+    );
+}
@@ -0,0 +1,222 @@
+use glob::glob;
+use librashader_common::fs::StdFileProvider;
+use librashader_common::FilterMode;
+use librashader_presets::{ShaderPreset, WildcardContext};
+use std::path::Path;
+
+fn canonicalize_paths(preset: &mut ShaderPreset) {
+    for pass in preset.shaders.iter_mut() {
+        pass.name = pass.name.canonicalize().unwrap_or(pass.name.clone());
+    }
+    for texture in preset.textures.iter_mut() {
+        texture.path = texture.path.canonicalize().unwrap_or(texture.path.clone());
+    }
+}
+
+fn assert_round_trip(path: &Path, index: usize) {
+    let mut preset = ShaderPreset::try_parse(path)
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {e:?}", path.display()));
+
+    let out = std::env::temp_dir().join(format!("librashader-write-{index}.slangp"));
+    preset
+        .write_preset(&out)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {e:?}", out.display()));
+
+    let mut written = ShaderPreset::try_parse(&out)
+        .unwrap_or_else(|e| panic!("Failed to parse written {}: {e:?}", path.display()));
+    std::fs::remove_file(&out).ok();
+
+    canonicalize_paths(&mut preset);
+    canonicalize_paths(&mut written);
+
+    assert_eq!(written.shader_count as usize, preset.shaders.len());
+    assert_eq!(written.shaders, preset.shaders, "{}", path.display());
+    assert_eq!(written.textures, preset.textures, "{}", path.display());
+    assert_eq!(written.parameters, preset.parameters, "{}", path.display());
+}
+
+#[test]
+fn round_trips_test_presets() {
+    for (index, path) in glob("../test/*.slangp").unwrap().flatten().enumerate() {
+        assert_round_trip(&path, index);
+    }
+}
+
+const SLANG_SHADERS: &str = "../test/shaders_slang";
+
+/// Presets that reference missing files and do not parse, as listed in `BROKEN_SHADERS.md`.
+const BROKEN_PRESETS: &[&str] = &[
+    "bezel/Mega_Bezel/shaders/hyllian/crt-super-xbr/crt-super-xbr.slangp",
+    "crt/crt-maximus-royale-fast-mode.slangp",
+    "crt/crt-maximus-royale-half-res-mode.slangp",
+    "crt/crt-maximus-royale.slangp",
+    "crt/mame_hlsl.slangp",
+    "denoisers/fast-bilateral-super-2xbr-3d-3p.slangp",
+    "presets/tvout/tvout+ntsc-256px-composite.slangp",
+    "presets/tvout/tvout+ntsc-256px-svideo.slangp",
+    "presets/tvout/tvout+ntsc-2phase-composite.slangp",
+    "presets/tvout/tvout+ntsc-2phase-svideo.slangp",
+    "presets/tvout/tvout+ntsc-320px-composite.slangp",
+    "presets/tvout/tvout+ntsc-320px-svideo.slangp",
+    "presets/tvout/tvout+ntsc-3phase-composite.slangp",
+    "presets/tvout/tvout+ntsc-3phase-svideo.slangp",
+    "presets/tvout/tvout+ntsc-nes.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-256px-composite+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-256px-svideo+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-2phase-composite+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-2phase-svideo+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-320px-composite+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-320px-svideo+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-3phase-composite+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-3phase-svideo+interlacing.slangp",
+    "presets/tvout+interlacing/tvout+ntsc-nes+interlacing.slangp",
+    "scalefx/shaders/old/scalefx-9x.slangp",
+    "scalefx/shaders/old/scalefx.slangp",
+];
+
+#[test]
+fn round_trips_all_slang_presets() {
+    if !Path::new(SLANG_SHADERS).is_dir() {
+        eprintln!("[write] {SLANG_SHADERS} is not checked out, skipping round trips");
+        return;
+    }
+
+    let mut count = 0;
+    for path in glob(&format!("{SLANG_SHADERS}/**/*.slangp"))
+        .unwrap()
+        .flatten()
+    {
+        let relative = path.strip_prefix(SLANG_SHADERS).unwrap();
+        if BROKEN_PRESETS
+            .iter()
+            .any(|broken| relative == Path::new(broken))
+        {
+            continue;
+        }
+
+        assert_round_trip(&path, count);
+        count += 1;
+    }
+
+    assert!(
+        count > 0,
+        "no presets in {SLANG_SHADERS} were round tripped"
+    );
+}
+
+#[test]
+fn writes_relative_paths() {
+    let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+    let out = Path::new("../test/nested/basic.slangp");
+    let string = preset.to_preset_string(out);
+    assert!(string.contains("shader0 = \"../basic.slang\""), "{string}");
+}
+
+#[test]
+fn writes_reference_preset() {
+    let reference = "../test/basic.slangp";
+    let mut preset = ShaderPreset::try_parse(reference).unwrap();
+    for parameter in preset.parameters.iter_mut() {
+        if parameter.name == "ColorMod" {
+            parameter.value = 0.5;
+        }
+    }
+
+    let out = std::env::temp_dir().join("librashader-write-reference.slangp");
+    preset.write_reference_preset(reference, &out).unwrap();
+
+    let string = std::fs::read_to_string(&out).unwrap();
+    let written = ShaderPreset::try_parse(&out);
+    std::fs::remove_file(&out).ok();
+
+    let lines: Vec<&str> = string.lines().collect();
+    assert_eq!(lines.len(), 2, "{string}");
+    assert!(lines[0].starts_with("#reference \"") && lines[0].ends_with("test/basic.slangp\""));
+    assert_eq!(lines[1], "ColorMod = \"0.5\"");
+
+    let written = written.unwrap();
+    assert_eq!(written.parameters, preset.parameters);
+    assert_eq!(written.shaders.len(), preset.shaders.len());
+}
+
+#[test]
+fn writes_reference_preset_pass_overrides() {
+    let reference = "../test/basic.slangp";
+    let mut preset = ShaderPreset::try_parse(reference).unwrap();
+    preset.shaders[0].filter = FilterMode::Linear;
+    preset.shaders[0].mipmap_input = false;
+
+    let out = std::env::temp_dir().join("librashader-write-reference-pass.slangp");
+    preset.write_reference_preset(reference, &out).unwrap();
+
+    let written = ShaderPreset::try_parse(&out);
+    std::fs::remove_file(&out).ok();
+
+    let written = written.unwrap();
+    assert_eq!(written.shaders[0].filter, FilterMode::Linear);
+    assert!(!written.shaders[0].mipmap_input);
+    assert_eq!(written.parameters, preset.parameters);
+}
+
+#[test]
+fn writes_reference_preset_removals_and_resets() {
+    let test_dir = Path::new("../test").canonicalize().unwrap();
+    let dir = std::env::temp_dir().join("librashader-write-reference-resets");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let reference = dir.join("base.slangp");
+    std::fs::write(
+        &reference,
+        format!(
+            r#"shaders = 3
+shader0 = "{test}/basic.slang"
+alias0 = "Base"
+frame_count_mod0 = "2"
+scale_type0 = "source"
+scale0 = "2.0"
+shader1 = "{test}/null.slang"
+scale_type_x1 = "absolute"
+scale_x1 = "320"
+scale_type_y1 = "source"
+scale_y1 = "1.0"
+shader2 = "{test}/null.slang"
+textures = "Image;Other"
+Image = "{test}/sf2.png"
+Other = "{test}/agb.png"
+"#,
+            test = test_dir.display()
+        ),
+    )
+    .unwrap();
+
+    let mut preset = ShaderPreset::try_parse(&reference).unwrap();
+    preset.shaders[0].alias = None;
+    preset.shaders[0].frame_count_mod = 0;
+    preset.shaders[1].scaling = preset.shaders[0].scaling.clone();
+    preset.shaders[0].scaling = preset.shaders[2].scaling.clone();
+    preset.textures.retain(|texture| texture.name == "Image");
+
+    let out = dir.join("override.slangp");
+    preset.write_reference_preset(&reference, &out).unwrap();
+    let written = ShaderPreset::try_parse(&out);
+    std::fs::remove_dir_all(&dir).ok();
+
+    let written = written.unwrap();
+    assert!(!written.shaders[0].scaling.valid);
+    assert_eq!(written.shaders, preset.shaders);
+    assert_eq!(written.textures, preset.textures);
+}
+
+#[test]
+fn first_value_takes_precedence_within_a_preset() {
+    let preset = ShaderPreset::try_parse_str(
+        "shaders = 1\nshader0 = basic.slang\nfilter_linear0 = true\nfilter_linear0 = false\nalias0 = First\nalias0 = Second\n",
+        Path::new("../test").canonicalize().unwrap(),
+        WildcardContext::new(),
+        &StdFileProvider,
+    )
+    .unwrap();
+
+    assert_eq!(preset.shaders[0].filter, FilterMode::Linear);
+    assert_eq!(preset.shaders[0].alias.as_deref(), Some("First"));
+}
@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+/// Find the `Cargo.lock` that the current build was resolved with.
+///
+/// The target directory is usually inside the workspace being built, so the ancestors of
+/// `OUT_DIR` are searched first. If the target directory was moved elsewhere, fall back to the
+/// ancestors of this package, which finds the lockfile when building in this workspace.
+fn find_lockfile() -> Option<PathBuf> {
+    let out_dir = std::env::var_os("OUT_DIR").map(PathBuf::from);
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+
+    [out_dir, manifest_dir]
+        .into_iter()
+        .flatten()
+        .find_map(|dir| {
+            dir.ancestors()
+                .map(|dir| dir.join("Cargo.lock"))
+                .find(|lockfile| lockfile.is_file())
+        })
+}
+
+/// Get the versions of the `glslang` package in a lockfile.
+fn glslang_versions(lockfile: &Path) -> Vec<String> {
+    let Ok(lockfile) = std::fs::read_to_string(lockfile) else {
+        return Vec::new();
+    };
+
+    let mut versions = Vec::new();
+    let mut name = None;
+    for line in lockfile.lines().map(str::trim) {
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"'));
+        } else if let Some(value) = line
+            .strip_prefix("version = ")
+            .filter(|_| name == Some("glslang"))
+        {
+            versions.push(value.trim_matches('"').to_string());
+        }
+    }
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+pub fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let versions = match find_lockfile() {
+        Some(lockfile) => {
+            println!("cargo:rerun-if-changed={}", lockfile.display());
+            glslang_versions(&lockfile)
+        }
+        None => Vec::new(),
+    };
+
+    // If more than one version is locked, the one that is used can not be told apart,
+    // so all of them are part of the version.
+    let version = if versions.is_empty() {
+        println!("cargo:warning=Could not find the resolved version of glslang in Cargo.lock.");
+        String::from("unknown")
+    } else {
+        versions.join("+")
+    };
+
+    println!("cargo:rustc-env=LIBRASHADER_GLSLANG_VERSION={version}");
+}
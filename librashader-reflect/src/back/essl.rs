@@ -0,0 +1,106 @@
+use crate::back::glsl::{CrossGlslContext, GlslVersion};
+use crate::back::targets::ESSL;
+use crate::back::{CompileShader, CompilerBackend, FromCompilation};
+use crate::error::ShaderReflectError;
+use crate::front::SpirvCompilation;
+use crate::reflect::cross::glsl::GlslReflect;
+use crate::reflect::cross::SpirvCross;
+use crate::reflect::ReflectShader;
+
+/// The GLSL ES version to target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EsslVersion {
+    /// GLSL ES 3.00 (`#version 300 es`), for OpenGL ES 3.0 and WebGL 2.
+    V3_00,
+    /// GLSL ES 3.10 (`#version 310 es`), for OpenGL ES 3.1.
+    V3_10,
+    /// GLSL ES 3.20 (`#version 320 es`), for OpenGL ES 3.2.
+    V3_20,
+}
+
+impl EsslVersion {
+    /// Whether or not explicit `binding` layout qualifiers are available in this version.
+    ///
+    /// If explicit bindings are unavailable, samplers must be bound to the texture units
+    /// in [`CrossGlslContext::sampler_bindings`], and uniform blocks must be bound by name.
+    pub fn has_explicit_bindings(&self) -> bool {
+        *self != EsslVersion::V3_00
+    }
+}
+
+impl From<EsslVersion> for GlslVersion {
+    fn from(value: EsslVersion) -> Self {
+        match value {
+            EsslVersion::V3_00 => GlslVersion::V3_00Es,
+            EsslVersion::V3_10 => GlslVersion::V3_10Es,
+            EsslVersion::V3_20 => GlslVersion::V3_20Es,
+        }
+    }
+}
+
+impl FromCompilation<SpirvCompilation, SpirvCross> for ESSL {
+    type Target = ESSL;
+    type Options = EsslVersion;
+    type Context = CrossGlslContext;
+    type Output = impl CompileShader<Self::Target, Options = EsslVersion, Context = Self::Context>
+        + ReflectShader;
+
+    fn from_compilation(
+        compile: SpirvCompilation,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        Ok(CompilerBackend {
+            backend: GlslReflect::try_from(&compile)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::back::essl::EsslVersion;
+    use crate::back::targets::ESSL;
+    use crate::back::{CompileShader, FromCompilation};
+    use crate::front::SpirvCompilation;
+    use crate::reflect::cross::SpirvCross;
+    use librashader_preprocess::ShaderSource;
+
+    fn validate(source: &str, stage: glslang::ShaderStage) {
+        let compiler = glslang::Compiler::acquire().unwrap();
+        let options = glslang::CompilerOptions {
+            source_language: glslang::SourceLanguage::GLSL,
+            target: glslang::Target::None(None),
+            version_profile: None,
+        };
+
+        let source = glslang::ShaderSource::from(source);
+        let input = glslang::ShaderInput::new(&source, stage, &options, None).unwrap();
+        assert_eq!(
+            source.parse_profile().map(|(_, profile)| profile),
+            Some(glslang::GlslProfile::ES)
+        );
+        if let Err(err) = compiler.create_shader(input) {
+            panic!("{err}");
+        }
+    }
+
+    #[test]
+    pub fn compiles_and_validates() {
+        let result = ShaderSource::load("../test/basic.slang").unwrap();
+
+        for version in [EsslVersion::V3_00, EsslVersion::V3_10, EsslVersion::V3_20] {
+            let compilation = SpirvCompilation::try_from(&result).unwrap();
+            let essl =
+                <ESSL as FromCompilation<_, SpirvCross>>::from_compilation(compilation).unwrap();
+            let compiled = essl.compile(version).unwrap();
+
+            assert!(compiled.fragment.contains("precision highp float;"));
+            assert_eq!(
+                compiled.fragment.contains("binding"),
+                version.has_explicit_bindings()
+            );
+            assert!(!compiled.context.sampler_bindings.is_empty());
+
+            validate(&compiled.vertex, glslang::ShaderStage::Vertex);
+            validate(&compiled.fragment, glslang::ShaderStage::Fragment);
+        }
+    }
+}
@@ -3,6 +3,8 @@ use crate::back::{CompileShader, CompilerBackend, FromCompilation};
 use crate::error::ShaderReflectError;
 use crate::front::SpirvCompilation;
 use crate::reflect::cross::{CompiledProgram, SpirvCross};
+#[cfg(feature = "naga")]
+use crate::reflect::naga::NagaReflect;
 use crate::reflect::ReflectShader;
 
 /// The GLSL version to target.
@@ -33,3 +35,37 @@ impl FromCompilation<SpirvCompilation, SpirvCross> for GLSL {
         })
     }
 }
+
+/// The context for a GLSL compilation via Naga.
+///
+/// Like the other Naga backends, resource names are generated by Naga rather than
+/// preserved from the shader source.
+#[cfg(feature = "naga")]
+pub struct NagaGlslContext {
+    /// A map of bindings of sampler names to binding locations.
+    pub sampler_bindings: Vec<(String, u32)>,
+    /// The vertex shader module after lowering.
+    pub vertex: naga::Module,
+    /// The fragment shader module after lowering.
+    pub fragment: naga::Module,
+}
+
+#[cfg(feature = "naga")]
+impl GLSL {
+    /// Tries to convert the input object into an object ready for compilation to GLSL with Naga.
+    ///
+    /// This is not an implementation of [`FromCompilation`], so that `GLSL::from_compilation`
+    /// unambiguously compiles with spirv-cross.
+    pub fn from_naga_compilation(
+        compile: SpirvCompilation,
+    ) -> Result<
+        CompilerBackend<
+            impl CompileShader<GLSL, Options = GlslVersion, Context = NagaGlslContext> + ReflectShader,
+        >,
+        ShaderReflectError,
+    > {
+        Ok(CompilerBackend {
+            backend: NagaReflect::try_from(&compile)?,
+        })
+    }
+}
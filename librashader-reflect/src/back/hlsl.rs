@@ -4,6 +4,8 @@ use crate::error::ShaderReflectError;
 use crate::front::SpirvCompilation;
 use crate::reflect::cross::hlsl::HlslReflect;
 use crate::reflect::cross::{CompiledProgram, SpirvCross};
+#[cfg(feature = "naga")]
+use crate::reflect::naga::NagaReflect;
 use crate::reflect::ReflectShader;
 
 /// The HLSL shader model version to target.
@@ -115,6 +117,36 @@ impl FromCompilation<SpirvCompilation, SpirvCross> for HLSL {
     }
 }
 
+/// The context for a HLSL compilation via Naga.
+#[cfg(feature = "naga")]
+pub struct NagaHlslContext {
+    /// The vertex shader module after lowering.
+    pub vertex: naga::Module,
+    /// The fragment shader module after lowering.
+    pub fragment: naga::Module,
+}
+
+#[cfg(feature = "naga")]
+impl HLSL {
+    /// Tries to convert the input object into an object ready for compilation to HLSL with Naga.
+    ///
+    /// This is not an implementation of [`FromCompilation`], so that `HLSL::from_compilation`
+    /// unambiguously compiles with spirv-cross.
+    pub fn from_naga_compilation(
+        compile: SpirvCompilation,
+    ) -> Result<
+        CompilerBackend<
+            impl CompileShader<HLSL, Options = Option<HlslShaderModel>, Context = NagaHlslContext>
+                + ReflectShader,
+        >,
+        ShaderReflectError,
+    > {
+        Ok(CompilerBackend {
+            backend: NagaReflect::try_from(&compile)?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::back::hlsl::HlslBufferAssignments;
@@ -1,5 +1,6 @@
 #[cfg(all(target_os = "windows", feature = "dxil"))]
 pub mod dxil;
+pub mod essl;
 pub mod glsl;
 pub mod hlsl;
 pub mod msl;
@@ -87,6 +87,7 @@ impl FromCompilation<SpirvCompilation, Naga> for SPIRV {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct NagaSpirvOptions {
     pub lowering: NagaLoweringOptions,
     pub version: (u8, u8),
@@ -7,6 +7,9 @@ pub trait OutputTarget {
 /// Shader compiler target for GLSL.
 #[derive(Debug)]
 pub struct GLSL;
+/// Shader compiler target for GLSL ES.
+#[derive(Debug)]
+pub struct ESSL;
 /// Shader compiler target for HLSL.
 #[derive(Debug)]
 pub struct HLSL;
@@ -33,6 +36,9 @@ pub struct WGSL;
 impl OutputTarget for GLSL {
     type Output = String;
 }
+impl OutputTarget for ESSL {
+    type Output = String;
+}
 impl OutputTarget for HLSL {
     type Output = String;
 }
@@ -18,6 +18,10 @@ pub enum ShaderCompileError {
     #[error("error when initializing glslang")]
     CompilerInitError,
 
+    /// Error when creating a thread pool to compile shader passes with.
+    #[error("error when creating a thread pool: {0}")]
+    ThreadPoolError(#[from] rayon::ThreadPoolBuildError),
+
     /// Error when transpiling from spirv-cross.
     #[error("spirv-cross error: {0:?}")]
     SpirvCrossCompileError(#[from] spirv_cross::ErrorCode),
@@ -37,6 +41,16 @@ pub enum ShaderCompileError {
     #[error("naga error when compiling spirv: {0:?}")]
     NagaSpvError(#[from] naga::back::spv::Error),
 
+    /// Error when transpiling from naga
+    #[cfg(feature = "naga")]
+    #[error("naga error when compiling glsl: {0:?}")]
+    NagaGlslError(#[from] naga::back::glsl::Error),
+
+    /// Error when transpiling from naga
+    #[cfg(feature = "naga")]
+    #[error("naga error when compiling hlsl: {0:?}")]
+    NagaHlslError(#[from] naga::back::hlsl::Error),
+
     /// Error when transpiling from naga
     #[cfg(all(feature = "naga", feature = "msl"))]
     #[error("naga error when compiling msl: {0:?}")]
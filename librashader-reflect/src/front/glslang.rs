@@ -2,21 +2,57 @@ use crate::error::ShaderCompileError;
 use glslang::{CompilerOptions, ShaderInput};
 use librashader_preprocess::ShaderSource;
 use rspirv::binary::Assemble;
-use rspirv::dr::Builder;
+use rspirv::dr::{Builder, Module};
 
+use crate::front::spirv_passes::eliminate_dead_code::EliminateDeadCode;
+use crate::front::spirv_passes::fold_parameters::FoldParameters;
+use crate::front::spirv_passes::inline_functions::InlineFunctions;
 use crate::front::spirv_passes::{link_input_outputs, load_module};
-use crate::front::{ShaderInputCompiler, SpirvCompilation};
+use crate::front::{ShaderInputCompiler, SpirvCompilation, SpirvOptimization};
 
 /// glslang compiler
 pub struct Glslang;
 
 impl ShaderInputCompiler<SpirvCompilation> for Glslang {
     fn compile(source: &ShaderSource) -> Result<SpirvCompilation, ShaderCompileError> {
-        compile_spirv(source)
+        compile_spirv(source, None)
     }
+
+    fn compile_with_options(
+        source: &ShaderSource,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<SpirvCompilation, ShaderCompileError> {
+        compile_spirv(source, optimization)
+    }
+}
+
+/// Run the enabled optimization passes over the module.
+fn optimize(module: Module, optimization: &SpirvOptimization) -> Module {
+    let mut builder = Builder::new_from_module(module);
+
+    if optimization.inline_functions {
+        let mut pass = InlineFunctions::new(&mut builder);
+        pass.do_pass();
+    }
+
+    if !optimization.constant_parameters.is_empty() {
+        let mut pass = FoldParameters::new(&mut builder, &optimization.constant_parameters);
+        pass.do_pass();
+    }
+
+    if optimization.eliminate_dead_code {
+        let mut pass =
+            EliminateDeadCode::new(builder.module_mut(), optimization.remove_unused_resources);
+        pass.do_pass();
+    }
+
+    builder.module()
 }
 
-pub(crate) fn compile_spirv(source: &ShaderSource) -> Result<SpirvCompilation, ShaderCompileError> {
+pub(crate) fn compile_spirv(
+    source: &ShaderSource,
+    optimization: Option<&SpirvOptimization>,
+) -> Result<SpirvCompilation, ShaderCompileError> {
     let compiler = glslang::Compiler::acquire().ok_or(ShaderCompileError::CompilerInitError)?;
     let options = CompilerOptions {
         source_language: glslang::SourceLanguage::GLSL,
@@ -45,19 +81,150 @@ pub(crate) fn compile_spirv(source: &ShaderSource) -> Result<SpirvCompilation, S
     let mut pass = link_input_outputs::LinkInputs::new(&vertex, &mut fragment);
     pass.do_pass();
 
+    let (vertex, fragment) = match optimization {
+        Some(optimization) => (
+            optimize(vertex, optimization),
+            optimize(fragment.module(), optimization),
+        ),
+        None => (vertex, fragment.module()),
+    };
+
     let vertex = vertex.assemble();
-    let fragment = fragment.module().assemble();
+    let fragment = fragment.assemble();
 
     Ok(SpirvCompilation { vertex, fragment })
 }
 
 #[cfg(test)]
 mod test {
+    use crate::back::targets::{HLSL, WGSL};
+    use crate::back::{CompileShader, FromCompilation};
     use crate::front::glslang::compile_spirv;
+    use crate::front::spirv_passes::load_module;
+    use crate::front::SpirvOptimization;
+    use crate::reflect::naga::{Naga, NagaLoweringOptions};
+    use crate::reflect::presets::preset_semantics;
+    use crate::reflect::ReflectShader;
     use librashader_preprocess::ShaderSource;
+    use librashader_presets::ParameterConfig;
+    use rspirv::dr::Operand;
+    use spirv::{Op, StorageClass};
+
+    const FRAGMENT_WITH_FUNCTIONS: &str = r#"#version 450
+layout(location = 0) in vec2 vTexCoord;
+layout(location = 0) out vec4 FragColor;
+layout(binding = 1) uniform sampler2D Source;
+layout(binding = 2) uniform sampler2D Unused;
+
+layout(push_constant) uniform Push {
+    float ColorMod2;
+} params;
+
+vec4 scale(vec4 color, float factor)
+{
+    vec4 scaled = color * factor;
+    if (factor > 1.0) {
+        scaled = clamp(scaled, 0.0, 1.0);
+    }
+    return scaled;
+}
+
+vec4 sample_source(vec2 coord)
+{
+    return scale(texture(Source, coord), params.ColorMod2);
+}
+
+void main()
+{
+    FragColor = sample_source(vTexCoord);
+}
+"#;
+
+    fn storage_classes(words: &[u32]) -> Vec<StorageClass> {
+        load_module(words)
+            .types_global_values
+            .iter()
+            .filter(|inst| inst.class.opcode == Op::Variable)
+            .filter_map(|inst| match inst.operands.first() {
+                Some(&Operand::StorageClass(storage_class)) => Some(storage_class),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn cross_compile(source: &ShaderSource, optimization: &SpirvOptimization) {
+        let compilation = compile_spirv(source, Some(optimization)).unwrap();
+        let hlsl = HLSL::from_compilation(compilation.clone()).unwrap();
+        hlsl.compile(None).unwrap();
+
+        // Naga validates the module after samplers are lowered.
+        let semantics = preset_semantics([source], [], &[]);
+        let mut wgsl = <WGSL as FromCompilation<_, Naga>>::from_compilation(compilation).unwrap();
+        wgsl.reflect(0, &semantics).unwrap();
+        wgsl.compile(NagaLoweringOptions {
+            write_pcb_as_ubo: true,
+            sampler_bind_group: 1,
+        })
+        .unwrap();
+    }
+
     #[test]
     pub fn compile_shader() {
         let result = ShaderSource::load("../test/basic.slang").unwrap();
-        let _spirv = compile_spirv(&result).unwrap();
+        let _spirv = compile_spirv(&result, None).unwrap();
+    }
+
+    #[test]
+    pub fn folds_preset_parameters() {
+        let result = ShaderSource::load("../test/basic.slang").unwrap();
+        let optimization = SpirvOptimization::all().with_preset_parameters(&[
+            ParameterConfig {
+                name: String::from("ColorMod"),
+                value: 1.0,
+            },
+            ParameterConfig {
+                name: String::from("ColorMod2"),
+                value: 1.0,
+            },
+        ]);
+
+        let unoptimized = compile_spirv(&result, None).unwrap();
+        let optimized = compile_spirv(&result, Some(&optimization)).unwrap();
+        assert!(optimized.fragment.len() < unoptimized.fragment.len());
+
+        // With both parameters folded, the fragment shader only reads the texture.
+        let fragment = storage_classes(&optimized.fragment);
+        assert!(!fragment.contains(&StorageClass::PushConstant));
+        assert!(!fragment.contains(&StorageClass::Uniform));
+        assert!(fragment.contains(&StorageClass::UniformConstant));
+
+        // The vertex shader still needs the MVP matrix.
+        assert!(storage_classes(&optimized.vertex).contains(&StorageClass::Uniform));
+
+        cross_compile(&result, &optimization);
+    }
+
+    #[test]
+    pub fn inlines_functions() {
+        let mut result = ShaderSource::load("../test/basic.slang").unwrap();
+        result.fragment = String::from(FRAGMENT_WITH_FUNCTIONS);
+
+        let optimization = SpirvOptimization::all();
+        let optimized = compile_spirv(&result, Some(&optimization)).unwrap();
+        let fragment = load_module(&optimized.fragment);
+        assert_eq!(fragment.functions.len(), 1);
+
+        // The unused texture is removed, but the parameter is still read.
+        let fragment = storage_classes(&optimized.fragment);
+        assert_eq!(
+            fragment
+                .iter()
+                .filter(|&&class| class == StorageClass::UniformConstant)
+                .count(),
+            1
+        );
+        assert!(fragment.contains(&StorageClass::PushConstant));
+
+        cross_compile(&result, &optimization);
     }
 }
@@ -1,5 +1,7 @@
 use crate::error::ShaderCompileError;
+use librashader_common::map::FastHashMap;
 use librashader_preprocess::ShaderSource;
+use librashader_presets::ParameterConfig;
 use serde::{Deserialize, Serialize};
 pub(crate) mod spirv_passes;
 
@@ -13,10 +15,23 @@ pub trait ShaderReflectObject: Sized {
 
 pub use crate::front::glslang::Glslang;
 
+/// The version of the glslang bindings that shader sources are compiled with.
+///
+/// This is the version of the `glslang` dependency in the `Cargo.lock` the crate was built with,
+/// or `unknown` if the lockfile could not be found.
+pub const GLSLANG_VERSION: &str = env!("LIBRASHADER_GLSLANG_VERSION");
+
 /// Trait for types that can compile shader sources into a compilation unit.
 pub trait ShaderInputCompiler<O: ShaderReflectObject>: Sized {
     /// Compile the input shader source file into a compilation unit.
     fn compile(source: &ShaderSource) -> Result<O, ShaderCompileError>;
+
+    /// Compile the input shader source file into a compilation unit, optimizing
+    /// the compilation if optimizations are given.
+    fn compile_with_options(
+        source: &ShaderSource,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<O, ShaderCompileError>;
 }
 
 /// Marker trait for types that are the reflectable outputs of a shader compilation.
@@ -35,7 +50,67 @@ pub struct SpirvCompilation {
 impl SpirvCompilation {
     /// Tries to compile SPIR-V from the provided shader source.
     pub fn compile(source: &ShaderSource) -> Result<Self, ShaderCompileError> {
-        glslang::compile_spirv(source)
+        glslang::compile_spirv(source, None)
+    }
+
+    /// Tries to compile SPIR-V from the provided shader source, optimizing
+    /// the SPIR-V before it is reflected and cross-compiled.
+    pub fn compile_with_optimization(
+        source: &ShaderSource,
+        optimization: &SpirvOptimization,
+    ) -> Result<Self, ShaderCompileError> {
+        glslang::compile_spirv(source, Some(optimization))
+    }
+
+    /// Tries to compile SPIR-V from the provided shader source, optimizing
+    /// the SPIR-V if optimizations are given.
+    pub fn compile_with_options(
+        source: &ShaderSource,
+        optimization: Option<&SpirvOptimization>,
+    ) -> Result<Self, ShaderCompileError> {
+        glslang::compile_spirv(source, optimization)
+    }
+}
+
+/// Optimizations to apply to SPIR-V before it is reflected and cross-compiled.
+///
+/// No optimizations are enabled by default.
+#[derive(Debug, Clone, Default)]
+pub struct SpirvOptimization {
+    /// Inline function calls into the entry point of the shader.
+    pub inline_functions: bool,
+    /// Remove functions, instructions, and variables that do not contribute to the output of the shader.
+    pub eliminate_dead_code: bool,
+    /// Remove uniform buffers, push constant blocks, and textures that are never accessed,
+    /// so that they are not reflected and do not need to be bound.
+    ///
+    /// This has no effect unless dead code elimination is enabled.
+    pub remove_unused_resources: bool,
+    /// Parameters with a fixed value that are folded into constants.
+    ///
+    /// A folded parameter is no longer read from the uniform buffer or push constant block,
+    /// and changing its value at runtime has no effect.
+    pub constant_parameters: FastHashMap<String, f32>,
+}
+
+impl SpirvOptimization {
+    /// Enable every optimization, without folding any parameters.
+    pub fn all() -> Self {
+        SpirvOptimization {
+            inline_functions: true,
+            eliminate_dead_code: true,
+            remove_unused_resources: true,
+            constant_parameters: FastHashMap::default(),
+        }
+    }
+
+    /// Fold the parameters set in a shader preset into constants with the value set in the preset.
+    pub fn with_preset_parameters(mut self, parameters: &[ParameterConfig]) -> Self {
+        for parameter in parameters {
+            self.constant_parameters
+                .insert(parameter.name.clone(), parameter.value);
+        }
+        self
     }
 }
 
@@ -47,3 +122,18 @@ impl TryFrom<&ShaderSource> for SpirvCompilation {
         Glslang::compile(source)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::GLSLANG_VERSION;
+
+    #[test]
+    pub fn resolves_glslang_version() {
+        assert!(
+            GLSLANG_VERSION
+                .split('.')
+                .all(|part| part.parse::<u32>().is_ok()),
+            "{GLSLANG_VERSION}"
+        );
+    }
+}
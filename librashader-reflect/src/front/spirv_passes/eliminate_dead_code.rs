@@ -0,0 +1,290 @@
+use crate::front::spirv_passes::{remove_dangling_references, remove_unreachable_functions};
+use rspirv::dr::{Instruction, Module, Operand};
+use rustc_hash::FxHashSet;
+use spirv::{Op, StorageClass, Word};
+
+/// Remove functions, instructions, stores, and global values that do not contribute
+/// to the output of the shader.
+///
+/// Shader inputs and outputs are always kept, since they form the interface between stages.
+/// Uniform buffers, push constant blocks, and textures are only removed if
+/// `remove_unused_resources` is set.
+pub struct EliminateDeadCode<'a> {
+    pub module: &'a mut Module,
+    pub remove_unused_resources: bool,
+}
+
+/// Whether or not the instruction has no side effects, and can be removed if its result is unused.
+fn is_pure(inst: &Instruction) -> bool {
+    matches!(
+        inst.class.opcode,
+        Op::Undef
+            | Op::Load
+            | Op::AccessChain
+            | Op::InBoundsAccessChain
+            | Op::CopyObject
+            | Op::Phi
+            | Op::ExtInst
+            | Op::VectorExtractDynamic
+            | Op::VectorInsertDynamic
+            | Op::VectorShuffle
+            | Op::CompositeConstruct
+            | Op::CompositeExtract
+            | Op::CompositeInsert
+            | Op::Transpose
+            | Op::SampledImage
+            | Op::Image
+            | Op::ImageSampleImplicitLod
+            | Op::ImageSampleExplicitLod
+            | Op::ImageSampleDrefImplicitLod
+            | Op::ImageSampleDrefExplicitLod
+            | Op::ImageSampleProjImplicitLod
+            | Op::ImageSampleProjExplicitLod
+            | Op::ImageFetch
+            | Op::ImageGather
+            | Op::ImageQuerySizeLod
+            | Op::ImageQuerySize
+            | Op::ImageQueryLevels
+            | Op::ConvertFToU
+            | Op::ConvertFToS
+            | Op::ConvertSToF
+            | Op::ConvertUToF
+            | Op::UConvert
+            | Op::SConvert
+            | Op::FConvert
+            | Op::Bitcast
+            | Op::SNegate
+            | Op::FNegate
+            | Op::IAdd
+            | Op::FAdd
+            | Op::ISub
+            | Op::FSub
+            | Op::IMul
+            | Op::FMul
+            | Op::UDiv
+            | Op::SDiv
+            | Op::FDiv
+            | Op::UMod
+            | Op::SRem
+            | Op::SMod
+            | Op::FRem
+            | Op::FMod
+            | Op::VectorTimesScalar
+            | Op::MatrixTimesScalar
+            | Op::VectorTimesMatrix
+            | Op::MatrixTimesVector
+            | Op::MatrixTimesMatrix
+            | Op::OuterProduct
+            | Op::Dot
+            | Op::Any
+            | Op::All
+            | Op::IsNan
+            | Op::IsInf
+            | Op::LogicalEqual
+            | Op::LogicalNotEqual
+            | Op::LogicalOr
+            | Op::LogicalAnd
+            | Op::LogicalNot
+            | Op::Select
+            | Op::IEqual
+            | Op::INotEqual
+            | Op::UGreaterThan
+            | Op::SGreaterThan
+            | Op::UGreaterThanEqual
+            | Op::SGreaterThanEqual
+            | Op::ULessThan
+            | Op::SLessThan
+            | Op::ULessThanEqual
+            | Op::SLessThanEqual
+            | Op::FOrdEqual
+            | Op::FUnordEqual
+            | Op::FOrdNotEqual
+            | Op::FUnordNotEqual
+            | Op::FOrdLessThan
+            | Op::FUnordLessThan
+            | Op::FOrdGreaterThan
+            | Op::FUnordGreaterThan
+            | Op::FOrdLessThanEqual
+            | Op::FUnordLessThanEqual
+            | Op::FOrdGreaterThanEqual
+            | Op::FUnordGreaterThanEqual
+            | Op::ShiftRightLogical
+            | Op::ShiftRightArithmetic
+            | Op::ShiftLeftLogical
+            | Op::BitwiseOr
+            | Op::BitwiseXor
+            | Op::BitwiseAnd
+            | Op::Not
+            | Op::DPdx
+            | Op::DPdy
+            | Op::Fwidth
+            | Op::DPdxFine
+            | Op::DPdyFine
+            | Op::FwidthFine
+            | Op::DPdxCoarse
+            | Op::DPdyCoarse
+            | Op::FwidthCoarse
+    )
+}
+
+/// Whether or not the storage class is of a resource bound by the runtime.
+fn is_resource(storage_class: StorageClass) -> bool {
+    matches!(
+        storage_class,
+        StorageClass::Uniform
+            | StorageClass::UniformConstant
+            | StorageClass::PushConstant
+            | StorageClass::StorageBuffer
+    )
+}
+
+impl<'a> EliminateDeadCode<'a> {
+    pub fn new(module: &'a mut Module, remove_unused_resources: bool) -> Self {
+        Self {
+            module,
+            remove_unused_resources,
+        }
+    }
+
+    pub fn do_pass(&mut self) {
+        loop {
+            let mut changed = remove_unreachable_functions(self.module);
+            changed |= self.remove_dead_instructions();
+            changed |= self.remove_dead_stores();
+            changed |= self.remove_unused_globals();
+            if !changed {
+                break;
+            }
+        }
+
+        remove_dangling_references(self.module);
+    }
+
+    /// Collect every id that is referenced by a global value or an instruction in a function.
+    ///
+    /// Debug names, decorations, and entry point interfaces do not count as uses.
+    fn used_ids(&self) -> FxHashSet<Word> {
+        let mut used = FxHashSet::default();
+        for inst in self
+            .module
+            .execution_modes
+            .iter()
+            .chain(&self.module.types_global_values)
+            .chain(self.module.functions.iter().flat_map(|f| f.all_inst_iter()))
+        {
+            used.extend(inst.result_type);
+            used.extend(inst.operands.iter().filter_map(|op| op.id_ref_any()));
+        }
+        used
+    }
+
+    fn remove_dead_instructions(&mut self) -> bool {
+        let used = self.used_ids();
+        let mut changed = false;
+        for block in self
+            .module
+            .functions
+            .iter_mut()
+            .flat_map(|f| f.blocks.iter_mut())
+        {
+            let count = block.instructions.len();
+            block.instructions.retain(|inst| {
+                !is_pure(inst) || inst.result_id.is_none_or(|id| used.contains(&id))
+            });
+            changed |= count != block.instructions.len();
+        }
+        changed
+    }
+
+    /// Remove function and private variables that are only ever stored to.
+    fn remove_dead_stores(&mut self) -> bool {
+        let mut variables: FxHashSet<Word> = self
+            .module
+            .types_global_values
+            .iter()
+            .chain(
+                self.module
+                    .functions
+                    .iter()
+                    .flat_map(|f| f.blocks.iter().flat_map(|b| &b.instructions)),
+            )
+            .filter(|inst| {
+                inst.class.opcode == Op::Variable
+                    && matches!(
+                        inst.operands.first(),
+                        Some(Operand::StorageClass(
+                            StorageClass::Function | StorageClass::Private
+                        ))
+                    )
+            })
+            .filter_map(|inst| inst.result_id)
+            .collect();
+
+        for inst in self.module.functions.iter().flat_map(|f| f.all_inst_iter()) {
+            for (index, op) in inst.operands.iter().enumerate() {
+                let Some(id) = op.id_ref_any() else {
+                    continue;
+                };
+
+                if inst.class.opcode != Op::Store || index != 0 {
+                    variables.remove(&id);
+                }
+            }
+        }
+
+        if variables.is_empty() {
+            return false;
+        }
+
+        let is_dead = |inst: &Instruction| match inst.class.opcode {
+            Op::Variable => inst.result_id.is_some_and(|id| variables.contains(&id)),
+            Op::Store => inst
+                .operands
+                .first()
+                .and_then(|op| op.id_ref_any())
+                .is_some_and(|id| variables.contains(&id)),
+            _ => false,
+        };
+
+        self.module
+            .types_global_values
+            .retain(|inst| !is_dead(inst));
+        for block in self
+            .module
+            .functions
+            .iter_mut()
+            .flat_map(|f| f.blocks.iter_mut())
+        {
+            block.instructions.retain(|inst| !is_dead(inst));
+        }
+        true
+    }
+
+    fn remove_unused_globals(&mut self) -> bool {
+        let used = self.used_ids();
+        let remove_unused_resources = self.remove_unused_resources;
+        let count = self.module.types_global_values.len();
+        self.module.types_global_values.retain(|inst| {
+            let Some(id) = inst.result_id else {
+                return true;
+            };
+
+            if used.contains(&id) {
+                return true;
+            }
+
+            if inst.class.opcode != Op::Variable {
+                return false;
+            }
+
+            match inst.operands.first() {
+                Some(&Operand::StorageClass(StorageClass::Input | StorageClass::Output)) => true,
+                Some(&Operand::StorageClass(storage_class)) if is_resource(storage_class) => {
+                    !remove_unused_resources
+                }
+                _ => false,
+            }
+        });
+        count != self.module.types_global_values.len()
+    }
+}
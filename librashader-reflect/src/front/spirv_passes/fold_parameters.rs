@@ -0,0 +1,371 @@
+use crate::front::spirv_passes::replace_uses;
+use librashader_common::map::FastHashMap;
+use rspirv::dr::{Builder, Instruction, Operand};
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Decoration, Op, StorageClass, Word};
+
+/// Fold parameters with a fixed value into constants, then fold the
+/// constant expressions that result.
+///
+/// Loads of a folded parameter from the uniform buffer or push constant block are replaced
+/// with the value of the parameter, so that the parameter is no longer read by the shader.
+pub struct FoldParameters<'a> {
+    pub builder: &'a mut Builder,
+    pub parameters: &'a FastHashMap<String, f32>,
+    constants: FxHashMap<(Word, u32), Word>,
+}
+
+/// The value of a folded parameter or constant expression.
+#[derive(Copy, Clone)]
+enum Value {
+    Float(f32),
+    Bool(bool),
+}
+
+impl<'a> FoldParameters<'a> {
+    pub fn new(builder: &'a mut Builder, parameters: &'a FastHashMap<String, f32>) -> Self {
+        Self {
+            builder,
+            parameters,
+            constants: FxHashMap::default(),
+        }
+    }
+
+    pub fn do_pass(&mut self) {
+        let loads = self.fold_parameter_loads();
+        self.substitute(loads);
+
+        loop {
+            let folded = self.fold_constant_expressions();
+            if folded.is_empty() {
+                break;
+            }
+            self.substitute(folded);
+        }
+    }
+
+    fn find_global(&self, id: Word) -> Option<&Instruction> {
+        self.builder
+            .module_ref()
+            .types_global_values
+            .iter()
+            .find(|inst| inst.result_id == Some(id))
+    }
+
+    /// Get the values of every scalar 32-bit float, boolean, and integer constant.
+    fn constant_values(&self) -> (FxHashMap<Word, Value>, FxHashMap<Word, u32>) {
+        let globals = &self.builder.module_ref().types_global_values;
+        let types: FxHashMap<Word, &Instruction> = globals
+            .iter()
+            .filter_map(|inst| Some((inst.result_id?, inst)))
+            .collect();
+
+        let mut values = FxHashMap::default();
+        let mut indices = FxHashMap::default();
+        for constant in globals {
+            let Some(id) = constant.result_id else {
+                continue;
+            };
+
+            match constant.class.opcode {
+                Op::ConstantTrue => {
+                    values.insert(id, Value::Bool(true));
+                }
+                Op::ConstantFalse => {
+                    values.insert(id, Value::Bool(false));
+                }
+                Op::Constant => {
+                    let (Some(ty), Some(&Operand::LiteralBit32(bits))) = (
+                        constant.result_type.and_then(|ty| types.get(&ty)),
+                        constant.operands.first(),
+                    ) else {
+                        continue;
+                    };
+
+                    match ty.class.opcode {
+                        Op::TypeFloat
+                            if ty.operands.first() == Some(&Operand::LiteralBit32(32)) =>
+                        {
+                            values.insert(id, Value::Float(f32::from_bits(bits)));
+                        }
+                        Op::TypeInt => {
+                            indices.insert(id, bits);
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (values, indices)
+    }
+
+    /// Get or create a constant of the given type.
+    fn constant(&mut self, ty: Word, value: Value) -> Word {
+        let bits = match value {
+            Value::Float(value) => value.to_bits(),
+            Value::Bool(value) => value as u32,
+        };
+
+        if let Some(&id) = self.constants.get(&(ty, bits)) {
+            return id;
+        }
+
+        let id = match value {
+            Value::Float(_) => self.builder.constant_bit32(ty, bits),
+            Value::Bool(true) => self.builder.constant_true(ty),
+            Value::Bool(false) => self.builder.constant_false(ty),
+        };
+        self.constants.insert((ty, bits), id);
+        id
+    }
+
+    /// Find the members of uniform buffers and push constant blocks that hold
+    /// the folded parameters, as pairs of the block variable and member index.
+    fn find_parameter_members(&self) -> FxHashMap<(Word, u32), (Word, f32)> {
+        let module = self.builder.module_ref();
+        let blocks: FxHashSet<Word> = module
+            .annotations
+            .iter()
+            .filter(|inst| {
+                inst.class.opcode == Op::Decorate
+                    && inst.operands.get(1) == Some(&Operand::Decoration(Decoration::Block))
+            })
+            .filter_map(|inst| inst.operands.first()?.id_ref_any())
+            .collect();
+
+        let mut members = FxHashMap::default();
+        for variable in module.types_global_values.iter() {
+            if variable.class.opcode != Op::Variable
+                || !matches!(
+                    variable.operands.first(),
+                    Some(Operand::StorageClass(
+                        StorageClass::Uniform | StorageClass::PushConstant
+                    ))
+                )
+            {
+                continue;
+            }
+
+            let (Some(id), Some(pointer)) = (
+                variable.result_id,
+                variable.result_type.and_then(|ty| self.find_global(ty)),
+            ) else {
+                continue;
+            };
+
+            let Some(block) = pointer.operands.get(1).and_then(|op| op.id_ref_any()) else {
+                continue;
+            };
+
+            if !blocks.contains(&block) {
+                continue;
+            }
+
+            let Some(block_type) = self.find_global(block) else {
+                continue;
+            };
+
+            for name in module.debug_names.iter() {
+                let (
+                    Op::MemberName,
+                    Some(&Operand::IdRef(target)),
+                    Some(&Operand::LiteralBit32(index)),
+                    Some(Operand::LiteralString(name)),
+                ) = (
+                    name.class.opcode,
+                    name.operands.first(),
+                    name.operands.get(1),
+                    name.operands.get(2),
+                )
+                else {
+                    continue;
+                };
+
+                if target != block {
+                    continue;
+                }
+
+                let Some(&value) = self.parameters.get(name) else {
+                    continue;
+                };
+
+                // Only scalar float members can be folded.
+                let Some(member_type) = block_type
+                    .operands
+                    .get(index as usize)
+                    .and_then(|op| op.id_ref_any())
+                else {
+                    continue;
+                };
+
+                let Some(ty) = self.find_global(member_type) else {
+                    continue;
+                };
+
+                if ty.class.opcode == Op::TypeFloat
+                    && ty.operands.first() == Some(&Operand::LiteralBit32(32))
+                {
+                    members.insert((id, index), (member_type, value));
+                }
+            }
+        }
+
+        members
+    }
+
+    /// Replace loads of folded parameters with constants.
+    fn fold_parameter_loads(&mut self) -> FxHashMap<Word, Word> {
+        let members = self.find_parameter_members();
+        if members.is_empty() {
+            return FxHashMap::default();
+        }
+
+        let (_, indices) = self.constant_values();
+
+        let mut chains = FxHashMap::default();
+        let mut loads = Vec::new();
+        for inst in self
+            .builder
+            .module_ref()
+            .functions
+            .iter()
+            .flat_map(|f| f.all_inst_iter())
+        {
+            match inst.class.opcode {
+                Op::AccessChain | Op::InBoundsAccessChain if inst.operands.len() == 2 => {
+                    let (Some(base), Some(index), Some(id)) = (
+                        inst.operands[0].id_ref_any(),
+                        inst.operands[1].id_ref_any(),
+                        inst.result_id,
+                    ) else {
+                        continue;
+                    };
+
+                    let Some(&index) = indices.get(&index) else {
+                        continue;
+                    };
+
+                    if let Some(&member) = members.get(&(base, index)) {
+                        chains.insert(id, member);
+                    }
+                }
+                Op::Load => {
+                    let (Some(pointer), Some(id)) = (
+                        inst.operands.first().and_then(|op| op.id_ref_any()),
+                        inst.result_id,
+                    ) else {
+                        continue;
+                    };
+
+                    if let Some(&member) = chains.get(&pointer) {
+                        loads.push((id, member));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        loads
+            .into_iter()
+            .map(|(load, (ty, value))| (load, self.constant(ty, Value::Float(value))))
+            .collect()
+    }
+
+    /// Fold scalar expressions with constant operands.
+    fn fold_constant_expressions(&mut self) -> FxHashMap<Word, Word> {
+        let (values, _) = self.constant_values();
+        let mut folded = Vec::new();
+        let mut substitutions = FxHashMap::default();
+        for inst in self
+            .builder
+            .module_ref()
+            .functions
+            .iter()
+            .flat_map(|f| f.all_inst_iter())
+        {
+            let (Some(id), Some(ty)) = (inst.result_id, inst.result_type) else {
+                continue;
+            };
+
+            let operand = |index: usize| {
+                inst.operands
+                    .get(index)
+                    .and_then(|op| op.id_ref_any())
+                    .map(|id| (id, values.get(&id).copied()))
+            };
+
+            let value = match (inst.class.opcode, operand(0), operand(1)) {
+                (Op::FNegate, Some((_, Some(Value::Float(a)))), _) => Value::Float(-a),
+                (Op::LogicalNot, Some((_, Some(Value::Bool(a)))), _) => Value::Bool(!a),
+                (op, Some((_, Some(Value::Float(a)))), Some((_, Some(Value::Float(b))))) => {
+                    match op {
+                        Op::FAdd => Value::Float(a + b),
+                        Op::FSub => Value::Float(a - b),
+                        Op::FMul => Value::Float(a * b),
+                        Op::FDiv => Value::Float(a / b),
+                        Op::FOrdEqual => Value::Bool(a == b),
+                        Op::FOrdNotEqual => Value::Bool(a != b),
+                        Op::FOrdLessThan => Value::Bool(a < b),
+                        Op::FOrdLessThanEqual => Value::Bool(a <= b),
+                        Op::FOrdGreaterThan => Value::Bool(a > b),
+                        Op::FOrdGreaterThanEqual => Value::Bool(a >= b),
+                        _ => continue,
+                    }
+                }
+                (op, Some((_, Some(Value::Bool(a)))), Some((_, Some(Value::Bool(b))))) => {
+                    match op {
+                        Op::LogicalAnd => Value::Bool(a && b),
+                        Op::LogicalOr => Value::Bool(a || b),
+                        Op::LogicalEqual => Value::Bool(a == b),
+                        Op::LogicalNotEqual => Value::Bool(a != b),
+                        _ => continue,
+                    }
+                }
+                // Multiplying by one is exact, so the other operand can be used directly.
+                (Op::FMul, Some((a, _)), Some((_, Some(Value::Float(b)))))
+                | (Op::FMul, Some((_, Some(Value::Float(b)))), Some((a, _)))
+                | (Op::VectorTimesScalar, Some((a, _)), Some((_, Some(Value::Float(b)))))
+                    if b == 1.0 =>
+                {
+                    substitutions.insert(id, a);
+                    continue;
+                }
+                (Op::Select, Some((_, Some(Value::Bool(condition)))), _) => {
+                    let Some((chosen, _)) = operand(if condition { 1 } else { 2 }) else {
+                        continue;
+                    };
+                    substitutions.insert(id, chosen);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            folded.push((id, ty, value));
+        }
+
+        for (id, ty, value) in folded {
+            let constant = self.constant(ty, value);
+            substitutions.insert(id, constant);
+        }
+        substitutions
+    }
+
+    /// Replace the uses of folded instructions, and remove the folded instructions.
+    fn substitute(&mut self, substitutions: FxHashMap<Word, Word>) {
+        let module = self.builder.module_mut();
+        replace_uses(module, &substitutions);
+
+        for block in module
+            .functions
+            .iter_mut()
+            .flat_map(|f| f.blocks.iter_mut())
+        {
+            block.instructions.retain(|inst| {
+                inst.result_id
+                    .is_none_or(|id| !substitutions.contains_key(&id))
+            });
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use crate::front::spirv_passes::{
+    remove_dangling_references, remove_unreachable_functions, replace_uses,
+};
+use rspirv::dr::{Block, Builder, Function, Instruction, Operand};
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Op, Word};
+
+/// Inline function calls into their callers.
+///
+/// Only functions that do not call other functions and have a single return are inlined,
+/// so that the inlined body keeps the structured control flow of the caller. Since callees
+/// are inlined first, this inlines every call in most shaders.
+pub struct InlineFunctions<'a> {
+    pub builder: &'a mut Builder,
+}
+
+/// The position of an inlinable `OpFunctionCall` within the module.
+struct CallSite {
+    function: usize,
+    block: usize,
+    instruction: usize,
+}
+
+impl<'a> InlineFunctions<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        Self { builder }
+    }
+
+    pub fn do_pass(&mut self) {
+        while let Some(call_site) = self.find_inlinable_call() {
+            self.inline_call(call_site);
+        }
+
+        remove_unreachable_functions(self.builder.module_mut());
+        remove_dangling_references(self.builder.module_mut());
+    }
+
+    /// Whether or not the function can be inlined into its callers.
+    fn is_inlinable(function: &Function) -> bool {
+        let mut returns = 0;
+        for inst in function.blocks.iter().flat_map(|b| &b.instructions) {
+            match inst.class.opcode {
+                Op::FunctionCall => return false,
+                Op::Return | Op::ReturnValue => returns += 1,
+                _ => {}
+            }
+        }
+        returns == 1
+    }
+
+    fn find_inlinable_call(&self) -> Option<CallSite> {
+        let module = self.builder.module_ref();
+        let inlinable: FxHashSet<Word> = module
+            .functions
+            .iter()
+            .filter(|f| Self::is_inlinable(f))
+            .filter_map(|f| f.def_id())
+            .collect();
+
+        for (function_index, function) in module.functions.iter().enumerate() {
+            // Splitting a loop header or continue target would break the structure of the loop.
+            let continue_targets: FxHashSet<Word> = function
+                .blocks
+                .iter()
+                .flat_map(|b| &b.instructions)
+                .filter(|inst| inst.class.opcode == Op::LoopMerge)
+                .filter_map(|inst| inst.operands.get(1)?.id_ref_any())
+                .collect();
+
+            for (block_index, block) in function.blocks.iter().enumerate() {
+                if block
+                    .instructions
+                    .iter()
+                    .any(|inst| inst.class.opcode == Op::LoopMerge)
+                    || block
+                        .label_id()
+                        .is_some_and(|label| continue_targets.contains(&label))
+                {
+                    continue;
+                }
+
+                for (index, inst) in block.instructions.iter().enumerate() {
+                    if inst.class.opcode != Op::FunctionCall {
+                        continue;
+                    }
+
+                    let Some(callee) = inst.operands.first().and_then(|op| op.id_ref_any()) else {
+                        continue;
+                    };
+
+                    if inlinable.contains(&callee) && function.def_id() != Some(callee) {
+                        return Some(CallSite {
+                            function: function_index,
+                            block: block_index,
+                            instruction: index,
+                        });
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn inline_call(&mut self, call_site: CallSite) {
+        let module = self.builder.module_ref();
+        let call = module.functions[call_site.function].blocks[call_site.block].instructions
+            [call_site.instruction]
+            .clone();
+        let callee_id = call.operands[0].unwrap_id_ref();
+        let Some(callee) = module
+            .functions
+            .iter()
+            .find(|f| f.def_id() == Some(callee_id))
+            .cloned()
+        else {
+            return;
+        };
+
+        // Parameters are replaced with the arguments of the call, and every other id
+        // defined in the callee gets a fresh id.
+        let mut ids: FxHashMap<Word, Word> = FxHashMap::default();
+        for (param, arg) in callee.parameters.iter().zip(&call.operands[1..]) {
+            if let (Some(param), Some(arg)) = (param.result_id, arg.id_ref_any()) {
+                ids.insert(param, arg);
+            }
+        }
+
+        for inst in callee
+            .blocks
+            .iter()
+            .flat_map(|b| b.label.iter().chain(&b.instructions))
+        {
+            if let Some(id) = inst.result_id {
+                let fresh = self.builder.id();
+                ids.insert(id, fresh);
+            }
+        }
+
+        let continue_label = self.builder.id();
+
+        let remap = |mut inst: Instruction| {
+            inst.result_id = inst.result_id.map(|id| *ids.get(&id).unwrap_or(&id));
+            for op in inst.operands.iter_mut() {
+                let Some(word) = op.id_ref_any_mut() else {
+                    continue;
+                };
+
+                if let Some(&id) = ids.get(word) {
+                    *word = id;
+                }
+            }
+            inst
+        };
+
+        let mut variables = Vec::new();
+        let mut initializers = Vec::new();
+        let mut return_value = None;
+        let mut blocks = Vec::new();
+        for block in callee.blocks {
+            let mut inlined = Block::new();
+            inlined.label = block.label.map(remap);
+
+            for inst in block.instructions {
+                let mut inst = remap(inst);
+                match inst.class.opcode {
+                    // Variables must be declared in the entry block of the caller,
+                    // so initializers become stores where the callee begins.
+                    Op::Variable => {
+                        if inst.operands.len() > 1 {
+                            let initializer = inst.operands.remove(1);
+                            initializers.push(Instruction::new(
+                                Op::Store,
+                                None,
+                                None,
+                                vec![Operand::IdRef(inst.result_id.unwrap()), initializer],
+                            ));
+                        }
+                        variables.push(inst);
+                    }
+                    Op::Return | Op::ReturnValue => {
+                        return_value = inst.operands.first().and_then(|op| op.id_ref_any());
+                        inlined.instructions.push(Instruction::new(
+                            Op::Branch,
+                            None,
+                            None,
+                            vec![Operand::IdRef(continue_label)],
+                        ));
+                    }
+                    _ => inlined.instructions.push(inst),
+                }
+            }
+            blocks.push(inlined);
+        }
+
+        let Some(entry_label) = blocks.first().and_then(|b| b.label_id()) else {
+            return;
+        };
+        blocks[0].instructions.splice(0..0, initializers);
+
+        let function = &mut self.builder.module_mut().functions[call_site.function];
+        let block = &mut function.blocks[call_site.block];
+        let original_label = block.label_id();
+
+        // Split the caller block at the call, and branch into the inlined body.
+        let rest = block.instructions.split_off(call_site.instruction + 1);
+        block.instructions.pop();
+        block.instructions.push(Instruction::new(
+            Op::Branch,
+            None,
+            None,
+            vec![Operand::IdRef(entry_label)],
+        ));
+
+        let mut continuation = Block::new();
+        continuation.label = Some(Instruction::new(
+            Op::Label,
+            None,
+            Some(continue_label),
+            vec![],
+        ));
+        continuation.instructions = rest;
+
+        function.blocks.splice(
+            call_site.block + 1..call_site.block + 1,
+            blocks.into_iter().chain([continuation]),
+        );
+
+        // The successors of the caller block are now the successors of the continuation.
+        for block in function.blocks.iter_mut() {
+            for inst in block.instructions.iter_mut() {
+                if inst.class.opcode != Op::Phi {
+                    continue;
+                }
+
+                for parent in inst.operands.iter_mut().skip(1).step_by(2) {
+                    if parent.id_ref_any() == original_label {
+                        *parent = Operand::IdRef(continue_label);
+                    }
+                }
+            }
+        }
+
+        let entry = &mut function.blocks[0];
+        let position = entry
+            .instructions
+            .iter()
+            .position(|inst| inst.class.opcode != Op::Variable)
+            .unwrap_or(entry.instructions.len());
+        entry.instructions.splice(position..position, variables);
+
+        if let (Some(result), Some(value)) = (call.result_id, return_value) {
+            let substitutions = FxHashMap::from_iter([(result, value)]);
+            replace_uses(self.builder.module_mut(), &substitutions);
+        }
+    }
+}
@@ -1,6 +1,13 @@
+pub mod eliminate_dead_code;
+pub mod fold_parameters;
+pub mod inline_functions;
 pub mod link_input_outputs;
 pub mod lower_samplers;
 
+use rspirv::dr::Module;
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Op, Word};
+
 // Load SPIR-V as an rspirv module
 pub(crate) fn load_module(words: &[u32]) -> rspirv::dr::Module {
     let mut loader = rspirv::dr::Loader::new();
@@ -8,3 +15,95 @@ pub(crate) fn load_module(words: &[u32]) -> rspirv::dr::Module {
     let module = loader.module();
     module
 }
+
+/// Replace every use of an id within the functions of the module with its substitution.
+pub(crate) fn replace_uses(module: &mut Module, substitutions: &FxHashMap<Word, Word>) {
+    if substitutions.is_empty() {
+        return;
+    }
+
+    for function in module.functions.iter_mut() {
+        for inst in function.all_inst_iter_mut() {
+            for op in inst.operands.iter_mut() {
+                let Some(word) = op.id_ref_any_mut() else {
+                    continue;
+                };
+
+                // Substitutions may be chained if a folded value is folded again.
+                while let Some(&substitution) = substitutions.get(word) {
+                    *word = substitution;
+                }
+            }
+        }
+    }
+}
+
+/// Remove functions that can not be reached from an entry point.
+///
+/// Returns whether or not any function was removed.
+pub(crate) fn remove_unreachable_functions(module: &mut Module) -> bool {
+    let mut reachable: FxHashSet<Word> = module
+        .entry_points
+        .iter()
+        .filter_map(|entry_point| entry_point.operands.get(1)?.id_ref_any())
+        .collect();
+
+    let mut queue: Vec<Word> = reachable.iter().copied().collect();
+    while let Some(id) = queue.pop() {
+        let Some(function) = module.functions.iter().find(|f| f.def_id() == Some(id)) else {
+            continue;
+        };
+
+        for inst in function.all_inst_iter() {
+            if inst.class.opcode != Op::FunctionCall {
+                continue;
+            }
+
+            let Some(callee) = inst.operands.first().and_then(|op| op.id_ref_any()) else {
+                continue;
+            };
+
+            if reachable.insert(callee) {
+                queue.push(callee);
+            }
+        }
+    }
+
+    let count = module.functions.len();
+    module
+        .functions
+        .retain(|f| f.def_id().is_some_and(|id| reachable.contains(&id)));
+    count != module.functions.len()
+}
+
+/// Remove debug names, decorations, and entry point interface references to ids
+/// that are no longer defined in the module.
+pub(crate) fn remove_dangling_references(module: &mut Module) {
+    let defined: FxHashSet<Word> = module
+        .all_inst_iter()
+        .filter_map(|inst| inst.result_id)
+        .collect();
+
+    let is_defined = |inst: &rspirv::dr::Instruction| {
+        inst.operands
+            .first()
+            .and_then(|op| op.id_ref_any())
+            .is_none_or(|target| defined.contains(&target))
+    };
+
+    module.debug_names.retain(is_defined);
+    module.annotations.retain(is_defined);
+
+    for entry_point in module.entry_points.iter_mut() {
+        // The execution model, entry point, and name precede the interface.
+        let interface = entry_point
+            .operands
+            .split_off(3.min(entry_point.operands.len()));
+        entry_point
+            .operands
+            .extend(interface.into_iter().filter(|op| {
+                op.id_ref_any()
+                    .is_none_or(|target| defined.contains(&target))
+            }));
+    }
+}
@@ -1,5 +1,6 @@
+use crate::back::essl::EsslVersion;
 use crate::back::glsl::CrossGlslContext;
-use crate::back::targets::GLSL;
+use crate::back::targets::{ESSL, GLSL};
 use crate::back::{CompileShader, ShaderCompilerOutput};
 use crate::error::ShaderCompileError;
 use crate::reflect::cross::{CompiledAst, CompiledProgram, CrossReflect};
@@ -13,9 +14,35 @@ impl CompileShader<GLSL> for CrossReflect<spirv_cross::glsl::Target> {
     type Context = CrossGlslContext;
 
     fn compile(
-        mut self,
+        self,
         version: Self::Options,
     ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        self.compile_glsl(version, false)
+    }
+}
+
+impl CompileShader<ESSL> for CrossReflect<spirv_cross::glsl::Target> {
+    type Options = EsslVersion;
+    type Context = CrossGlslContext;
+
+    fn compile(
+        self,
+        version: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        self.compile_glsl(version.into(), version.has_explicit_bindings())
+    }
+}
+
+impl GlslReflect {
+    /// Compile to GLSL or GLSL ES.
+    ///
+    /// If `explicit_bindings` is false, binding decorations are removed from uniform blocks
+    /// and samplers, and must be assigned by the runtime.
+    fn compile_glsl(
+        mut self,
+        version: spirv_cross::glsl::Version,
+        explicit_bindings: bool,
+    ) -> Result<ShaderCompilerOutput<String, CrossGlslContext>, ShaderCompileError> {
         let mut options: spirv_cross::glsl::CompilerOptions = Default::default();
         options.version = version;
         options.fragment.default_float_precision = spirv_cross::glsl::Precision::High;
@@ -73,7 +100,9 @@ impl CompileShader<GLSL> for CrossReflect<spirv_cross::glsl::Target> {
             self.vertex.set_name(res.base_type_id, "LIBRA_UBO_VERTEX")?;
             self.vertex
                 .unset_decoration(res.id, Decoration::DescriptorSet)?;
-            self.vertex.unset_decoration(res.id, Decoration::Binding)?;
+            if !explicit_bindings {
+                self.vertex.unset_decoration(res.id, Decoration::Binding)?;
+            }
         }
 
         if fragment_resources.push_constant_buffers.len() > 1 {
@@ -108,8 +137,10 @@ impl CompileShader<GLSL> for CrossReflect<spirv_cross::glsl::Target> {
                 .set_name(res.base_type_id, "LIBRA_UBO_FRAGMENT")?;
             self.fragment
                 .unset_decoration(res.id, Decoration::DescriptorSet)?;
-            self.fragment
-                .unset_decoration(res.id, Decoration::Binding)?;
+            if !explicit_bindings {
+                self.fragment
+                    .unset_decoration(res.id, Decoration::Binding)?;
+            }
         }
 
         let mut texture_fixups = Vec::new();
@@ -117,8 +148,10 @@ impl CompileShader<GLSL> for CrossReflect<spirv_cross::glsl::Target> {
             let binding = self.fragment.get_decoration(res.id, Decoration::Binding)?;
             self.fragment
                 .unset_decoration(res.id, Decoration::DescriptorSet)?;
-            self.fragment
-                .unset_decoration(res.id, Decoration::Binding)?;
+            if !explicit_bindings {
+                self.fragment
+                    .unset_decoration(res.id, Decoration::Binding)?;
+            }
             let mut name = res.name;
             name.push('\0');
             texture_fixups.push((name, binding));
@@ -1,6 +1,7 @@
 pub mod glsl;
 pub mod hlsl;
 pub mod msl;
+mod types;
 
 use crate::error::{SemanticsErrorKind, ShaderReflectError};
 use crate::front::SpirvCompilation;
@@ -17,6 +18,7 @@ use std::ops::Deref;
 use spirv_cross::spirv::{Ast, Decoration, Module, Resource, ShaderResources, Type};
 use spirv_cross::ErrorCode;
 
+use crate::reflect::cross::types::SpirvTypes;
 use crate::reflect::helper::{SemanticErrorBlame, TextureData, UboData};
 
 /// Reflect shaders under SPIRV-Cross semantics.
@@ -39,6 +41,8 @@ where
 {
     vertex: Ast<T>,
     fragment: Ast<T>,
+    vertex_types: SpirvTypes,
+    fragment_types: SpirvTypes,
 }
 
 ///The output of the SPIR-V AST after compilation.
@@ -97,13 +101,23 @@ impl ValidateTypeSemantics<Type> for UniqueSemantics {
             UniqueSemantics::FrameCount
             | UniqueSemantics::Rotation
             | UniqueSemantics::TotalSubFrames
-            | UniqueSemantics::CurrentSubFrame => {
+            | UniqueSemantics::CurrentSubFrame
+            | UniqueSemantics::FrameTimeDelta
+            | UniqueSemantics::EnableHDR
+            | UniqueSemantics::ExpandGamut
+            | UniqueSemantics::InverseTonemap
+            | UniqueSemantics::HDR10 => {
                 matches!(ty, Type::UInt { .. }) && vecsize == 1 && columns == 1
             }
             UniqueSemantics::FrameDirection => {
                 matches!(ty, Type::Int { .. }) && vecsize == 1 && columns == 1
             }
-            UniqueSemantics::FloatParameter => {
+            UniqueSemantics::FloatParameter
+            | UniqueSemantics::OriginalAspect
+            | UniqueSemantics::OriginalAspectRotated
+            | UniqueSemantics::OriginalFPS
+            | UniqueSemantics::PaperWhiteNits
+            | UniqueSemantics::MaxNits => {
                 matches!(ty, Type::Float { .. }) && vecsize == 1 && columns == 1
             }
             _ => matches!(ty, Type::Float { .. }) && vecsize == 4 && columns == 1,
@@ -162,7 +176,12 @@ where
         let vertex = Ast::parse(&vertex_module)?;
         let fragment = Ast::parse(&fragment_module)?;
 
-        Ok(CrossReflect { vertex, fragment })
+        Ok(CrossReflect {
+            vertex,
+            fragment,
+            vertex_types: SpirvTypes::new(&value.vertex),
+            fragment_types: SpirvTypes::new(&value.fragment),
+        })
     }
 }
 
@@ -305,6 +324,7 @@ where
 
     fn reflect_buffer_range_metas(
         ast: &Ast<T>,
+        types: &SpirvTypes,
         resource: &Resource,
         pass_number: usize,
         semantics: &ShaderSemantics,
@@ -315,14 +335,14 @@ where
         let ranges = ast.get_active_buffer_ranges(resource.id)?;
         for range in ranges {
             let name = ast.get_member_name(resource.base_type_id, range.index)?;
-            let ubo_type = ast.get_type(resource.base_type_id)?;
+            let ubo_type = types.get_type(resource.base_type_id);
             let range_type = match ubo_type {
                 Type::Struct { member_types, .. } => {
                     let range_type = member_types
                         .get(range.index as usize)
                         .cloned()
                         .ok_or(blame.error(SemanticsErrorKind::InvalidRange(range.index)))?;
-                    ast.get_type(range_type)?
+                    types.get_type(range_type)
                 }
                 _ => return Err(blame.error(SemanticsErrorKind::InvalidResourceType)),
             };
@@ -667,6 +687,7 @@ where
         if let Some(ubo) = vertex_ubo {
             Self::reflect_buffer_range_metas(
                 &self.vertex,
+                &self.vertex_types,
                 ubo,
                 pass_number,
                 semantics,
@@ -679,6 +700,7 @@ where
         if let Some(ubo) = fragment_ubo {
             Self::reflect_buffer_range_metas(
                 &self.fragment,
+                &self.fragment_types,
                 ubo,
                 pass_number,
                 semantics,
@@ -691,6 +713,7 @@ where
         if let Some(push) = vertex_push {
             Self::reflect_buffer_range_metas(
                 &self.vertex,
+                &self.vertex_types,
                 push,
                 pass_number,
                 semantics,
@@ -703,6 +726,7 @@ where
         if let Some(push) = fragment_push {
             Self::reflect_buffer_range_metas(
                 &self.fragment,
+                &self.fragment_types,
                 push,
                 pass_number,
                 semantics,
@@ -0,0 +1,168 @@
+//! Types declared in a SPIR-V module, for reflection under SPIRV-Cross semantics.
+//!
+//! `Ast::get_type` reads the array dimensions and member types of a type through pointers
+//! that spirv-cross leaves uninitialized when the type has none, which is undefined behaviour
+//! for every scalar, vector and matrix type. The types of buffer members are read from the
+//! SPIR-V module instead.
+use librashader_common::map::FastHashMap;
+use spirv_cross::spirv::Type;
+
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_CONSTANT: u32 = 43;
+
+/// The length of the SPIR-V module header, in words.
+const HEADER_LENGTH: usize = 5;
+
+enum TypeDeclaration {
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { element: u32, length: Option<u32> },
+    Struct { members: Vec<u32> },
+}
+
+/// The types declared in a SPIR-V module.
+pub(crate) struct SpirvTypes {
+    types: FastHashMap<u32, TypeDeclaration>,
+}
+
+impl SpirvTypes {
+    /// Collect the type declarations of the SPIR-V module.
+    pub fn new(words: &[u32]) -> SpirvTypes {
+        let mut types = FastHashMap::default();
+        let mut constants = FastHashMap::default();
+
+        let mut instructions = words.get(HEADER_LENGTH..).unwrap_or_default();
+        while let Some(&first) = instructions.first() {
+            let count = (first >> 16) as usize;
+            let Some(operands) = instructions.get(1..count) else {
+                break;
+            };
+            instructions = &instructions[count..];
+
+            let declaration = match (first & 0xffff, operands) {
+                (OP_TYPE_INT, &[id, width, signed, ..]) => (
+                    id,
+                    TypeDeclaration::Int {
+                        width,
+                        signed: signed != 0,
+                    },
+                ),
+                (OP_TYPE_FLOAT, &[id, width, ..]) => (id, TypeDeclaration::Float { width }),
+                (OP_TYPE_VECTOR, &[id, component, count, ..]) => {
+                    (id, TypeDeclaration::Vector { component, count })
+                }
+                (OP_TYPE_MATRIX, &[id, column, count, ..]) => {
+                    (id, TypeDeclaration::Matrix { column, count })
+                }
+                (OP_TYPE_ARRAY, &[id, element, length, ..]) => (
+                    id,
+                    TypeDeclaration::Array {
+                        element,
+                        length: constants.get(&length).copied(),
+                    },
+                ),
+                (OP_TYPE_RUNTIME_ARRAY, &[id, element, ..]) => (
+                    id,
+                    TypeDeclaration::Array {
+                        element,
+                        length: None,
+                    },
+                ),
+                (OP_TYPE_STRUCT, &[id, ref members @ ..]) => (
+                    id,
+                    TypeDeclaration::Struct {
+                        members: members.to_vec(),
+                    },
+                ),
+                (OP_CONSTANT, &[_, id, value, ..]) => {
+                    constants.insert(id, value);
+                    continue;
+                }
+                _ => continue,
+            };
+            types.insert(declaration.0, declaration.1);
+        }
+
+        SpirvTypes { types }
+    }
+
+    /// Get the type with the given ID, in the same form as `Ast::get_type`.
+    ///
+    /// Only integer, floating point and struct types, and arrays of them, are described.
+    /// Every other type is [`Type::Unknown`].
+    pub fn get_type(&self, id: u32) -> Type {
+        let mut id = id;
+        let mut array = Vec::new();
+        let mut array_size_literal = Vec::new();
+        while let Some(&TypeDeclaration::Array { element, length }) = self.types.get(&id) {
+            array.push(length.unwrap_or_default());
+            array_size_literal.push(length.is_some());
+            id = element;
+        }
+
+        let mut columns = 1;
+        if let Some(&TypeDeclaration::Matrix { column, count }) = self.types.get(&id) {
+            columns = count;
+            id = column;
+        }
+
+        let mut vecsize = 1;
+        if let Some(&TypeDeclaration::Vector { component, count }) = self.types.get(&id) {
+            vecsize = count;
+            id = component;
+        }
+
+        match self.types.get(&id) {
+            Some(TypeDeclaration::Float { width: 16 }) => Type::Half {
+                vecsize,
+                columns,
+                array,
+                array_size_literal,
+            },
+            Some(TypeDeclaration::Float { width: 32 }) => Type::Float {
+                vecsize,
+                columns,
+                array,
+                array_size_literal,
+            },
+            Some(TypeDeclaration::Float { width: 64 }) => Type::Double {
+                vecsize,
+                columns,
+                array,
+                array_size_literal,
+            },
+            Some(TypeDeclaration::Int {
+                width: 32,
+                signed: true,
+            }) => Type::Int {
+                vecsize,
+                columns,
+                array,
+                array_size_literal,
+            },
+            Some(TypeDeclaration::Int {
+                width: 32,
+                signed: false,
+            }) => Type::UInt {
+                vecsize,
+                columns,
+                array,
+                array_size_literal,
+            },
+            Some(TypeDeclaration::Struct { members }) => Type::Struct {
+                member_types: members.clone(),
+                array,
+                array_size_literal,
+            },
+            _ => Type::Unknown,
+        }
+    }
+}
@@ -15,6 +15,10 @@ mod helper;
 #[cfg(feature = "naga")]
 pub mod naga;
 
+/// Shader compatibility reports across output targets and reflectors.
+#[cfg(all(feature = "serialize", feature = "naga"))]
+pub mod report;
+
 /// A trait for compilation outputs that can provide reflection information.
 pub trait ReflectShader {
     /// Reflect the shader as the given pass within the shader preset, against the provided
@@ -0,0 +1,117 @@
+use crate::back::glsl::{GlslVersion, NagaGlslContext};
+use crate::back::targets::GLSL;
+use crate::back::{CompileShader, ShaderCompilerOutput};
+use crate::error::ShaderCompileError;
+use crate::reflect::naga::{NagaLoweringOptions, NagaReflect};
+use naga::back::glsl::{BindingMap, Options, PipelineOptions, ReflectionInfo, Version};
+use naga::proc::BoundsCheckPolicies;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{Module, ShaderStage};
+
+fn glsl_version_to_naga_glsl(version: GlslVersion) -> Result<Version, ShaderCompileError> {
+    let version = match version {
+        GlslVersion::V1_10 => Version::Desktop(110),
+        GlslVersion::V1_20 => Version::Desktop(120),
+        GlslVersion::V1_30 => Version::Desktop(130),
+        GlslVersion::V1_40 => Version::Desktop(140),
+        GlslVersion::V1_50 => Version::Desktop(150),
+        GlslVersion::V3_30 => Version::Desktop(330),
+        GlslVersion::V4_00 => Version::Desktop(400),
+        GlslVersion::V4_10 => Version::Desktop(410),
+        GlslVersion::V4_20 => Version::Desktop(420),
+        GlslVersion::V4_30 => Version::Desktop(430),
+        GlslVersion::V4_40 => Version::Desktop(440),
+        GlslVersion::V4_50 => Version::Desktop(450),
+        GlslVersion::V4_60 => Version::Desktop(460),
+        GlslVersion::V1_00Es => Version::new_gles(100),
+        GlslVersion::V3_00Es => Version::new_gles(300),
+        GlslVersion::V3_10Es => Version::new_gles(310),
+        GlslVersion::V3_20Es => Version::new_gles(320),
+        _ => return Err(naga::back::glsl::Error::VersionNotSupported.into()),
+    };
+    Ok(version)
+}
+
+/// Bind every resource to its binding number, if explicit bindings are supported by the version.
+fn generate_bindings(module: &Module, binding_map: &mut BindingMap) {
+    for (_, variable) in module.global_variables.iter() {
+        let Some(binding) = &variable.binding else {
+            continue;
+        };
+
+        binding_map.insert(binding.clone(), binding.binding as u8);
+    }
+}
+
+impl CompileShader<GLSL> for NagaReflect {
+    type Options = GlslVersion;
+    type Context = NagaGlslContext;
+
+    fn compile(
+        mut self,
+        version: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        fn write_glsl(
+            module: &Module,
+            options: &Options,
+            shader_stage: ShaderStage,
+        ) -> Result<(String, ReflectionInfo), ShaderCompileError> {
+            let mut valid = Validator::new(ValidationFlags::all(), Capabilities::empty());
+            let info = valid.validate(module)?;
+
+            let pipeline_options = PipelineOptions {
+                shader_stage,
+                entry_point: String::from("main"),
+                multiview: None,
+            };
+
+            let mut glsl = String::new();
+            let mut writer = naga::back::glsl::Writer::new(
+                &mut glsl,
+                module,
+                &info,
+                options,
+                &pipeline_options,
+                BoundsCheckPolicies::default(),
+            )?;
+            let reflection = writer.write()?;
+            Ok((glsl, reflection))
+        }
+
+        self.do_lowering(&NagaLoweringOptions {
+            write_pcb_as_ubo: true,
+            sampler_bind_group: 1,
+        });
+
+        let mut options = Options {
+            version: glsl_version_to_naga_glsl(version)?,
+            ..Default::default()
+        };
+        generate_bindings(&self.vertex, &mut options.binding_map);
+        generate_bindings(&self.fragment, &mut options.binding_map);
+
+        let (vertex, _) = write_glsl(&self.vertex, &options, ShaderStage::Vertex)?;
+        let (fragment, reflection) = write_glsl(&self.fragment, &options, ShaderStage::Fragment)?;
+
+        let mut sampler_bindings = Vec::new();
+        for (name, mapping) in reflection.texture_mapping {
+            let Some(binding) = &self.fragment.global_variables[mapping.texture].binding else {
+                continue;
+            };
+            let mut name = name;
+            name.push('\0');
+            sampler_bindings.push((name, binding.binding));
+        }
+        sampler_bindings.sort_by_key(|(_, binding)| *binding);
+
+        Ok(ShaderCompilerOutput {
+            vertex,
+            fragment,
+            context: NagaGlslContext {
+                sampler_bindings,
+                vertex: self.vertex,
+                fragment: self.fragment,
+            },
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use crate::back::hlsl::{HlslShaderModel, NagaHlslContext};
+use crate::back::targets::HLSL;
+use crate::back::{CompileShader, ShaderCompilerOutput};
+use crate::error::ShaderCompileError;
+use crate::reflect::naga::{NagaLoweringOptions, NagaReflect};
+use naga::back::hlsl::{BindTarget, BindingMap, Options, ShaderModel};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::Module;
+
+fn hlsl_shader_model_to_naga_hlsl(
+    shader_model: HlslShaderModel,
+) -> Result<ShaderModel, ShaderCompileError> {
+    match shader_model {
+        HlslShaderModel::V5_0 => Ok(ShaderModel::V5_0),
+        HlslShaderModel::V5_1 => Ok(ShaderModel::V5_1),
+        HlslShaderModel::V6_0 => Ok(ShaderModel::V6_0),
+        _ => Err(ShaderCompileError::NagaHlslError(
+            naga::back::hlsl::Error::Custom(format!(
+                "shader model {shader_model:?} is not supported"
+            )),
+        )),
+    }
+}
+
+/// Bind every resource to the register of its binding number.
+///
+/// Textures, samplers and constant buffers each have their own register class,
+/// so textures and samplers of the same binding share the same register number.
+fn generate_bindings(module: &Module, binding_map: &mut BindingMap) {
+    for (_, variable) in module.global_variables.iter() {
+        let Some(binding) = &variable.binding else {
+            continue;
+        };
+
+        binding_map.insert(
+            binding.clone(),
+            BindTarget {
+                space: 0,
+                register: binding.binding,
+                binding_array_size: None,
+            },
+        );
+    }
+}
+
+impl CompileShader<HLSL> for NagaReflect {
+    type Options = Option<HlslShaderModel>;
+    type Context = NagaHlslContext;
+
+    fn compile(
+        mut self,
+        options: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        fn write_hlsl(module: &Module, options: &Options) -> Result<String, ShaderCompileError> {
+            let mut valid = Validator::new(ValidationFlags::all(), Capabilities::empty());
+            let info = valid.validate(module)?;
+
+            let mut hlsl = String::new();
+            let mut writer = naga::back::hlsl::Writer::new(&mut hlsl, options);
+            let reflection = writer.write(module, &info)?;
+
+            // Entry points that could not be written are skipped rather than failing the write.
+            for entry_point in reflection.entry_point_names {
+                if let Err(err) = entry_point {
+                    return Err(ShaderCompileError::NagaHlslError(
+                        naga::back::hlsl::Error::Custom(err.to_string()),
+                    ));
+                }
+            }
+            Ok(hlsl)
+        }
+
+        self.do_lowering(&NagaLoweringOptions {
+            write_pcb_as_ubo: true,
+            sampler_bind_group: 1,
+        });
+
+        let mut options = Options {
+            shader_model: hlsl_shader_model_to_naga_hlsl(options.unwrap_or(HlslShaderModel::V5_0))?,
+            fake_missing_bindings: false,
+            ..Default::default()
+        };
+        generate_bindings(&self.vertex, &mut options.binding_map);
+        generate_bindings(&self.fragment, &mut options.binding_map);
+
+        let vertex = write_hlsl(&self.vertex, &options)?;
+        let fragment = write_hlsl(&self.fragment, &options)?;
+
+        Ok(ShaderCompilerOutput {
+            vertex,
+            fragment,
+            context: NagaHlslContext {
+                vertex: self.vertex,
+                fragment: self.fragment,
+            },
+        })
+    }
+}
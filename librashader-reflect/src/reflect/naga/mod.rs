@@ -1,3 +1,5 @@
+pub mod glsl;
+pub mod hlsl;
 pub mod msl;
 pub mod spirv;
 pub mod wgsl;
@@ -164,7 +166,12 @@ impl ValidateTypeSemantics<&TypeInner> for UniqueSemantics {
             UniqueSemantics::FrameCount
             | UniqueSemantics::Rotation
             | UniqueSemantics::CurrentSubFrame
-            | UniqueSemantics::TotalSubFrames => {
+            | UniqueSemantics::TotalSubFrames
+            | UniqueSemantics::FrameTimeDelta
+            | UniqueSemantics::EnableHDR
+            | UniqueSemantics::ExpandGamut
+            | UniqueSemantics::InverseTonemap
+            | UniqueSemantics::HDR10 => {
                 // Uint32 == width 4
                 if matches!(ty, TypeInner::Scalar( Scalar { kind, width }) if *kind == ScalarKind::Uint && *width == 4)
                 {
@@ -184,7 +191,12 @@ impl ValidateTypeSemantics<&TypeInner> for UniqueSemantics {
                     });
                 }
             }
-            UniqueSemantics::FloatParameter => {
+            UniqueSemantics::FloatParameter
+            | UniqueSemantics::OriginalAspect
+            | UniqueSemantics::OriginalAspectRotated
+            | UniqueSemantics::OriginalFPS
+            | UniqueSemantics::PaperWhiteNits
+            | UniqueSemantics::MaxNits => {
                 // Float32 == width 4
                 if matches!(ty, TypeInner::Scalar( Scalar { kind, width }) if *kind == ScalarKind::Float && *width == 4)
                 {
@@ -1005,10 +1017,210 @@ impl ReflectShader for NagaReflect {
 
 #[cfg(test)]
 mod test {
-    use crate::reflect::semantics::{Semantic, TextureSemantics, UniformSemantic};
+    use crate::back::glsl::GlslVersion;
+    use crate::back::hlsl::HlslShaderModel;
+    use crate::back::targets::{GLSL, HLSL};
+    use crate::back::{CompileShader, FromCompilation};
+    use crate::front::SpirvCompilation;
+    use crate::reflect::semantics::{Semantic, ShaderSemantics, UniformSemantic, UniqueSemantics};
+    use crate::reflect::{ReflectShader, ShaderReflection};
     use librashader_common::map::FastHashMap;
     use librashader_preprocess::ShaderSource;
-    use librashader_presets::ShaderPreset;
+
+    const TEST_SHADERS: [&str; 2] = ["../test/basic.slang", "../test/null.slang"];
+
+    fn semantics(source: &ShaderSource) -> ShaderSemantics {
+        let mut uniform_semantics: FastHashMap<String, UniformSemantic> = Default::default();
+        for param in source.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+
+        ShaderSemantics {
+            uniform_semantics,
+            texture_semantics: Default::default(),
+        }
+    }
+
+    /// Summarize the reflection in a form that is comparable across reflectors.
+    fn summarize(reflection: &ShaderReflection) -> Vec<String> {
+        let mut summary = vec![
+            format!(
+                "ubo {:?}",
+                reflection
+                    .ubo
+                    .as_ref()
+                    .map(|ubo| (ubo.binding, ubo.size, ubo.stage_mask))
+            ),
+            format!(
+                "push {:?}",
+                reflection
+                    .push_constant
+                    .as_ref()
+                    .map(|push| (push.size, push.stage_mask))
+            ),
+        ];
+
+        let meta = &reflection.meta;
+        for (name, param) in &meta.parameter_meta {
+            summary.push(format!("param {name} {:?} {}", param.offset, param.size));
+        }
+        for (semantics, unique) in &meta.unique_meta {
+            summary.push(format!(
+                "unique {semantics:?} {:?} {}",
+                unique.offset, unique.size
+            ));
+        }
+        for (semantics, texture) in &meta.texture_meta {
+            summary.push(format!("texture {semantics:?} {}", texture.binding));
+        }
+        for (semantics, size) in &meta.texture_size_meta {
+            summary.push(format!(
+                "size {semantics:?} {:?} {:?}",
+                size.offset, size.stage_mask
+            ));
+        }
+
+        summary.sort();
+        summary
+    }
+
+    fn texture_bindings(reflection: &ShaderReflection) -> Vec<u32> {
+        let mut bindings: Vec<u32> = reflection
+            .meta
+            .texture_meta
+            .values()
+            .map(|texture| texture.binding)
+            .collect();
+        bindings.sort();
+        bindings
+    }
+
+    #[test]
+    pub fn glsl_parity_with_cross() {
+        for path in TEST_SHADERS {
+            let source = ShaderSource::load(path).unwrap();
+            let semantics = semantics(&source);
+
+            let mut cross =
+                GLSL::from_compilation(SpirvCompilation::try_from(&source).unwrap()).unwrap();
+            let cross_reflection = cross.reflect(0, &semantics).unwrap();
+            let cross = cross.compile(GlslVersion::V3_30).unwrap();
+
+            let mut naga =
+                GLSL::from_naga_compilation(SpirvCompilation::try_from(&source).unwrap()).unwrap();
+            let naga_reflection = naga.reflect(0, &semantics).unwrap();
+            let naga = naga.compile(GlslVersion::V3_30).unwrap();
+
+            assert_eq!(summarize(&cross_reflection), summarize(&naga_reflection));
+
+            let cross_samplers: Vec<u32> = cross
+                .context
+                .sampler_bindings
+                .iter()
+                .map(|(_, binding)| *binding)
+                .collect();
+            let naga_samplers: Vec<u32> = naga
+                .context
+                .sampler_bindings
+                .iter()
+                .map(|(_, binding)| *binding)
+                .collect();
+            assert_eq!(cross_samplers.len(), naga_samplers.len());
+            assert_eq!(naga_samplers, texture_bindings(&naga_reflection));
+
+            for (name, _) in &naga.context.sampler_bindings {
+                assert!(naga.fragment.contains(name.trim_end_matches('\0')));
+            }
+
+            let compiler = glslang::Compiler::acquire().unwrap();
+            let options = glslang::CompilerOptions {
+                source_language: glslang::SourceLanguage::GLSL,
+                target: glslang::Target::None(None),
+                version_profile: None,
+            };
+            for (glsl, stage) in [
+                (&naga.vertex, glslang::ShaderStage::Vertex),
+                (&naga.fragment, glslang::ShaderStage::Fragment),
+            ] {
+                let glsl = glslang::ShaderSource::from(glsl.as_str());
+                let input = glslang::ShaderInput::new(&glsl, stage, &options, None).unwrap();
+                if let Err(err) = compiler.create_shader(input) {
+                    panic!("{err}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn hlsl_parity_with_cross() {
+        for path in TEST_SHADERS {
+            let source = ShaderSource::load(path).unwrap();
+            let semantics = semantics(&source);
+
+            let mut cross =
+                HLSL::from_compilation(SpirvCompilation::try_from(&source).unwrap()).unwrap();
+            let cross_reflection = cross.reflect(0, &semantics).unwrap();
+            let cross = cross.compile(Some(HlslShaderModel::V5_0)).unwrap();
+
+            let mut naga =
+                HLSL::from_naga_compilation(SpirvCompilation::try_from(&source).unwrap()).unwrap();
+            let naga_reflection = naga.reflect(0, &semantics).unwrap();
+            let naga = naga.compile(Some(HlslShaderModel::V5_0)).unwrap();
+
+            assert_eq!(summarize(&cross_reflection), summarize(&naga_reflection));
+
+            // Textures and samplers must be bound to the same registers.
+            for binding in texture_bindings(&naga_reflection) {
+                for register in [
+                    format!("register(t{binding})"),
+                    format!("register(s{binding})"),
+                ] {
+                    assert!(cross.fragment.contains(&register));
+                    assert!(naga.fragment.contains(&register));
+                }
+            }
+
+            if let Some(ubo) = &naga_reflection.ubo {
+                let register = format!("register(b{})", ubo.binding);
+                assert!(cross.vertex.contains(&register));
+                assert!(naga.vertex.contains(&register));
+            }
+        }
+    }
+
+    #[test]
+    pub fn reflect_unique_semantics() {
+        let source = ShaderSource::load("../test/semantics.slang").unwrap();
+        let mut naga =
+            GLSL::from_naga_compilation(SpirvCompilation::try_from(&source).unwrap()).unwrap();
+        let reflection = naga.reflect(0, &semantics(&source)).unwrap();
+
+        for semantic in [
+            UniqueSemantics::OriginalAspect,
+            UniqueSemantics::OriginalAspectRotated,
+            UniqueSemantics::OriginalFPS,
+            UniqueSemantics::FrameTimeDelta,
+            UniqueSemantics::EnableHDR,
+            UniqueSemantics::PaperWhiteNits,
+            UniqueSemantics::MaxNits,
+            UniqueSemantics::ExpandGamut,
+            UniqueSemantics::InverseTonemap,
+            UniqueSemantics::HDR10,
+        ] {
+            let meta = reflection
+                .meta
+                .unique_meta
+                .get(&semantic)
+                .unwrap_or_else(|| panic!("{semantic:?} should be reflected"));
+            assert_eq!(meta.size, 1);
+        }
+    }
 
     // #[test]
     // pub fn test_into() {
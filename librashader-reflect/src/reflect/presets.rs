@@ -1,13 +1,21 @@
 use crate::back::targets::OutputTarget;
 use crate::back::{CompilerBackend, FromCompilation};
 use crate::error::{ShaderCompileError, ShaderReflectError};
-use crate::front::{ShaderInputCompiler, ShaderReflectObject};
+use crate::front::{ShaderInputCompiler, ShaderReflectObject, SpirvOptimization};
 use crate::reflect::semantics::{
     Semantic, ShaderSemantics, TextureSemantics, UniformSemantic, UniqueSemantics,
 };
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
-use librashader_preprocess::{PreprocessError, ShaderSource};
+use librashader_preprocess::{Defines, PreprocessError, ShaderSource};
 use librashader_presets::{ShaderPassConfig, TextureConfig};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Thread pools created to compile passes with a limited number of threads, by thread count.
+static THREAD_POOLS: Mutex<Vec<(NonZeroUsize, Arc<ThreadPool>)>> = Mutex::new(Vec::new());
 
 /// Artifacts of a reflected and compiled shader pass.
 ///
@@ -29,6 +37,54 @@ use librashader_presets::{ShaderPassConfig, TextureConfig};
 /// This allows a runtime to not name the backing type of the compiled artifact if not necessary.
 pub type ShaderPassArtifact<T> = (ShaderPassConfig, ShaderSource, CompilerBackend<T>);
 
+/// A function that compiles a preprocessed shader source with the defines it was loaded with
+/// and the optimizations to apply.
+pub type PresetCompiler<'a, I> = dyn Fn(&ShaderSource, &Defines, Option<&SpirvOptimization>) -> Result<I, ShaderCompileError>
+    + Sync
+    + 'a;
+
+/// Options for compiling the passes of a shader preset.
+pub struct PresetCompileOptions<'a, I> {
+    /// The maximum number of threads to preprocess and compile passes with.
+    ///
+    /// If `None`, the global rayon thread pool is used. Otherwise, a thread pool is created
+    /// the first time a thread count is used, and kept for later compilations.
+    pub max_threads: Option<NonZeroUsize>,
+    /// The file provider to read shader sources and the files they include through.
+    pub provider: &'a dyn FileProvider,
+    /// Macros to define when preprocessing shader sources.
+    pub defines: &'a Defines,
+    /// Optimizations to apply to the compiled SPIR-V, if any.
+    pub optimization: Option<&'a SpirvOptimization>,
+    /// The function to compile each preprocessed shader source with.
+    ///
+    /// This allows compiling with a compiler that has state, such as a shader cache.
+    /// If `None`, sources are compiled with the compiler of the compilation type.
+    pub compiler: Option<&'a PresetCompiler<'a, I>>,
+}
+
+static NO_DEFINES: Defines = Defines::new();
+
+impl<I> Default for PresetCompileOptions<'_, I> {
+    fn default() -> Self {
+        Self {
+            max_threads: None,
+            provider: &StdFileProvider,
+            defines: &NO_DEFINES,
+            optimization: None,
+            compiler: None,
+        }
+    }
+}
+
+impl<I> Clone for PresetCompileOptions<'_, I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I> Copy for PresetCompileOptions<'_, I> {}
+
 impl<T: OutputTarget> CompilePresetTarget for T {}
 
 /// Trait for target shading languages that can compile output with
@@ -36,6 +92,9 @@ impl<T: OutputTarget> CompilePresetTarget for T {}
 pub trait CompilePresetTarget: OutputTarget {
     /// Compile passes of a shader preset given the applicable
     /// shader output target, compilation type, and resulting error.
+    ///
+    /// Passes are preprocessed and compiled in parallel on the global rayon thread pool,
+    /// reading sources from the host filesystem.
     fn compile_preset_passes<I, R, E>(
         passes: Vec<ShaderPassConfig>,
         textures: &[TextureConfig],
@@ -47,7 +106,37 @@ pub trait CompilePresetTarget: OutputTarget {
         E,
     >
     where
-        I: ShaderReflectObject,
+        I: ShaderReflectObject + Send,
+        Self: Sized,
+        Self: FromCompilation<I, R>,
+        I::Compiler: ShaderInputCompiler<I>,
+        E: From<PreprocessError>,
+        E: From<ShaderReflectError>,
+        E: From<ShaderCompileError>,
+    {
+        Self::compile_preset_passes_with_options::<I, R, E>(
+            passes,
+            textures,
+            &PresetCompileOptions::default(),
+        )
+    }
+
+    /// Compile passes of a shader preset given the applicable
+    /// shader output target, compilation type, and resulting error,
+    /// with the given options.
+    fn compile_preset_passes_with_options<I, R, E>(
+        passes: Vec<ShaderPassConfig>,
+        textures: &[TextureConfig],
+        options: &PresetCompileOptions<I>,
+    ) -> Result<
+        (
+            Vec<ShaderPassArtifact<<Self as FromCompilation<I, R>>::Output>>,
+            ShaderSemantics,
+        ),
+        E,
+    >
+    where
+        I: ShaderReflectObject + Send,
         Self: Sized,
         Self: FromCompilation<I, R>,
         I::Compiler: ShaderInputCompiler<I>,
@@ -55,7 +144,69 @@ pub trait CompilePresetTarget: OutputTarget {
         E: From<ShaderReflectError>,
         E: From<ShaderCompileError>,
     {
-        compile_preset_passes::<Self, I, R, E>(passes, textures)
+        compile_preset_passes::<Self, I, R, E>(passes, textures, options)
+    }
+}
+
+/// An error from preprocessing or compiling a pass on a worker thread.
+///
+/// This is converted into the caller's error type once all passes have been compiled,
+/// so that the error type does not need to be `Send`.
+enum CompileSourceError {
+    Preprocess(PreprocessError),
+    Compile(ShaderCompileError),
+}
+
+/// Get the thread pool with the given number of threads, creating it if needed.
+fn thread_pool(threads: NonZeroUsize) -> Result<Arc<ThreadPool>, ShaderCompileError> {
+    let mut pools = THREAD_POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, pool)) = pools.iter().find(|(count, _)| *count == threads) {
+        return Ok(Arc::clone(pool));
+    }
+
+    let pool = Arc::new(
+        ThreadPoolBuilder::new()
+            .num_threads(threads.get())
+            .build()?,
+    );
+    pools.push((threads, Arc::clone(&pool)));
+    Ok(pool)
+}
+
+/// Load and compile the source of each pass in parallel, returning the results in pass order.
+fn compile_sources<I>(
+    passes: &[ShaderPassConfig],
+    options: &PresetCompileOptions<I>,
+) -> Result<Vec<(ShaderSource, I)>, CompileSourceError>
+where
+    I: ShaderReflectObject + Send,
+    I::Compiler: ShaderInputCompiler<I>,
+{
+    let compile = || {
+        passes
+            .par_iter()
+            .map(|shader| {
+                let source = ShaderSource::load_with_defines(
+                    &shader.name,
+                    options.defines,
+                    options.provider,
+                )
+                .map_err(CompileSourceError::Preprocess)?;
+                let compiled = match options.compiler {
+                    Some(compile) => compile(&source, options.defines, options.optimization),
+                    None => I::Compiler::compile_with_options(&source, options.optimization),
+                }
+                .map_err(CompileSourceError::Compile)?;
+                Ok((source, compiled))
+            })
+            .collect()
+    };
+
+    match options.max_threads {
+        Some(threads) => thread_pool(threads)
+            .map_err(CompileSourceError::Compile)?
+            .install(compile),
+        None => compile(),
     }
 }
 
@@ -64,6 +215,7 @@ pub trait CompilePresetTarget: OutputTarget {
 fn compile_preset_passes<T, I, R, E>(
     passes: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
+    options: &PresetCompileOptions<I>,
 ) -> Result<
     (
         Vec<ShaderPassArtifact<<T as FromCompilation<I, R>>::Output>>,
@@ -72,49 +224,71 @@ fn compile_preset_passes<T, I, R, E>(
     E,
 >
 where
-    I: ShaderReflectObject,
+    I: ShaderReflectObject + Send,
+    I::Compiler: ShaderInputCompiler<I>,
     T: OutputTarget,
     T: FromCompilation<I, R>,
-    I::Compiler: ShaderInputCompiler<I>,
     E: From<PreprocessError>,
     E: From<ShaderReflectError>,
     E: From<ShaderCompileError>,
 {
-    let mut uniform_semantics: FastHashMap<String, UniformSemantic> = Default::default();
-    let mut texture_semantics: FastHashMap<String, Semantic<TextureSemantics>> = Default::default();
+    let sources = compile_sources::<I>(&passes, options).map_err(|e| match e {
+        CompileSourceError::Preprocess(e) => E::from(e),
+        CompileSourceError::Compile(e) => E::from(e),
+    })?;
 
+    // Reflection and semantic collection happen in pass order so the results are deterministic.
     let passes = passes
         .into_iter()
-        .map(|shader| {
-            let source: ShaderSource = ShaderSource::load(&shader.name)?;
-
-            let compiled = I::Compiler::compile(&source)?;
+        .zip(sources)
+        .map(|(shader, (source, compiled))| {
             let reflect = T::from_compilation(compiled)?;
-
-            for parameter in source.parameters.values() {
-                uniform_semantics.insert(
-                    parameter.id.clone(),
-                    UniformSemantic::Unique(Semantic {
-                        semantics: UniqueSemantics::FloatParameter,
-                        index: (),
-                    }),
-                );
-            }
             Ok::<_, E>((shader, source, reflect))
         })
         .collect::<Result<Vec<(ShaderPassConfig, ShaderSource, CompilerBackend<_>)>, E>>()?;
 
-    for details in &passes {
-        insert_pass_semantics(&mut uniform_semantics, &mut texture_semantics, &details.0)
+    let semantics = preset_semantics(
+        passes.iter().map(|(_, source, _)| source),
+        passes.iter().map(|(shader, _, _)| shader),
+        textures,
+    );
+
+    Ok((passes, semantics))
+}
+
+/// Collect the semantics available to the passes of a shader preset.
+///
+/// Parameters of every source are inserted first, followed by the semantics of each pass
+/// and the semantics of each lookup texture.
+pub fn preset_semantics<'a>(
+    sources: impl IntoIterator<Item = &'a ShaderSource>,
+    passes: impl IntoIterator<Item = &'a ShaderPassConfig>,
+    textures: &[TextureConfig],
+) -> ShaderSemantics {
+    let mut uniform_semantics: FastHashMap<String, UniformSemantic> = Default::default();
+    let mut texture_semantics: FastHashMap<String, Semantic<TextureSemantics>> = Default::default();
+
+    for source in sources {
+        for parameter in source.parameters.values() {
+            uniform_semantics.insert(
+                parameter.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+    }
+
+    for config in passes {
+        insert_pass_semantics(&mut uniform_semantics, &mut texture_semantics, config)
     }
     insert_lut_semantics(textures, &mut uniform_semantics, &mut texture_semantics);
 
-    let semantics = ShaderSemantics {
+    ShaderSemantics {
         uniform_semantics,
         texture_semantics,
-    };
-
-    Ok((passes, semantics))
+    }
 }
 
 /// Insert the available semantics for the input pass config into the provided semantic maps.
@@ -191,3 +365,67 @@ fn insert_lut_semantics(
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::back::targets::SPIRV;
+    use crate::front::SpirvCompilation;
+    use crate::reflect::cross::SpirvCross;
+    use crate::reflect::presets::{CompilePresetTarget, PresetCompileOptions};
+    use librashader_presets::ShaderPreset;
+    use std::error::Error;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    pub fn compiles_passes_in_order() {
+        let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+        let mut passes = preset.shaders.clone();
+        for id in 1..4 {
+            let mut pass = passes[0].clone();
+            pass.id = id;
+            pass.alias = Some(format!("Pass{id}"));
+            passes.push(pass);
+        }
+
+        let (parallel, semantics) = SPIRV::compile_preset_passes::<
+            SpirvCompilation,
+            SpirvCross,
+            Box<dyn Error>,
+        >(passes.clone(), &preset.textures)
+        .unwrap();
+        let (limited, limited_semantics) = SPIRV::compile_preset_passes_with_options::<
+            SpirvCompilation,
+            SpirvCross,
+            Box<dyn Error>,
+        >(
+            passes,
+            &preset.textures,
+            &PresetCompileOptions {
+                max_threads: NonZeroUsize::new(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let ids: Vec<i32> = parallel.iter().map(|(config, _, _)| config.id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        assert!(parallel
+            .iter()
+            .zip(&limited)
+            .all(|(a, b)| a.0 == b.0 && a.1 == b.1));
+
+        let mut uniforms: Vec<&String> = semantics.uniform_semantics.keys().collect();
+        let mut limited_uniforms: Vec<&String> =
+            limited_semantics.uniform_semantics.keys().collect();
+        uniforms.sort();
+        limited_uniforms.sort();
+        assert_eq!(uniforms, limited_uniforms);
+        assert!(semantics.texture_semantics.contains_key("Pass3"));
+
+        let threads = NonZeroUsize::new(2).unwrap();
+        let first = super::thread_pool(threads).unwrap();
+        let second = super::thread_pool(threads).unwrap();
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+        assert_eq!(first.current_num_threads(), 2);
+    }
+}
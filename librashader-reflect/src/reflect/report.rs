@@ -0,0 +1,452 @@
+//! A [`CompatibilityReport`] records, for every output target and reflector, whether each pass
+//! of a shader preset can be reflected and compiled, and the error for each pass that can not.
+use crate::back::glsl::GlslVersion;
+use crate::back::hlsl::HlslShaderModel;
+use crate::back::msl::MslVersion;
+use crate::back::spirv::NagaSpirvOptions;
+use crate::back::targets::{OutputTarget, GLSL, HLSL, MSL, SPIRV, WGSL};
+use crate::back::{CompileShader, FromCompilation};
+use crate::error::ShaderReflectError;
+use crate::front::SpirvCompilation;
+use crate::reflect::cross::SpirvCross;
+use crate::reflect::naga::{Naga, NagaLoweringOptions};
+use crate::reflect::presets::preset_semantics;
+use crate::reflect::semantics::ShaderSemantics;
+use crate::reflect::ReflectShader;
+use librashader_common::fs::{FileProvider, StdFileProvider};
+use librashader_preprocess::ShaderSource;
+use librashader_presets::ShaderPreset;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt::Debug;
+use std::io::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+
+/// The output targets to report compatibility for.
+///
+/// Every target is reported for each reflector that supports it.
+#[derive(Debug, Clone)]
+pub struct ReportTargets {
+    /// The GLSL versions to report.
+    pub glsl: Vec<GlslVersion>,
+    /// The HLSL shader models to report.
+    pub hlsl: Vec<HlslShaderModel>,
+    /// The MSL versions to report.
+    pub msl: Vec<MslVersion>,
+    /// Whether or not to report WGSL.
+    pub wgsl: bool,
+    /// Whether or not to report SPIR-V.
+    pub spirv: bool,
+}
+
+impl Default for ReportTargets {
+    fn default() -> Self {
+        ReportTargets {
+            glsl: vec![GlslVersion::V3_30, GlslVersion::V4_60],
+            hlsl: vec![HlslShaderModel::V5_0],
+            msl: vec![MslVersion::V2_0],
+            wgsl: true,
+            spirv: true,
+        }
+    }
+}
+
+/// The result of reflecting and compiling a shader pass for a target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PassStatus {
+    /// The pass was reflected and compiled successfully.
+    Compiled,
+    /// The pass was reflected successfully, but could not be compiled.
+    Reflected,
+    /// The pass could not be reflected.
+    Failed,
+}
+
+/// The source of a shader pass within the report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassSource {
+    /// The index of the pass within the shader preset.
+    pub index: usize,
+    /// The path to the shader source of the pass.
+    pub path: PathBuf,
+    /// The error if the pass could not be preprocessed or compiled to SPIR-V.
+    pub error: Option<String>,
+}
+
+/// The result of a shader pass for a target within the report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassReport {
+    /// The index of the pass within the shader preset.
+    pub index: usize,
+    /// Whether the pass was compiled, reflected, or failed.
+    pub status: PassStatus,
+    /// The error if the pass could not be reflected or compiled.
+    pub error: Option<String>,
+}
+
+/// The results of every shader pass for a pair of output target and reflector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetReport {
+    /// The name of the output target.
+    pub target: String,
+    /// The version of the output target, if applicable.
+    pub version: Option<String>,
+    /// The name of the reflector.
+    pub reflector: String,
+    /// The result of each pass.
+    pub passes: Vec<PassReport>,
+}
+
+impl TargetReport {
+    /// Whether or not every pass compiled for this target.
+    pub fn is_compatible(&self) -> bool {
+        self.passes
+            .iter()
+            .all(|pass| pass.status == PassStatus::Compiled)
+    }
+}
+
+/// A report of the compatibility of a shader preset across output targets and reflectors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    /// The sources of each pass in the shader preset.
+    pub passes: Vec<PassSource>,
+    /// The results for each pair of output target and reflector.
+    pub targets: Vec<TargetReport>,
+}
+
+impl CompatibilityReport {
+    /// Reflect and compile every pass of the shader preset for each of the given targets,
+    /// with every reflector that supports the target.
+    ///
+    /// Errors are recorded in the report rather than returned. Panics within a reflector
+    /// are caught and reported as an error for the pass.
+    pub fn new(preset: &ShaderPreset, targets: &ReportTargets) -> CompatibilityReport {
+        Self::new_with_provider(preset, targets, &StdFileProvider)
+    }
+
+    /// Reflect and compile every pass of the shader preset for each of the given targets,
+    /// reading shader sources through the given file provider.
+    ///
+    /// The file provider should be the one the shader preset was parsed with.
+    /// See [`new`](Self::new) for how errors are reported.
+    pub fn new_with_provider(
+        preset: &ShaderPreset,
+        targets: &ReportTargets,
+        provider: &dyn FileProvider,
+    ) -> CompatibilityReport {
+        let sources: Vec<_> = preset
+            .shaders
+            .iter()
+            .map(|shader| {
+                let source = ShaderSource::load_with_provider(&shader.name, provider)
+                    .map_err(|e| e.to_string())?;
+                let compilation = SpirvCompilation::try_from(&source).map_err(|e| e.to_string())?;
+                Ok::<_, String>((source, compilation))
+            })
+            .collect();
+
+        let semantics = preset_semantics(
+            sources
+                .iter()
+                .filter_map(|s| s.as_ref().ok().map(|(s, _)| s)),
+            &preset.shaders,
+            &preset.textures,
+        );
+
+        let compilations: Vec<_> = sources
+            .iter()
+            .map(|s| s.as_ref().map(|(_, c)| c).map_err(String::as_str))
+            .collect();
+
+        let mut reports = Vec::new();
+        for &version in &targets.glsl {
+            reports.push(report_target::<GLSL, _>(
+                "SpirvCross",
+                GLSL::from_compilation,
+                version,
+                Some(version),
+                &compilations,
+                &semantics,
+            ));
+            reports.push(report_target::<GLSL, _>(
+                "Naga",
+                GLSL::from_naga_compilation,
+                version,
+                Some(version),
+                &compilations,
+                &semantics,
+            ));
+        }
+
+        for &model in &targets.hlsl {
+            reports.push(report_target::<HLSL, _>(
+                "SpirvCross",
+                HLSL::from_compilation,
+                Some(model),
+                Some(model),
+                &compilations,
+                &semantics,
+            ));
+            reports.push(report_target::<HLSL, _>(
+                "Naga",
+                HLSL::from_naga_compilation,
+                Some(model),
+                Some(model),
+                &compilations,
+                &semantics,
+            ));
+        }
+
+        for &version in &targets.msl {
+            reports.push(report_target::<MSL, _>(
+                "SpirvCross",
+                <MSL as FromCompilation<_, SpirvCross>>::from_compilation,
+                Some(version),
+                Some(version),
+                &compilations,
+                &semantics,
+            ));
+            reports.push(report_target::<MSL, _>(
+                "Naga",
+                <MSL as FromCompilation<_, Naga>>::from_compilation,
+                Some(version),
+                Some(version),
+                &compilations,
+                &semantics,
+            ));
+        }
+
+        if targets.wgsl {
+            reports.push(report_target::<WGSL, _>(
+                "Naga",
+                WGSL::from_compilation,
+                NagaLoweringOptions {
+                    write_pcb_as_ubo: true,
+                    sampler_bind_group: 1,
+                },
+                None::<()>,
+                &compilations,
+                &semantics,
+            ));
+        }
+
+        if targets.spirv {
+            reports.push(report_target::<SPIRV, _>(
+                "SpirvCross",
+                <SPIRV as FromCompilation<_, SpirvCross>>::from_compilation,
+                None,
+                None::<()>,
+                &compilations,
+                &semantics,
+            ));
+            reports.push(report_target::<SPIRV, _>(
+                "Naga",
+                <SPIRV as FromCompilation<_, Naga>>::from_compilation,
+                NagaSpirvOptions {
+                    lowering: NagaLoweringOptions {
+                        write_pcb_as_ubo: true,
+                        sampler_bind_group: 1,
+                    },
+                    version: (1, 0),
+                },
+                None::<()>,
+                &compilations,
+                &semantics,
+            ));
+        }
+
+        let passes = preset
+            .shaders
+            .iter()
+            .zip(&sources)
+            .enumerate()
+            .map(|(index, (shader, source))| PassSource {
+                index,
+                path: shader.name.clone(),
+                error: source.as_ref().err().cloned(),
+            })
+            .collect();
+
+        CompatibilityReport {
+            passes,
+            targets: reports,
+        }
+    }
+
+    /// Whether or not every pass compiled for every target in the report.
+    pub fn is_compatible(&self) -> bool {
+        self.targets.iter().all(TargetReport::is_compatible)
+    }
+
+    /// Serialize the report as pretty-printed JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Write the report as pretty-printed JSON to the given writer.
+    pub fn write_json(&self, writer: impl Write) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+/// Get the message of a caught panic.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("panicked: {message}")
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("panicked: {message}")
+    } else {
+        String::from("panicked")
+    }
+}
+
+/// Reflect and compile every pass for the output target with the given reflector.
+///
+/// `from_compilation` creates the compiler for the target with the reflector.
+fn report_target<T, B>(
+    reflector: &str,
+    from_compilation: impl Fn(SpirvCompilation) -> Result<B, ShaderReflectError>,
+    options: B::Options,
+    version: Option<impl Debug>,
+    compilations: &[Result<&SpirvCompilation, &str>],
+    semantics: &ShaderSemantics,
+) -> TargetReport
+where
+    T: OutputTarget,
+    B: CompileShader<T> + ReflectShader,
+    B::Options: Clone,
+{
+    let passes = compilations
+        .iter()
+        .enumerate()
+        .map(|(index, compilation)| {
+            let compilation = match compilation {
+                Ok(compilation) => (*compilation).clone(),
+                Err(error) => {
+                    return PassReport {
+                        index,
+                        status: PassStatus::Failed,
+                        error: Some(error.to_string()),
+                    }
+                }
+            };
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                let mut backend = match from_compilation(compilation) {
+                    Ok(backend) => backend,
+                    Err(e) => return (PassStatus::Failed, Some(e.to_string())),
+                };
+                if let Err(e) = backend.reflect(index, semantics) {
+                    return (PassStatus::Failed, Some(e.to_string()));
+                }
+                match backend.compile(options.clone()) {
+                    Ok(_) => (PassStatus::Compiled, None),
+                    Err(e) => (PassStatus::Reflected, Some(e.to_string())),
+                }
+            }));
+
+            let (status, error) =
+                result.unwrap_or_else(|payload| (PassStatus::Failed, Some(panic_message(payload))));
+            PassReport {
+                index,
+                status,
+                error,
+            }
+        })
+        .collect();
+
+    TargetReport {
+        target: std::any::type_name::<T>()
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        version: version.map(|version| format!("{version:?}")),
+        reflector: reflector.to_string(),
+        passes,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reflect::report::{CompatibilityReport, PassStatus, ReportTargets};
+    use librashader_common::fs::MemoryFileProvider;
+    use librashader_presets::{ShaderPreset, WildcardContext};
+    use std::path::PathBuf;
+
+    fn absolute(path: &str) -> PathBuf {
+        let root = if cfg!(windows) { r"C:\" } else { "/" };
+        path.split('/')
+            .fold(PathBuf::from(root), |path, component| path.join(component))
+    }
+
+    #[test]
+    pub fn reports_basic_preset() {
+        let preset = ShaderPreset::try_parse("../test/basic.slangp").unwrap();
+        let report = CompatibilityReport::new(&preset, &ReportTargets::default());
+
+        assert_eq!(report.passes.len(), preset.shaders.len());
+        assert!(report.passes.iter().all(|pass| pass.error.is_none()));
+
+        // Every GLSL, HLSL and MSL target is reported with both reflectors.
+        assert_eq!(report.targets.len(), 11);
+        for target in &report.targets {
+            assert_eq!(target.passes.len(), preset.shaders.len());
+            assert!(
+                target
+                    .passes
+                    .iter()
+                    .all(|pass| pass.status == PassStatus::Compiled && pass.error.is_none()),
+                "{} {:?} with {} did not compile",
+                target.target,
+                target.version,
+                target.reflector
+            );
+        }
+        assert!(report
+            .targets
+            .iter()
+            .any(|target| target.target == "GLSL" && target.reflector == "SpirvCross"));
+        assert!(report.is_compatible());
+
+        let json = report.to_json().unwrap();
+        assert!(json.contains("\"status\": \"compiled\""));
+        let parsed: CompatibilityReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.targets.len(), report.targets.len());
+        assert!(parsed.is_compatible());
+    }
+
+    #[test]
+    pub fn reports_preset_from_provider() {
+        let mut provider = MemoryFileProvider::new();
+        provider.insert(
+            absolute("presets/basic.slangp"),
+            "shaders = 1\nshader0 = shaders/basic.slang\n",
+        );
+        provider.insert(
+            absolute("presets/shaders/basic.slang"),
+            std::fs::read("../test/basic.slang").unwrap(),
+        );
+
+        let preset = ShaderPreset::try_parse_with_provider(
+            absolute("presets/basic.slangp"),
+            WildcardContext::new(),
+            &provider,
+        )
+        .unwrap();
+        let targets = ReportTargets {
+            glsl: vec![],
+            hlsl: vec![],
+            msl: vec![],
+            wgsl: true,
+            spirv: false,
+        };
+        let report = CompatibilityReport::new_with_provider(&preset, &targets, &provider);
+
+        assert!(report.passes.iter().all(|pass| pass.error.is_none()));
+        assert!(report.is_compatible());
+    }
+}
@@ -1,5 +1,7 @@
 use bitflags::bitflags;
 use librashader_common::map::FastHashMap;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
 /// The maximum number of bindings allowed in a shader.
@@ -9,6 +11,7 @@ pub const MAX_PUSH_BUFFER_SIZE: u32 = 128;
 
 /// The type of a uniform.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum UniformType {
     /// A matrix of 4x4 floats (`mat4`).
     Mat4,
@@ -26,6 +29,7 @@ pub enum UniformType {
 /// that are always available.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Hash)]
 #[repr(i32)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum UniqueSemantics {
     // mat4, MVP
     /// The Model View Projection matrix for the frame.
@@ -52,6 +56,36 @@ pub enum UniqueSemantics {
     /// A user defined float parameter.
     // float, user defined parameter, array
     FloatParameter = 8,
+    // float, aspect ratio of the original input
+    /// The aspect ratio of the original input, as reported by the core.
+    OriginalAspect = 9,
+    // float, aspect ratio of the original input after rotation
+    /// The aspect ratio of the original input, accounting for rotation.
+    OriginalAspectRotated = 10,
+    // float, frames per second of the core
+    /// The frame rate of the original input, in frames per second.
+    OriginalFPS = 11,
+    // uint, time since the last frame in microseconds
+    /// The time elapsed since the previous frame, in microseconds.
+    FrameTimeDelta = 12,
+    // uint, whether HDR output is enabled
+    /// Whether or not HDR output is enabled (0 or 1).
+    EnableHDR = 13,
+    // float, paper white luminance in nits
+    /// The luminance of paper white, in nits.
+    PaperWhiteNits = 14,
+    // float, peak luminance in nits
+    /// The peak luminance of the display, in nits.
+    MaxNits = 15,
+    // uint, gamut expansion mode
+    /// The gamut expansion mode to use when converting to HDR.
+    ExpandGamut = 16,
+    // uint, whether to apply inverse tonemapping
+    /// Whether or not to apply inverse tonemapping to SDR content (0 or 1).
+    InverseTonemap = 17,
+    // uint, whether to output in HDR10
+    /// Whether or not the output is encoded as HDR10 (0 or 1).
+    HDR10 = 18,
 }
 
 impl UniqueSemantics {
@@ -75,6 +109,16 @@ impl UniqueSemantics {
             UniqueSemantics::TotalSubFrames => UniformType::Unsigned,
             UniqueSemantics::CurrentSubFrame => UniformType::Unsigned,
             UniqueSemantics::FloatParameter => UniformType::Float,
+            UniqueSemantics::OriginalAspect => UniformType::Float,
+            UniqueSemantics::OriginalAspectRotated => UniformType::Float,
+            UniqueSemantics::OriginalFPS => UniformType::Float,
+            UniqueSemantics::FrameTimeDelta => UniformType::Unsigned,
+            UniqueSemantics::EnableHDR => UniformType::Unsigned,
+            UniqueSemantics::PaperWhiteNits => UniformType::Float,
+            UniqueSemantics::MaxNits => UniformType::Float,
+            UniqueSemantics::ExpandGamut => UniformType::Unsigned,
+            UniqueSemantics::InverseTonemap => UniformType::Unsigned,
+            UniqueSemantics::HDR10 => UniformType::Unsigned,
         }
     }
 }
@@ -84,6 +128,7 @@ impl UniqueSemantics {
 /// Texture semantics are used to relate both texture samplers and `*Size` uniforms.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Hash)]
 #[repr(i32)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum TextureSemantics {
     /// The original input of the filter chain.
     Original = 0,
@@ -163,6 +208,7 @@ pub(crate) trait ValidateTypeSemantics<T> {
 
 /// A unit of unique or indexed semantic.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Semantic<T, I = usize> {
     /// The semantics of this unit.
     pub semantics: T,
@@ -173,6 +219,7 @@ pub struct Semantic<T, I = usize> {
 bitflags! {
     /// The pipeline stage for which a uniform is bound.
     #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub struct BindingStage: u8 {
         const NONE = 0b00000000;
         const VERTEX = 0b00000001;
@@ -182,6 +229,7 @@ bitflags! {
 
 /// Reflection information for the Uniform Buffer or Push Constant Block
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct BufferReflection<T> {
     /// The binding point for this buffer, if applicable
     pub binding: T,
@@ -195,6 +243,7 @@ pub struct BufferReflection<T> {
 ///
 /// A uniform can be bound to both the UBO, or as a Push Constant.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct MemberOffset {
     /// The offset of the uniform member within the UBO.
     pub ubo: Option<usize>,
@@ -204,6 +253,7 @@ pub struct MemberOffset {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// The block where a uniform member is located.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum UniformMemberBlock {
     /// The offset is for a UBO.
     Ubo,
@@ -248,6 +298,7 @@ impl MemberOffset {
 
 /// Reflection information about a non-texture related uniform variable.
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct VariableMeta {
     // this might bite us in the back because retroarch keeps separate UBO/push offsets.. eh
     /// The offset of this variable uniform.
@@ -260,6 +311,7 @@ pub struct VariableMeta {
 
 /// Reflection information about a texture size uniform variable.
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct TextureSizeMeta {
     // this might bite us in the back because retroarch keeps separate UBO/push offsets..
     /// The offset of this size uniform.
@@ -272,6 +324,7 @@ pub struct TextureSizeMeta {
 
 /// Reflection information about texture samplers.
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct TextureBinding {
     /// The binding index of the texture.
     pub binding: u32,
@@ -279,6 +332,7 @@ pub struct TextureBinding {
 
 /// Reflection information about a shader.
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct ShaderReflection {
     /// Reflection information about the UBO for this shader.
     pub ubo: Option<BufferReflection<u32>>,
@@ -427,6 +481,46 @@ impl UniqueSemanticMap for FastHashMap<String, UniformSemantic> {
                     semantics: UniqueSemantics::TotalSubFrames,
                     index: (),
                 }),
+                "OriginalAspect" => Some(Semantic {
+                    semantics: UniqueSemantics::OriginalAspect,
+                    index: (),
+                }),
+                "OriginalAspectRotated" => Some(Semantic {
+                    semantics: UniqueSemantics::OriginalAspectRotated,
+                    index: (),
+                }),
+                "OriginalFPS" => Some(Semantic {
+                    semantics: UniqueSemantics::OriginalFPS,
+                    index: (),
+                }),
+                "FrameTimeDelta" => Some(Semantic {
+                    semantics: UniqueSemantics::FrameTimeDelta,
+                    index: (),
+                }),
+                "EnableHDR" => Some(Semantic {
+                    semantics: UniqueSemantics::EnableHDR,
+                    index: (),
+                }),
+                "PaperWhiteNits" => Some(Semantic {
+                    semantics: UniqueSemantics::PaperWhiteNits,
+                    index: (),
+                }),
+                "MaxNits" => Some(Semantic {
+                    semantics: UniqueSemantics::MaxNits,
+                    index: (),
+                }),
+                "ExpandGamut" => Some(Semantic {
+                    semantics: UniqueSemantics::ExpandGamut,
+                    index: (),
+                }),
+                "InverseTonemap" => Some(Semantic {
+                    semantics: UniqueSemantics::InverseTonemap,
+                    index: (),
+                }),
+                "HDR10" => Some(Semantic {
+                    semantics: UniqueSemantics::HDR10,
+                    index: (),
+                }),
                 _ => None,
             },
             Some(UniformSemantic::Unique(variable)) => Some(*variable),
@@ -437,6 +531,7 @@ impl UniqueSemanticMap for FastHashMap<String, UniformSemantic> {
 
 /// Semantic assignment of a shader uniform to filter chain semantics.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum UniformSemantic {
     /// A unique semantic.
     Unique(Semantic<UniqueSemantics, ()>),
@@ -446,6 +541,7 @@ pub enum UniformSemantic {
 
 /// The runtime provided maps of uniform and texture variables to filter chain semantics.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct ShaderSemantics {
     /// A map of uniform names to filter chain semantics.
     pub uniform_semantics: FastHashMap<String, UniformSemantic>,
@@ -458,6 +554,7 @@ pub struct ShaderSemantics {
 /// Used in combination with [`MemberOffset`](crate::reflect::semantics::MemberOffset) to keep track
 /// of semantics at each frame pass.
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum UniformBinding {
     /// A user parameter (`float`) binding.
     Parameter(String),
@@ -481,6 +578,7 @@ impl From<Semantic<TextureSemantics>> for UniformBinding {
 
 /// Reflection metadata about the various bindings for this shader.
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct BindingMeta {
     /// A map of parameter names to uniform binding metadata.
     pub parameter_meta: FastHashMap<String, VariableMeta>,
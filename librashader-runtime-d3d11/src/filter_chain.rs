@@ -1,15 +1,17 @@
 use crate::texture::{D3D11InputView, InputTexture};
 use librashader_common::{ImageFormat, Size, Viewport};
 
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_preprocess::Defines;
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
 use librashader_reflect::back::targets::HLSL;
-use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
+use librashader_reflect::back::CompileReflectShader;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
 use librashader_reflect::reflect::semantics::ShaderSemantics;
-use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::image::{Image, ImageError, UVDirection};
 use std::collections::VecDeque;
+use std::num::NonZeroUsize;
 
 use std::path::Path;
 
@@ -23,13 +25,16 @@ use crate::options::{FilterChainOptionsD3D11, FrameOptionsD3D11};
 use crate::samplers::SamplerSet;
 use crate::util::d3d11_compile_bound_shader;
 use crate::{error, util, D3D11OutputView};
-use librashader_cache::cache_shader_object;
-use librashader_cache::CachedCompilation;
+use librashader_cache::{cache_reflection, cache_shader_object, cache_shader_output};
+use librashader_cache::{CachedCompilation, ShaderCache};
 use librashader_presets::context::VideoDriver;
 use librashader_reflect::reflect::cross::SpirvCross;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_runtime::binding::{BindingUtil, TextureInput};
 use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::scaling::ScaleFramebuffer;
@@ -56,6 +61,7 @@ pub struct FilterChainD3D11 {
     history_framebuffers: VecDeque<OwnedImage>,
     state: D3D11State,
     default_options: FrameOptionsD3D11,
+    hdr_mode: HdrMode,
 }
 
 pub(crate) struct Direct3D11 {
@@ -80,17 +86,42 @@ type ShaderPassMeta =
 fn compile_passes(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
-    disable_cache: bool,
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) = if !disable_cache {
-        HLSL::compile_preset_passes::<
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        HLSL::compile_preset_passes_with_options::<
             CachedCompilation<SpirvCompilation>,
             SpirvCross,
             FilterChainError,
-        >(shaders, &textures)?
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
     } else {
-        HLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
+        HLSL::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?
     };
 
@@ -105,7 +136,13 @@ impl FilterChainD3D11 {
         options: Option<&FilterChainOptionsD3D11>,
     ) -> error::Result<FilterChainD3D11> {
         // load passes from preset
-        let preset = ShaderPreset::try_parse_with_driver_context(path, VideoDriver::Direct3D11)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::Direct3D11);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
 
         unsafe { Self::load_from_preset(preset, device, options) }
     }
@@ -141,19 +178,49 @@ impl FilterChainD3D11 {
         ctx: &ID3D11DeviceContext,
         options: Option<&FilterChainOptionsD3D11>,
     ) -> error::Result<FilterChainD3D11> {
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
+        let mut preset = preset;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        if hdr_mode.is_enabled() && options.is_some_and(|o| o.hdr_output_pass) {
+            hdr::append_hdr_pass(&mut preset, &defines, provider)?;
+        }
 
-        let (passes, semantics) = compile_passes(preset.shaders, &preset.textures, disable_cache)?;
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
+
+        let (passes, semantics) = compile_passes(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            provider,
+        )?;
 
         let samplers = SamplerSet::new(device)?;
 
         // initialize passes
-        let filters = FilterChainD3D11::init_passes(device, passes, &semantics, disable_cache)?;
+        let filters = FilterChainD3D11::init_passes(
+            device,
+            passes,
+            &semantics,
+            cache.as_ref(),
+            &defines,
+            optimization.as_ref(),
+        )?;
 
         let immediate_context = unsafe { device.GetImmediateContext()? };
 
         // load luts
-        let luts = FilterChainD3D11::load_luts(device, &ctx, &preset.textures)?;
+        let luts = FilterChainD3D11::load_luts(device, &ctx, &preset.textures, provider)?;
 
         let framebuffer_gen =
             || OwnedImage::new(device, Size::new(1, 1), ImageFormat::R8G8B8A8Unorm, false);
@@ -204,6 +271,7 @@ impl FilterChainD3D11 {
             },
             state,
             default_options: Default::default(),
+            hdr_mode,
         })
     }
 }
@@ -233,14 +301,17 @@ impl FilterChainD3D11 {
         device: &ID3D11Device,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
+        defines: &Defines,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<Vec<FilterPass>> {
         let device_is_singlethreaded =
             unsafe { (device.GetCreationFlags() & D3D11_CREATE_DEVICE_SINGLETHREADED.0) == 1 };
 
         let builder_fn = |(index, (config, source, mut reflect)): (usize, ShaderPassMeta)| {
-            let reflection = reflect.reflect(index, semantics)?;
-            let hlsl = reflect.compile(None)?;
+            let reflection =
+                cache_reflection(&mut reflect, &source, optimization, index, semantics, cache)?;
+            let hlsl = cache_shader_output(&source, defines, optimization, reflect, None, cache)?;
 
             let (vs, vertex_dxbc) = cache_shader_object(
                 "dxbc",
@@ -257,7 +328,7 @@ impl FilterChainD3D11 {
                         blob,
                     ))
                 },
-                disable_cache,
+                cache,
             )?;
 
             let ia_desc = DrawQuad::get_spirv_cross_vbo_desc();
@@ -270,7 +341,7 @@ impl FilterChainD3D11 {
                 |blob| {
                     d3d11_compile_bound_shader(device, &blob, None, ID3D11Device::CreatePixelShader)
                 },
-                disable_cache,
+                cache,
             )?;
 
             let ubo_cbuffer = if let Some(ubo) = &reflection.ubo
@@ -355,11 +426,12 @@ impl FilterChainD3D11 {
         device: &ID3D11Device,
         context: &ID3D11DeviceContext,
         textures: &[TextureConfig],
+        provider: &dyn FileProvider,
     ) -> error::Result<FastHashMap<usize, LutTexture>> {
         let mut luts = FastHashMap::default();
         let images = textures
             .par_iter()
-            .map(|texture| Image::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| Image::load_with_provider(&texture.path, UVDirection::TopLeft, provider))
             .collect::<Result<Vec<Image>, ImageError>>()?;
 
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
@@ -418,6 +490,8 @@ impl FilterChainD3D11 {
         }
 
         let options = options.unwrap_or(&self.default_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
         let filter = passes[0].config.filter;
         let wrap_mode = passes[0].config.wrap_mode;
 
@@ -37,6 +37,13 @@ fn triangle_d3d11_args() {
         Some(&FilterChainOptionsD3D11 {
             force_no_mipmaps: false,
             disable_cache: false,
+            cache: None,
+            hdr_mode: Default::default(),
+            hdr_output_pass: false,
+            file_provider: None,
+            defines: Default::default(),
+            optimization: None,
+            max_compile_threads: None,
         }),
         // replace below with 'None' for the triangle
         Some(image),
@@ -62,6 +69,13 @@ fn triangle_d3d11() {
         Some(&FilterChainOptionsD3D11 {
             force_no_mipmaps: false,
             disable_cache: false,
+            cache: None,
+            hdr_mode: Default::default(),
+            hdr_output_pass: false,
+            file_provider: None,
+            defines: Default::default(),
+            optimization: None,
+            max_compile_threads: None,
         }),
         // replace below with 'None' for the triangle
         // None,
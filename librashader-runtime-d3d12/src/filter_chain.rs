@@ -14,21 +14,25 @@ use crate::options::{FilterChainOptionsD3D12, FrameOptionsD3D12};
 use crate::samplers::SamplerSet;
 use crate::texture::{D3D12InputImage, D3D12OutputView, InputTexture, OutputDescriptor};
 use crate::{error, util};
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
 use librashader_common::{ImageFormat, Size, Viewport};
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_preprocess::Defines;
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
 use librashader_reflect::back::targets::{DXIL, HLSL};
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_reflect::reflect::semantics::{ShaderSemantics, MAX_BINDINGS_COUNT};
-use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::{BindingUtil, TextureInput};
 use librashader_runtime::image::{Image, ImageError, UVDirection};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::uniforms::UniformStorage;
 use std::collections::VecDeque;
 use std::mem::ManuallyDrop;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use windows::core::ComInterface;
 use windows::Win32::Foundation::CloseHandle;
@@ -45,10 +49,11 @@ use windows::Win32::Graphics::Direct3D12::{
 use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_UNKNOWN;
 use windows::Win32::System::Threading::{CreateEventA, WaitForSingleObject, INFINITE};
 
-use librashader_cache::CachedCompilation;
+use librashader_cache::{cache_reflection, cache_shader_output, CachedCompilation, ShaderCache};
 use librashader_presets::context::VideoDriver;
 use librashader_reflect::reflect::cross::SpirvCross;
 use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
 use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::scaling::ScaleFramebuffer;
 use rayon::prelude::*;
@@ -79,6 +84,7 @@ pub struct FilterChainD3D12 {
     disable_mipmaps: bool,
 
     default_options: FrameOptionsD3D12,
+    hdr_mode: HdrMode,
 }
 
 pub(crate) struct FilterCommon {
@@ -151,17 +157,42 @@ type DxilShaderPassMeta =
 fn compile_passes_dxil(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
-    disable_cache: bool,
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<DxilShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) = if !disable_cache {
-        DXIL::compile_preset_passes::<
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        DXIL::compile_preset_passes_with_options::<
             CachedCompilation<SpirvCompilation>,
             SpirvCross,
             FilterChainError,
-        >(shaders, &textures)?
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
     } else {
-        DXIL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
+        DXIL::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?
     };
 
@@ -172,17 +203,42 @@ type HlslShaderPassMeta =
 fn compile_passes_hlsl(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
-    disable_cache: bool,
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<HlslShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) = if !disable_cache {
-        HLSL::compile_preset_passes::<
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        HLSL::compile_preset_passes_with_options::<
             CachedCompilation<SpirvCompilation>,
             SpirvCross,
             FilterChainError,
-        >(shaders, &textures)?
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
     } else {
-        HLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
+        HLSL::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?
     };
 
@@ -197,7 +253,13 @@ impl FilterChainD3D12 {
         options: Option<&FilterChainOptionsD3D12>,
     ) -> error::Result<FilterChainD3D12> {
         // load passes from preset
-        let preset = ShaderPreset::try_parse_with_driver_context(path, VideoDriver::Direct3D12)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::Direct3D12);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
 
         unsafe { Self::load_from_preset(preset, device, options) }
     }
@@ -254,15 +316,45 @@ impl FilterChainD3D12 {
         cmd: &ID3D12GraphicsCommandList,
         options: Option<&FilterChainOptionsD3D12>,
     ) -> error::Result<FilterChainD3D12> {
+        let mut preset = preset;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        if hdr_mode.is_enabled() && options.is_some_and(|o| o.hdr_output_pass) {
+            hdr::append_hdr_pass(&mut preset, &defines, provider)?;
+        }
+
         let shader_count = preset.shaders.len();
         let lut_count = preset.textures.len();
 
         let shader_copy = preset.shaders.clone();
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
 
-        let (passes, semantics) =
-            compile_passes_dxil(preset.shaders, &preset.textures, disable_cache)?;
-        let (hlsl_passes, _) = compile_passes_hlsl(shader_copy, &preset.textures, disable_cache)?;
+        let (passes, semantics) = compile_passes_dxil(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            provider,
+        )?;
+        let (hlsl_passes, _) = compile_passes_hlsl(
+            shader_copy,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            provider,
+        )?;
 
         let samplers = SamplerSet::new(device)?;
         let mipmap_gen = D3D12MipmapGen::new(device, false)?;
@@ -290,7 +382,9 @@ impl FilterChainD3D12 {
             hlsl_passes,
             &semantics,
             options.map_or(false, |o| o.force_hlsl_pipeline),
-            disable_cache,
+            cache.as_ref(),
+            &defines,
+            optimization.as_ref(),
         )?;
 
         let mut residuals = FrameResiduals::new();
@@ -302,6 +396,7 @@ impl FilterChainD3D12 {
             &mut mipmap_heap,
             &mut residuals,
             &preset.textures,
+            provider,
         )?;
 
         let framebuffer_gen = || {
@@ -361,6 +456,7 @@ impl FilterChainD3D12 {
             disable_mipmaps: options.map_or(false, |o| o.force_no_mipmaps),
             residuals,
             default_options: Default::default(),
+            hdr_mode,
         })
     }
 
@@ -371,6 +467,7 @@ impl FilterChainD3D12 {
         mipmap_heap: &mut D3D12DescriptorHeap<ResourceWorkHeap>,
         gc: &mut FrameResiduals,
         textures: &[TextureConfig],
+        provider: &dyn FileProvider,
     ) -> error::Result<FastHashMap<usize, LutTexture>> {
         // use separate mipgen to load luts.
         let mipmap_gen = D3D12MipmapGen::new(device, true)?;
@@ -378,7 +475,7 @@ impl FilterChainD3D12 {
         let mut luts = FastHashMap::default();
         let images = textures
             .par_iter()
-            .map(|texture| Image::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| Image::load_with_provider(&texture.path, UVDirection::TopLeft, provider))
             .collect::<Result<Vec<Image>, ImageError>>()?;
 
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
@@ -421,7 +518,9 @@ impl FilterChainD3D12 {
         hlsl_passes: Vec<HlslShaderPassMeta>,
         semantics: &ShaderSemantics,
         force_hlsl: bool,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
+        defines: &Defines,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<(
         ID3D12DescriptorHeap,
         ID3D12DescriptorHeap,
@@ -474,7 +573,14 @@ impl FilterChainD3D12 {
                         ));
                     };
 
-                    let dxil_reflection = dxil.reflect(index, semantics)?;
+                    let dxil_reflection = cache_reflection(
+                        &mut dxil,
+                        &source,
+                        optimization,
+                        index,
+                        semantics,
+                        cache,
+                    )?;
                     let dxil = dxil.compile(Some(
                         librashader_reflect::back::dxil::ShaderModel::ShaderModel6_0,
                     ))?;
@@ -497,15 +603,27 @@ impl FilterChainD3D12 {
                             &dxil,
                             root_signature,
                             render_format,
-                            disable_cache,
+                            cache,
                         ) && !force_hlsl
                         {
                             (dxil_reflection, graphics_pipeline)
                         } else {
-                            let hlsl_reflection = hlsl.reflect(index, semantics)?;
-                            let hlsl = hlsl.compile(Some(
-                                librashader_reflect::back::hlsl::HlslShaderModel::V6_0,
-                            ))?;
+                            let hlsl_reflection = cache_reflection(
+                                &mut hlsl,
+                                &source,
+                                optimization,
+                                index,
+                                semantics,
+                                cache,
+                            )?;
+                            let hlsl = cache_shader_output(
+                                &source,
+                                defines,
+                                optimization,
+                                hlsl,
+                                Some(librashader_reflect::back::hlsl::HlslShaderModel::V6_0),
+                                cache,
+                            )?;
 
                             let graphics_pipeline = D3D12GraphicsPipeline::new_from_hlsl(
                                 device,
@@ -514,7 +632,7 @@ impl FilterChainD3D12 {
                                 &hlsl,
                                 root_signature,
                                 render_format,
-                                disable_cache,
+                                cache,
                             )?;
                             (hlsl_reflection, graphics_pipeline)
                         };
@@ -623,6 +741,8 @@ impl FilterChainD3D12 {
         }
 
         let options = options.unwrap_or(&self.default_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
 
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled);
         let passes = &mut self.passes[0..max];
@@ -2,9 +2,8 @@ use crate::draw_quad::DrawQuad;
 use crate::error::assume_d3d12_init;
 use crate::error::FilterChainError::Direct3DOperationError;
 use crate::{error, util};
-use librashader_cache::{cache_pipeline, cache_shader_object};
+use librashader_cache::{cache_pipeline, cache_shader_object, ShaderCache};
 use librashader_reflect::back::dxil::DxilObject;
-use librashader_reflect::back::hlsl::CrossHlslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use std::mem::ManuallyDrop;
 use std::ops::Deref;
@@ -38,7 +37,7 @@ pub struct D3D12GraphicsPipeline {
     pub(crate) format: DXGI_FORMAT,
     vertex: Vec<u8>,
     fragment: Vec<u8>,
-    cache_disabled: bool,
+    cache: Option<ShaderCache>,
 }
 
 const D3D12_SLANG_ROOT_PARAMETERS: &[D3D12_ROOT_PARAMETER1; 4] = &[
@@ -155,7 +154,7 @@ impl D3D12GraphicsPipeline {
         fragment_dxil: IDxcBlob,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
     ) -> error::Result<D3D12GraphicsPipeline> {
         let input_element = DrawQuad::get_spirv_cross_vbo_desc();
 
@@ -250,7 +249,7 @@ impl D3D12GraphicsPipeline {
                     let cached_pso = pso.GetCachedBlob()?;
                     Ok(cached_pso)
                 },
-                disable_cache,
+                cache,
             )?;
 
             // cleanup handle
@@ -273,7 +272,7 @@ impl D3D12GraphicsPipeline {
                 format: render_format,
                 vertex,
                 fragment,
-                cache_disabled: disable_cache,
+                cache: cache.cloned(),
             })
         }
     }
@@ -304,7 +303,7 @@ impl D3D12GraphicsPipeline {
             fragment.cast()?,
             root_sig,
             format,
-            self.cache_disabled,
+            self.cache.as_ref(),
         )?;
 
         std::mem::swap(self, &mut new_pipeline);
@@ -318,7 +317,7 @@ impl D3D12GraphicsPipeline {
         shader_assembly: &ShaderCompilerOutput<DxilObject, ()>,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
     ) -> error::Result<D3D12GraphicsPipeline> {
         if shader_assembly.vertex.requires_runtime_data() {
             return Err(Direct3DOperationError(
@@ -336,7 +335,7 @@ impl D3D12GraphicsPipeline {
             &[shader_assembly.vertex.deref()],
             |&[source]| util::dxc_validate_shader(library, validator, source),
             |f| Ok(f),
-            disable_cache,
+            cache,
         )?;
 
         let fragment_dxil = cache_shader_object(
@@ -344,7 +343,7 @@ impl D3D12GraphicsPipeline {
             &[shader_assembly.fragment.deref()],
             |&[source]| util::dxc_validate_shader(library, validator, source),
             |f| Ok(f),
-            disable_cache,
+            cache,
         )?;
 
         Self::new_from_blobs(
@@ -353,7 +352,7 @@ impl D3D12GraphicsPipeline {
             fragment_dxil,
             root_signature,
             render_format,
-            disable_cache,
+            cache,
         )
     }
 
@@ -361,17 +360,17 @@ impl D3D12GraphicsPipeline {
         device: &ID3D12Device,
         library: &IDxcUtils,
         dxc: &IDxcCompiler,
-        shader_assembly: &ShaderCompilerOutput<String, CrossHlslContext>,
+        shader_assembly: &ShaderCompilerOutput<String>,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
     ) -> error::Result<D3D12GraphicsPipeline> {
         let vertex_dxil = cache_shader_object(
             "dxil",
             &[shader_assembly.vertex.as_bytes()],
             |&[source]| util::dxc_compile_shader(library, dxc, source, u16cstr!("vs_6_0")),
             |f| Ok(f),
-            disable_cache,
+            cache,
         )?;
 
         let fragment_dxil = cache_shader_object(
@@ -379,7 +378,7 @@ impl D3D12GraphicsPipeline {
             &[shader_assembly.fragment.as_bytes()],
             |&[source]| util::dxc_compile_shader(library, dxc, source, u16cstr!("ps_6_0")),
             |f| Ok(f),
-            disable_cache,
+            cache,
         )?;
 
         Self::new_from_blobs(
@@ -388,7 +387,7 @@ impl D3D12GraphicsPipeline {
             fragment_dxil,
             root_signature,
             render_format,
-            disable_cache,
+            cache,
         )
     }
 }
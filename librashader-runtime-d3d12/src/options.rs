@@ -1,10 +1,16 @@
 //! Direct3D 12 shader runtime options.
 
+use librashader_cache::ShaderCache;
+use librashader_common::fs::FileProvider;
+use librashader_preprocess::Defines;
+use librashader_reflect::front::SpirvOptimization;
+use librashader_runtime::hdr::HdrMode;
 use librashader_runtime::impl_default_frame_options;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
 impl_default_frame_options!(FrameOptionsD3D12);
 
 /// Options for Direct3D 12 filter chain creation.
-#[repr(C)]
 #[derive(Default, Debug, Clone)]
 pub struct FilterChainOptionsD3D12 {
     /// Force the HLSL shader pipeline. This may reduce shader compatibility.
@@ -18,4 +24,20 @@ pub struct FilterChainOptionsD3D12 {
     /// Disable the shader object cache. Shaders will be
     /// recompiled rather than loaded from the cache.
     pub disable_cache: bool,
+    /// The shader cache to use. If `None`, the default shader cache is used.
+    pub cache: Option<ShaderCache>,
+    /// The color space of the output. See [`HdrMode`].
+    pub hdr_mode: HdrMode,
+    /// Whether or not to append the built-in HDR output pass when HDR output is enabled.
+    /// See [`append_hdr_pass`](librashader_runtime::hdr::append_hdr_pass).
+    pub hdr_output_pass: bool,
+    /// The [`FileProvider`] to read files with. If `None`, files are read from the host filesystem.
+    pub file_provider: Option<Arc<dyn FileProvider>>,
+    /// The [`Defines`] to preprocess shader sources with.
+    pub defines: Defines,
+    /// The [`SpirvOptimization`] to apply to every shader pass, if any.
+    pub optimization: Option<SpirvOptimization>,
+    /// The maximum number of threads to compile shader passes with.
+    /// See [`PresetCompileOptions::max_threads`](librashader_reflect::reflect::presets::PresetCompileOptions::max_threads).
+    pub max_compile_threads: Option<NonZeroUsize>,
 }
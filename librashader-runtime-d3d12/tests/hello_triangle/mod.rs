@@ -296,6 +296,7 @@ pub mod d3d12_hello_triangle {
                     Some(
                         &librashader_runtime_d3d12::options::FilterChainOptionsD3D12 {
                             disable_cache: true,
+                            cache: None,
                             force_hlsl_pipeline: false,
                             force_no_mipmaps: false,
                             ..Default::default()
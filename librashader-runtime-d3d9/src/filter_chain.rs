@@ -8,27 +8,32 @@ use crate::options::{FilterChainOptionsD3D9, FrameOptionsD3D9};
 use crate::samplers::SamplerSet;
 use crate::texture::{D3D9InputTexture, D3D9Texture};
 use crate::{error, util};
-use librashader_cache::{cache_shader_object, CachedCompilation};
+use librashader_cache::{cache_reflection, cache_shader_object, CachedCompilation, ShaderCache};
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
 use librashader_common::{ImageFormat, Size, Viewport};
+use librashader_preprocess::Defines;
 use librashader_presets::context::VideoDriver;
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
 use librashader_reflect::back::hlsl::HlslShaderModel;
 use librashader_reflect::back::targets::HLSL;
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
 use librashader_reflect::reflect::cross::SpirvCross;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_reflect::reflect::semantics::ShaderSemantics;
-use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::{BindingUtil, TextureInput};
 use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
 use librashader_runtime::image::{Image, ImageError, UVDirection, ARGB8};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::scaling::ScaleFramebuffer;
 use librashader_runtime::uniforms::UniformStorage;
 use std::collections::VecDeque;
+use std::num::NonZeroUsize;
 
 use std::path::Path;
 
@@ -61,6 +66,7 @@ pub struct FilterChainD3D9 {
     feedback_framebuffers: Box<[D3D9Texture]>,
     history_framebuffers: VecDeque<D3D9Texture>,
     default_options: FrameOptionsD3D9,
+    hdr_mode: HdrMode,
 }
 
 type ShaderPassMeta =
@@ -69,17 +75,42 @@ type ShaderPassMeta =
 fn compile_passes(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
-    disable_cache: bool,
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) = if !disable_cache {
-        HLSL::compile_preset_passes::<
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        HLSL::compile_preset_passes_with_options::<
             CachedCompilation<SpirvCompilation>,
             SpirvCross,
             FilterChainError,
-        >(shaders, &textures)?
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
     } else {
-        HLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
+        HLSL::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?
     };
 
@@ -91,10 +122,12 @@ impl FilterChainD3D9 {
         device: &IDirect3DDevice9,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<Vec<FilterPass>> {
         let builder_fn = |(index, (config, source, mut reflect)): (usize, ShaderPassMeta)| {
-            let mut reflection = reflect.reflect(index, semantics)?;
+            let mut reflection =
+                cache_reflection(&mut reflect, &source, optimization, index, semantics, cache)?;
             let hlsl = reflect.compile(Some(HlslShaderModel::V3_0))?;
 
             // eprintln!("===vs===\n{}", hlsl.vertex);
@@ -109,7 +142,7 @@ impl FilterChainD3D9 {
                         blob,
                     ))
                 },
-                disable_cache,
+                cache,
             )?;
 
             // eprintln!("===ps===\n{}", hlsl.fragment);
@@ -124,7 +157,7 @@ impl FilterChainD3D9 {
                         blob,
                     ))
                 },
-                disable_cache,
+                cache,
             )?;
 
             let uniform_storage = UniformStorage::new(
@@ -176,11 +209,12 @@ impl FilterChainD3D9 {
     fn load_luts(
         device: &IDirect3DDevice9,
         textures: &[TextureConfig],
+        provider: &dyn FileProvider,
     ) -> error::Result<FastHashMap<usize, LutTexture>> {
         let mut luts = FastHashMap::default();
         let images = textures
             .iter()
-            .map(|texture| Image::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| Image::load_with_provider(&texture.path, UVDirection::TopLeft, provider))
             .collect::<Result<Vec<Image<ARGB8>>, ImageError>>()?;
 
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
@@ -199,7 +233,13 @@ impl FilterChainD3D9 {
         options: Option<&FilterChainOptionsD3D9>,
     ) -> error::Result<FilterChainD3D9> {
         // load passes from preset
-        let preset = ShaderPreset::try_parse_with_driver_context(path, VideoDriver::Direct3D11)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::Direct3D11);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
 
         unsafe { Self::load_from_preset(preset, device, options) }
     }
@@ -210,17 +250,46 @@ impl FilterChainD3D9 {
         device: &IDirect3DDevice9,
         options: Option<&FilterChainOptionsD3D9>,
     ) -> error::Result<FilterChainD3D9> {
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
+        let mut preset = preset;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        if hdr_mode.is_enabled() && options.is_some_and(|o| o.hdr_output_pass) {
+            hdr::append_hdr_pass(&mut preset, &defines, provider)?;
+        }
 
-        let (passes, semantics) = compile_passes(preset.shaders, &preset.textures, disable_cache)?;
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
+
+        let (passes, semantics) = compile_passes(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            provider,
+        )?;
 
         let samplers = SamplerSet::new()?;
 
         // initialize passes
-        let filters = FilterChainD3D9::init_passes(device, passes, &semantics, disable_cache)?;
+        let filters = FilterChainD3D9::init_passes(
+            device,
+            passes,
+            &semantics,
+            cache.as_ref(),
+            optimization.as_ref(),
+        )?;
 
         // load luts
-        let luts = FilterChainD3D9::load_luts(device, &preset.textures)?;
+        let luts = FilterChainD3D9::load_luts(device, &preset.textures, provider)?;
 
         let framebuffer_gen =
             || D3D9Texture::new(device, Size::new(1, 1), ImageFormat::R8G8B8A8Unorm, false);
@@ -267,6 +336,7 @@ impl FilterChainD3D9 {
                 draw_quad,
             },
             default_options: Default::default(),
+            hdr_mode,
         })
     }
 
@@ -305,6 +375,8 @@ impl FilterChainD3D9 {
         }
 
         let options = options.unwrap_or(&self.default_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
         let filter = passes[0].config.filter;
         let wrap_mode = passes[0].config.wrap_mode;
 
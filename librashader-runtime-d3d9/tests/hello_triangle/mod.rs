@@ -261,6 +261,13 @@ pub mod d3d9_hello_triangle {
                     Some(&FilterChainOptionsD3D9 {
                         force_no_mipmaps: false,
                         disable_cache: true,
+                        cache: None,
+                        hdr_mode: Default::default(),
+                        hdr_output_pass: false,
+                        file_provider: None,
+                        defines: Default::default(),
+                        optimization: None,
+                        max_compile_threads: None,
                     }),
                 )
                 .unwrap()
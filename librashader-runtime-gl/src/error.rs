@@ -4,6 +4,7 @@ use gl::types::GLenum;
 use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
+use librashader_runtime::framebuffer::FramebufferKind;
 use librashader_runtime::image::ImageError;
 use thiserror::Error;
 
@@ -30,6 +31,14 @@ pub enum FilterChainError {
     GLLinkError,
     #[error("opengl could not compile program")]
     GlCompileError,
+    #[error("framebuffer does not exist")]
+    InvalidFramebuffer(FramebufferKind),
+    #[error("framebuffer format can not be read back")]
+    UnsupportedReadbackFormat(GLenum),
+    #[error("framebuffer could not be read back")]
+    ReadbackError(ImageError),
+    #[error("copy target does not match the size or format of the framebuffer")]
+    MismatchedCopyTarget,
 }
 
 /// Result type for OpenGL filter chains.
@@ -9,27 +9,37 @@ use crate::samplers::SamplerSet;
 use crate::texture::InputTexture;
 use crate::util::{gl_get_version, gl_u16_to_version};
 use crate::{error, GLImage};
-use gl::types::GLuint;
-use librashader_common::Viewport;
+use gl::types::{GLenum, GLuint};
+use librashader_common::{Size, Viewport};
 
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_preprocess::Defines;
+use librashader_presets::context::VideoDriver;
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
 use librashader_reflect::back::glsl::GlslVersion;
 use librashader_reflect::back::targets::GLSL;
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
 use librashader_reflect::reflect::semantics::{ShaderSemantics, UniformMeta};
 
-use librashader_cache::CachedCompilation;
+use librashader_cache::{cache_reflection, CachedCompilation, ShaderCache};
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
 use librashader_reflect::reflect::cross::SpirvCross;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
-use librashader_reflect::reflect::ReflectShader;
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_runtime::binding::BindingUtil;
-use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::framebuffer::{FramebufferInfo, FramebufferInit, FramebufferKind};
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
+use librashader_runtime::image::Image;
 use librashader_runtime::quad::QuadType;
+use librashader_runtime::reload::PresetReload;
 use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::scaling::ScaleFramebuffer;
 use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Arc;
 
 pub(crate) struct FilterChainImpl<T: GLInterface> {
     pub(crate) common: FilterCommon,
@@ -39,6 +49,15 @@ pub(crate) struct FilterChainImpl<T: GLInterface> {
     feedback_framebuffers: Box<[GLFramebuffer]>,
     history_framebuffers: VecDeque<GLFramebuffer>,
     default_options: FrameOptionsGL,
+    hdr_mode: HdrMode,
+    hdr_output_pass: bool,
+    version: GlslVersion,
+    cache: Option<ShaderCache>,
+    textures: Box<[TextureConfig]>,
+    max_threads: Option<NonZeroUsize>,
+    defines: Defines,
+    optimization: Option<SpirvOptimization>,
+    file_provider: Arc<dyn FileProvider>,
 }
 
 pub(crate) struct FilterCommon {
@@ -97,17 +116,42 @@ type ShaderPassMeta =
 fn compile_passes(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
-    disable_cache: bool,
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) = if !disable_cache {
-        GLSL::compile_preset_passes::<
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        GLSL::compile_preset_passes_with_options::<
             CachedCompilation<SpirvCompilation>,
             SpirvCross,
             FilterChainError,
-        >(shaders, &textures)?
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
     } else {
-        GLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
+        GLSL::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?
     };
 
@@ -120,12 +164,42 @@ impl<T: GLInterface> FilterChainImpl<T> {
         preset: ShaderPreset,
         options: Option<&FilterChainOptionsGL>,
     ) -> error::Result<Self> {
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
-        let (passes, semantics) = compile_passes(preset.shaders, &preset.textures, disable_cache)?;
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
+        let mut preset = preset;
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let file_provider = options
+            .and_then(|o| o.file_provider.clone())
+            .unwrap_or_else(|| Arc::new(StdFileProvider));
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        let hdr_output_pass = options.is_some_and(|o| o.hdr_output_pass);
+        if hdr_mode.is_enabled() && hdr_output_pass {
+            hdr::append_hdr_pass(&mut preset, &defines, &*file_provider)?;
+        }
+
+        let (passes, semantics) = compile_passes(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            &*file_provider,
+        )?;
         let version = options.map_or_else(gl_get_version, |o| gl_u16_to_version(o.glsl_version));
 
         // initialize passes
-        let filters = Self::init_passes(version, passes, &semantics, disable_cache)?;
+        let filters = Self::init_passes(
+            version,
+            passes.into_iter().enumerate().collect(),
+            &semantics,
+            cache.as_ref(),
+            optimization.as_ref(),
+        )?;
 
         let default_filter = filters.first().map(|f| f.config.filter).unwrap_or_default();
         let default_wrap = filters
@@ -136,7 +210,7 @@ impl<T: GLInterface> FilterChainImpl<T> {
         let samplers = SamplerSet::new();
 
         // load luts
-        let luts = T::LoadLut::load_luts(&preset.textures)?;
+        let luts = T::LoadLut::load_luts(&preset.textures, &*file_provider)?;
 
         let framebuffer_gen = || Ok::<_, FilterChainError>(T::FramebufferInterface::new(1));
         let input_gen = || InputTexture {
@@ -188,23 +262,149 @@ impl<T: GLInterface> FilterChainImpl<T> {
                 history_textures,
             },
             default_options: Default::default(),
+            hdr_mode,
+            hdr_output_pass,
+            version,
+            cache,
+            textures: preset.textures.into_boxed_slice(),
+            max_threads,
+            defines,
+            optimization,
+            file_provider,
         })
     }
 
+    /// Reload the filter chain with the shader preset at the given path, reading it through
+    /// the file provider of the filter chain.
+    pub(crate) unsafe fn reload_from_path(&mut self, path: &Path) -> error::Result<()> {
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path);
+        context.add_video_driver_defaults(VideoDriver::GlCore);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, &*self.file_provider)?;
+        unsafe { self.reload(preset) }
+    }
+
+    /// Reload the filter chain with a pre-parsed `ShaderPreset`.
+    pub(crate) unsafe fn reload(&mut self, preset: ShaderPreset) -> error::Result<()> {
+        let mut preset = preset;
+        if self.hdr_mode.is_enabled() && self.hdr_output_pass {
+            hdr::append_hdr_pass(&mut preset, &self.defines, &*self.file_provider)?;
+        }
+
+        let reload = PresetReload::new(
+            &preset,
+            self.passes.iter().map(|pass| (&pass.config, &pass.source)),
+            &self.textures,
+            &self.defines,
+            &*self.file_provider,
+        )?;
+
+        let changed: Vec<usize> = reload.changed_passes().collect();
+        let (passes, _) = compile_passes(
+            changed
+                .iter()
+                .map(|&index| preset.shaders[index].clone())
+                .collect(),
+            &preset.textures,
+            self.cache.as_ref(),
+            self.max_threads,
+            &self.defines,
+            self.optimization.as_ref(),
+            &*self.file_provider,
+        )?;
+        let compiled = Self::init_passes(
+            self.version,
+            changed.into_iter().zip(passes).collect(),
+            &reload.semantics,
+            self.cache.as_ref(),
+            self.optimization.as_ref(),
+        )?;
+
+        let textures: Vec<TextureConfig> = reload
+            .changed_luts()
+            .map(|index| preset.textures[index].clone())
+            .collect();
+        let loaded = T::LoadLut::load_luts(&textures, &*self.file_provider)?;
+
+        let (passes, replaced) = reload.merge_passes(std::mem::take(&mut self.passes), compiled);
+        self.passes = passes;
+        for pass in replaced {
+            unsafe { gl::DeleteProgram(pass.program) }
+        }
+
+        let mut luts = std::mem::take(&mut self.common.luts);
+        self.common.luts = reload.merge_luts(&mut luts, loaded);
+        for lut in luts.values() {
+            unsafe { gl::DeleteTextures(1, &lut.image.handle) }
+        }
+
+        let default_filter = self
+            .passes
+            .first()
+            .map(|f| f.config.filter)
+            .unwrap_or_default();
+        let default_wrap = self
+            .passes
+            .first()
+            .map(|f| f.config.wrap_mode)
+            .unwrap_or_default();
+
+        let framebuffer_gen = || Ok::<_, FilterChainError>(T::FramebufferInterface::new(1));
+        let input_gen = || InputTexture {
+            image: Default::default(),
+            filter: default_filter,
+            mip_filter: default_filter,
+            wrap_mode: default_wrap,
+        };
+
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init
+            .resize_output_framebuffers(std::mem::take(&mut self.output_framebuffers))?;
+        let (feedback_framebuffers, feedback_textures) = framebuffer_init
+            .resize_output_framebuffers(std::mem::take(&mut self.feedback_framebuffers))?;
+        let (history_framebuffers, history_textures) =
+            framebuffer_init.resize_history(std::mem::take(&mut self.history_framebuffers))?;
+
+        self.output_framebuffers = output_framebuffers;
+        self.feedback_framebuffers = feedback_framebuffers;
+        self.history_framebuffers = history_framebuffers;
+        self.common.output_textures = output_textures;
+        self.common.feedback_textures = feedback_textures;
+        self.common.history_textures = history_textures;
+
+        self.common.config = FilterMutable {
+            passes_enabled: preset.shader_count as usize,
+            parameters: preset
+                .parameters
+                .into_iter()
+                .map(|param| (param.name, param.value))
+                .collect(),
+        };
+        self.textures = preset.textures.into_boxed_slice();
+        Ok(())
+    }
+
     fn init_passes(
         version: GlslVersion,
-        passes: Vec<ShaderPassMeta>,
+        passes: Vec<(usize, ShaderPassMeta)>,
         semantics: &ShaderSemantics,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<Box<[FilterPass<T>]>> {
         let mut filters = Vec::new();
 
         // initialize passes
-        for (index, (config, source, mut reflect)) in passes.into_iter().enumerate() {
-            let reflection = reflect.reflect(index, semantics)?;
+        for (index, (config, source, mut reflect)) in passes {
+            let reflection =
+                cache_reflection(&mut reflect, &source, optimization, index, semantics, cache)?;
             let glsl = reflect.compile(version)?;
 
-            let (program, ubo_location) = T::CompileShader::compile_program(glsl, !disable_cache)?;
+            let (program, ubo_location) = T::CompileShader::compile_program(glsl, cache)?;
 
             let ubo_ring = if let Some(ubo) = &reflection.ubo {
                 let ring = UboRing::new(ubo.size);
@@ -283,6 +483,8 @@ impl<T: GLInterface> FilterChainImpl<T> {
             return Ok(());
         }
         let options = options.unwrap_or(&self.default_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
 
         // do not need to rebind FBO 0 here since first `draw` will
         // bind automatically.
@@ -398,3 +600,97 @@ impl<T: GLInterface> FilterChainImpl<T> {
         Ok(())
     }
 }
+
+/// Whether or not the internal format is an integer format, which can not be read back as
+/// normalized RGBA8.
+fn is_integer_format(format: GLenum) -> bool {
+    matches!(
+        format,
+        gl::R8UI
+            | gl::R8I
+            | gl::RG8UI
+            | gl::RG8I
+            | gl::RGBA8UI
+            | gl::RGBA8I
+            | gl::RGB10_A2UI
+            | gl::R16UI
+            | gl::R16I
+            | gl::RG16UI
+            | gl::RG16I
+            | gl::RGBA16UI
+            | gl::RGBA16I
+            | gl::R32UI
+            | gl::R32I
+            | gl::RG32UI
+            | gl::RG32I
+            | gl::RGBA32UI
+            | gl::RGBA32I
+    )
+}
+
+impl<T: GLInterface> FilterChainImpl<T> {
+    pub fn framebuffer_info(
+        &self,
+        framebuffer: FramebufferKind,
+    ) -> Option<FramebufferInfo<GLenum>> {
+        let fb = framebuffer.select(
+            &self.output_framebuffers,
+            &self.feedback_framebuffers,
+            &self.history_framebuffers,
+        )?;
+
+        Some(FramebufferInfo {
+            size: fb.size,
+            format: fb.format,
+            mip_levels: fb.mip_levels,
+        })
+    }
+
+    fn framebuffer(&self, framebuffer: FramebufferKind) -> error::Result<&GLFramebuffer> {
+        framebuffer
+            .select(
+                &self.output_framebuffers,
+                &self.feedback_framebuffers,
+                &self.history_framebuffers,
+            )
+            .ok_or(FilterChainError::InvalidFramebuffer(framebuffer))
+    }
+
+    pub unsafe fn copy_framebuffer(
+        &self,
+        framebuffer: FramebufferKind,
+        target: &GLFramebuffer,
+    ) -> error::Result<()> {
+        let fb = self.framebuffer(framebuffer)?;
+        if target.size != fb.size || target.format != fb.format {
+            return Err(FilterChainError::MismatchedCopyTarget);
+        }
+
+        T::FramebufferInterface::copy_to(fb, target);
+        Ok(())
+    }
+
+    pub unsafe fn read_framebuffer(&self, framebuffer: FramebufferKind) -> error::Result<Image> {
+        let fb = self.framebuffer(framebuffer)?;
+        if is_integer_format(fb.format) {
+            return Err(FilterChainError::UnsupportedReadbackFormat(fb.format));
+        }
+
+        // the framebuffer texture has not been created yet.
+        if fb.image == 0 || fb.size.width == 0 || fb.size.height == 0 {
+            return Image::from_rgba8(Vec::new(), Size::new(0, 0))
+                .map_err(FilterChainError::ReadbackError);
+        }
+
+        // OpenGL images start at the bottom left, so flip rows to start at the top left.
+        let pixels = T::FramebufferInterface::read_pixels(fb);
+        let pixels = pixels
+            .chunks_exact(fb.size.width as usize * 4)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        Image::from_rgba8(pixels, fb.size).map_err(FilterChainError::ReadbackError)
+    }
+}
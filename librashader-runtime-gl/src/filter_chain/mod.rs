@@ -1,4 +1,4 @@
-use std::panic::catch_unwind;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::Path;
 
 use crate::error::{FilterChainError, Result};
@@ -6,15 +6,19 @@ use crate::filter_chain::filter_impl::FilterChainImpl;
 use crate::filter_chain::inner::FilterChainDispatch;
 use crate::options::{FilterChainOptionsGL, FrameOptionsGL};
 use crate::{GLFramebuffer, GLImage};
-use librashader_presets::ShaderPreset;
+use librashader_common::fs::StdFileProvider;
+use librashader_presets::{ShaderPreset, WildcardContext};
 
 mod filter_impl;
 mod inner;
 mod parameters;
 
 pub(crate) use filter_impl::FilterCommon;
+use gl::types::GLenum;
 use librashader_common::Viewport;
 use librashader_presets::context::VideoDriver;
+use librashader_runtime::framebuffer::{FramebufferInfo, FramebufferKind};
+use librashader_runtime::image::Image;
 
 /// An OpenGL filter chain.
 pub struct FilterChainGL {
@@ -27,7 +31,8 @@ impl FilterChainGL {
         preset: ShaderPreset,
         options: Option<&FilterChainOptionsGL>,
     ) -> Result<Self> {
-        let result = catch_unwind(|| {
+        // The options are only read, and nothing is kept if loading panics.
+        let result = catch_unwind(AssertUnwindSafe(|| {
             if let Some(options) = options
                 && options.use_dsa
             {
@@ -42,7 +47,7 @@ impl FilterChainGL {
                     FilterChainImpl::load_from_preset(preset, options)?
                 }),
             })
-        });
+        }));
         result.unwrap_or_else(|_| Err(FilterChainError::GLLoadError))
     }
 
@@ -52,10 +57,39 @@ impl FilterChainGL {
         options: Option<&FilterChainOptionsGL>,
     ) -> Result<Self> {
         // load passes from preset
-        let preset = ShaderPreset::try_parse_with_driver_context(path, VideoDriver::GlCore)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::GlCore);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
         unsafe { Self::load_from_preset(preset, options) }
     }
 
+    /// Reload the filter chain with a pre-parsed `ShaderPreset`.
+    ///
+    /// Only passes whose source or configuration changed are recompiled. Lookup textures
+    /// with an unchanged path are kept, as are the history and feedback framebuffers.
+    /// If the new preset fails to compile, the filter chain is left unchanged.
+    pub unsafe fn reload(&mut self, preset: ShaderPreset) -> Result<()> {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => unsafe { p.reload(preset) },
+            FilterChainDispatch::Compatibility(p) => unsafe { p.reload(preset) },
+        }
+    }
+
+    /// Reload the filter chain with the shader preset at the given path.
+    ///
+    /// See [`reload`](Self::reload) for the parts of the filter chain that are kept.
+    pub unsafe fn reload_from_path(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => unsafe { p.reload_from_path(path) },
+            FilterChainDispatch::Compatibility(p) => unsafe { p.reload_from_path(path) },
+        }
+    }
+
     /// Process a frame with the input image.
     ///
     /// When this frame returns, `GL_FRAMEBUFFER` is bound to 0 if not using Direct State Access.
@@ -76,4 +110,49 @@ impl FilterChainGL {
             },
         }
     }
+
+    /// Get the current size and internal format of a framebuffer, or `None` if it does not exist.
+    pub fn framebuffer_info(
+        &self,
+        framebuffer: FramebufferKind,
+    ) -> Option<FramebufferInfo<GLenum>> {
+        match &self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.framebuffer_info(framebuffer),
+            FilterChainDispatch::Compatibility(p) => p.framebuffer_info(framebuffer),
+        }
+    }
+
+    /// Copy the first mip level of a framebuffer into the target framebuffer.
+    ///
+    /// The target must have the same size and internal format as the framebuffer, and is
+    /// drawn to through its first color attachment.
+    ///
+    /// When this function returns, `GL_FRAMEBUFFER` is bound to 0 if not using Direct State Access.
+    /// Otherwise, it is untouched.
+    pub unsafe fn copy_framebuffer(
+        &self,
+        framebuffer: FramebufferKind,
+        target: &GLFramebuffer,
+    ) -> Result<()> {
+        match &self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => unsafe {
+                p.copy_framebuffer(framebuffer, target)
+            },
+            FilterChainDispatch::Compatibility(p) => unsafe {
+                p.copy_framebuffer(framebuffer, target)
+            },
+        }
+    }
+
+    /// Read the first mip level of a framebuffer back into an image, with the origin
+    /// at the top left.
+    ///
+    /// Framebuffers with integer formats can not be read back.
+    /// When this function returns, `GL_PIXEL_PACK_BUFFER` is bound to 0.
+    pub unsafe fn read_framebuffer(&self, framebuffer: FramebufferKind) -> Result<Image> {
+        match &self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => unsafe { p.read_framebuffer(framebuffer) },
+            FilterChainDispatch::Compatibility(p) => unsafe { p.read_framebuffer(framebuffer) },
+        }
+    }
 }
@@ -184,6 +184,15 @@ impl<T: GLInterface> FilterPass<T> {
                 frame_direction: options.frame_direction,
                 framebuffer_size: fb_size,
                 viewport_size: viewport.output.size,
+                aspect_ratio: options.aspect_ratio,
+                frames_per_second: options.frames_per_second,
+                frametime_delta: options.frametime_delta,
+                enable_hdr: options.enable_hdr,
+                paper_white_nits: options.paper_white_nits,
+                max_nits: options.max_nits,
+                expand_gamut: options.expand_gamut,
+                inverse_tonemap: options.inverse_tonemap,
+                hdr10: options.hdr10,
             },
             original,
             source,
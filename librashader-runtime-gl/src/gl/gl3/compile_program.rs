@@ -3,6 +3,7 @@ use crate::error::FilterChainError;
 use crate::gl::CompileProgram;
 use crate::util;
 use gl::types::{GLint, GLuint};
+use librashader_cache::ShaderCache;
 use librashader_reflect::back::glsl::CrossGlslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use spirv_cross::spirv::Decoration;
@@ -12,7 +13,7 @@ pub struct Gl3CompileProgram;
 impl CompileProgram for Gl3CompileProgram {
     fn compile_program(
         glsl: ShaderCompilerOutput<String, CrossGlslContext>,
-        _cache: bool,
+        _cache: Option<&ShaderCache>,
     ) -> crate::error::Result<(GLuint, UniformLocation<GLuint>)> {
         let vertex_resources = glsl.context.artifact.vertex.get_shader_resources()?;
 
@@ -150,6 +150,55 @@ impl FramebufferInterface for Gl3Framebuffer {
 
         Ok(())
     }
+    fn copy_to(fb: &GLFramebuffer, target: &GLFramebuffer) {
+        unsafe {
+            // the framebuffer object may not have the image attached, so read through a temporary one.
+            let mut read_fbo = 0;
+            gl::GenFramebuffers(1, &mut read_fbo);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, read_fbo);
+            gl::FramebufferTexture2D(
+                gl::READ_FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                fb.image,
+                0,
+            );
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.fbo);
+
+            gl::BlitFramebuffer(
+                0,
+                0,
+                fb.size.width as GLint,
+                fb.size.height as GLint,
+                0,
+                0,
+                fb.size.width as GLint,
+                fb.size.height as GLint,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteFramebuffers(1, &read_fbo);
+        }
+    }
+    fn read_pixels(fb: &GLFramebuffer) -> Vec<u8> {
+        let mut pixels = vec![0u8; fb.size.width as usize * fb.size.height as usize * 4];
+        unsafe {
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+            gl::BindTexture(gl::TEXTURE_2D, fb.image);
+            gl::GetTexImage(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr().cast(),
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        pixels
+    }
     fn init(fb: &mut GLFramebuffer, mut size: Size<u32>, format: impl Into<GLenum>) -> Result<()> {
         if fb.is_raw {
             return Ok(());
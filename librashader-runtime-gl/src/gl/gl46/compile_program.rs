@@ -3,7 +3,7 @@ use crate::error::FilterChainError;
 use crate::gl::CompileProgram;
 use crate::util;
 use gl::types::{GLint, GLsizei, GLuint};
-use librashader_cache::Cacheable;
+use librashader_cache::{Cacheable, ShaderCache};
 use librashader_reflect::back::glsl::CrossGlslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use spirv_cross::spirv::Decoration;
@@ -43,7 +43,7 @@ impl Cacheable for GlProgramBinary {
 impl CompileProgram for Gl4CompileProgram {
     fn compile_program(
         glsl: ShaderCompilerOutput<String, CrossGlslContext>,
-        cache: bool,
+        cache: Option<&ShaderCache>,
     ) -> crate::error::Result<(GLuint, UniformLocation<GLuint>)> {
         let vertex_resources = glsl.context.artifact.vertex.get_shader_resources()?;
 
@@ -120,7 +120,7 @@ impl CompileProgram for Gl4CompileProgram {
                 }
                 return Ok(program);
             },
-            !cache,
+            cache,
         )?;
 
         let ubo_location = unsafe {
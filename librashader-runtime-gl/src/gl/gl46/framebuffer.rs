@@ -113,6 +113,47 @@ impl FramebufferInterface for Gl46Framebuffer {
 
         Ok(())
     }
+    fn copy_to(fb: &GLFramebuffer, target: &GLFramebuffer) {
+        unsafe {
+            // the framebuffer object may not have the image attached, so read through a temporary one.
+            let mut read_fbo = 0;
+            gl::CreateFramebuffers(1, &mut read_fbo);
+            gl::NamedFramebufferTexture(read_fbo, gl::COLOR_ATTACHMENT0, fb.image, 0);
+            gl::NamedFramebufferReadBuffer(read_fbo, gl::COLOR_ATTACHMENT0);
+
+            gl::BlitNamedFramebuffer(
+                read_fbo,
+                target.fbo,
+                0,
+                0,
+                fb.size.width as GLint,
+                fb.size.height as GLint,
+                0,
+                0,
+                fb.size.width as GLint,
+                fb.size.height as GLint,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+
+            gl::DeleteFramebuffers(1, &read_fbo);
+        }
+    }
+    fn read_pixels(fb: &GLFramebuffer) -> Vec<u8> {
+        let mut pixels = vec![0u8; fb.size.width as usize * fb.size.height as usize * 4];
+        unsafe {
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+            gl::GetTextureImage(
+                fb.image,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.len() as GLsizei,
+                pixels.as_mut_ptr().cast(),
+            );
+        }
+        pixels
+    }
     fn init(fb: &mut GLFramebuffer, mut size: Size<u32>, format: impl Into<GLenum>) -> Result<()> {
         if fb.is_raw {
             return Ok(());
@@ -187,4 +228,4 @@ impl FramebufferInterface for Gl46Framebuffer {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -3,6 +3,7 @@ use crate::framebuffer::GLImage;
 use crate::gl::LoadLut;
 use crate::texture::InputTexture;
 use gl::types::{GLsizei, GLuint};
+use librashader_common::fs::FileProvider;
 use librashader_common::map::FastHashMap;
 use librashader_presets::TextureConfig;
 use librashader_runtime::image::{Image, ImageError, UVDirection};
@@ -11,7 +12,10 @@ use rayon::prelude::*;
 
 pub struct Gl46LutLoad;
 impl LoadLut for Gl46LutLoad {
-    fn load_luts(textures: &[TextureConfig]) -> Result<FastHashMap<usize, InputTexture>> {
+    fn load_luts(
+        textures: &[TextureConfig],
+        provider: &dyn FileProvider,
+    ) -> Result<FastHashMap<usize, InputTexture>> {
         let mut luts = FastHashMap::default();
         let pixel_unpack = unsafe {
             let mut binding = 0;
@@ -25,7 +29,7 @@ impl LoadLut for Gl46LutLoad {
 
         let images = textures
             .par_iter()
-            .map(|texture| Image::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| Image::load_with_provider(&texture.path, UVDirection::TopLeft, provider))
             .collect::<std::result::Result<Vec<Image>, ImageError>>()?;
 
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
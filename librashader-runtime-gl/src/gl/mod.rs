@@ -9,6 +9,8 @@ use crate::samplers::SamplerSet;
 use crate::texture::InputTexture;
 pub use framebuffer::GLFramebuffer;
 use gl::types::{GLenum, GLuint};
+use librashader_cache::ShaderCache;
+use librashader_common::fs::FileProvider;
 use librashader_common::map::FastHashMap;
 use librashader_common::{ImageFormat, Size};
 use librashader_presets::{Scale2D, TextureConfig};
@@ -57,13 +59,16 @@ static FINAL_VBO_DATA: &[VertexInput; 4] = &[
 ];
 
 pub(crate) trait LoadLut {
-    fn load_luts(textures: &[TextureConfig]) -> Result<FastHashMap<usize, InputTexture>>;
+    fn load_luts(
+        textures: &[TextureConfig],
+        provider: &dyn FileProvider,
+    ) -> Result<FastHashMap<usize, InputTexture>>;
 }
 
 pub(crate) trait CompileProgram {
     fn compile_program(
         shader: ShaderCompilerOutput<String, CrossGlslContext>,
-        cache: bool,
+        cache: Option<&ShaderCache>,
     ) -> Result<(GLuint, UniformLocation<GLuint>)>;
 }
 
@@ -96,6 +101,8 @@ pub(crate) trait FramebufferInterface {
     ) -> Result<Size<u32>>;
     fn clear<const REBIND: bool>(fb: &GLFramebuffer);
     fn copy_from(fb: &mut GLFramebuffer, image: &GLImage) -> Result<()>;
+    fn copy_to(fb: &GLFramebuffer, target: &GLFramebuffer);
+    fn read_pixels(fb: &GLFramebuffer) -> Vec<u8>;
     fn init(fb: &mut GLFramebuffer, size: Size<u32>, format: impl Into<GLenum>) -> Result<()>;
 }
 
@@ -15,6 +15,8 @@ fn triangle_gl() {
                 use_dsa: false,
                 force_no_mipmaps: false,
                 disable_cache: false,
+                cache: None,
+                ..Default::default()
             }),
         )
         // FilterChain::load_from_path("../test/slang-shaders/bezel/Mega_Bezel/Presets/MBZ__0__SMOOTH-ADV.slangp", None)
@@ -37,6 +39,8 @@ fn triangle_gl46() {
                 use_dsa: true,
                 force_no_mipmaps: false,
                 disable_cache: false,
+                cache: None,
+                ..Default::default()
             }),
         )
         // FilterChain::load_from_path("../test/slang-shaders/bezel/Mega_Bezel/Presets/MBZ__0__SMOOTH-ADV.slangp", None)
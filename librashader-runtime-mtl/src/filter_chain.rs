@@ -14,20 +14,25 @@ use icrate::Metal::{
     MTLPixelFormat, MTLPixelFormatRGBA8Unorm, MTLRenderPassDescriptor, MTLResource,
     MTLStoreActionDontCare, MTLStoreActionStore, MTLTexture,
 };
+use librashader_cache::{cache_reflection, cache_shader_output, CachedCompilation, ShaderCache};
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
 use librashader_common::{ImageFormat, Size, Viewport};
+use librashader_preprocess::Defines;
 use librashader_presets::context::VideoDriver;
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
-use librashader_reflect::back::msl::MslVersion;
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
+use librashader_reflect::back::msl::{CrossMslContext, MslVersion};
 use librashader_reflect::back::targets::MSL;
-use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
+use librashader_reflect::back::CompileReflectShader;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
 use librashader_reflect::reflect::cross::SpirvCross;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_reflect::reflect::semantics::ShaderSemantics;
-use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
 use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
 use librashader_runtime::image::{Image, ImageError, UVDirection, BGRA8};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
@@ -38,6 +43,7 @@ use objc2::runtime::ProtocolObject;
 use rayon::prelude::*;
 use std::collections::VecDeque;
 use std::fmt::{Debug, Formatter};
+use std::num::NonZeroUsize;
 use std::path::Path;
 
 type ShaderPassMeta =
@@ -45,11 +51,44 @@ type ShaderPassMeta =
 fn compile_passes(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) =
-        MSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
-        )?;
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        MSL::compile_preset_passes_with_options::<
+            CachedCompilation<SpirvCompilation>,
+            SpirvCross,
+            FilterChainError,
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
+    } else {
+        MSL::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
+        )?
+    };
     Ok((passes, semantics))
 }
 
@@ -62,6 +101,7 @@ pub struct FilterChainMetal {
     history_framebuffers: VecDeque<OwnedTexture>,
     disable_mipmaps: bool,
     default_options: FrameOptionsMetal,
+    hdr_mode: HdrMode,
 }
 
 impl Debug for FilterChainMetal {
@@ -95,7 +135,13 @@ impl FilterChainMetal {
         options: Option<&FilterChainOptionsMetal>,
     ) -> error::Result<FilterChainMetal> {
         // load passes from preset
-        let preset = ShaderPreset::try_parse_with_driver_context(path, VideoDriver::Metal)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::Metal);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
         Self::load_from_preset(preset, queue, options)
     }
 
@@ -122,6 +168,7 @@ impl FilterChainMetal {
         device: &ProtocolObject<dyn MTLDevice>,
         cmd: &ProtocolObject<dyn MTLCommandBuffer>,
         textures: &[TextureConfig],
+        provider: &dyn FileProvider,
     ) -> error::Result<FastHashMap<usize, LutTexture>> {
         let mut luts = FastHashMap::default();
 
@@ -131,7 +178,9 @@ impl FilterChainMetal {
 
         let images = textures
             .par_iter()
-            .map(|texture| Image::<BGRA8>::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| {
+                Image::<BGRA8>::load_with_provider(&texture.path, UVDirection::TopLeft, provider)
+            })
             .collect::<Result<Vec<Image<BGRA8>>, ImageError>>()?;
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
             let texture = LutTexture::new(device, image, texture, &mipmapper)?;
@@ -146,14 +195,25 @@ impl FilterChainMetal {
         device: &Id<ProtocolObject<dyn MTLDevice>>,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
+        cache: Option<&ShaderCache>,
+        defines: &Defines,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<Box<[FilterPass]>> {
         // todo: fix this to allow send
         let filters: Vec<error::Result<FilterPass>> = passes
             .into_iter()
             .enumerate()
             .map(|(index, (config, source, mut reflect))| {
-                let reflection = reflect.reflect(index, semantics)?;
-                let msl = reflect.compile(Some(MslVersion::V2_0))?;
+                let reflection =
+                    cache_reflection(&mut reflect, &source, optimization, index, semantics, cache)?;
+                let msl = cache_shader_output(
+                    &source,
+                    defines,
+                    optimization,
+                    reflect,
+                    Some(MslVersion::V2_0),
+                    cache,
+                )?;
 
                 let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
                 let push_size = reflection
@@ -175,7 +235,7 @@ impl FilterChainMetal {
                         source.format.into()
                     };
 
-                let graphics_pipeline = MetalGraphicsPipeline::new(
+                let graphics_pipeline = MetalGraphicsPipeline::new::<CrossMslContext, _>(
                     &device,
                     &msl,
                     if render_pass_format == 0 {
@@ -262,12 +322,43 @@ impl FilterChainMetal {
         cmd: &ProtocolObject<dyn MTLCommandBuffer>,
         options: Option<&FilterChainOptionsMetal>,
     ) -> error::Result<FilterChainMetal> {
-        let (passes, semantics) = compile_passes(preset.shaders, &preset.textures)?;
+        let mut preset = preset;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        if hdr_mode.is_enabled() && options.is_some_and(|o| o.hdr_output_pass) {
+            hdr::append_hdr_pass(&mut preset, &defines, provider)?;
+        }
+
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
+        let (passes, semantics) = compile_passes(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            provider,
+        )?;
 
-        let filters = Self::init_passes(&device, passes, &semantics)?;
+        let filters = Self::init_passes(
+            &device,
+            passes,
+            &semantics,
+            cache.as_ref(),
+            &defines,
+            optimization.as_ref(),
+        )?;
 
         let samplers = SamplerSet::new(&device)?;
-        let luts = FilterChainMetal::load_luts(&device, &cmd, &preset.textures)?;
+        let luts = FilterChainMetal::load_luts(&device, &cmd, &preset.textures, provider)?;
         let framebuffer_gen = || {
             Ok::<_, error::FilterChainError>(OwnedTexture::new(
                 &device,
@@ -317,6 +408,7 @@ impl FilterChainMetal {
             history_framebuffers,
             disable_mipmaps: options.map(|f| f.force_no_mipmaps).unwrap_or(false),
             default_options: Default::default(),
+            hdr_mode,
         })
     }
 
@@ -428,6 +520,8 @@ impl FilterChainMetal {
         let passes_len = passes.len();
         let (pass, last) = passes.split_at_mut(passes_len - 1);
         let options = options.unwrap_or(&self.default_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
 
         for (index, pass) in pass.iter_mut().enumerate() {
             let target = &self.output_framebuffers[index];
@@ -52,8 +52,8 @@ impl MslEntryPoint for NagaMslContext {
 }
 
 impl PipelineLayoutObjects {
-    pub fn new<T: MslEntryPoint>(
-        shader_assembly: &ShaderCompilerOutput<String, T>,
+    pub fn new<T: MslEntryPoint, C>(
+        shader_assembly: &ShaderCompilerOutput<String, C>,
         device: &ProtocolObject<dyn MTLDevice>,
     ) -> Result<Self> {
         let entry = T::entry_point();
@@ -148,12 +148,12 @@ impl PipelineLayoutObjects {
 }
 
 impl MetalGraphicsPipeline {
-    pub fn new<T: MslEntryPoint>(
+    pub fn new<T: MslEntryPoint, C>(
         device: &ProtocolObject<dyn MTLDevice>,
-        shader_assembly: &ShaderCompilerOutput<String, T>,
+        shader_assembly: &ShaderCompilerOutput<String, C>,
         render_pass_format: MTLPixelFormat,
     ) -> Result<Self> {
-        let layout = PipelineLayoutObjects::new(shader_assembly, device)?;
+        let layout = PipelineLayoutObjects::new::<T, C>(shader_assembly, device)?;
         let pipeline = layout.create_pipeline(device, render_pass_format)?;
         Ok(Self {
             layout,
@@ -3,6 +3,7 @@ use gpu_allocator::AllocationError;
 use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
+use librashader_runtime::framebuffer::FramebufferKind;
 use librashader_runtime::image::ImageError;
 use std::convert::Infallible;
 use thiserror::Error;
@@ -28,6 +29,14 @@ pub enum FilterChainError {
     AllocationError(#[from] AllocationError),
     #[error("allocation is already freed")]
     AllocationDoesNotExist,
+    #[error("framebuffer does not exist")]
+    InvalidFramebuffer(FramebufferKind),
+    #[error("framebuffer format can not be read back")]
+    UnsupportedReadbackFormat(ash::vk::Format),
+    #[error("framebuffer could not be read back")]
+    ReadbackError(ImageError),
+    #[error("copy target does not match the size or format of the framebuffer")]
+    MismatchedCopyTarget,
 }
 
 impl From<Infallible> for FilterChainError {
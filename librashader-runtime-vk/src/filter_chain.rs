@@ -4,7 +4,7 @@ use crate::filter_pass::FilterPass;
 use crate::framebuffer::OutputImage;
 use crate::graphics_pipeline::VulkanGraphicsPipeline;
 use crate::luts::LutTexture;
-use crate::memory::RawVulkanBuffer;
+use crate::memory::{RawVulkanBuffer, VulkanBuffer};
 use crate::options::{FilterChainOptionsVulkan, FrameOptionsVulkan};
 use crate::queue_selection::get_graphics_queue;
 use crate::samplers::SamplerSet;
@@ -14,28 +14,34 @@ use ash::vk;
 use librashader_common::{ImageFormat, Size, Viewport};
 
 use gpu_allocator::vulkan::Allocator;
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_preprocess::Defines;
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
 use librashader_reflect::back::targets::SPIRV;
-use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::back::CompileReflectShader;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_reflect::reflect::semantics::ShaderSemantics;
-use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
-use librashader_runtime::image::{Image, ImageError, UVDirection, BGRA8};
+use librashader_runtime::image::{pixel_size, Image, ImageError, PixelFormat, UVDirection, BGRA8};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::uniforms::UniformStorage;
 use parking_lot::Mutex;
 use std::collections::VecDeque;
 use std::convert::Infallible;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::sync::Arc;
 
-use librashader_cache::CachedCompilation;
+use librashader_cache::{cache_reflection, cache_shader_output, CachedCompilation, ShaderCache};
 use librashader_presets::context::VideoDriver;
 use librashader_reflect::reflect::cross::SpirvCross;
-use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::framebuffer::{FramebufferInfo, FramebufferInit, FramebufferKind};
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
+use librashader_runtime::reload::PresetReload;
 use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::scaling::ScaleFramebuffer;
 use rayon::prelude::*;
@@ -126,6 +132,15 @@ pub struct FilterChainVulkan {
     disable_mipmaps: bool,
     residuals: Box<[FrameResiduals]>,
     default_options: FrameOptionsVulkan,
+    hdr_mode: HdrMode,
+    hdr_output_pass: bool,
+    use_dynamic_rendering: bool,
+    cache: Option<ShaderCache>,
+    textures: Box<[TextureConfig]>,
+    max_threads: Option<NonZeroUsize>,
+    defines: Defines,
+    optimization: Option<SpirvOptimization>,
+    file_provider: Arc<dyn FileProvider>,
 }
 
 pub struct FilterMutable {
@@ -214,17 +229,42 @@ type ShaderPassMeta =
 fn compile_passes(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
-    disable_cache: bool,
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-    let (passes, semantics) = if !disable_cache {
-        SPIRV::compile_preset_passes::<
+    let provider = BuiltinFileProvider::new(provider);
+    let (passes, semantics) = if let Some(cache) = cache {
+        SPIRV::compile_preset_passes_with_options::<
             CachedCompilation<SpirvCompilation>,
             SpirvCross,
             FilterChainError,
-        >(shaders, &textures)?
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        )?
     } else {
-        SPIRV::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
-            shaders, &textures,
+        SPIRV::compile_preset_passes_with_options::<SpirvCompilation, SpirvCross, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?
     };
 
@@ -243,7 +283,13 @@ impl FilterChainVulkan {
         FilterChainError: From<E>,
     {
         // load passes from preset
-        let preset = ShaderPreset::try_parse_with_driver_context(path, VideoDriver::Vulkan)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::Vulkan);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
 
         unsafe { Self::load_from_preset(preset, vulkan, options) }
     }
@@ -329,8 +375,32 @@ impl FilterChainVulkan {
         V: TryInto<VulkanObjects, Error = E>,
         FilterChainError: From<E>,
     {
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
-        let (passes, semantics) = compile_passes(preset.shaders, &preset.textures, disable_cache)?;
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
+        let mut preset = preset;
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let file_provider = options
+            .and_then(|o| o.file_provider.clone())
+            .unwrap_or_else(|| Arc::new(StdFileProvider));
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        let hdr_output_pass = options.is_some_and(|o| o.hdr_output_pass);
+        if hdr_mode.is_enabled() && hdr_output_pass {
+            hdr::append_hdr_pass(&mut preset, &defines, &*file_provider)?;
+        }
+
+        let (passes, semantics) = compile_passes(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            &*file_provider,
+        )?;
 
         let device = vulkan.try_into().map_err(From::from)?;
 
@@ -339,17 +409,21 @@ impl FilterChainVulkan {
             frames_in_flight = 3;
         }
 
+        let use_dynamic_rendering = options.map_or(false, |o| o.use_dynamic_rendering);
+
         // initialize passes
         let filters = Self::init_passes(
             &device,
-            passes,
+            passes.into_iter().enumerate().collect(),
             &semantics,
             frames_in_flight,
-            options.map_or(false, |o| o.use_dynamic_rendering),
-            disable_cache,
+            use_dynamic_rendering,
+            cache.as_ref(),
+            &defines,
+            optimization.as_ref(),
         )?;
 
-        let luts = FilterChainVulkan::load_luts(&device, cmd, &preset.textures)?;
+        let luts = FilterChainVulkan::load_luts(&device, cmd, &preset.textures, &*file_provider)?;
         let samplers = SamplerSet::new(&device.device)?;
 
         let framebuffer_gen =
@@ -403,25 +477,201 @@ impl FilterChainVulkan {
             residuals: intermediates.into_boxed_slice(),
             disable_mipmaps: options.map_or(false, |o| o.force_no_mipmaps),
             default_options: Default::default(),
+            hdr_mode,
+            hdr_output_pass,
+            use_dynamic_rendering,
+            cache,
+            textures: preset.textures.into_boxed_slice(),
+            max_threads,
+            defines,
+            optimization,
+            file_provider,
         })
     }
 
+    /// Reload the filter chain with a pre-parsed `ShaderPreset`.
+    ///
+    /// Only passes whose source or configuration changed are recompiled. Lookup textures
+    /// with an unchanged path are kept, as are the history and feedback framebuffers.
+    /// If the new preset fails to compile, the filter chain is left unchanged.
+    ///
+    /// ## Safety
+    /// No command buffer recorded by [`frame`](Self::frame) may be pending execution.
+    pub unsafe fn reload(&mut self, preset: ShaderPreset) -> error::Result<()> {
+        let device = Arc::clone(&self.vulkan.device);
+        let queue = self.vulkan.queue;
+
+        let command_pool = unsafe {
+            device.create_command_pool(
+                &vk::CommandPoolCreateInfo::builder()
+                    .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER),
+                None,
+            )?
+        };
+
+        let command_buffer = unsafe {
+            // panic safety: command buffer count = 1
+            device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(command_pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )?[0]
+        };
+
+        let result = unsafe {
+            device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo::builder()
+                    .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )?;
+
+            self.reload_deferred(preset, command_buffer)
+        };
+
+        unsafe {
+            device.end_command_buffer(command_buffer)?;
+
+            let buffers = [command_buffer];
+            if result.is_ok() {
+                let submit_info = vk::SubmitInfo::builder().command_buffers(&buffers);
+                device.queue_submit(queue, &[*submit_info], vk::Fence::null())?;
+                device.queue_wait_idle(queue)?;
+            }
+            device.free_command_buffers(command_pool, &buffers);
+            device.destroy_command_pool(command_pool, None);
+        }
+
+        result
+    }
+
+    /// Reload the filter chain with the shader preset at the given path.
+    ///
+    /// ## Safety
+    /// See [`reload`](Self::reload).
+    pub unsafe fn reload_from_path(&mut self, path: impl AsRef<Path>) -> error::Result<()> {
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        context.add_video_driver_defaults(VideoDriver::Vulkan);
+        let preset = ShaderPreset::try_parse_with_provider(path, context, &*self.file_provider)?;
+        unsafe { self.reload(preset) }
+    }
+
+    /// Reload the filter chain with a pre-parsed `ShaderPreset`, deferring any GPU-side
+    /// initialization to the caller.
+    ///
+    /// ## Safety
+    /// No command buffer recorded by [`frame`](Self::frame) may be pending execution.
+    /// The provided command buffer must be ready for recording. The caller is responsible
+    /// for ending the command buffer and submitting it to a graphics queue. The command buffer
+    /// must be completely executed before calling [`frame`](Self::frame).
+    pub unsafe fn reload_deferred(
+        &mut self,
+        preset: ShaderPreset,
+        cmd: vk::CommandBuffer,
+    ) -> error::Result<()> {
+        let mut preset = preset;
+        if self.hdr_mode.is_enabled() && self.hdr_output_pass {
+            hdr::append_hdr_pass(&mut preset, &self.defines, &*self.file_provider)?;
+        }
+
+        let reload = PresetReload::new(
+            &preset,
+            self.passes.iter().map(|pass| (&pass.config, &pass.source)),
+            &self.textures,
+            &self.defines,
+            &*self.file_provider,
+        )?;
+
+        let changed: Vec<usize> = reload.changed_passes().collect();
+        let (passes, _) = compile_passes(
+            changed
+                .iter()
+                .map(|&index| preset.shaders[index].clone())
+                .collect(),
+            &preset.textures,
+            self.cache.as_ref(),
+            self.max_threads,
+            &self.defines,
+            self.optimization.as_ref(),
+            &*self.file_provider,
+        )?;
+        let compiled = Self::init_passes(
+            &self.vulkan,
+            changed.into_iter().zip(passes).collect(),
+            &reload.semantics,
+            self.residuals.len() as u32,
+            self.use_dynamic_rendering,
+            self.cache.as_ref(),
+            &self.defines,
+            self.optimization.as_ref(),
+        )?;
+
+        let textures: Vec<TextureConfig> = reload
+            .changed_luts()
+            .map(|index| preset.textures[index].clone())
+            .collect();
+        let loaded =
+            FilterChainVulkan::load_luts(&self.vulkan, cmd, &textures, &*self.file_provider)?;
+
+        let (passes, _) = reload.merge_passes(std::mem::take(&mut self.passes), compiled);
+        self.passes = passes;
+        self.common.luts = reload.merge_luts(&mut self.common.luts, loaded);
+
+        let framebuffer_gen =
+            || OwnedImage::new(&self.vulkan, Size::new(1, 1), ImageFormat::R8G8B8A8Unorm, 1);
+        let input_gen = || None;
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init
+            .resize_output_framebuffers(std::mem::take(&mut self.output_framebuffers))?;
+        let (feedback_framebuffers, feedback_textures) = framebuffer_init
+            .resize_output_framebuffers(std::mem::take(&mut self.feedback_framebuffers))?;
+        let (history_framebuffers, history_textures) =
+            framebuffer_init.resize_history(std::mem::take(&mut self.history_framebuffers))?;
+
+        self.output_framebuffers = output_framebuffers;
+        self.feedback_framebuffers = feedback_framebuffers;
+        self.history_framebuffers = history_framebuffers;
+        self.common.output_textures = output_textures;
+        self.common.feedback_textures = feedback_textures;
+        self.common.history_textures = history_textures;
+
+        self.common.config = FilterMutable {
+            passes_enabled: preset.shader_count as usize,
+            parameters: preset
+                .parameters
+                .into_iter()
+                .map(|param| (param.name, param.value))
+                .collect(),
+        };
+        self.textures = preset.textures.into_boxed_slice();
+        Ok(())
+    }
+
     fn init_passes(
         vulkan: &VulkanObjects,
-        passes: Vec<ShaderPassMeta>,
+        passes: Vec<(usize, ShaderPassMeta)>,
         semantics: &ShaderSemantics,
         frames_in_flight: u32,
         use_dynamic_rendering: bool,
-        disable_cache: bool,
+        cache: Option<&ShaderCache>,
+        defines: &Defines,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<Box<[FilterPass]>> {
         let frames_in_flight = std::cmp::max(1, frames_in_flight);
 
         let filters: Vec<error::Result<FilterPass>> = passes
             .into_par_iter()
-            .enumerate()
             .map(|(index, (config, source, mut reflect))| {
-                let reflection = reflect.reflect(index, semantics)?;
-                let spirv_words = reflect.compile(None)?;
+                let reflection =
+                    cache_reflection(&mut reflect, &source, optimization, index, semantics, cache)?;
+                let spirv_words =
+                    cache_shader_output(&source, defines, optimization, reflect, None, cache)?;
 
                 let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
                 let uniform_storage = UniformStorage::new_with_ubo_storage(
@@ -455,7 +705,7 @@ impl FilterChainVulkan {
                     &reflection,
                     frames_in_flight,
                     render_pass_format,
-                    disable_cache,
+                    cache,
                 )?;
 
                 Ok(FilterPass {
@@ -481,11 +731,12 @@ impl FilterChainVulkan {
         vulkan: &VulkanObjects,
         command_buffer: vk::CommandBuffer,
         textures: &[TextureConfig],
+        provider: &dyn FileProvider,
     ) -> error::Result<FastHashMap<usize, LutTexture>> {
         let mut luts = FastHashMap::default();
         let images = textures
             .par_iter()
-            .map(|texture| Image::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| Image::load_with_provider(&texture.path, UVDirection::TopLeft, provider))
             .collect::<Result<Vec<Image<BGRA8>>, ImageError>>()?;
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
             let texture = LutTexture::new(vulkan, command_buffer, image, texture)?;
@@ -668,6 +919,8 @@ impl FilterChainVulkan {
         let (pass, last) = passes.split_at_mut(passes_len - 1);
 
         let options = options.unwrap_or(&self.default_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
 
         self.common
             .draw_quad
@@ -748,3 +1001,215 @@ impl FilterChainVulkan {
         Ok(())
     }
 }
+
+impl FilterChainVulkan {
+    /// Get the current size and format of a framebuffer, or `None` if it does not exist.
+    pub fn framebuffer_info(
+        &self,
+        framebuffer: FramebufferKind,
+    ) -> Option<FramebufferInfo<vk::Format>> {
+        let image = framebuffer.select(
+            &self.output_framebuffers,
+            &self.feedback_framebuffers,
+            &self.history_framebuffers,
+        )?;
+
+        Some(FramebufferInfo {
+            size: image.image.size,
+            format: image.image.format,
+            mip_levels: image.levels,
+        })
+    }
+
+    fn framebuffer(&self, framebuffer: FramebufferKind) -> error::Result<&OwnedImage> {
+        framebuffer
+            .select(
+                &self.output_framebuffers,
+                &self.feedback_framebuffers,
+                &self.history_framebuffers,
+            )
+            .ok_or(FilterChainError::InvalidFramebuffer(framebuffer))
+    }
+
+    /// Records commands to transition the first mip level of the framebuffer to
+    /// `VK_TRANSFER_SRC_OPTIMAL`, record the copy, then transition it back.
+    unsafe fn record_framebuffer_copy(
+        &self,
+        image: &OwnedImage,
+        cmd: vk::CommandBuffer,
+        copy: impl FnOnce(vk::Image),
+    ) {
+        unsafe {
+            util::vulkan_image_layout_transition_levels(
+                &self.vulkan.device,
+                cmd,
+                image.image.image,
+                1,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags::SHADER_READ,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+            );
+
+            copy(image.image.image);
+
+            util::vulkan_image_layout_transition_levels(
+                &self.vulkan.device,
+                cmd,
+                image.image.image,
+                1,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::AccessFlags::SHADER_READ,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+            );
+        }
+    }
+
+    /// Records commands to copy the first mip level of a framebuffer into the target image.
+    ///
+    /// ## Safety
+    /// * The commands recorded by [`frame`](Self::frame) must be submitted before, or recorded
+    ///   earlier in the same command buffer as, these commands.
+    /// * The target image must have the same size and format as the framebuffer, and must be in
+    ///   the `VK_TRANSFER_DST_OPTIMAL` layout.
+    pub unsafe fn copy_framebuffer(
+        &self,
+        framebuffer: FramebufferKind,
+        target: &VulkanImage,
+        cmd: vk::CommandBuffer,
+    ) -> error::Result<()> {
+        let image = self.framebuffer(framebuffer)?;
+        if target.size != image.image.size || target.format != image.image.format {
+            return Err(FilterChainError::MismatchedCopyTarget);
+        }
+
+        let region = vk::ImageCopy::builder()
+            .src_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .dst_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .extent(image.image.size.into());
+
+        unsafe {
+            self.record_framebuffer_copy(image, cmd, |source| {
+                self.vulkan.device.cmd_copy_image(
+                    cmd,
+                    source,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    target.image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[*region],
+                )
+            });
+        }
+        Ok(())
+    }
+
+    /// Read the first mip level of a framebuffer back into an image.
+    ///
+    /// This submits a command buffer to the graphics queue of the filter chain, and blocks until
+    /// the queue is idle. Pixels are converted to RGBA8, and framebuffers with an integer format
+    /// can not be read back.
+    ///
+    /// ## Safety
+    /// * The command buffer passed to [`frame`](Self::frame) must have been submitted before
+    ///   calling this function.
+    /// * Access to the graphics queue must be externally synchronized.
+    pub unsafe fn read_framebuffer(&self, framebuffer: FramebufferKind) -> error::Result<Image> {
+        let image = self.framebuffer(framebuffer)?;
+        // BGRA images have no equivalent image format, so read them as RGBA and swizzle.
+        let (format, swizzle) = match image.image.format {
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => {
+                (ImageFormat::R8G8B8A8Unorm, true)
+            }
+            format => (ImageFormat::from(format), false),
+        };
+        let bytes_per_pixel = pixel_size(format).ok_or(
+            FilterChainError::UnsupportedReadbackFormat(image.image.format),
+        )?;
+
+        let size = image.image.size;
+        let len = size.width as usize * size.height as usize * bytes_per_pixel;
+        let mut buffer = VulkanBuffer::new(
+            &self.vulkan.device,
+            &self.vulkan.alloc,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            len,
+        )?;
+
+        let device = &self.vulkan.device;
+        let region = vk::BufferImageCopy::builder()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_extent(size.into());
+
+        unsafe {
+            let command_pool = device.create_command_pool(
+                &vk::CommandPoolCreateInfo::builder().flags(vk::CommandPoolCreateFlags::TRANSIENT),
+                None,
+            )?;
+
+            // panic safety: command buffer count = 1
+            let cmd = device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(command_pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )?[0];
+
+            device.begin_command_buffer(
+                cmd,
+                &vk::CommandBufferBeginInfo::builder()
+                    .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )?;
+
+            self.record_framebuffer_copy(image, cmd, |source| {
+                device.cmd_copy_image_to_buffer(
+                    cmd,
+                    source,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    buffer.handle,
+                    &[*region],
+                )
+            });
+
+            device.end_command_buffer(cmd)?;
+
+            let buffers = [cmd];
+            let submit_info = vk::SubmitInfo::builder().command_buffers(&buffers);
+            device.queue_submit(self.vulkan.queue, &[*submit_info], vk::Fence::null())?;
+            device.queue_wait_idle(self.vulkan.queue)?;
+            device.free_command_buffers(command_pool, &buffers);
+            device.destroy_command_pool(command_pool, None);
+        }
+
+        let mut image = Image::from_pixels(&buffer.as_mut_slice()?[..len], size, format)
+            .map_err(FilterChainError::ReadbackError)?;
+        if swizzle {
+            BGRA8::convert(&mut image.bytes);
+        }
+
+        Ok(image)
+    }
+}
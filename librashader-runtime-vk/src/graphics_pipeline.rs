@@ -6,7 +6,7 @@ use crate::framebuffer::OutputImage;
 use crate::render_pass::VulkanRenderPass;
 use ash::vk::PushConstantRange;
 use bytemuck::offset_of;
-use librashader_cache::cache_pipeline;
+use librashader_cache::{cache_pipeline, ShaderCache};
 use librashader_reflect::back::ShaderCompilerOutput;
 use librashader_reflect::reflect::semantics::{BufferReflection, TextureBinding};
 use librashader_reflect::reflect::ShaderReflection;
@@ -301,7 +301,7 @@ impl VulkanGraphicsPipeline {
         reflection: &ShaderReflection,
         replicas: u32,
         render_pass_format: vk::Format,
-        bypass_cache: bool,
+        cache: Option<&ShaderCache>,
     ) -> error::Result<VulkanGraphicsPipeline> {
         let pipeline_layout = PipelineLayoutObjects::new(reflection, replicas, device)?;
 
@@ -344,7 +344,7 @@ impl VulkanGraphicsPipeline {
                 Ok::<_, FilterChainError>((pipeline, pipeline_cache))
             },
             |(_pipeline, cache)| unsafe { Ok(device.get_pipeline_cache_data(*cache)?) },
-            bypass_cache,
+            cache,
         )?;
 
         Ok(VulkanGraphicsPipeline {
@@ -0,0 +1,5 @@
+//! Golden image tests of the Vulkan runtime on lavapipe.
+mod headless;
+
+#[path = "../../test/headless/golden.rs"]
+mod golden;
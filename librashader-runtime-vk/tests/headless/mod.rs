@@ -0,0 +1,585 @@
+//! Headless rendering of a shader preset on a software Vulkan implementation.
+use ash::vk;
+use image::RgbaImage;
+use librashader_common::{Size, Viewport};
+use librashader_presets::ShaderPreset;
+use librashader_runtime::framebuffer::FramebufferKind;
+use librashader_runtime::image::Image;
+use librashader_runtime_vk::error::FilterChainError;
+use librashader_runtime_vk::options::{FilterChainOptionsVulkan, FrameOptionsVulkan};
+use librashader_runtime_vk::{FilterChainVulkan, VulkanImage};
+use std::path::Path;
+
+pub use librashader_runtime_vk::options::FilterChainOptionsVulkan as FilterChainOptions;
+pub use librashader_runtime_vk::options::FrameOptionsVulkan as FrameOptions;
+
+/// The name of the runtime in golden image failures.
+pub const RUNTIME: &str = "vk";
+
+const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+const COLOR_SUBRESOURCE: vk::ImageSubresourceRange = vk::ImageSubresourceRange {
+    aspect_mask: vk::ImageAspectFlags::COLOR,
+    base_mip_level: 0,
+    level_count: 1,
+    base_array_layer: 0,
+    layer_count: 1,
+};
+
+const COLOR_LAYERS: vk::ImageSubresourceLayers = vk::ImageSubresourceLayers {
+    aspect_mask: vk::ImageAspectFlags::COLOR,
+    mip_level: 0,
+    base_array_layer: 0,
+    layer_count: 1,
+};
+
+/// A device on a CPU implementation of Vulkan, such as lavapipe.
+struct Headless {
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    device: ash::Device,
+    queue: vk::Queue,
+    pool: vk::CommandPool,
+    mem_props: vk::PhysicalDeviceMemoryProperties,
+}
+
+/// An image or buffer along with its backing memory.
+struct Allocation<T> {
+    handle: T,
+    memory: vk::DeviceMemory,
+}
+
+impl Headless {
+    /// Create a device on a CPU physical device.
+    ///
+    /// Returns `None` if the Vulkan loader or a CPU physical device is not available.
+    fn new() -> Option<Headless> {
+        unsafe {
+            let entry = ash::Entry::load().ok()?;
+            let app_info = vk::ApplicationInfo::builder()
+                .application_name(c"librashader headless")
+                .engine_name(c"librashader headless")
+                .api_version(vk::make_api_version(0, 1, 3, 0));
+
+            let instance = entry
+                .create_instance(
+                    &vk::InstanceCreateInfo::builder().application_info(&app_info),
+                    None,
+                )
+                .ok()?;
+
+            let Some(physical_device) = instance
+                .enumerate_physical_devices()
+                .unwrap_or_default()
+                .into_iter()
+                .find(|&physical_device| {
+                    instance
+                        .get_physical_device_properties(physical_device)
+                        .device_type
+                        == vk::PhysicalDeviceType::CPU
+                })
+            else {
+                instance.destroy_instance(None);
+                return None;
+            };
+
+            let queue_family = instance
+                .get_physical_device_queue_family_properties(physical_device)
+                .iter()
+                .position(|family| family.queue_flags.contains(vk::QueueFlags::GRAPHICS))?
+                as u32;
+
+            let queue_info = [*vk::DeviceQueueCreateInfo::builder()
+                .queue_family_index(queue_family)
+                .queue_priorities(&[1.0f32])];
+
+            let device = instance
+                .create_device(
+                    physical_device,
+                    &vk::DeviceCreateInfo::builder().queue_create_infos(&queue_info),
+                    None,
+                )
+                .ok()?;
+
+            let queue = device.get_device_queue(queue_family, 0);
+            let pool = device
+                .create_command_pool(
+                    &vk::CommandPoolCreateInfo::builder()
+                        .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+                        .queue_family_index(queue_family),
+                    None,
+                )
+                .unwrap();
+
+            let mem_props = instance.get_physical_device_memory_properties(physical_device);
+
+            Some(Headless {
+                _entry: entry,
+                instance,
+                physical_device,
+                device,
+                queue,
+                pool,
+                mem_props,
+            })
+        }
+    }
+
+    unsafe fn allocate(
+        &self,
+        reqs: vk::MemoryRequirements,
+        flags: vk::MemoryPropertyFlags,
+    ) -> vk::DeviceMemory {
+        let memory_type = (0..self.mem_props.memory_type_count)
+            .find(|&i| {
+                reqs.memory_type_bits & (1 << i) != 0
+                    && self.mem_props.memory_types[i as usize]
+                        .property_flags
+                        .contains(flags)
+            })
+            .expect("no suitable memory type");
+
+        self.device
+            .allocate_memory(
+                &vk::MemoryAllocateInfo::builder()
+                    .allocation_size(reqs.size)
+                    .memory_type_index(memory_type),
+                None,
+            )
+            .unwrap()
+    }
+
+    unsafe fn create_image(
+        &self,
+        size: Size<u32>,
+        usage: vk::ImageUsageFlags,
+    ) -> Allocation<vk::Image> {
+        let image = self
+            .device
+            .create_image(
+                &vk::ImageCreateInfo::builder()
+                    .image_type(vk::ImageType::TYPE_2D)
+                    .format(FORMAT)
+                    .extent(size.into())
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .tiling(vk::ImageTiling::OPTIMAL)
+                    .usage(usage)
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .initial_layout(vk::ImageLayout::UNDEFINED),
+                None,
+            )
+            .unwrap();
+
+        let memory = self.allocate(
+            self.device.get_image_memory_requirements(image),
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+        self.device.bind_image_memory(image, memory, 0).unwrap();
+
+        Allocation {
+            handle: image,
+            memory,
+        }
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> Allocation<vk::Buffer> {
+        let buffer = self
+            .device
+            .create_buffer(
+                &vk::BufferCreateInfo::builder()
+                    .size(size)
+                    .usage(usage)
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                None,
+            )
+            .unwrap();
+
+        let memory = self.allocate(
+            self.device.get_buffer_memory_requirements(buffer),
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+        self.device.bind_buffer_memory(buffer, memory, 0).unwrap();
+
+        Allocation {
+            handle: buffer,
+            memory,
+        }
+    }
+
+    /// Record commands into a one-time command buffer, then submit it and wait for completion.
+    unsafe fn submit(&self, record: impl FnOnce(vk::CommandBuffer)) {
+        let cmd = self
+            .device
+            .allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(self.pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )
+            .unwrap()[0];
+
+        self.device
+            .begin_command_buffer(
+                cmd,
+                &vk::CommandBufferBeginInfo::builder()
+                    .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )
+            .unwrap();
+        record(cmd);
+        self.device.end_command_buffer(cmd).unwrap();
+
+        let cmds = [cmd];
+        self.device
+            .queue_submit(
+                self.queue,
+                &[*vk::SubmitInfo::builder().command_buffers(&cmds)],
+                vk::Fence::null(),
+            )
+            .unwrap();
+        self.device.queue_wait_idle(self.queue).unwrap();
+        self.device.free_command_buffers(self.pool, &cmds);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn barrier(
+        &self,
+        cmd: vk::CommandBuffer,
+        image: vk::Image,
+        src_access: vk::AccessFlags,
+        dst_access: vk::AccessFlags,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+    ) {
+        let barrier = vk::ImageMemoryBarrier::builder()
+            .src_access_mask(src_access)
+            .dst_access_mask(dst_access)
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(COLOR_SUBRESOURCE);
+
+        self.device.cmd_pipeline_barrier(
+            cmd,
+            src_stage,
+            dst_stage,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[*barrier],
+        );
+    }
+
+    /// Upload the image at the given path to an image in `VK_SHADER_READ_ONLY_OPTIMAL`
+    /// that can be used as the input of a frame.
+    unsafe fn load_input(&self, path: &Path) -> (Allocation<vk::Image>, Size<u32>) {
+        let image = image::open(path).unwrap().to_rgba8();
+        let size = Size::new(image.width(), image.height());
+        let pixels = image.as_raw();
+
+        let staging = self.create_buffer(
+            pixels.len() as vk::DeviceSize,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+        );
+        let mapped = self
+            .device
+            .map_memory(
+                staging.memory,
+                0,
+                vk::WHOLE_SIZE,
+                vk::MemoryMapFlags::empty(),
+            )
+            .unwrap();
+        std::ptr::copy_nonoverlapping(pixels.as_ptr(), mapped.cast(), pixels.len());
+        self.device.unmap_memory(staging.memory);
+
+        let input = self.create_image(
+            size,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+        );
+
+        self.submit(|cmd| {
+            self.barrier(
+                cmd,
+                input.handle,
+                vk::AccessFlags::empty(),
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+            );
+
+            self.device.cmd_copy_buffer_to_image(
+                cmd,
+                staging.handle,
+                input.handle,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[*vk::BufferImageCopy::builder()
+                    .image_subresource(COLOR_LAYERS)
+                    .image_extent(size.into())],
+            );
+
+            self.barrier(
+                cmd,
+                input.handle,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::SHADER_READ,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+            );
+        });
+
+        self.destroy_buffer(staging);
+        (input, size)
+    }
+
+    /// Copy the image in `VK_COLOR_ATTACHMENT_OPTIMAL` into a CPU-side image.
+    unsafe fn read_back(&self, image: vk::Image, size: Size<u32>) -> RgbaImage {
+        let len = 4 * size.width as usize * size.height as usize;
+        let readback =
+            self.create_buffer(len as vk::DeviceSize, vk::BufferUsageFlags::TRANSFER_DST);
+
+        self.submit(|cmd| {
+            self.barrier(
+                cmd,
+                image,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::TRANSFER,
+            );
+
+            self.device.cmd_copy_image_to_buffer(
+                cmd,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                readback.handle,
+                &[*vk::BufferImageCopy::builder()
+                    .image_subresource(COLOR_LAYERS)
+                    .image_extent(size.into())],
+            );
+        });
+
+        let mapped = self
+            .device
+            .map_memory(
+                readback.memory,
+                0,
+                vk::WHOLE_SIZE,
+                vk::MemoryMapFlags::empty(),
+            )
+            .unwrap();
+        let pixels = std::slice::from_raw_parts(mapped.cast::<u8>(), len).to_vec();
+        self.device.unmap_memory(readback.memory);
+        self.destroy_buffer(readback);
+
+        RgbaImage::from_raw(size.width, size.height, pixels).unwrap()
+    }
+
+    unsafe fn destroy_image(&self, image: Allocation<vk::Image>) {
+        self.device.destroy_image(image.handle, None);
+        self.device.free_memory(image.memory, None);
+    }
+
+    unsafe fn destroy_buffer(&self, buffer: Allocation<vk::Buffer>) {
+        self.device.destroy_buffer(buffer.handle, None);
+        self.device.free_memory(buffer.memory, None);
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.device_wait_idle().unwrap();
+            self.device.destroy_command_pool(self.pool, None);
+            self.device.destroy_device(None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+/// Render the shader preset over the input image for the given number of frames, and read
+/// back the final frame.
+pub fn render(
+    preset: impl AsRef<Path>,
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+) -> RgbaImage {
+    render_and_inspect(preset, input, output_size, frames, |_| {})
+}
+
+/// Render the shader preset like [`render`], then pass the filter chain to `inspect`
+/// once the final frame has completed.
+pub fn render_and_inspect(
+    preset: impl AsRef<Path>,
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+    inspect: impl FnOnce(&FilterChainVulkan),
+) -> RgbaImage {
+    render_with_options(preset, input, output_size, frames, None, None, inspect)
+}
+
+/// Render the shader preset like [`render_and_inspect`], with the given filter chain
+/// and frame options.
+pub fn render_with_options(
+    preset: impl AsRef<Path>,
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+    options: Option<&FilterChainOptionsVulkan>,
+    frame_options: Option<&FrameOptionsVulkan>,
+    inspect: impl FnOnce(&FilterChainVulkan),
+) -> RgbaImage {
+    render_sequence(
+        &[preset.as_ref()],
+        input.as_ref(),
+        output_size,
+        frames,
+        options,
+        frame_options,
+        inspect,
+    )
+}
+
+/// Render the first shader preset like [`render_and_inspect`], then reload the filter chain
+/// with each following shader preset in turn, rendering the given number of frames after
+/// each reload.
+pub fn render_with_reloads(
+    presets: &[&str],
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+    inspect: impl FnOnce(&FilterChainVulkan),
+) -> RgbaImage {
+    let presets: Vec<&Path> = presets.iter().map(Path::new).collect();
+    render_sequence(
+        &presets,
+        input.as_ref(),
+        output_size,
+        frames,
+        None,
+        None,
+        inspect,
+    )
+}
+
+fn render_sequence(
+    presets: &[&Path],
+    input: &Path,
+    output_size: Size<u32>,
+    frames: usize,
+    options: Option<&FilterChainOptionsVulkan>,
+    frame_options: Option<&FrameOptionsVulkan>,
+    inspect: impl FnOnce(&FilterChainVulkan),
+) -> RgbaImage {
+    let vulkan = Headless::new().expect("no CPU Vulkan implementation is available");
+
+    let (first, reloads) = presets.split_first().unwrap();
+    let preset = ShaderPreset::try_parse(first).unwrap();
+
+    unsafe {
+        let mut chain = FilterChainVulkan::load_from_preset(
+            preset,
+            (
+                vulkan.physical_device,
+                vulkan.instance.clone(),
+                vulkan.device.clone(),
+            ),
+            options,
+        )
+        .unwrap();
+
+        let (input, input_size) = vulkan.load_input(input);
+        let output = vulkan.create_image(
+            output_size,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+        );
+
+        vulkan.submit(|cmd| {
+            vulkan.barrier(
+                cmd,
+                output.handle,
+                vk::AccessFlags::empty(),
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            );
+        });
+
+        let input_image = VulkanImage {
+            image: input.handle,
+            size: input_size,
+            format: FORMAT,
+        };
+
+        let viewport = Viewport {
+            x: 0.0,
+            y: 0.0,
+            mvp: None,
+            output: VulkanImage {
+                image: output.handle,
+                size: output_size,
+                format: FORMAT,
+            },
+        };
+
+        for frame in 0..frames {
+            vulkan.submit(|cmd| {
+                chain
+                    .frame(&input_image, &viewport, cmd, frame, frame_options)
+                    .unwrap();
+            });
+        }
+
+        for preset in reloads {
+            chain
+                .reload(ShaderPreset::try_parse(preset).unwrap())
+                .unwrap();
+            for frame in 0..frames {
+                vulkan.submit(|cmd| {
+                    chain
+                        .frame(&input_image, &viewport, cmd, frame, frame_options)
+                        .unwrap();
+                });
+            }
+        }
+
+        inspect(&chain);
+        let output_image = vulkan.read_back(output.handle, output_size);
+
+        drop(chain);
+        vulkan.destroy_image(input);
+        vulkan.destroy_image(output);
+
+        output_image
+    }
+}
+
+/// Read back the framebuffer of the filter chain.
+pub fn read_framebuffer(
+    chain: &FilterChainVulkan,
+    framebuffer: FramebufferKind,
+) -> Result<Image, FilterChainError> {
+    // The frames rendered by the headless device have completed by the time the filter
+    // chain is inspected.
+    unsafe { chain.read_framebuffer(framebuffer) }
+}
@@ -262,6 +262,7 @@ impl VulkanWindow {
                         current_subframe: 1,
                         rotation: 0,
                         total_subframes: 1,
+                        ..Default::default()
                     }),
                 )
                 .unwrap();
@@ -21,6 +21,8 @@ fn triangle_vk() {
                 force_no_mipmaps: false,
                 use_dynamic_rendering: true,
                 disable_cache: true,
+                cache: None,
+                ..Default::default()
             }),
         )
         .unwrap();
@@ -2,6 +2,7 @@
 use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
+use librashader_runtime::framebuffer::FramebufferKind;
 use librashader_runtime::image::ImageError;
 use thiserror::Error;
 
@@ -18,6 +19,16 @@ pub enum FilterChainError {
     ShaderReflectError(#[from] ShaderReflectError),
     #[error("lut loading error")]
     LutLoadError(#[from] ImageError),
+    #[error("framebuffer does not exist")]
+    InvalidFramebuffer(FramebufferKind),
+    #[error("framebuffer format can not be read back")]
+    UnsupportedReadbackFormat(wgpu::TextureFormat),
+    #[error("framebuffer could not be read back")]
+    ReadbackError(ImageError),
+    #[error("copy target does not match the size or format of the framebuffer")]
+    MismatchedCopyTarget,
+    #[error("buffer mapping error")]
+    BufferMapError(#[from] wgpu::BufferAsyncError),
 }
 
 /// Result type for wgpu filter chains.
@@ -1,18 +1,28 @@
+#[cfg(not(target_arch = "wasm32"))]
+use librashader_cache::{cache_reflection, cache_shader_output, CachedCompilation, ShaderCache};
+use librashader_common::fs::{FileProvider, StdFileProvider};
 use librashader_common::map::FastHashMap;
-use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig};
+use librashader_preprocess::Defines;
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
 use librashader_reflect::back::targets::WGSL;
-use librashader_reflect::back::{CompileReflectShader, CompileShader};
-use librashader_reflect::front::SpirvCompilation;
-use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::back::CompileReflectShader;
+#[cfg(target_arch = "wasm32")]
+use librashader_reflect::back::CompileShader;
+use librashader_reflect::front::{SpirvCompilation, SpirvOptimization};
+use librashader_reflect::reflect::presets::{
+    CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+};
 use librashader_reflect::reflect::semantics::ShaderSemantics;
+#[cfg(target_arch = "wasm32")]
 use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
-use librashader_runtime::image::{Image, ImageError, UVDirection};
+use librashader_runtime::image::{pixel_size, Image, ImageError, PixelFormat, UVDirection, BGRA8};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::uniforms::UniformStorage;
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
 use std::collections::VecDeque;
+use std::num::NonZeroUsize;
 use std::path::Path;
 
 use rayon::ThreadPoolBuilder;
@@ -20,9 +30,11 @@ use std::sync::Arc;
 
 use crate::buffer::WgpuStagedBuffer;
 use crate::draw_quad::DrawQuad;
-use librashader_common::{FilterMode, Size, Viewport, WrapMode};
+use librashader_common::{FilterMode, ImageFormat, Size, Viewport, WrapMode};
 use librashader_reflect::reflect::naga::{Naga, NagaLoweringOptions};
-use librashader_runtime::framebuffer::FramebufferInit;
+use librashader_runtime::framebuffer::{FramebufferInfo, FramebufferInit, FramebufferKind};
+use librashader_runtime::hdr::{self, BuiltinFileProvider, HdrMode};
+use librashader_runtime::reload::PresetReload;
 use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::scaling::ScaleFramebuffer;
 use wgpu::{Device, TextureFormat};
@@ -38,15 +50,56 @@ use crate::options::{FilterChainOptionsWgpu, FrameOptionsWgpu};
 use crate::samplers::SamplerSet;
 use crate::texture::{InputImage, OwnedImage};
 
+/// The shader cache is not available on wasm, so there is never a cache to compile with.
+#[cfg(target_arch = "wasm32")]
+type ShaderCache = std::convert::Infallible;
+
 type ShaderPassMeta =
     ShaderPassArtifact<impl CompileReflectShader<WGSL, SpirvCompilation, Naga> + Send>;
 fn compile_passes(
     shaders: Vec<ShaderPassConfig>,
     textures: &[TextureConfig],
+    cache: Option<&ShaderCache>,
+    max_threads: Option<NonZeroUsize>,
+    defines: &Defines,
+    optimization: Option<&SpirvOptimization>,
+    provider: &dyn FileProvider,
 ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
+    let provider = BuiltinFileProvider::new(provider);
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(cache) = cache {
+        return WGSL::compile_preset_passes_with_options::<
+            CachedCompilation<SpirvCompilation>,
+            Naga,
+            FilterChainError,
+        >(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: Some(&|source, defines, optimization| {
+                    cache.compile_with_options(source, defines, optimization)
+                }),
+            },
+        );
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = cache;
+
     let (passes, semantics) =
-        WGSL::compile_preset_passes::<SpirvCompilation, Naga, FilterChainError>(
-            shaders, &textures,
+        WGSL::compile_preset_passes_with_options::<SpirvCompilation, Naga, FilterChainError>(
+            shaders,
+            textures,
+            &PresetCompileOptions {
+                max_threads,
+                provider: &provider,
+                defines,
+                optimization,
+                compiler: None,
+            },
         )?;
     Ok((passes, semantics))
 }
@@ -61,6 +114,14 @@ pub struct FilterChainWgpu {
     disable_mipmaps: bool,
     mipmapper: MipmapGen,
     default_frame_options: FrameOptionsWgpu,
+    hdr_mode: HdrMode,
+    hdr_output_pass: bool,
+    textures: Box<[TextureConfig]>,
+    max_threads: Option<NonZeroUsize>,
+    defines: Defines,
+    optimization: Option<SpirvOptimization>,
+    file_provider: Arc<dyn FileProvider>,
+    cache: Option<ShaderCache>,
 }
 
 pub struct FilterMutable {
@@ -90,7 +151,12 @@ impl FilterChainWgpu {
         options: Option<&FilterChainOptionsWgpu>,
     ) -> error::Result<FilterChainWgpu> {
         // load passes from preset
-        let preset = ShaderPreset::try_parse(path)?;
+        let provider = options
+            .and_then(|o| o.file_provider.as_deref())
+            .unwrap_or(&StdFileProvider);
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        let preset = ShaderPreset::try_parse_with_provider(path, context, provider)?;
 
         Self::load_from_preset(preset, device, queue, options)
     }
@@ -136,10 +202,45 @@ impl FilterChainWgpu {
         cmd: &mut wgpu::CommandEncoder,
         options: Option<&FilterChainOptionsWgpu>,
     ) -> error::Result<FilterChainWgpu> {
-        let (passes, semantics) = compile_passes(preset.shaders, &preset.textures)?;
+        let mut preset = preset;
+        let defines = options.map(|o| o.defines.clone()).unwrap_or_default();
+        let optimization = options.and_then(|o| o.optimization.clone());
+        let max_threads = options.and_then(|o| o.max_compile_threads);
+        let file_provider = options
+            .and_then(|o| o.file_provider.clone())
+            .unwrap_or_else(|| Arc::new(StdFileProvider));
+        let hdr_mode = options.map_or(HdrMode::Disabled, |o| o.hdr_mode);
+        let hdr_output_pass = options.is_some_and(|o| o.hdr_output_pass);
+        if hdr_mode.is_enabled() && hdr_output_pass {
+            hdr::append_hdr_pass(&mut preset, &defines, &*file_provider)?;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let cache = ShaderCache::for_filter_chain(
+            options.and_then(|o| o.cache.as_ref()),
+            options.map_or(false, |o| o.disable_cache),
+        );
+        #[cfg(target_arch = "wasm32")]
+        let cache = None;
+        let (passes, semantics) = compile_passes(
+            preset.shaders,
+            &preset.textures,
+            cache.as_ref(),
+            max_threads,
+            &defines,
+            optimization.as_ref(),
+            &*file_provider,
+        )?;
 
         // // initialize passes
-        let filters = Self::init_passes(Arc::clone(&device), passes, &semantics)?;
+        let filters = Self::init_passes(
+            Arc::clone(&device),
+            passes.into_iter().enumerate().collect(),
+            &semantics,
+            cache.as_ref(),
+            &defines,
+            optimization.as_ref(),
+        )?;
 
         let samplers = SamplerSet::new(&device);
         let mut mipmapper = MipmapGen::new(Arc::clone(&device));
@@ -150,6 +251,7 @@ impl FilterChainWgpu {
             &mut mipmapper,
             &samplers,
             &preset.textures,
+            &*file_provider,
         )?;
         //
         let framebuffer_gen = || {
@@ -207,9 +309,155 @@ impl FilterChainWgpu {
             disable_mipmaps: options.map(|f| f.force_no_mipmaps).unwrap_or(false),
             mipmapper,
             default_frame_options: Default::default(),
+            hdr_mode,
+            hdr_output_pass,
+            textures: preset.textures.into_boxed_slice(),
+            max_threads,
+            defines,
+            optimization,
+            file_provider,
+            cache,
         })
     }
 
+    /// Reload the filter chain with a pre-parsed `ShaderPreset`.
+    ///
+    /// Only passes whose source or configuration changed are recompiled. Lookup textures
+    /// with an unchanged path are kept, as are the history and feedback framebuffers.
+    /// If the new preset fails to compile, the filter chain is left unchanged.
+    pub fn reload(&mut self, preset: ShaderPreset) -> error::Result<()> {
+        let mut cmd = self
+            .common
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("librashader reload cmd"),
+            });
+
+        self.reload_deferred(preset, &mut cmd)?;
+
+        // Wait for device
+        let index = self.common.queue.submit([cmd.finish()]);
+        self.common
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+        Ok(())
+    }
+
+    /// Reload the filter chain with the shader preset at the given path.
+    ///
+    /// See [`reload`](Self::reload) for the parts of the filter chain that are kept.
+    pub fn reload_from_path(&mut self, path: impl AsRef<Path>) -> error::Result<()> {
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        let preset = ShaderPreset::try_parse_with_provider(path, context, &*self.file_provider)?;
+        self.reload(preset)
+    }
+
+    /// Reload the filter chain with a pre-parsed `ShaderPreset`, deferring any GPU-side
+    /// initialization to the caller.
+    ///
+    /// ## Safety
+    /// The provided command buffer must be ready for recording. The caller is responsible
+    /// for submitting it before the next call to [`frame`](Self::frame).
+    pub fn reload_deferred(
+        &mut self,
+        preset: ShaderPreset,
+        cmd: &mut wgpu::CommandEncoder,
+    ) -> error::Result<()> {
+        let mut preset = preset;
+        if self.hdr_mode.is_enabled() && self.hdr_output_pass {
+            hdr::append_hdr_pass(&mut preset, &self.defines, &*self.file_provider)?;
+        }
+
+        let reload = PresetReload::new(
+            &preset,
+            self.passes.iter().map(|pass| (&pass.config, &pass.source)),
+            &self.textures,
+            &self.defines,
+            &*self.file_provider,
+        )?;
+
+        let changed: Vec<usize> = reload.changed_passes().collect();
+        let (passes, _) = compile_passes(
+            changed
+                .iter()
+                .map(|&index| preset.shaders[index].clone())
+                .collect(),
+            &preset.textures,
+            self.cache.as_ref(),
+            self.max_threads,
+            &self.defines,
+            self.optimization.as_ref(),
+            &*self.file_provider,
+        )?;
+        let compiled = Self::init_passes(
+            Arc::clone(&self.common.device),
+            changed.into_iter().zip(passes).collect(),
+            &reload.semantics,
+            self.cache.as_ref(),
+            &self.defines,
+            self.optimization.as_ref(),
+        )?;
+
+        let textures: Vec<TextureConfig> = reload
+            .changed_luts()
+            .map(|index| preset.textures[index].clone())
+            .collect();
+        let loaded = FilterChainWgpu::load_luts(
+            &self.common.device,
+            &self.common.queue,
+            cmd,
+            &mut self.mipmapper,
+            &self.common.samplers,
+            &textures,
+            &*self.file_provider,
+        )?;
+
+        let (passes, _) = reload.merge_passes(std::mem::take(&mut self.passes), compiled);
+        self.passes = passes;
+        self.common.luts = reload.merge_luts(&mut self.common.luts, loaded);
+
+        let framebuffer_gen = || {
+            Ok::<_, error::FilterChainError>(OwnedImage::new(
+                Arc::clone(&self.common.device),
+                Size::new(1, 1),
+                1,
+                TextureFormat::Bgra8Unorm,
+            ))
+        };
+        let input_gen = || None;
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init
+            .resize_output_framebuffers(std::mem::take(&mut self.output_framebuffers))?;
+        let (feedback_framebuffers, feedback_textures) = framebuffer_init
+            .resize_output_framebuffers(std::mem::take(&mut self.feedback_framebuffers))?;
+        let (history_framebuffers, history_textures) =
+            framebuffer_init.resize_history(std::mem::take(&mut self.history_framebuffers))?;
+
+        self.output_framebuffers = output_framebuffers;
+        self.feedback_framebuffers = feedback_framebuffers;
+        self.history_framebuffers = history_framebuffers;
+        self.common.output_textures = output_textures;
+        self.common.feedback_textures = feedback_textures;
+        self.common.history_textures = history_textures;
+
+        self.common.config = FilterMutable {
+            passes_enabled: preset.shader_count as usize,
+            parameters: preset
+                .parameters
+                .into_iter()
+                .map(|param| (param.name, param.value))
+                .collect(),
+        };
+        self.textures = preset.textures.into_boxed_slice();
+        Ok(())
+    }
+
     fn load_luts(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -217,6 +465,7 @@ impl FilterChainWgpu {
         mipmapper: &mut MipmapGen,
         sampler_set: &SamplerSet,
         textures: &[TextureConfig],
+        provider: &dyn FileProvider,
     ) -> error::Result<FastHashMap<usize, LutTexture>> {
         let mut luts = FastHashMap::default();
 
@@ -227,7 +476,7 @@ impl FilterChainWgpu {
         let images_iter = textures.iter();
 
         let images = images_iter
-            .map(|texture| Image::load(&texture.path, UVDirection::TopLeft))
+            .map(|texture| Image::load_with_provider(&texture.path, UVDirection::TopLeft, provider))
             .collect::<Result<Vec<Image>, ImageError>>()?;
         for (index, (texture, image)) in textures.iter().zip(images).enumerate() {
             let texture =
@@ -260,8 +509,11 @@ impl FilterChainWgpu {
 
     fn init_passes(
         device: Arc<Device>,
-        passes: Vec<ShaderPassMeta>,
+        passes: Vec<(usize, ShaderPassMeta)>,
         semantics: &ShaderSemantics,
+        cache: Option<&ShaderCache>,
+        defines: &Defines,
+        optimization: Option<&SpirvOptimization>,
     ) -> error::Result<Box<[FilterPass]>> {
         #[cfg(not(target_arch = "wasm32"))]
         let filter_creation_fn = || {
@@ -270,13 +522,42 @@ impl FilterChainWgpu {
             let passes_iter = passes.into_iter();
 
             let filters: Vec<error::Result<FilterPass>> = passes_iter
-                .enumerate()
                 .map(|(index, (config, source, mut reflect))| {
-                    let reflection = reflect.reflect(index, semantics)?;
-                    let wgsl = reflect.compile(NagaLoweringOptions {
+                    let options = NagaLoweringOptions {
                         write_pcb_as_ubo: true,
                         sampler_bind_group: 1,
-                    })?;
+                    };
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let (reflection, wgsl) = (
+                        cache_reflection(
+                            &mut reflect,
+                            &source,
+                            optimization,
+                            index,
+                            semantics,
+                            cache,
+                        )?,
+                        cache_shader_output(
+                            &source,
+                            defines,
+                            optimization,
+                            reflect,
+                            options,
+                            cache,
+                        )?,
+                    );
+                    #[cfg(target_arch = "wasm32")]
+                    let (reflection, wgsl) = {
+                        let _ = (cache, defines, optimization);
+                        let reflection = reflect.reflect(index, semantics)?;
+                        let wgsl = reflect.compile(options)?;
+                        let wgsl = librashader_reflect::back::ShaderCompilerOutput {
+                            vertex: wgsl.vertex,
+                            fragment: wgsl.fragment,
+                            context: (),
+                        };
+                        (reflection, wgsl)
+                    };
 
                     let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
                     let push_size = reflection
@@ -430,6 +711,8 @@ impl FilterChainWgpu {
         let (pass, last) = passes.split_at_mut(passes_len - 1);
 
         let options = options.unwrap_or(&self.default_frame_options);
+        let options = options.with_hdr_mode(self.hdr_mode);
+        let options = options.as_ref();
 
         for (index, pass) in pass.iter_mut().enumerate() {
             let target = &self.output_framebuffers[index];
@@ -498,3 +781,136 @@ impl FilterChainWgpu {
         Ok(())
     }
 }
+
+impl FilterChainWgpu {
+    /// Get the current size and format of a framebuffer, or `None` if it does not exist.
+    pub fn framebuffer_info(
+        &self,
+        framebuffer: FramebufferKind,
+    ) -> Option<FramebufferInfo<wgpu::TextureFormat>> {
+        let image = framebuffer.select(
+            &self.output_framebuffers,
+            &self.feedback_framebuffers,
+            &self.history_framebuffers,
+        )?;
+
+        Some(FramebufferInfo {
+            size: image.size,
+            format: image.image.format(),
+            mip_levels: image.levels,
+        })
+    }
+
+    fn framebuffer(&self, framebuffer: FramebufferKind) -> error::Result<&OwnedImage> {
+        framebuffer
+            .select(
+                &self.output_framebuffers,
+                &self.feedback_framebuffers,
+                &self.history_framebuffers,
+            )
+            .ok_or(FilterChainError::InvalidFramebuffer(framebuffer))
+    }
+
+    /// Records commands to copy the first mip level of a framebuffer into the target texture.
+    ///
+    /// The target texture must have the same size and format as the framebuffer, and must
+    /// have been created with `COPY_DST` usage.
+    pub fn copy_framebuffer(
+        &self,
+        framebuffer: FramebufferKind,
+        target: &wgpu::Texture,
+        cmd: &mut wgpu::CommandEncoder,
+    ) -> error::Result<()> {
+        let image = self.framebuffer(framebuffer)?;
+        if target.size() != image.image.size() || target.format() != image.image.format() {
+            return Err(FilterChainError::MismatchedCopyTarget);
+        }
+
+        cmd.copy_texture_to_texture(
+            image.image.as_image_copy(),
+            target.as_image_copy(),
+            image.image.size(),
+        );
+        Ok(())
+    }
+
+    /// Read the first mip level of a framebuffer back into an image.
+    ///
+    /// The command encoder passed to [`frame`](Self::frame) must have been submitted before
+    /// calling this function. This blocks until the copy has completed on the device.
+    /// Pixels are converted to RGBA8, and framebuffers with an integer format can not be
+    /// read back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_framebuffer(&self, framebuffer: FramebufferKind) -> error::Result<Image> {
+        let image = self.framebuffer(framebuffer)?;
+        // BGRA textures have no equivalent image format, so read them as RGBA and swizzle.
+        let (format, swizzle) = match image.image.format() {
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+                (ImageFormat::R8G8B8A8Unorm, true)
+            }
+            format => (ImageFormat::from(format), false),
+        };
+        let bytes_per_pixel = pixel_size(format).ok_or(
+            FilterChainError::UnsupportedReadbackFormat(image.image.format()),
+        )?;
+
+        let size = image.image.size();
+        let row_size = bytes_per_pixel as u32 * size.width;
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = self.common.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("librashader readback"),
+            size: (padded_row_size * size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut cmd = self
+            .common
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("librashader readback cmd"),
+            });
+
+        cmd.copy_texture_to_buffer(
+            image.image.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+
+        let index = self.common.queue.submit([cmd.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.common
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+
+        // panic safety: the callback is always invoked once the submission completes.
+        receiver.recv().unwrap()?;
+
+        let mut pixels = Vec::with_capacity((row_size * size.height) as usize);
+        for row in slice.get_mapped_range().chunks(padded_row_size as usize) {
+            pixels.extend_from_slice(&row[..row_size as usize]);
+        }
+        buffer.unmap();
+
+        let mut image = Image::from_pixels(&pixels, size.into(), format)
+            .map_err(FilterChainError::ReadbackError)?;
+        if swizzle {
+            BGRA8::convert(&mut image.bytes);
+        }
+
+        Ok(image)
+    }
+}
@@ -1,6 +1,5 @@
 use crate::framebuffer::WgpuOutputView;
 use crate::util;
-use librashader_reflect::back::wgsl::NagaWgslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use librashader_reflect::reflect::ShaderReflection;
 use librashader_runtime::quad::VertexInput;
@@ -15,6 +14,11 @@ use wgpu::{
     VertexBufferLayout,
 };
 
+/// The name of the entry points of compiled shaders.
+///
+/// glslang always names the entry point `main`, and naga keeps the name when lowering to WGSL.
+const ENTRY_POINT: &str = "main";
+
 pub struct WgpuGraphicsPipeline {
     pub layout: PipelineLayoutObjects,
     render_pipeline: wgpu::RenderPipeline,
@@ -25,8 +29,6 @@ pub struct PipelineLayoutObjects {
     layout: PipelineLayout,
     pub main_bind_group_layout: BindGroupLayout,
     pub sampler_bind_group_layout: BindGroupLayout,
-    fragment_entry_name: String,
-    vertex_entry_name: String,
     vertex: ShaderModule,
     fragment: ShaderModule,
     device: Arc<wgpu::Device>,
@@ -35,7 +37,7 @@ pub struct PipelineLayoutObjects {
 impl PipelineLayoutObjects {
     pub fn new(
         reflection: &ShaderReflection,
-        shader_assembly: &ShaderCompilerOutput<String, NagaWgslContext>,
+        shader_assembly: &ShaderCompilerOutput<String>,
         device: Arc<wgpu::Device>,
     ) -> Self {
         let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -134,10 +136,6 @@ impl PipelineLayoutObjects {
             layout,
             main_bind_group_layout: main_bind_group,
             sampler_bind_group_layout: sampler_bind_group,
-            fragment_entry_name: shader_assembly.context.fragment.entry_points[0]
-                .name
-                .clone(),
-            vertex_entry_name: shader_assembly.context.vertex.entry_points[0].name.clone(),
             vertex,
             fragment,
             device,
@@ -151,7 +149,7 @@ impl PipelineLayoutObjects {
                 layout: Some(&self.layout),
                 vertex: wgpu::VertexState {
                     module: &self.vertex,
-                    entry_point: &self.vertex_entry_name,
+                    entry_point: ENTRY_POINT,
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
                     buffers: &[VertexBufferLayout {
                         array_stride: std::mem::size_of::<VertexInput>() as wgpu::BufferAddress,
@@ -174,7 +172,7 @@ impl PipelineLayoutObjects {
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &self.fragment,
-                    entry_point: &self.fragment_entry_name,
+                    entry_point: ENTRY_POINT,
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
                     targets: &[Some(wgpu::ColorTargetState {
                         format: framebuffer_format,
@@ -205,7 +203,7 @@ impl PipelineLayoutObjects {
 impl WgpuGraphicsPipeline {
     pub fn new(
         device: Arc<wgpu::Device>,
-        shader_assembly: &ShaderCompilerOutput<String, NagaWgslContext>,
+        shader_assembly: &ShaderCompilerOutput<String>,
         reflection: &ShaderReflection,
         render_pass_format: TextureFormat,
     ) -> Self {
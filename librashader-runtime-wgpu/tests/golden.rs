@@ -0,0 +1,5 @@
+//! Golden image tests of the wgpu runtime on a software adapter.
+mod headless;
+
+#[path = "../../test/headless/golden.rs"]
+mod golden;
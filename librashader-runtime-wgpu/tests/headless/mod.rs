@@ -0,0 +1,275 @@
+//! Headless rendering of a shader preset on a software adapter.
+use image::RgbaImage;
+use librashader_common::{Size, Viewport};
+use librashader_presets::ShaderPreset;
+use librashader_runtime::framebuffer::FramebufferKind;
+use librashader_runtime::image::Image;
+use librashader_runtime_wgpu::error::FilterChainError;
+use librashader_runtime_wgpu::options::{FilterChainOptionsWgpu, FrameOptionsWgpu};
+use librashader_runtime_wgpu::{FilterChainWgpu, WgpuOutputView};
+use std::path::Path;
+use std::sync::Arc;
+
+pub use librashader_runtime_wgpu::options::FilterChainOptionsWgpu as FilterChainOptions;
+pub use librashader_runtime_wgpu::options::FrameOptionsWgpu as FrameOptions;
+
+/// The name of the runtime in golden image failures.
+pub const RUNTIME: &str = "wgpu";
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Request a device on the fallback (software) adapter, such as lavapipe or WARP.
+///
+/// Panics if no fallback adapter is available.
+fn request_device() -> (Arc<wgpu::Device>, Arc<wgpu::Queue>) {
+    let instance = wgpu::Instance::default();
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        compatible_surface: None,
+        force_fallback_adapter: true,
+    }))
+    .expect("no software adapter is available");
+
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: Some("librashader headless"),
+            required_features: adapter.features() & wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER,
+            required_limits: wgpu::Limits::default(),
+        },
+        None,
+    ))
+    .expect("failed to create a device on the software adapter");
+
+    (Arc::new(device), Arc::new(queue))
+}
+
+/// Upload the image at the given path to a texture that can be used as the input of a frame.
+fn load_input(device: &wgpu::Device, queue: &wgpu::Queue, path: &Path) -> Arc<wgpu::Texture> {
+    let image = image::open(path).unwrap().to_rgba8();
+    let size = wgpu::Extent3d {
+        width: image.width(),
+        height: image.height(),
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("librashader headless input"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        image.as_raw(),
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * image.width()),
+            rows_per_image: Some(image.height()),
+        },
+        size,
+    );
+
+    Arc::new(texture)
+}
+
+/// Copy the texture into a CPU-side image.
+fn read_back(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> RgbaImage {
+    let size = texture.size();
+    let unpadded_bytes_per_row = 4 * size.width;
+    let padded_bytes_per_row =
+        unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("librashader headless readback"),
+        size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut cmd = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("librashader headless readback cmd"),
+    });
+    cmd.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+    let index = queue.submit([cmd.finish()]);
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+
+    let data = slice.get_mapped_range();
+    let pixels = data
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+        .copied()
+        .collect();
+    drop(data);
+    buffer.unmap();
+
+    RgbaImage::from_raw(size.width, size.height, pixels).unwrap()
+}
+
+/// Render the shader preset over the input image for the given number of frames, and read
+/// back the final frame.
+pub fn render(
+    preset: impl AsRef<Path>,
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+) -> RgbaImage {
+    render_and_inspect(preset, input, output_size, frames, |_| {})
+}
+
+/// Render the shader preset like [`render`], then pass the filter chain to `inspect`
+/// once the final frame has completed.
+pub fn render_and_inspect(
+    preset: impl AsRef<Path>,
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+    inspect: impl FnOnce(&FilterChainWgpu),
+) -> RgbaImage {
+    render_with_options(preset, input, output_size, frames, None, None, inspect)
+}
+
+/// Render the shader preset like [`render_and_inspect`], with the given filter chain
+/// and frame options.
+pub fn render_with_options(
+    preset: impl AsRef<Path>,
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+    options: Option<&FilterChainOptionsWgpu>,
+    frame_options: Option<&FrameOptionsWgpu>,
+    inspect: impl FnOnce(&FilterChainWgpu),
+) -> RgbaImage {
+    let (device, queue) = request_device();
+
+    let preset = ShaderPreset::try_parse(preset).unwrap();
+    let mut chain =
+        FilterChainWgpu::load_from_preset(preset, Arc::clone(&device), Arc::clone(&queue), options)
+            .unwrap();
+
+    let input = load_input(&device, &queue, input.as_ref());
+    let output = create_output(&device, output_size);
+    render_frames(
+        &mut chain,
+        &device,
+        &queue,
+        &input,
+        &output,
+        frames,
+        frame_options,
+    );
+
+    inspect(&chain);
+    read_back(&device, &queue, &output)
+}
+
+/// Render the first shader preset like [`render_and_inspect`], then reload the filter chain
+/// with each following shader preset in turn, rendering the given number of frames after
+/// each reload.
+pub fn render_with_reloads(
+    presets: &[&str],
+    input: impl AsRef<Path>,
+    output_size: Size<u32>,
+    frames: usize,
+    inspect: impl FnOnce(&FilterChainWgpu),
+) -> RgbaImage {
+    let (device, queue) = request_device();
+
+    let (first, reloads) = presets.split_first().unwrap();
+    let mut chain =
+        FilterChainWgpu::load_from_path(first, Arc::clone(&device), Arc::clone(&queue), None)
+            .unwrap();
+
+    let input = load_input(&device, &queue, input.as_ref());
+    let output = create_output(&device, output_size);
+    render_frames(&mut chain, &device, &queue, &input, &output, frames, None);
+
+    for preset in reloads {
+        chain.reload_from_path(preset).unwrap();
+        render_frames(&mut chain, &device, &queue, &input, &output, frames, None);
+    }
+
+    inspect(&chain);
+    read_back(&device, &queue, &output)
+}
+
+/// Read back the framebuffer of the filter chain.
+pub fn read_framebuffer(
+    chain: &FilterChainWgpu,
+    framebuffer: FramebufferKind,
+) -> Result<Image, FilterChainError> {
+    chain.read_framebuffer(framebuffer)
+}
+
+fn create_output(device: &wgpu::Device, output_size: Size<u32>) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("librashader headless output"),
+        size: output_size.into(),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+fn render_frames(
+    chain: &mut FilterChainWgpu,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    input: &Arc<wgpu::Texture>,
+    output: &wgpu::Texture,
+    frames: usize,
+    frame_options: Option<&FrameOptionsWgpu>,
+) {
+    let output_size = output.size().into();
+    let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+
+    for frame in 0..frames {
+        let mut cmd = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("librashader headless frame"),
+        });
+
+        chain
+            .frame(
+                Arc::clone(input),
+                &Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    mvp: None,
+                    output: WgpuOutputView::new_from_raw(&output_view, output_size, FORMAT),
+                },
+                &mut cmd,
+                frame,
+                frame_options,
+            )
+            .unwrap();
+
+        let index = queue.submit([cmd.finish()]);
+        device.poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+    }
+}
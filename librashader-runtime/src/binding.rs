@@ -65,6 +65,24 @@ pub struct UniformInputs<'a> {
     pub framebuffer_size: Size<u32>,
     /// FinalViewportSize
     pub viewport_size: Size<u32>,
+    /// OriginalAspect, or 0 to use the aspect ratio of the original input.
+    pub aspect_ratio: f32,
+    /// OriginalFPS
+    pub frames_per_second: f32,
+    /// FrameTimeDelta
+    pub frametime_delta: u32,
+    /// EnableHDR
+    pub enable_hdr: bool,
+    /// PaperWhiteNits
+    pub paper_white_nits: f32,
+    /// MaxNits
+    pub max_nits: f32,
+    /// ExpandGamut
+    pub expand_gamut: u32,
+    /// InverseTonemap
+    pub inverse_tonemap: bool,
+    /// HDR10
+    pub hdr10: bool,
 }
 
 /// Trait that abstracts binding of semantics to shader uniforms.
@@ -202,6 +220,109 @@ where
             );
         }
 
+        let original_aspect = if uniform_inputs.aspect_ratio > 0.0 {
+            uniform_inputs.aspect_ratio
+        } else {
+            let size = original.size();
+            size.width as f32 / size.height as f32
+        };
+
+        // bind OriginalAspect
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::OriginalAspect.into()) {
+            uniform_storage.bind_scalar(offset.offset(), original_aspect, offset.context(), device);
+        }
+
+        // bind OriginalAspectRotated
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::OriginalAspectRotated.into()) {
+            // 90deg and 270deg rotations swap the width and height of the original.
+            let rotated_aspect = if uniform_inputs.rotation % 2 == 1 {
+                1.0 / original_aspect
+            } else {
+                original_aspect
+            };
+            uniform_storage.bind_scalar(offset.offset(), rotated_aspect, offset.context(), device);
+        }
+
+        // bind OriginalFPS
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::OriginalFPS.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                uniform_inputs.frames_per_second,
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind FrameTimeDelta
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::FrameTimeDelta.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                uniform_inputs.frametime_delta,
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind EnableHDR
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::EnableHDR.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                u32::from(uniform_inputs.enable_hdr),
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind PaperWhiteNits
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::PaperWhiteNits.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                uniform_inputs.paper_white_nits,
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind MaxNits
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::MaxNits.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                uniform_inputs.max_nits,
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind ExpandGamut
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::ExpandGamut.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                uniform_inputs.expand_gamut,
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind InverseTonemap
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::InverseTonemap.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                u32::from(uniform_inputs.inverse_tonemap),
+                offset.context(),
+                device,
+            );
+        }
+
+        // bind HDR10
+        if let Some(offset) = uniform_bindings.get(&UniqueSemantics::HDR10.into()) {
+            uniform_storage.bind_scalar(
+                offset.offset(),
+                u32::from(uniform_inputs.hdr10),
+                offset.context(),
+                device,
+            );
+        }
+
         // bind Original sampler
         if let Some(binding) = texture_meta.get(&TextureSemantics::Original.semantics(0)) {
             Self::bind_texture(descriptor_set, sampler_set, binding, original, device);
@@ -428,6 +549,25 @@ macro_rules! impl_default_frame_options {
             pub total_subframes: u32,
             // The current sub frame. Default is 1.
             pub current_subframe: u32,
+            /// The aspect ratio of the original input. If this is 0, the aspect ratio
+            /// is calculated from the size of the original input. Default is 0.
+            pub aspect_ratio: f32,
+            /// The frame rate of the original input, in frames per second. Default is 60.
+            pub frames_per_second: f32,
+            /// The time elapsed since the previous frame, in microseconds. Default is 0.
+            pub frametime_delta: u32,
+            /// Whether or not HDR output is enabled. Default is false.
+            pub enable_hdr: bool,
+            /// The luminance of paper white, in nits. Default is 200.
+            pub paper_white_nits: f32,
+            /// The peak luminance of the display, in nits. Default is 1000.
+            pub max_nits: f32,
+            /// The gamut expansion mode to use when converting to HDR. Default is 0.
+            pub expand_gamut: u32,
+            /// Whether or not to apply inverse tonemapping to SDR content. Default is false.
+            pub inverse_tonemap: bool,
+            /// Whether or not the output is encoded as HDR10. Default is false.
+            pub hdr10: bool,
         }
 
         impl Default for $ty {
@@ -438,7 +578,36 @@ macro_rules! impl_default_frame_options {
                     rotation: 0,
                     total_subframes: 1,
                     current_subframe: 1,
+                    aspect_ratio: 0.0,
+                    frames_per_second: 60.0,
+                    frametime_delta: 0,
+                    enable_hdr: false,
+                    paper_white_nits: 200.0,
+                    max_nits: 1000.0,
+                    expand_gamut: 0,
+                    inverse_tonemap: false,
+                    hdr10: false,
+                }
+            }
+        }
+
+        impl $ty {
+            /// Get the options for a frame rendered by a filter chain with the given HDR mode.
+            ///
+            /// If HDR output is enabled, `enable_hdr` and `hdr10` are set to match the mode.
+            pub fn with_hdr_mode(
+                &self,
+                mode: $crate::hdr::HdrMode,
+            ) -> ::std::borrow::Cow<'_, Self> {
+                if !mode.is_enabled() {
+                    return ::std::borrow::Cow::Borrowed(self);
                 }
+
+                ::std::borrow::Cow::Owned(Self {
+                    enable_hdr: true,
+                    hdr10: mode == $crate::hdr::HdrMode::Hdr10,
+                    ..self.clone()
+                })
             }
         }
     };
@@ -1,7 +1,51 @@
 use crate::binding::BindingUtil;
+use librashader_common::Size;
 use librashader_reflect::reflect::semantics::BindingMeta;
 use std::collections::VecDeque;
 
+/// Selects a framebuffer of a filter chain to inspect after a frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FramebufferKind {
+    /// The output of the pass at the given index in the last frame.
+    ///
+    /// The final pass renders directly to the viewport, so its output framebuffer
+    /// is never written to.
+    Output(usize),
+    /// The output of the pass at the given index in the frame before the last frame.
+    Feedback(usize),
+    /// The original input of a previous frame, where `History(0)` is the input of the last frame.
+    ///
+    /// History framebuffers only exist if a pass in the preset uses `OriginalHistory`.
+    History(usize),
+}
+
+impl FramebufferKind {
+    /// Select the framebuffer from the output, feedback, and history framebuffers of a filter chain.
+    pub fn select<'a, F>(
+        &self,
+        output: &'a [F],
+        feedback: &'a [F],
+        history: &'a VecDeque<F>,
+    ) -> Option<&'a F> {
+        match *self {
+            FramebufferKind::Output(index) => output.get(index),
+            FramebufferKind::Feedback(index) => feedback.get(index),
+            FramebufferKind::History(index) => history.get(index),
+        }
+    }
+}
+
+/// The current size and format of a framebuffer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FramebufferInfo<F> {
+    /// The size of the framebuffer.
+    pub size: Size<u32>,
+    /// The format of the framebuffer in the graphics API of the runtime.
+    pub format: F,
+    /// The number of mipmap levels of the framebuffer.
+    pub mip_levels: u32,
+}
+
 /// Helper to initialize framebuffers in a graphics API agnostic way.
 pub struct FramebufferInit<'a, F, I, E> {
     owned_generator: &'a dyn Fn() -> Result<F, E>,
@@ -45,6 +89,46 @@ impl<'a, F, I, E> FramebufferInit<'a, F, I, E> {
             self.input_generator,
         )
     }
+
+    /// Resize existing history framebuffers to the required number of history images,
+    /// keeping the most recent images. Views are recreated.
+    pub fn resize_history(&self, framebuffers: VecDeque<F>) -> Result<(VecDeque<F>, Box<[I]>), E> {
+        if self.required_history <= 1 {
+            return Ok((VecDeque::new(), Box::new([])));
+        }
+
+        let mut framebuffers = framebuffers;
+        framebuffers.truncate(self.required_history);
+        while framebuffers.len() < self.required_history {
+            framebuffers.push_back((self.owned_generator)()?);
+        }
+
+        let mut history_textures = Vec::new();
+        history_textures.resize_with(self.required_history, self.input_generator);
+
+        Ok((framebuffers, history_textures.into_boxed_slice()))
+    }
+
+    /// Resize existing output framebuffers to the number of filters,
+    /// keeping the framebuffers of passes that still exist. Views are recreated.
+    pub fn resize_output_framebuffers(
+        &self,
+        framebuffers: Box<[F]>,
+    ) -> Result<(Box<[F]>, Box<[I]>), E> {
+        let mut framebuffers = framebuffers.into_vec();
+        framebuffers.truncate(self.filters_count);
+        while framebuffers.len() < self.filters_count {
+            framebuffers.push((self.owned_generator)()?);
+        }
+
+        let mut output_textures = Vec::new();
+        output_textures.resize_with(self.filters_count, self.input_generator);
+
+        Ok((
+            framebuffers.into_boxed_slice(),
+            output_textures.into_boxed_slice(),
+        ))
+    }
 }
 
 fn init_history<'a, F, I, E>(
@@ -0,0 +1,172 @@
+use librashader_common::fs::{FileProvider, StdFileProvider};
+use librashader_common::{FilterMode, ImageFormat, WrapMode};
+use librashader_preprocess::{Defines, PreprocessError, ShaderSource};
+use librashader_presets::{
+    ParameterConfig, Scale2D, ScaleFactor, ScaleType, Scaling, ShaderPassConfig, ShaderPreset,
+};
+use std::path::{Path, PathBuf};
+
+/// The path the built-in HDR output pass is served from by [`BuiltinFileProvider`].
+pub const HDR_PASS_PATH: &str = "librashader://builtin/hdr.slang";
+
+/// The parameter of the built-in HDR output pass that controls whether its input is
+/// gamma-encoded and has to be linearized.
+pub const HDR_DECODE_GAMMA_PARAMETER: &str = "HdrDecodeGamma";
+
+const HDR_PASS_SOURCE: &str = include_str!("hdr.slang");
+
+/// The color space of the output of a filter chain.
+///
+/// If HDR output is enabled, the final pass should render to a target of the format given by
+/// [`HdrMode::output_format`], and the HDR uniforms of every frame are set to match.
+#[repr(i32)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum HdrMode {
+    /// SDR output.
+    #[default]
+    Disabled = 0,
+    /// scRGB output, with linear Rec. 709 primaries where 1.0 is 80 nits.
+    ///
+    /// The final pass should render to a `R16G16B16A16Sfloat` target.
+    ScRgb,
+    /// HDR10 output, with Rec. 2020 primaries encoded with the ST 2084 (PQ) transfer function.
+    ///
+    /// The final pass should render to a `A2B10G10R10UnormPack32` target.
+    Hdr10,
+}
+
+impl HdrMode {
+    /// Returns whether or not HDR output is enabled.
+    pub const fn is_enabled(&self) -> bool {
+        !matches!(self, HdrMode::Disabled)
+    }
+
+    /// The format of the target the final pass is expected to render to in this mode.
+    pub const fn output_format(&self) -> ImageFormat {
+        match self {
+            HdrMode::Disabled => ImageFormat::R8G8B8A8Unorm,
+            HdrMode::ScRgb => ImageFormat::R16G16B16A16Sfloat,
+            HdrMode::Hdr10 => ImageFormat::A2B10G10R10UnormPack32,
+        }
+    }
+}
+
+/// Append the built-in HDR output pass to the passes of a shader preset.
+///
+/// The output pass converts the output of the preset to scRGB or HDR10 depending on the
+/// `HDR10` uniform, using the `PaperWhiteNits`, `MaxNits`, `ExpandGamut` and `InverseTonemap`
+/// uniforms. The pass that was previously last renders to an FP16 framebuffer instead,
+/// scaled to the viewport unless its scaling was set explicitly.
+///
+/// If the pass that was previously last already wrote linear values, because it rendered to an
+/// sRGB framebuffer either through `srgb_framebuffer` or its `#pragma format`, the
+/// [`HDR_DECODE_GAMMA_PARAMETER`] parameter is set so that the output pass does not linearize
+/// its input a second time. The source of that pass is read with the given defines and provider.
+///
+/// Presets that produce HDR output by themselves should not have the output pass appended.
+/// The source of the output pass is only available through [`BuiltinFileProvider`].
+pub fn append_hdr_pass(
+    preset: &mut ShaderPreset,
+    defines: &Defines,
+    provider: &dyn FileProvider,
+) -> Result<(), PreprocessError> {
+    let viewport = Scaling {
+        scale_type: ScaleType::Viewport,
+        factor: ScaleFactor::Float(1.0),
+    };
+    let viewport = Scale2D {
+        valid: true,
+        x: viewport.clone(),
+        y: viewport,
+    };
+
+    let linear = match preset.shaders.last() {
+        Some(last) => {
+            last.srgb_framebuffer
+                || (!last.float_framebuffer
+                    && ShaderSource::load_with_defines(&last.name, defines, provider)?.format
+                        == ImageFormat::R8G8B8A8Srgb)
+        }
+        None => false,
+    };
+    preset
+        .parameters
+        .retain(|param| param.name != HDR_DECODE_GAMMA_PARAMETER);
+    if linear {
+        preset.parameters.push(ParameterConfig {
+            name: HDR_DECODE_GAMMA_PARAMETER.to_string(),
+            value: 0.0,
+        });
+    }
+
+    if let Some(last) = preset.shaders.last_mut() {
+        last.float_framebuffer = true;
+        last.srgb_framebuffer = false;
+        if !last.scaling.valid {
+            last.scaling = viewport.clone();
+        }
+    }
+
+    preset.shaders.push(ShaderPassConfig {
+        id: preset.shaders.len() as i32,
+        name: PathBuf::from(HDR_PASS_PATH),
+        alias: None,
+        filter: FilterMode::Nearest,
+        wrap_mode: WrapMode::ClampToEdge,
+        frame_count_mod: 0,
+        srgb_framebuffer: false,
+        float_framebuffer: false,
+        mipmap_input: false,
+        scaling: viewport,
+    });
+    preset.shader_count += 1;
+    Ok(())
+}
+
+/// A file provider that serves the built-in shaders of librashader,
+/// and reads all other files through another file provider.
+#[derive(Debug, Copy, Clone)]
+pub struct BuiltinFileProvider<'a> {
+    provider: &'a dyn FileProvider,
+}
+
+impl<'a> BuiltinFileProvider<'a> {
+    /// Serve the built-in shaders on top of the given file provider.
+    pub fn new(provider: &'a dyn FileProvider) -> Self {
+        Self { provider }
+    }
+
+    fn builtin(path: &Path) -> Option<&'static str> {
+        (path == Path::new(HDR_PASS_PATH)).then_some(HDR_PASS_SOURCE)
+    }
+}
+
+impl Default for BuiltinFileProvider<'static> {
+    fn default() -> Self {
+        Self::new(&StdFileProvider)
+    }
+}
+
+impl FileProvider for BuiltinFileProvider<'_> {
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        match Self::builtin(path) {
+            Some(source) => Ok(source.as_bytes().to_vec()),
+            None => self.provider.read(path),
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        match Self::builtin(path) {
+            Some(_) => Ok(path.to_path_buf()),
+            None => self.provider.canonicalize(path),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.provider.is_dir(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        Self::builtin(path).is_some() || self.provider.exists(path)
+    }
+}
@@ -1,5 +1,9 @@
+use image::error::{
+    ImageFormatHint, ParameterError, ParameterErrorKind, UnsupportedError, UnsupportedErrorKind,
+};
 pub use image::ImageError;
-use librashader_common::Size;
+use librashader_common::fs::{FileProvider, StdFileProvider};
+use librashader_common::{ImageFormat, Size};
 use std::marker::PhantomData;
 
 use crate::array_chunks_mut::ArrayChunksMut;
@@ -60,6 +64,68 @@ impl PixelFormat for ARGB8 {
     }
 }
 
+/// How the channels of a pixel are stored.
+#[derive(Copy, Clone)]
+enum Channel {
+    Unorm8,
+    /// Three 10-bit color channels and a 2-bit alpha channel packed into 32 bits.
+    Unorm10,
+    Float16,
+    Float32,
+}
+
+/// Get how the channels of a pixel of the format are stored, and how many channels there are.
+fn channel_layout(format: ImageFormat) -> Option<(Channel, usize)> {
+    Some(match format {
+        ImageFormat::R8Unorm => (Channel::Unorm8, 1),
+        ImageFormat::R8G8Unorm => (Channel::Unorm8, 2),
+        ImageFormat::R8G8B8A8Unorm | ImageFormat::R8G8B8A8Srgb => (Channel::Unorm8, 4),
+        ImageFormat::A2B10G10R10UnormPack32 => (Channel::Unorm10, 4),
+        ImageFormat::R16Sfloat => (Channel::Float16, 1),
+        ImageFormat::R16G16Sfloat => (Channel::Float16, 2),
+        ImageFormat::R16G16B16A16Sfloat => (Channel::Float16, 4),
+        ImageFormat::R32Sfloat => (Channel::Float32, 1),
+        ImageFormat::R32G32Sfloat => (Channel::Float32, 2),
+        ImageFormat::R32G32B32A32Sfloat => (Channel::Float32, 4),
+        _ => return None,
+    })
+}
+
+/// Get the size in bytes of a pixel of the format, if pixels of the format can be converted
+/// to RGBA8 with [`Image::from_pixels`].
+///
+/// Integer formats can not be converted.
+pub fn pixel_size(format: ImageFormat) -> Option<usize> {
+    let (channel, count) = channel_layout(format)?;
+    Some(match channel {
+        Channel::Unorm8 => count,
+        Channel::Unorm10 => 4,
+        Channel::Float16 => count * 2,
+        Channel::Float32 => count * 4,
+    })
+}
+
+fn unorm_to_u8(value: u32, max: u32) -> u8 {
+    ((value * 255 + max / 2) / max) as u8
+}
+
+fn float_to_u8(value: f32) -> u8 {
+    // NaN saturates to zero.
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((half >> 10) & 0x1f);
+    let mantissa = f32::from(half & 0x3ff);
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
 /// The direction of UV coordinates to load the image for.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum UVDirection {
@@ -70,9 +136,108 @@ pub enum UVDirection {
 }
 
 impl<P: PixelFormat> Image<P> {
+    /// Create an image from tightly packed RGBA8 pixels, such as those read back from a texture.
+    ///
+    /// Returns an error if the number of pixels does not match the size.
+    pub fn from_rgba8(mut bytes: Vec<u8>, size: Size<u32>) -> Result<Self, ImageError> {
+        if bytes.len() != size.width as usize * size.height as usize * 4 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+
+        P::convert(&mut bytes);
+        Ok(Image {
+            bytes,
+            pitch: size.width as usize * 4,
+            size,
+            _pd: Default::default(),
+        })
+    }
+
+    /// Create an image from tightly packed pixels of the given format, such as those read back
+    /// from a framebuffer, converting them to RGBA8.
+    ///
+    /// Like reading back a texture as RGBA8 with OpenGL, floating point channels are clamped
+    /// to `[0, 1]`, missing color channels are zero, and a missing alpha channel is one.
+    /// Returns an error if the format can not be converted, or the number of pixels does not
+    /// match the size.
+    pub fn from_pixels(
+        bytes: &[u8],
+        size: Size<u32>,
+        format: ImageFormat,
+    ) -> Result<Self, ImageError> {
+        let Some((channel, count)) = channel_layout(format) else {
+            return Err(ImageError::Unsupported(
+                UnsupportedError::from_format_and_kind(
+                    ImageFormatHint::Unknown,
+                    UnsupportedErrorKind::GenericFeature(format!("{format:?}")),
+                ),
+            ));
+        };
+
+        // panic safety: the layout exists, so the pixel size does too.
+        let pixel_size = pixel_size(format).unwrap();
+        if bytes.len() != size.width as usize * size.height as usize * pixel_size {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+
+        let mut pixels = Vec::with_capacity(bytes.len() / pixel_size * 4);
+        for pixel in bytes.chunks_exact(pixel_size) {
+            let mut rgba = [0, 0, 0, u8::MAX];
+            match channel {
+                Channel::Unorm8 => rgba[..count].copy_from_slice(pixel),
+                Channel::Unorm10 => {
+                    let packed = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                    rgba = [
+                        unorm_to_u8(packed & 0x3ff, 0x3ff),
+                        unorm_to_u8((packed >> 10) & 0x3ff, 0x3ff),
+                        unorm_to_u8((packed >> 20) & 0x3ff, 0x3ff),
+                        unorm_to_u8(packed >> 30, 0x3),
+                    ];
+                }
+                Channel::Float16 => {
+                    for (rgba, channel) in rgba.iter_mut().zip(pixel.chunks_exact(2)) {
+                        let half = u16::from_ne_bytes([channel[0], channel[1]]);
+                        *rgba = float_to_u8(f16_to_f32(half));
+                    }
+                }
+                Channel::Float32 => {
+                    for (rgba, channel) in rgba.iter_mut().zip(pixel.chunks_exact(4)) {
+                        let float =
+                            f32::from_ne_bytes([channel[0], channel[1], channel[2], channel[3]]);
+                        *rgba = float_to_u8(float);
+                    }
+                }
+            }
+            pixels.extend_from_slice(&rgba);
+        }
+
+        Self::from_rgba8(pixels, size)
+    }
+
     /// Load the image from the path as RGBA8.
     pub fn load(path: impl AsRef<Path>, direction: UVDirection) -> Result<Self, ImageError> {
-        let mut image = image::open(path.as_ref())?;
+        Self::load_with_provider(path, direction, &StdFileProvider)
+    }
+
+    /// Load the image from the path as RGBA8, reading the file through the given file provider.
+    pub fn load_with_provider(
+        path: impl AsRef<Path>,
+        direction: UVDirection,
+        provider: &dyn FileProvider,
+    ) -> Result<Self, ImageError> {
+        let path = path.as_ref();
+        let bytes = provider.read(path).map_err(ImageError::IoError)?;
+
+        // Prefer the format implied by the extension like `image::open`, but fall back to
+        // guessing from the contents.
+        let mut image = match image::ImageFormat::from_path(path) {
+            Ok(format) => image::load_from_memory_with_format(&bytes, format)?,
+            Err(_) => image::load_from_memory(&bytes)?,
+        };
 
         if direction == UVDirection::BottomLeft {
             image = image.flipv();
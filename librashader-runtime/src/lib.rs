@@ -37,5 +37,11 @@ pub mod render_target;
 /// Helpers for handling framebuffers.
 pub mod framebuffer;
 
+/// HDR output helpers and the built-in HDR output pass.
+pub mod hdr;
+
+/// Helpers to reload the shader preset of a filter chain.
+pub mod reload;
+
 /// array_chunks_mut polyfill
 mod array_chunks_mut;
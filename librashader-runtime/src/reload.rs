@@ -0,0 +1,271 @@
+use crate::hdr::BuiltinFileProvider;
+use librashader_common::fs::{FileProvider, StdFileProvider};
+use librashader_common::map::FastHashMap;
+use librashader_preprocess::{Defines, PreprocessError, ShaderSource};
+use librashader_presets::{ShaderPassConfig, ShaderPreset, TextureConfig, WildcardContext};
+use librashader_reflect::reflect::presets::preset_semantics;
+use librashader_reflect::reflect::semantics::ShaderSemantics;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// The parts of a filter chain that can be kept when reloading a shader preset.
+pub struct PresetReload {
+    /// The semantics of the new shader preset.
+    pub semantics: ShaderSemantics,
+    /// For each pass of the new preset, whether the pass at the same index of the
+    /// filter chain can be kept.
+    pub reuse_passes: Vec<bool>,
+    /// For each lookup texture of the new preset, the index of the lookup texture
+    /// of the filter chain that can be kept.
+    pub reuse_luts: Vec<Option<usize>>,
+}
+
+impl PresetReload {
+    /// Compare the passes and lookup textures of a filter chain to those of a new shader preset.
+    ///
+    /// The sources of every pass of the new preset are preprocessed to find changes to
+    /// a source or the files it includes. A pass is kept if its config and preprocessed source
+    /// did not change, it is still the final pass only if it was before, and the pass
+    /// aliases and lookup texture names of the preset did not change.
+    ///
+    /// A lookup texture is kept if a lookup texture of the filter chain has the same
+    /// path, wrap mode, filter mode and mipmap setting. The image is not read again.
+    ///
+    /// Sources are preprocessed with the given macros defined, and read through the given
+    /// file provider, which also serves the built-in shaders.
+    pub fn new<'a>(
+        preset: &ShaderPreset,
+        passes: impl Iterator<Item = (&'a ShaderPassConfig, &'a ShaderSource)>,
+        textures: &[TextureConfig],
+        defines: &Defines,
+        provider: &dyn FileProvider,
+    ) -> Result<Self, PreprocessError> {
+        let provider = BuiltinFileProvider::new(provider);
+        let sources = preset
+            .shaders
+            .iter()
+            .map(|config| ShaderSource::load_with_defines(&config.name, defines, &provider))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (old_configs, old_sources): (Vec<_>, Vec<_>) = passes.unzip();
+        let old_semantics = preset_semantics(
+            old_sources.iter().copied(),
+            old_configs.iter().copied(),
+            textures,
+        );
+        let semantics = preset_semantics(&sources, &preset.shaders, &preset.textures);
+
+        // Reflection of a pass depends on the indices of the textures it binds.
+        let bindings_unchanged = old_semantics.texture_semantics == semantics.texture_semantics;
+        let old_final = old_configs.len().checked_sub(1);
+        let new_final = preset.shaders.len().checked_sub(1);
+
+        let reuse_passes = preset
+            .shaders
+            .iter()
+            .zip(&sources)
+            .enumerate()
+            .map(|(index, (config, source))| {
+                bindings_unchanged
+                    && (old_final == Some(index)) == (new_final == Some(index))
+                    && old_configs.get(index) == Some(&config)
+                    && old_sources.get(index) == Some(&source)
+            })
+            .collect();
+
+        let mut claimed = vec![false; textures.len()];
+        let reuse_luts = preset
+            .textures
+            .iter()
+            .map(|texture| {
+                let index = textures.iter().enumerate().position(|(index, old)| {
+                    !claimed[index]
+                        && old.path == texture.path
+                        && old.wrap_mode == texture.wrap_mode
+                        && old.filter_mode == texture.filter_mode
+                        && old.mipmap == texture.mipmap
+                })?;
+                claimed[index] = true;
+                Some(index)
+            })
+            .collect();
+
+        Ok(Self {
+            semantics,
+            reuse_passes,
+            reuse_luts,
+        })
+    }
+
+    /// The indices of the passes of the new preset that need to be compiled.
+    pub fn changed_passes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.reuse_passes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, reuse)| (!reuse).then_some(index))
+    }
+
+    /// The indices of the lookup textures of the new preset that need to be loaded.
+    pub fn changed_luts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.reuse_luts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, reuse)| reuse.is_none().then_some(index))
+    }
+
+    /// Merge the kept passes of a filter chain with the passes that were compiled
+    /// in the order of [`changed_passes`](Self::changed_passes).
+    ///
+    /// Returns the passes of the new preset, and the passes of the filter chain that were replaced.
+    pub fn merge_passes<P>(&self, passes: Box<[P]>, compiled: Box<[P]>) -> (Box<[P]>, Vec<P>) {
+        let mut old = passes.into_vec().into_iter();
+        let mut compiled = compiled.into_vec().into_iter();
+        let mut replaced = Vec::new();
+
+        let passes = self
+            .reuse_passes
+            .iter()
+            .map(|&reuse| {
+                let old = old.next();
+                match old {
+                    Some(old) if reuse => old,
+                    old => {
+                        replaced.extend(old);
+                        // panic safety: a pass is compiled for every pass that is not kept.
+                        compiled.next().unwrap()
+                    }
+                }
+            })
+            .collect();
+
+        replaced.extend(old);
+        (passes, replaced)
+    }
+
+    /// Merge the kept lookup textures of a filter chain with the lookup textures that were
+    /// loaded in the order of [`changed_luts`](Self::changed_luts).
+    ///
+    /// Kept lookup textures are removed from `luts`, leaving the lookup textures
+    /// that are no longer used.
+    pub fn merge_luts<L>(
+        &self,
+        luts: &mut FastHashMap<usize, L>,
+        mut loaded: FastHashMap<usize, L>,
+    ) -> FastHashMap<usize, L> {
+        let mut merged = FastHashMap::default();
+        for (index, old) in self.reuse_luts.iter().enumerate() {
+            if let Some(lut) = old.and_then(|old| luts.remove(&old)) {
+                merged.insert(index, lut);
+            }
+        }
+
+        for (loaded_index, index) in self.changed_luts().enumerate() {
+            if let Some(lut) = loaded.remove(&loaded_index) {
+                merged.insert(index, lut);
+            }
+        }
+        merged
+    }
+}
+
+/// Watches the files of a shader preset for changes by polling their modification times.
+///
+/// The preset file, the presets it references, the source of every pass and the files they
+/// include are watched. Lookup texture images are not watched, because lookup textures with
+/// an unchanged path are not read again when reloading a filter chain.
+pub struct PresetWatcher {
+    path: PathBuf,
+    context: WildcardContext,
+    defines: Defines,
+    provider: Arc<dyn FileProvider>,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl PresetWatcher {
+    /// Start watching the shader preset at the given path.
+    ///
+    /// The preset is parsed with the path defaults as its wildcard context, and sources are read
+    /// from the host filesystem without any additional macros defined.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        let mut context = WildcardContext::new();
+        context.add_path_defaults(path.as_ref());
+        Self::with_options(path, context, Defines::new(), Arc::new(StdFileProvider))
+    }
+
+    /// Start watching the shader preset at the given path, parsing it with the given
+    /// wildcard context and reading the preset and sources through the given file provider.
+    ///
+    /// The context, defines and file provider should match those of the filter chain that is
+    /// reloaded, so that the same files are watched as are read when reloading. Modification
+    /// times are always read from the host filesystem.
+    pub fn with_options(
+        path: impl AsRef<Path>,
+        context: WildcardContext,
+        defines: Defines,
+        provider: Arc<dyn FileProvider>,
+    ) -> Self {
+        let mut watcher = Self {
+            path: path.as_ref().to_path_buf(),
+            context,
+            defines,
+            provider,
+            files: Vec::new(),
+        };
+        watcher.refresh();
+        watcher
+    }
+
+    /// The path to the watched shader preset.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether any watched file was modified, created or removed since the last poll.
+    ///
+    /// If a file changed, the list of watched files is updated to match the preset, so that
+    /// newly included files are watched as well.
+    pub fn poll(&mut self) -> bool {
+        let changed = self
+            .files
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified);
+
+        if changed {
+            self.refresh();
+        }
+        changed
+    }
+
+    fn refresh(&mut self) {
+        let mut files = vec![self.path.clone()];
+
+        // If the preset can not be parsed, only the preset file is watched until it is fixed.
+        if let Ok(preset) =
+            ShaderPreset::try_parse_with_provider(&self.path, self.context.clone(), &*self.provider)
+        {
+            files.extend(preset.files().iter().cloned());
+            for config in &preset.shaders {
+                match ShaderSource::load_with_defines(&config.name, &self.defines, &*self.provider)
+                {
+                    Ok(source) => files.extend(source.files().into_iter().map(Path::to_path_buf)),
+                    Err(_) => files.push(config.name.clone()),
+                }
+            }
+        }
+
+        files.sort();
+        files.dedup();
+        self.files = files
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
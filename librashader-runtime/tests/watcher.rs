@@ -0,0 +1,84 @@
+use librashader_common::fs::StdFileProvider;
+use librashader_preprocess::Defines;
+use librashader_presets::WildcardContext;
+use librashader_runtime::reload::PresetWatcher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "librashader-runtime-test-watcher-{name}-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Write a preset that references another preset, with a pass that includes a file,
+/// and a second file that is only included when `WATCH_EXTRA` is defined.
+fn write_preset(directory: &Path) -> PathBuf {
+    let files = [
+        ("base.slangp", "shaders = 1\nshader0 = pass.slang\n"),
+        ("preset.slangp", "#reference \"base.slangp\"\nfilter_linear0 = true\n"),
+        (
+            "pass.slang",
+            "#version 450\n#include \"include.inc\"\n#ifdef WATCH_EXTRA\n#include \"extra.inc\"\n#endif\n",
+        ),
+        ("include.inc", "// include\n"),
+        ("extra.inc", "// extra\n"),
+    ];
+    for (name, contents) in files {
+        std::fs::write(directory.join(name), contents).unwrap();
+    }
+    directory.join("preset.slangp")
+}
+
+/// Move the modification time of a file forward, since writes in quick succession
+/// may not change it on filesystems with a coarse timestamp resolution.
+fn touch(path: &Path) {
+    let file = std::fs::File::options().append(true).open(path).unwrap();
+    let modified = file.metadata().unwrap().modified().unwrap();
+    file.set_modified(modified.max(SystemTime::now()) + Duration::from_secs(10))
+        .unwrap();
+}
+
+#[test]
+fn polls_sources_includes_and_references() {
+    let directory = temp_dir("files");
+    let path = write_preset(&directory);
+    let mut watcher = PresetWatcher::new(&path);
+    assert!(!watcher.poll());
+
+    for name in ["pass.slang", "include.inc", "base.slangp", "preset.slangp"] {
+        touch(&directory.join(name));
+        assert!(watcher.poll(), "{name} was not watched");
+        assert!(!watcher.poll(), "{name} was reported twice");
+    }
+
+    // The extra include is inactive without the define.
+    touch(&directory.join("extra.inc"));
+    assert!(!watcher.poll());
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn polls_files_included_with_defines() {
+    let directory = temp_dir("defines");
+    let path = write_preset(&directory);
+
+    let mut context = WildcardContext::new();
+    context.add_path_defaults(&path);
+    let defines = Defines::from_iter([("WATCH_EXTRA", "1")]);
+    let mut watcher =
+        PresetWatcher::with_options(&path, context, defines, Arc::new(StdFileProvider));
+    assert!(!watcher.poll());
+
+    touch(&directory.join("extra.inc"));
+    assert!(watcher.poll());
+    assert!(!watcher.poll());
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
@@ -78,6 +78,15 @@ pub mod presets {
         let iters = iters?;
         Ok(iters.into_iter().flatten())
     }
+
+    /// Check a shader preset for mistakes before creating a filter chain, including parameters
+    /// that are set in the preset but not declared by any pass.
+    ///
+    /// This loads the shader source of every pass to find the declared parameters.
+    pub fn validate(preset: &ShaderPreset) -> Result<Vec<ValidationIssue>, PreprocessError> {
+        let declared: Vec<String> = get_parameter_meta(preset)?.map(|p| p.id).collect();
+        Ok(preset.validate_with_parameters(declared.iter().map(String::as_str)))
+    }
 }
 
 #[cfg(feature = "preprocess")]
@@ -145,6 +154,7 @@ pub mod reflect {
     /// Supported shader compiler targets.
     pub mod targets {
         pub use librashader_reflect::back::targets::DXIL;
+        pub use librashader_reflect::back::targets::ESSL;
         pub use librashader_reflect::back::targets::GLSL;
         pub use librashader_reflect::back::targets::HLSL;
         pub use librashader_reflect::back::targets::MSL;
@@ -161,7 +171,9 @@ pub mod reflect {
         FromCompilation, ShaderCompilerOutput,
     };
 
-    pub use librashader_reflect::front::{ShaderReflectObject, SpirvCompilation};
+    pub use librashader_reflect::front::{
+        ShaderReflectObject, SpirvCompilation, SpirvOptimization,
+    };
 
     /// Reflection via SPIRV-Cross.
     #[cfg(feature = "reflect-cross")]
@@ -173,6 +185,10 @@ pub mod reflect {
         ///
         pub use librashader_reflect::back::glsl::GlslVersion;
 
+        /// The version of GLSL ES to target.
+        ///
+        pub use librashader_reflect::back::essl::EsslVersion;
+
         /// The HLSL Shader Model to target.
         ///
         pub use librashader_reflect::back::hlsl::HlslShaderModel;
@@ -207,6 +223,8 @@ pub mod reflect {
     #[cfg(feature = "reflect-naga")]
     #[doc(cfg(feature = "reflect-naga"))]
     pub mod naga {
+        pub use librashader_reflect::back::glsl::NagaGlslContext;
+        pub use librashader_reflect::back::hlsl::NagaHlslContext;
         pub use librashader_reflect::back::wgsl::NagaWgslContext;
         pub use librashader_reflect::reflect::naga::Naga;
         pub use librashader_reflect::reflect::naga::NagaLoweringOptions;
@@ -214,7 +232,14 @@ pub mod reflect {
 
     pub use librashader_reflect::reflect::semantics::BindingMeta;
 
-    pub use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+    pub use librashader_reflect::reflect::presets::{
+        CompilePresetTarget, PresetCompileOptions, ShaderPassArtifact,
+    };
+
+    /// Shader compatibility reports across output targets and reflectors.
+    #[cfg(all(feature = "serialize", feature = "reflect-naga"))]
+    #[doc(cfg(all(feature = "serialize", feature = "reflect-naga")))]
+    pub use librashader_reflect::reflect::report;
 
     pub use librashader_reflect::front::ShaderInputCompiler;
     #[doc(hidden)]
@@ -227,12 +252,33 @@ pub mod reflect {
     }
 }
 
+/// Shader object and pipeline caching for filter chains.
+///
+/// Compiled shaders are cached in a [`ShaderCache`](crate::cache::ShaderCache). Filter chains use
+/// the default cache unless a cache is passed in the filter chain options.
+///
+/// The storage of a cache can be replaced by implementing [`CacheBackend`](crate::cache::CacheBackend).
+/// Shaders can be compiled ahead of time into a [`ShaderArchive`](crate::cache::ShaderArchive),
+/// which can be loaded into a cache with [`ShaderCache::with_archive`](crate::cache::ShaderCache::with_archive).
+#[cfg(feature = "runtime")]
+#[doc(cfg(feature = "runtime"))]
+pub mod cache {
+    pub use librashader_cache::{
+        ArchiveTargets, CacheBackend, CacheEntry, CacheError, CacheStats, EvictionPolicy,
+        FilesBackend, IndexStats, MemoryBackend, PersyBackend, ShaderArchive, ShaderCache,
+    };
+}
+
 /// Shader runtimes to execute a filter chain on a GPU surface.
 #[cfg(feature = "runtime")]
 #[doc(cfg(feature = "runtime"))]
 pub mod runtime {
     pub use librashader_common::{Size, Viewport};
+    pub use librashader_runtime::framebuffer::{FramebufferInfo, FramebufferKind};
+    pub use librashader_runtime::hdr::HdrMode;
+    pub use librashader_runtime::image::Image;
     pub use librashader_runtime::parameters::FilterChainParameters;
+    pub use librashader_runtime::reload::PresetWatcher;
 
     #[cfg(feature = "runtime-gl")]
     #[doc(cfg(feature = "runtime-gl"))]
@@ -340,4 +386,5 @@ pub mod runtime {
     }
 }
 
+pub use librashader_common::fs;
 pub use librashader_common::{FilterMode, ImageFormat, WrapMode};
@@ -0,0 +1,373 @@
+//! Golden image tests shared by the runtimes that can render headlessly.
+//!
+//! Each runtime includes this module into its `golden` test, next to a `headless` module that
+//! renders shader presets with that runtime. Rendering requires a software implementation,
+//! such as lavapipe or WARP, so the tests are ignored unless run with `--ignored`. Without a
+//! software implementation, they fail.
+//!
+//! Golden images are stored in `test/golden`. Set `LIBRASHADER_BLESS_GOLDEN` to save the
+//! current output as the golden image, which also creates missing golden images.
+use crate::headless::{self, FilterChainOptions, FrameOptions, RUNTIME};
+use image::RgbaImage;
+use librashader_common::Size;
+use librashader_reflect::front::SpirvOptimization;
+use librashader_runtime::framebuffer::FramebufferKind;
+use librashader_runtime::hdr::HdrMode;
+use std::path::PathBuf;
+
+/// Set to re-bless every golden image with the current output.
+const BLESS_VAR: &str = "LIBRASHADER_BLESS_GOLDEN";
+
+const INPUT: &str = "../test/sf2.png";
+const OUTPUT_SIZE: Size<u32> = Size {
+    width: 640,
+    height: 448,
+};
+const FRAMES: usize = 3;
+
+/// How far the output is allowed to stray from the golden image.
+#[derive(Debug, Copy, Clone)]
+pub struct Tolerance {
+    /// The largest difference of a single channel for a pixel to still match.
+    pub max_difference: u8,
+    /// The largest fraction of pixels that may not match.
+    pub max_mismatched: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            max_difference: 2,
+            max_mismatched: 0.001,
+        }
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../test/golden")
+        .join(format!("{name}.png"))
+}
+
+/// Compare the output against the golden image of the given name.
+///
+/// If `LIBRASHADER_BLESS_GOLDEN` is set, the output is saved as the golden image instead.
+/// On a mismatch, the output is saved to the test temporary directory so that it can be
+/// inspected.
+pub fn assert_golden(name: &str, output: &RgbaImage, tolerance: Tolerance) {
+    let path = golden_path(name);
+    if std::env::var_os(BLESS_VAR).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        output.save(&path).unwrap();
+        eprintln!("[golden] blessed {}", path.display());
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "[golden] {name} ({RUNTIME}): {} does not exist, set {BLESS_VAR} to create it",
+        path.display()
+    );
+
+    let golden = image::open(&path).unwrap().to_rgba8();
+    assert_eq!(
+        golden.dimensions(),
+        output.dimensions(),
+        "[golden] {name} ({RUNTIME}) has different dimensions than the golden image"
+    );
+
+    let mismatched = golden
+        .pixels()
+        .zip(output.pixels())
+        .filter(|(golden, output)| {
+            golden
+                .0
+                .iter()
+                .zip(output.0.iter())
+                .any(|(a, b)| a.abs_diff(*b) > tolerance.max_difference)
+        })
+        .count();
+
+    let ratio = mismatched as f64 / golden.pixels().len() as f64;
+    if ratio > tolerance.max_mismatched {
+        let actual = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+            .join("golden")
+            .join(format!("{name}.{RUNTIME}.png"));
+        std::fs::create_dir_all(actual.parent().unwrap()).unwrap();
+        output.save(&actual).unwrap();
+
+        panic!(
+            "[golden] {name} ({RUNTIME}): {mismatched} pixels ({:.3}%) differ from {}, output saved to {}",
+            ratio * 100.0,
+            path.display(),
+            actual.display()
+        );
+    }
+}
+
+fn check(name: &str, preset: &str) {
+    let output = headless::render(preset, INPUT, OUTPUT_SIZE, FRAMES);
+    assert_golden(name, &output, Tolerance::default());
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn golden_basic() {
+    check("basic", "../test/basic.slangp")
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn golden_null() {
+    check("null", "../test/null.slangp")
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn golden_optimized() {
+    // Optimizing the shaders must not change the output.
+    let options = FilterChainOptions {
+        optimization: Some(SpirvOptimization::all()),
+        ..Default::default()
+    };
+
+    let output = headless::render_with_options(
+        "../test/basic.slangp",
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        Some(&options),
+        None,
+        |_| {},
+    );
+
+    assert_golden("basic", &output, Tolerance::default());
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn read_framebuffer() {
+    headless::render_and_inspect(
+        "../test/multipass.slangp",
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        |chain| {
+            let info = chain
+                .framebuffer_info(FramebufferKind::Output(0))
+                .expect("pass 0 should have an output framebuffer");
+
+            let image = headless::read_framebuffer(chain, FramebufferKind::Output(0)).unwrap();
+            assert_eq!(image.size, info.size);
+            assert_eq!(
+                image.bytes.len(),
+                info.size.width as usize * info.size.height as usize * 4
+            );
+            assert!(
+                image.bytes.iter().any(|&b| b != 0),
+                "pass 0 output should not be empty"
+            );
+
+            assert!(chain
+                .framebuffer_info(FramebufferKind::Output(16))
+                .is_none());
+            assert!(headless::read_framebuffer(chain, FramebufferKind::Output(16)).is_err());
+        },
+    );
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn read_float_framebuffer() {
+    // FP16 framebuffers are converted to RGBA8, and match the pass output of an 8-bit framebuffer.
+    let mut expected = None;
+    headless::render_and_inspect(
+        "../test/multipass.slangp",
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        |chain| {
+            expected = Some(headless::read_framebuffer(chain, FramebufferKind::Output(0)).unwrap());
+        },
+    );
+    let expected = expected.unwrap();
+
+    headless::render_and_inspect(
+        "../test/float.slangp",
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        |chain| {
+            let image = headless::read_framebuffer(chain, FramebufferKind::Output(0)).unwrap();
+            assert_eq!(image.size, expected.size);
+
+            let mismatched = image
+                .bytes
+                .iter()
+                .zip(expected.bytes.iter())
+                .filter(|(a, b)| a.abs_diff(**b) > Tolerance::default().max_difference)
+                .count();
+            assert_eq!(
+                mismatched, 0,
+                "FP16 pass output differs from RGBA8 pass output"
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn reload() {
+    let output = headless::render_with_reloads(
+        &[
+            "../test/multipass.slangp",
+            "../test/basic.slangp",
+            "../test/basic.slangp",
+        ],
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        |_| {},
+    );
+    assert_golden("basic", &output, Tolerance::default());
+
+    let output = headless::render_with_reloads(
+        &["../test/multipass.slangp", "../test/null.slangp"],
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        |chain| {
+            assert!(chain.framebuffer_info(FramebufferKind::Output(0)).is_some());
+            assert!(chain.framebuffer_info(FramebufferKind::Output(1)).is_none());
+        },
+    );
+    assert_golden("null", &output, Tolerance::default());
+}
+
+/// Render `uniforms.slang` and compare the uniforms it encodes in each third of the output.
+fn check_uniforms(frame_options: &FrameOptions, expected: [[f32; 3]; 3]) {
+    let output = headless::render_with_options(
+        "../test/uniforms.slangp",
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        None,
+        Some(frame_options),
+        |_| {},
+    );
+
+    let y = OUTPUT_SIZE.height / 2;
+    for (third, expected) in expected.iter().enumerate() {
+        let x = OUTPUT_SIZE.width * (2 * third as u32 + 1) / 6;
+        let pixel = output.get_pixel(x, y).0;
+        for (channel, expected) in expected.iter().enumerate() {
+            let expected = (expected * 255.0).round() as u8;
+            assert!(
+                pixel[channel].abs_diff(expected) <= Tolerance::default().max_difference,
+                "[uniforms] ({RUNTIME}) channel {channel} of third {third} is {}, expected {expected}",
+                pixel[channel]
+            );
+        }
+    }
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn binds_frame_uniforms() {
+    check_uniforms(
+        &FrameOptions {
+            aspect_ratio: 2.0,
+            frames_per_second: 50.0,
+            frametime_delta: 25000,
+            enable_hdr: true,
+            paper_white_nits: 250.0,
+            max_nits: 5000.0,
+            expand_gamut: 2,
+            inverse_tonemap: true,
+            hdr10: true,
+            ..Default::default()
+        },
+        [[0.5, 0.5, 0.25], [1.0, 0.25, 0.5], [0.5, 1.0, 1.0]],
+    )
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn binds_default_frame_uniforms() {
+    // Without an aspect ratio, OriginalAspect is the aspect ratio of the 320x224 input.
+    check_uniforms(
+        &FrameOptions::default(),
+        [
+            [320.0 / 224.0 / 4.0, 0.6, 0.0],
+            [0.0, 0.2, 0.1],
+            [0.0, 0.0, 0.0],
+        ],
+    )
+}
+
+fn check_hdr(name: &str, preset: &str, mode: HdrMode, frame_options: &FrameOptions) {
+    let options = FilterChainOptions {
+        hdr_mode: mode,
+        hdr_output_pass: true,
+        ..Default::default()
+    };
+
+    let output = headless::render_with_options(
+        preset,
+        INPUT,
+        OUTPUT_SIZE,
+        FRAMES,
+        Some(&options),
+        Some(frame_options),
+        |_| {},
+    );
+
+    assert_golden(name, &output, Tolerance::default());
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn golden_hdr_scrgb() {
+    // With paper white at 80 nits and no inverse tonemapping, scRGB output is the linearized
+    // SDR output.
+    check_hdr(
+        "hdr_scrgb",
+        "../test/basic.slangp",
+        HdrMode::ScRgb,
+        &FrameOptions {
+            paper_white_nits: 80.0,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn golden_hdr_scrgb_srgb_last_pass() {
+    // An sRGB last pass already writes linear values, which the output pass must not linearize
+    // a second time, so the scRGB output matches the values written by the SDR pass.
+    check_hdr(
+        "basic",
+        "../test/srgb.slangp",
+        HdrMode::ScRgb,
+        &FrameOptions {
+            paper_white_nits: 80.0,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+#[ignore = "requires a software renderer"]
+fn golden_hdr10() {
+    check_hdr(
+        "hdr10",
+        "../test/basic.slangp",
+        HdrMode::Hdr10,
+        &FrameOptions {
+            inverse_tonemap: true,
+            expand_gamut: 1,
+            ..Default::default()
+        },
+    )
+}